@@ -133,7 +133,7 @@ fn main() {
     std::thread::sleep(std::time::Duration::from_millis(STABILIZATION_DELAY_MS));
 
     // Measure and display the output voltage
-    let measured_voltage = psu.read_output_voltage_mv().unwrap();
+    let measured_voltage = psu.read_output_voltage_mv().unwrap().0;
     println!(
         "Measured output voltage: {:.3}V",
         measured_voltage as f32 / 1000.0
@@ -146,18 +146,17 @@ fn main() {
 
     // Modify protection settings
     println!("\n--- Updating Protection Settings ---");
-    use fugit::Duration;
     use sinilink_xy_psu::preset::ProtectionConfig;
 
     let new_protections = ProtectionConfig {
-        under_voltage_mv: 11000,                        // 11.0V minimum
-        over_voltage_mv: 15000,                         // 15.0V maximum
-        over_current_ma: 123,                           // 0.123A maximum
-        over_power_mw: 9876,                            // 9.876W maximum
-        over_time: Duration::<u32, 1, 1>::hours(1),     // 1 hour maximum runtime
-        over_capacity_mah: 1000,                        // 1000 mAh maximum
-        over_energy_mwh: 5000,                          // 5 Wh maximum
-        over_temperature: protections.over_temperature, // Keep existing temperature setting
+        under_voltage_mv: 11000,                         // 11.0V minimum
+        over_voltage_mv: 15000,                          // 15.0V maximum
+        over_current_ma: 123,                            // 0.123A maximum
+        over_power_mw: 9876,                             // 9.876W maximum
+        over_time: std::time::Duration::from_secs(3600), // 1 hour maximum runtime
+        over_capacity_mah: 1000,                         // 1000 mAh maximum
+        over_energy_mwh: 5000,                           // 5 Wh maximum
+        over_temperature: protections.over_temperature,  // Keep existing temperature setting
     };
 
     println!("New protection configuration:");
@@ -0,0 +1,447 @@
+//! Async driver, for executors such as Embassy. Only available with the `async` feature.
+//!
+//! [`XyPsuAsync`] has the same methods as [`XyPsu`](crate::psu::XyPsu), as `async fn`s, over any interface which
+//! implements [`embedded_io_async::Read`] & [`embedded_io_async::Write`].
+//!
+//! The transaction path, scaling detection, firmware [`quirks`](crate::quirks) and power cycle handling are shared with
+//! the blocking driver. Detecting front panel changes is only supported by the blocking driver for now.
+//!
+//! With the `embassy` feature, responses can time out, a gap can be kept between frames, and the output voltage can be
+//! ramped, all using `embassy_time` timers. Without it, the driver has no way to wait that doesn't block the executor:
+//! a response is waited for as long as the interface's reads take, and request gaps and retry backoffs are refused.
+//!
+//! ```ignore
+//! let mut psu = XyPsuAsync::<_, 128>::new(uart, 0x01);
+//! psu.set_output_voltage_mv(12000).await?;
+//! psu.set_output_state(true).await?;
+//! let current_ma = psu.read_current_ma().await?;
+//! ```
+
+use crate::{
+    cache::RegisterCache,
+    error::{Error, Result},
+    frame::{self, Frame},
+    limits::ModelLimits,
+    psu::{Direction, FrameHook, ReadFunction, RequestGap, RetryPolicy, WriteVerification},
+    quirks::Quirks,
+    register::{BaudRate, XyRegister},
+    register_map::RegisterMap,
+    scaling::{ScalingFactors, ScalingRegistry},
+    specs::ModelRegistry,
+};
+use embedded_io::Error as _;
+
+/// Async equivalent of [`XyPsu`](crate::psu::XyPsu).
+pub struct XyPsuAsync<S: embedded_io_async::Read + embedded_io_async::Write, const L: usize = 128> {
+    interface: S,
+    /// Default for PSU is 0x01.
+    pub(crate) unit_id: u8,
+    /// Scaling factors for this PSU model. Lazily loaded on first use of scaled functions.
+    pub(crate) scaling: Option<ScalingFactors>,
+    /// Addresses of registers which differ from the defaults on this model.
+    pub(crate) register_map: RegisterMap,
+    /// Whether the scaling factors were set by the user, rather than detected from the model.
+    pub(crate) scaling_is_manual: bool,
+    /// Maximum setpoints, detected with the scaling factors or set by the user.
+    pub(crate) limits: Option<ModelLimits>,
+    /// Scaling factors of models added at runtime.
+    pub(crate) scaling_registry: ScalingRegistry,
+    /// Models described at runtime, see [`XyPsu::register_model`](crate::psu::XyPsu::register_model).
+    pub(crate) model_registry: ModelRegistry,
+    /// Firmware quirks being worked around, see [`Self::identify`].
+    pub(crate) quirks: Quirks,
+    /// Number of transactions which have failed in a row.
+    pub(crate) consecutive_failures: u8,
+    /// Set when the link recovers after repeated failures, as the device may have been power cycled.
+    pub(crate) stale: bool,
+    /// Output time seen by the last call to [`Self::check_power_cycle`], in seconds.
+    pub(crate) last_output_time_secs: Option<u32>,
+    /// Slave address written to the device, which only takes effect after a power cycle.
+    pub(crate) pending_unit_id: Option<u8>,
+    /// Baud rate written to the device, which only takes effect after a power cycle.
+    pub(crate) pending_baud_rate: Option<BaudRate>,
+    /// Slow-changing registers, see [`Self::set_register_cache`].
+    pub(crate) register_cache: RegisterCache,
+    /// Request frame of the most recent transaction.
+    pub(crate) last_request: heapless::Vec<u8, L>,
+    /// Response frame of the most recent transaction.
+    pub(crate) last_response: heapless::Vec<u8, L>,
+    /// How failed transactions are retried.
+    pub(crate) retry_policy: RetryPolicy,
+    /// Longest wait for each read of a response, see [`Self::set_response_timeout`].
+    #[cfg(feature = "embassy")]
+    response_timeout: Option<embassy_time::Duration>,
+    /// Least silence between frames, see [`Self::set_inter_frame_gap`].
+    #[cfg(feature = "embassy")]
+    inter_frame_gap: embassy_time::Duration,
+    /// When the most recent response was received.
+    #[cfg(feature = "embassy")]
+    last_frame_end: Option<embassy_time::Instant>,
+    /// Whether to refuse to enable the output while a protection is active, see [`Self::set_strict_mode`].
+    pub(crate) strict_mode: bool,
+    /// How registers are read, see [`Self::set_read_function`].
+    pub(crate) read_function: ReadFunction,
+    /// Whether setters read back what they wrote, see [`Self::set_write_verification`].
+    pub(crate) write_verification: WriteVerification,
+    /// Called with every frame sent and received, see [`Self::set_frame_hook`].
+    pub(crate) frame_hook: Option<FrameHook>,
+    /// Wait between transactions, see [`Self::set_request_gap`].
+    pub(crate) request_gap: Option<RequestGap>,
+    /// Whether a transaction has been made since the last wait for the request gap.
+    #[cfg(feature = "embassy")]
+    gap_pending: bool,
+}
+
+/// Time between voltage steps while ramping, see [`XyPsuAsync::ramp_output_voltage_mv`].
+#[cfg(feature = "embassy")]
+pub const RAMP_STEP: embassy_time::Duration = embassy_time::Duration::from_millis(100);
+
+impl<S: embedded_io_async::Read + embedded_io_async::Write, const L: usize> XyPsuAsync<S, L> {
+    /// Create a new XyPsuAsync instance with the given interface and unit ID
+    ///
+    /// Scaling factors are lazily loaded on first use of scaled measurement functions.
+    /// You can manually specify scaling factors using [`Self::set_scaling_factors`].
+    pub fn new(interface: S, unit_id: u8) -> Self {
+        Self {
+            interface,
+            unit_id,
+            scaling: None,
+            register_map: RegisterMap::new(),
+            scaling_is_manual: false,
+            limits: None,
+            scaling_registry: ScalingRegistry::new(),
+            model_registry: ModelRegistry::new(),
+            quirks: Quirks::default(),
+            consecutive_failures: 0,
+            stale: false,
+            last_output_time_secs: None,
+            pending_unit_id: None,
+            pending_baud_rate: None,
+            register_cache: RegisterCache::default(),
+            last_request: heapless::Vec::new(),
+            last_response: heapless::Vec::new(),
+            retry_policy: RetryPolicy::default(),
+            #[cfg(feature = "embassy")]
+            response_timeout: None,
+            #[cfg(feature = "embassy")]
+            inter_frame_gap: embassy_time::Duration::from_ticks(0),
+            #[cfg(feature = "embassy")]
+            last_frame_end: None,
+            strict_mode: false,
+            read_function: ReadFunction::default(),
+            write_verification: WriteVerification::default(),
+            frame_hook: None,
+            request_gap: None,
+            #[cfg(feature = "embassy")]
+            gap_pending: false,
+        }
+    }
+
+    /// Access the underlying interface, e.g. to queue mock responses.
+    #[cfg(test)]
+    pub(crate) fn interface_mut(&mut self) -> &mut S {
+        &mut self.interface
+    }
+
+    /// Forget everything cached about the device, so it is read again when next needed.
+    ///
+    /// Scaling factors set with [`Self::set_scaling_factors`] are kept.
+    pub fn invalidate_cache(&mut self) {
+        if !self.scaling_is_manual {
+            self.scaling = None;
+        }
+        self.last_output_time_secs = None;
+        self.register_cache.invalidate();
+        self.stale = false;
+    }
+
+    /// Note that `register` was written with `data`, for the register cache.
+    pub(crate) fn record_write(&mut self, register: XyRegister, data: u16) {
+        self.register_cache.store(register, data);
+    }
+
+    /// Set how transactions which fail due to line noise are retried. By default they are not.
+    ///
+    /// Returns `NotSupported` for a policy with a [`backoff`](RetryPolicy::backoff), as calling it would block the
+    /// executor. With the `embassy` feature, retries can be spaced with [`Self::set_inter_frame_gap`] instead.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) -> Result<(), S::Error> {
+        if retry_policy.backoff.is_some() {
+            return Err(Error::NotSupported);
+        }
+        self.retry_policy = retry_policy;
+        Ok(())
+    }
+
+    /// Wait at least `min_gap` between transactions, for firmware which drops requests sent back-to-back. `None`, the
+    /// default, sends each request straight away.
+    ///
+    /// The gap is waited on an `embassy_time` timer, so the gap's blocking [`delay`](RequestGap::delay) isn't
+    /// called. Without the `embassy` feature there is no timer to wait on, so setting a gap returns `NotSupported`.
+    pub fn set_request_gap(&mut self, gap: Option<RequestGap>) -> Result<(), S::Error> {
+        if gap.is_some() && cfg!(not(feature = "embassy")) {
+            return Err(Error::NotSupported);
+        }
+        self.request_gap = gap;
+        Ok(())
+    }
+
+    /// Set the longest wait for each read of a response. Once some of the response has been received, a read timing out
+    /// ends it. Otherwise the transaction fails with a [`TransactionFailure::Timeout`](crate::error::TransactionFailure::Timeout). `None`, the default, waits as long as the
+    /// interface does.
+    ///
+    /// Only available with the `embassy` feature.
+    #[cfg(feature = "embassy")]
+    pub fn set_response_timeout(&mut self, timeout: Option<embassy_time::Duration>) {
+        self.response_timeout = timeout;
+    }
+
+    /// Set the least silence between the end of a response and the next request. Modbus RTU requires 3.5 characters,
+    /// about 0.3ms at 115200 baud, which some USB adapters need enforcing. The default is no gap.
+    ///
+    /// Only available with the `embassy` feature.
+    #[cfg(feature = "embassy")]
+    pub fn set_inter_frame_gap(&mut self, gap: embassy_time::Duration) {
+        self.inter_frame_gap = gap;
+    }
+
+    /// Move the output voltage setting from its current value to `target_mv`, at `rate_mv_per_s`.
+    ///
+    /// The setting is changed every [`RAMP_STEP`]. Returns the last voltage setting applied, in millivolts. To cancel,
+    /// drop the future, e.g. with `select`. The setting is left where it got to, which can be read back with
+    /// [`Self::get_output_voltage_mv`].
+    ///
+    /// A `rate_mv_per_s` of 0 is treated as 1mV/s. Only available with the `embassy` feature.
+    #[cfg(feature = "embassy")]
+    pub async fn ramp_output_voltage_mv(
+        &mut self,
+        target_mv: u32,
+        rate_mv_per_s: u32,
+    ) -> Result<u32, S::Error> {
+        let step_ms = RAMP_STEP.as_millis() as u32;
+        let step_mv = (rate_mv_per_s.saturating_mul(step_ms) / 1000).max(1);
+        let mut applied_mv = self.get_output_voltage_mv().await?.0;
+        while applied_mv != target_mv {
+            embassy_time::Timer::after(RAMP_STEP).await;
+            applied_mv = if applied_mv < target_mv {
+                applied_mv.saturating_add(step_mv).min(target_mv)
+            } else {
+                applied_mv.saturating_sub(step_mv).max(target_mv)
+            };
+            self.set_output_voltage_mv(applied_mv).await?;
+        }
+        Ok(applied_mv)
+    }
+
+    /// Write the request frame and read the response frame into `last_response`.
+    pub(crate) async fn exchange(&mut self, frame: &Frame<L>) -> Result<(), S::Error> {
+        self.last_request.clone_from(&frame.bytes);
+        self.last_response.clear();
+
+        #[cfg(feature = "embassy")]
+        {
+            if let Some(gap) = self.request_gap.filter(|_| self.gap_pending) {
+                let micros = u64::try_from(gap.min_gap.as_micros()).unwrap_or(u64::MAX);
+                embassy_time::Timer::after(embassy_time::Duration::from_micros(micros)).await;
+            }
+            self.gap_pending = true;
+
+            if let Some(end) = self.last_frame_end {
+                embassy_time::Timer::at(end + self.inter_frame_gap).await;
+            }
+        }
+
+        #[cfg(feature = "trace-frames")]
+        let sent = crate::trace::sent(&frame.context, &frame.bytes);
+        if let Some(hook) = self.frame_hook {
+            hook(Direction::Tx, &frame.bytes);
+        }
+        self.interface
+            .write_all(&frame.bytes)
+            .await
+            .map_err(Error::SerialError)?;
+
+        let mut temp_buf = [0u8; 16];
+        loop {
+            #[cfg(feature = "embassy")]
+            let read = match self.response_timeout {
+                Some(timeout) => {
+                    match embassy_time::with_timeout(timeout, self.interface.read(&mut temp_buf))
+                        .await
+                    {
+                        Ok(read) => read,
+                        Err(_) if self.last_response.is_empty() => return Err(Error::Timeout),
+                        Err(_) => break,
+                    }
+                }
+                None => self.interface.read(&mut temp_buf).await,
+            };
+            #[cfg(not(feature = "embassy"))]
+            let read = self.interface.read(&mut temp_buf).await;
+
+            match read {
+                Ok(bytes_read) => {
+                    let bytes = temp_buf.get(..bytes_read).ok_or(Error::BufferError)?;
+                    if frame.receive(&mut self.last_response, bytes)? {
+                        break;
+                    }
+                }
+                Err(e) if frame::ends_response(e.kind(), self.last_response.len()) => break,
+                Err(e) => return Err(Error::SerialError(e)),
+            }
+        }
+        #[cfg(feature = "embassy")]
+        {
+            self.last_frame_end = Some(embassy_time::Instant::now());
+        }
+        #[cfg(feature = "trace-frames")]
+        crate::trace::received(&frame.context, &self.last_response, sent);
+        if let Some(hook) = self.frame_hook {
+            hook(Direction::Rx, &self.last_response);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_serial::{MockResponse, MockSerial},
+        register::ProductModel,
+    };
+    use embassy_futures::block_on;
+
+    #[test]
+    fn async_read_and_write() {
+        let mut psu: XyPsuAsync<MockSerial, 128> = XyPsuAsync::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+
+        psu.interface_mut().queue_read_response(0x01, &[1234]);
+        assert_eq!(block_on(psu.read_output_voltage_mv()).unwrap().0, 12340);
+
+        psu.interface_mut().queue_response(MockResponse::Echo);
+        block_on(psu.set_output_state(true)).unwrap();
+        assert_eq!(
+            psu.last_transaction().request.get(..6),
+            Some([0x01, 0x06, 0x00, 0x12, 0x00, 0x01].as_slice())
+        );
+
+        psu.interface_mut().queue_read_response(0x01, &[0x6500]);
+        assert!(matches!(
+            block_on(psu.get_product_model()),
+            Ok(ProductModel::XY7025)
+        ));
+    }
+
+    #[test]
+    fn async_scaling_from_model_registry() {
+        let mut psu: XyPsuAsync<MockSerial, 128> = XyPsuAsync::new(MockSerial::new(), 0x01);
+        let info = crate::specs::ModelInfo {
+            scaling: ScalingFactors::new(100, 100, 100, 100, 100).unwrap(),
+            specs: ProductModel::XY6020L.specs(),
+            name: "XY6020L-B",
+        };
+        psu.model_registry_mut().insert(0x6503, info).unwrap();
+
+        psu.interface_mut().queue_read_response(0x01, &[0x6503]);
+        assert_eq!(block_on(psu.ensure_scaling()).unwrap(), info.scaling);
+        assert_eq!(psu.limits(), info.specs.limits);
+
+        // Detected scaling factors are forgotten with the cache, manual ones aren't.
+        psu.invalidate_cache();
+        assert_eq!(psu.scaling_factors(), None);
+        psu.set_scaling_factors(info.scaling);
+        psu.invalidate_cache();
+        assert_eq!(psu.scaling_factors(), Some(info.scaling));
+    }
+
+    #[test]
+    fn async_retry_policy_and_quirks() {
+        let garbage: heapless::Vec<u8, 256> =
+            heapless::Vec::from_slice(&[0x01, 0x03, 0x02, 0x56, 0x78, 0x00, 0x00]).unwrap();
+        let mut psu: XyPsuAsync<MockSerial, 128> = XyPsuAsync::new(MockSerial::new(), 0x01);
+        psu.set_retry_policy(RetryPolicy {
+            attempts: 2,
+            backoff: None,
+        })
+        .unwrap();
+        psu.interface_mut()
+            .queue_response(MockResponse::Data(garbage));
+        psu.interface_mut().queue_read_response(0x01, &[0x5678]);
+        assert_eq!(block_on(psu.read_modbus_single(0x20_u16)).unwrap(), 0x5678);
+
+        psu.register_map_mut().set_battery_full_current(true);
+        psu.interface_mut()
+            .queue_read_response(0x01, &[ProductModel::XY6020L.raw()]);
+        psu.interface_mut().queue_read_response(0x01, &[113]);
+        assert!(block_on(psu.identify()).unwrap().battery_full_current_inert);
+        assert!(!psu.register_map().has_battery_full_current());
+    }
+
+    #[test]
+    fn async_refuses_blocking_waits() {
+        let mut psu: XyPsuAsync<MockSerial, 128> = XyPsuAsync::new(MockSerial::new(), 0x01);
+        let policy = RetryPolicy {
+            attempts: 2,
+            backoff: Some(|_| {}),
+        };
+        assert!(matches!(
+            psu.set_retry_policy(policy),
+            Err(Error::NotSupported)
+        ));
+        assert_eq!(psu.retry_policy().attempts, 1);
+
+        let gap = RequestGap {
+            min_gap: core::time::Duration::from_millis(20),
+            delay: |_| panic!("blocking delay called"),
+        };
+        match cfg!(feature = "embassy") {
+            true => assert!(psu.set_request_gap(Some(gap)).is_ok()),
+            false => assert!(matches!(
+                psu.set_request_gap(Some(gap)),
+                Err(Error::NotSupported)
+            )),
+        }
+    }
+
+    #[cfg(feature = "embassy")]
+    #[test]
+    fn async_request_gap_waits_on_timer() {
+        let mut psu: XyPsuAsync<MockSerial, 128> = XyPsuAsync::new(MockSerial::new(), 0x01);
+        psu.set_request_gap(Some(RequestGap {
+            min_gap: core::time::Duration::from_millis(20),
+            delay: |_| panic!("blocking delay called"),
+        }))
+        .unwrap();
+        psu.interface_mut().queue_read_response(0x01, &[1]);
+        psu.interface_mut().queue_read_response(0x01, &[2]);
+
+        let start = embassy_time::Instant::now();
+        assert_eq!(block_on(psu.read_modbus_single(0x20_u16)).unwrap(), 1);
+        assert_eq!(block_on(psu.read_modbus_single(0x20_u16)).unwrap(), 2);
+        assert!(start.elapsed() >= embassy_time::Duration::from_millis(20));
+    }
+
+    #[cfg(feature = "embassy")]
+    #[test]
+    fn async_ramp_with_inter_frame_gap() {
+        let mut psu: XyPsuAsync<MockSerial, 128> = XyPsuAsync::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        psu.set_inter_frame_gap(embassy_time::Duration::from_millis(5));
+        psu.interface_mut().queue_read_response(0x01, &[500]);
+        for _ in 0..3 {
+            psu.interface_mut().queue_response(MockResponse::Echo);
+        }
+
+        let start = embassy_time::Instant::now();
+        assert_eq!(
+            block_on(psu.ramp_output_voltage_mv(5500, 2000)).unwrap(),
+            5500
+        );
+        assert!(start.elapsed() >= RAMP_STEP * 3);
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([0x00, 0x00, 0x02, 0x26].as_slice())
+        );
+    }
+}
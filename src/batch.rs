@@ -0,0 +1,176 @@
+//! Queue writes to several registers, then send them in as few transactions as possible, e.g. to apply a whole
+//! settings panel at once.
+//!
+//! Registers at neighbouring addresses are written together with one bulk write, and the rest one at a time.
+//!
+//! ```ignore
+//! use sinilink_xy_psu::typed::{BLed, Buzzer, ISet, VSet};
+//!
+//! let mut batch = psu.batch();
+//! batch.set::<VSet>(12_000)?;
+//! batch.set::<ISet>(1_500)?;
+//! batch.set::<BLed>(BacklightBrightness::Level3)?;
+//! batch.set::<Buzzer>(State::Off)?;
+//! // VSet and ISet in one transaction, BLed and Buzzer in one each.
+//! assert_eq!(batch.flush()?, 3);
+//! ```
+
+use crate::{
+    error::{Error, Result},
+    psu::XyPsu,
+    register::XyRegister,
+    transport::Transport,
+    typed::Writable,
+};
+
+/// Default number of registers a [`WriteBatch`] can hold.
+pub const DEFAULT_BATCH_SIZE: usize = 16;
+
+/// Writes queued for an [`XyPsu`], see [`XyPsu::batch`]. Nothing is sent until [`Self::flush`], and dropping the batch
+/// without flushing discards the writes.
+pub struct WriteBatch<'a, S: Transport, const L: usize, const N: usize = DEFAULT_BATCH_SIZE> {
+    psu: &'a mut XyPsu<S, L>,
+    pending: heapless::Vec<(XyRegister, u16), N>,
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Start queuing writes, to send them together with [`WriteBatch::flush`].
+    pub fn batch(&mut self) -> WriteBatch<'_, S, L> {
+        self.batch_with_capacity()
+    }
+
+    /// Start queuing up to `N` writes, see [`Self::batch`].
+    pub fn batch_with_capacity<const N: usize>(&mut self) -> WriteBatch<'_, S, L, N> {
+        WriteBatch {
+            psu: self,
+            pending: heapless::Vec::new(),
+        }
+    }
+}
+
+impl<S: Transport, const L: usize, const N: usize> WriteBatch<'_, S, L, N> {
+    /// Queue a write of a typed register, e.g. `batch.set::<typed::VSet>(12_000)`. Replaces any write already queued
+    /// for the register.
+    ///
+    /// Registers in millivolts, milliamps or milliwatts require known scaling factors for the PSU model, which may be
+    /// read from the PSU now, and return `InvalidRange` for values above its [`limits`](XyPsu::limits). Returns
+    /// `BufferError` if the batch is full.
+    pub fn set<R: Writable>(&mut self, value: R::Value) -> Result<(), S::Error> {
+        let scaling = if R::UNIT.is_scaled() {
+            Some(self.psu.ensure_scaling()?)
+        } else {
+            None
+        };
+        let limits = self.psu.limits();
+        let raw = R::encode(value, scaling.as_ref(), limits.as_ref()).ok_or(Error::InvalidRange)?;
+        self.set_raw(R::REGISTER, raw)
+    }
+
+    /// Queue a write of a raw value, replacing any write already queued for the register.
+    ///
+    /// Returns `BufferError` if the batch is full.
+    pub fn set_raw(&mut self, register: XyRegister, raw: u16) -> Result<(), S::Error> {
+        match self.pending.iter_mut().find(|(r, _)| *r == register) {
+            Some((_, value)) => *value = raw,
+            None => self
+                .pending
+                .push((register, raw))
+                .map_err(|_| Error::BufferError)?,
+        }
+        Ok(())
+    }
+
+    /// Number of registers queued.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether nothing is queued.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Send the queued writes, in address order, with one bulk write per run of neighbouring registers. Returns the
+    /// number of transactions.
+    ///
+    /// The queue is emptied, even on error. An error stops the flush, leaving the earlier runs written and the later
+    /// ones not.
+    pub fn flush(&mut self) -> Result<usize, S::Error> {
+        let mut writes = heapless::Vec::<(u16, XyRegister, u16), N>::new();
+        for (register, value) in core::mem::take(&mut self.pending) {
+            let address = self.psu.register_address(register)?;
+            writes
+                .push((address, register, value))
+                .map_err(|_| Error::BufferError)?;
+        }
+        writes.sort_unstable_by_key(|(address, _, _)| *address);
+
+        let mut transactions = 0;
+        let mut rest = writes.as_slice();
+        while let Some(&(start, _, _)) = rest.first() {
+            let run_len = rest
+                .iter()
+                .zip(start..)
+                .take_while(|((address, _, _), expected)| address == expected)
+                .count();
+            let (run, after) = rest.split_at(run_len);
+            rest = after;
+
+            let values: heapless::Vec<u16, N> = run.iter().map(|(_, _, value)| *value).collect();
+            match values.as_slice() {
+                [value] => self.psu.write_modbus_single(start, *value)?,
+                values => self.psu.write_modbus_bulk(start, values)?,
+            }
+            transactions += 1;
+            for (_, register, value) in run {
+                self.psu.record_write(*register, *value);
+            }
+            if let Err(e) = self.psu.verify_write(start, &values) {
+                self.psu.register_cache_mut().invalidate();
+                return Err(e);
+            }
+        }
+        Ok(transactions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_serial::{MockResponse, MockSerial},
+        register::{BacklightBrightness, State},
+        scaling::ScalingFactors,
+        typed::{BLed, Buzzer, ISet, VSet},
+    };
+
+    #[test]
+    fn batch_flushed_as_runs() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        let mut response: heapless::Vec<u8, 256> =
+            heapless::Vec::from_slice(&[0x01, 0x10, 0x00, 0x00, 0x00, 0x02]).unwrap();
+        let crc = crate::mock_serial::crc16(&response);
+        response.extend_from_slice(&crc).unwrap();
+        psu.interface_mut()
+            .queue_response(MockResponse::Data(response));
+        psu.interface_mut().queue_response(MockResponse::Echo);
+        psu.interface_mut().queue_response(MockResponse::Echo);
+
+        let mut batch = psu.batch();
+        batch.set::<Buzzer>(State::On).unwrap();
+        batch.set::<ISet>(1_500).unwrap();
+        batch.set::<BLed>(BacklightBrightness::Level3).unwrap();
+        batch.set::<VSet>(5_000).unwrap();
+        batch.set::<Buzzer>(State::Off).unwrap();
+        assert_eq!(batch.len(), 4);
+        assert_eq!(batch.flush().unwrap(), 3);
+        assert!(batch.is_empty());
+
+        // The buzzer is last, with the value queued last.
+        assert_eq!(
+            psu.last_transaction().request.get(..6),
+            Some([0x01, 0x06, 0x00, 0x1C, 0x00, 0x00].as_slice())
+        );
+    }
+}
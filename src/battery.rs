@@ -0,0 +1,177 @@
+//! Charge settings for common battery chemistries, so a [`Charger`](crate::charger::Charger) can be set up from the
+//! battery's label rather than a datasheet.
+//!
+//! ```ignore
+//! // A 4S 5Ah LiFePO4 pack.
+//! let battery = Battery::new(Chemistry::LiFePo4, 4, 5_000);
+//! psu.set_protections(battery.protections())?;
+//! let mut charger = Charger::new(psu, battery.charge_profile());
+//! ```
+//!
+//! The values are conservative defaults. Check them against the battery's datasheet, especially the charge rate.
+
+use crate::charger::{ChargeProfile, FloatProfile};
+#[cfg(feature = "presets")]
+use crate::preset::ProtectionConfig;
+
+/// Charge settings for one cell, with currents as a rate of the capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellProfile {
+    /// Charge (absorption) voltage per cell, in millivolts.
+    pub charge_mv: u32,
+    /// Charge current, in thousandths of the capacity per hour (C), e.g. 500 for 0.5C.
+    pub charge_rate: u32,
+    /// Current the charge ends at, in thousandths of C.
+    pub cutoff_rate: u32,
+    /// Float voltage and recharge threshold per cell, in millivolts, for chemistries which are kept on float.
+    pub float: Option<FloatProfile>,
+}
+
+/// Li-ion and LiPo, 4.2V per cell, charged at 0.5C down to C/20. Not floated.
+pub const LI_ION: CellProfile = CellProfile {
+    charge_mv: 4200,
+    charge_rate: 500,
+    cutoff_rate: 50,
+    float: None,
+};
+
+/// LiFePO4, 3.65V per cell, charged at 0.5C down to C/20. Not floated.
+pub const LIFEPO4: CellProfile = CellProfile {
+    charge_mv: 3650,
+    charge_rate: 500,
+    cutoff_rate: 50,
+    float: None,
+};
+
+/// Flooded and AGM lead-acid, bulk at 0.2C and absorption at 2.4V per cell down to C/50, then floated at 2.25V per
+/// cell. Charges again if a cell sags below 2.13V.
+pub const LEAD_ACID: CellProfile = CellProfile {
+    charge_mv: 2400,
+    charge_rate: 200,
+    cutoff_rate: 20,
+    float: Some(FloatProfile {
+        voltage_mv: 2250,
+        recharge_mv: 2130,
+    }),
+};
+
+/// Battery chemistry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Chemistry {
+    /// Li-ion and LiPo, see [`LI_ION`].
+    LiIon,
+    /// LiFePO4, see [`LIFEPO4`].
+    LiFePo4,
+    /// Flooded and AGM lead-acid, see [`LEAD_ACID`].
+    LeadAcid,
+}
+
+impl Chemistry {
+    /// Return the charge settings for one cell.
+    pub const fn cell(&self) -> CellProfile {
+        match self {
+            Chemistry::LiIon => LI_ION,
+            Chemistry::LiFePo4 => LIFEPO4,
+            Chemistry::LeadAcid => LEAD_ACID,
+        }
+    }
+}
+
+/// A battery of `cells` cells in series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Battery {
+    /// Chemistry of the cells, which sets the voltages and rates.
+    pub chemistry: Chemistry,
+    /// Number of cells in series, e.g. 6 for a 12V lead-acid battery.
+    pub cells: u8,
+    /// Capacity in milliamp-hours.
+    pub capacity_mah: u32,
+}
+
+impl Battery {
+    /// A battery of `cells` cells of `chemistry` in series, with a capacity of `capacity_mah`.
+    pub const fn new(chemistry: Chemistry, cells: u8, capacity_mah: u32) -> Self {
+        Self {
+            chemistry,
+            cells,
+            capacity_mah,
+        }
+    }
+
+    /// Return the profile to charge the battery with.
+    pub fn charge_profile(&self) -> ChargeProfile {
+        let cell = self.chemistry.cell();
+        let profile = ChargeProfile::new(
+            self.pack_mv(cell.charge_mv),
+            self.rate_ma(cell.charge_rate),
+            self.rate_ma(cell.cutoff_rate),
+        );
+        match cell.float {
+            Some(float) => profile.with_float(FloatProfile::new(
+                self.pack_mv(float.voltage_mv),
+                self.pack_mv(float.recharge_mv),
+            )),
+            None => profile,
+        }
+    }
+
+    /// Return protections to back up the charger, in case the host stops controlling the PSU: 2% over the charge
+    /// voltage, 20% over the charge current, 20% over the capacity, and 1.5 times the time a full charge should take.
+    #[cfg(feature = "presets")]
+    pub fn protections(&self) -> ProtectionConfig {
+        let profile = self.charge_profile();
+        let charge_rate = u64::from(self.chemistry.cell().charge_rate.max(1));
+        // Hours to charge at the charge rate is 1000 / rate, plus half again.
+        let over_time_secs = 3600 * 1000 * 3 / 2 / charge_rate;
+        ProtectionConfig {
+            over_voltage_mv: profile.voltage_mv.saturating_mul(102) / 100,
+            over_current_ma: profile.current_ma.saturating_mul(120) / 100,
+            over_capacity_mah: self.capacity_mah.saturating_mul(120) / 100,
+            over_time: core::time::Duration::from_secs(over_time_secs),
+            ..ProtectionConfig::default()
+        }
+    }
+
+    fn pack_mv(&self, cell_mv: u32) -> u32 {
+        cell_mv.saturating_mul(u32::from(self.cells))
+    }
+
+    fn rate_ma(&self, rate: u32) -> u32 {
+        (u64::from(self.capacity_mah) * u64::from(rate) / 1000) as u32
+    }
+}
+
+impl From<Battery> for ChargeProfile {
+    fn from(battery: Battery) -> Self {
+        battery.charge_profile()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn battery_profiles() {
+        let lead_acid = Battery::new(Chemistry::LeadAcid, 6, 7_000).charge_profile();
+        assert_eq!(
+            lead_acid,
+            ChargeProfile::new(14_400, 1_400, 140).with_float(FloatProfile::new(13_500, 12_780))
+        );
+        assert_eq!(
+            ChargeProfile::from(Battery::new(Chemistry::LiIon, 3, 2_000)),
+            ChargeProfile::new(12_600, 1_000, 100)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "presets")]
+    fn battery_protections() {
+        let protections = Battery::new(Chemistry::LiFePo4, 4, 5_000).protections();
+        assert_eq!(protections.over_voltage_mv, 14_892);
+        assert_eq!(protections.over_current_ma, 3_000);
+        assert_eq!(protections.over_capacity_mah, 6_000);
+        assert_eq!(protections.over_time.as_secs(), 3 * 3600);
+    }
+}
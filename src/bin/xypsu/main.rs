@@ -0,0 +1,318 @@
+//! Bench tool for XY PSUs on a serial port. Only built with the `cli` feature.
+//!
+//! ```text
+//! xypsu <PORT> [--unit ID] [--baud RATE] <COMMAND>
+//! ```
+//!
+//! Run without arguments for the list of commands.
+
+use std::{fs, io, process::ExitCode, time::Duration};
+
+#[cfg(feature = "tui")]
+mod tui;
+
+use serialport::SerialPort;
+use sinilink_xy_psu::{
+    dump::RegisterDump,
+    preset::{PresetGroup, ProtectionConfig, XyPreset, XyPresetBuilder},
+    psu::XyPsu,
+    register::{State, Temperature},
+    scan::{FoundUnit, UNIT_IDS, scan_bus},
+    status::format_status,
+};
+
+const USAGE: &str = "\
+Usage: xypsu <PORT> [--unit ID] [--baud RATE] <COMMAND>
+
+Commands:
+  status                  Show the output voltage, current, power, mode and protections
+  set-v <VOLTS>           Set the output voltage, e.g. 12.5
+  set-i <AMPS>            Set the current limit, e.g. 1.25
+  on                      Enable the output
+  off                     Disable the output
+  presets export [FILE]   Write all preset groups to FILE, or stdout
+  presets import <FILE>   Write the preset groups in FILE to the PSU
+  scan                    List the unit IDs of the PSUs on the bus
+  dump [START] [END]      Show the raw values of registers START to END, 0x00 to 0x80 by default
+  monitor                 Graph the output live, with hotkeys for the output and setpoints (tui feature)";
+
+/// The PSU can take a while to respond, a reasonably large time out is required.
+const TIMEOUT: Duration = Duration::from_millis(300);
+/// Short enough for a full scan of the bus to take under 15 seconds.
+const SCAN_TIMEOUT: Duration = Duration::from_millis(50);
+
+type CliResult<T = ()> = Result<T, String>;
+
+/// Serial port usable as an `embedded-io` interface.
+struct Port(Box<dyn SerialPort>);
+
+impl embedded_io::ErrorType for Port {
+    type Error = io::Error;
+}
+
+impl embedded_io::Read for Port {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        io::Read::read(&mut self.0, buf)
+    }
+}
+
+impl embedded_io::Write for Port {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        io::Write::write(&mut self.0, buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        io::Write::flush(&mut self.0)
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> CliResult {
+    let [port_name, rest @ ..] = args else {
+        return Err(USAGE.into());
+    };
+    let mut unit_id = 0x01;
+    let mut baud_rate = 115200;
+    let mut rest = rest;
+    loop {
+        match rest {
+            [flag, value, tail @ ..] if flag == "--unit" => {
+                unit_id = parse_number(value)?;
+                rest = tail;
+            }
+            [flag, value, tail @ ..] if flag == "--baud" => {
+                baud_rate = parse_number(value)?;
+                rest = tail;
+            }
+            _ => break,
+        }
+    }
+    let [command, command_args @ ..] = rest else {
+        return Err(USAGE.into());
+    };
+
+    let port = serialport::new(port_name, baud_rate)
+        .timeout(TIMEOUT)
+        .open()
+        .map_err(|e| format!("Failed to open {port_name}: {e}"))?;
+    let mut port = Port(port);
+
+    if command == "scan" {
+        return scan(&mut port);
+    }
+    let mut psu: XyPsu<_> = XyPsu::new(port, unit_id);
+    match (command.as_str(), command_args) {
+        ("status", []) => status(&mut psu),
+        ("set-v", [volts]) => psu
+            .set_output_voltage_mv(parse_milli(volts)?)
+            .map_err(|e| e.to_string()),
+        ("set-i", [amps]) => psu
+            .set_current_limit_ma(parse_milli(amps)?)
+            .map_err(|e| e.to_string()),
+        ("on", []) => psu.set_output_state(State::On).map_err(|e| e.to_string()),
+        ("off", []) => psu.set_output_state(State::Off).map_err(|e| e.to_string()),
+        ("presets", [action, file @ ..]) => match (action.as_str(), file) {
+            ("export", []) => export_presets(&mut psu, &mut io::stdout()),
+            ("export", [path]) => {
+                let mut file = fs::File::create(path).map_err(|e| e.to_string())?;
+                export_presets(&mut psu, &mut file)
+            }
+            ("import", [path]) => import_presets(&mut psu, path),
+            _ => Err(USAGE.into()),
+        },
+        #[cfg(feature = "tui")]
+        ("monitor", []) => tui::run(&mut psu),
+        ("dump", range) => {
+            let start = range.first().map(|s| parse_number(s)).transpose()?;
+            let end = range.get(1).map(|s| parse_number(s)).transpose()?;
+            dump(&mut psu, start.unwrap_or(0x00)..end.unwrap_or(0x80))
+        }
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn status(psu: &mut XyPsu<Port>) -> CliResult {
+    let status = psu.read_status().map_err(|e| e.to_string())?;
+    let mut line = String::new();
+    format_status(&status, &mut line).map_err(|e| e.to_string())?;
+    println!("{line}");
+    Ok(())
+}
+
+fn scan(port: &mut Port) -> CliResult {
+    port.0
+        .set_timeout(SCAN_TIMEOUT)
+        .map_err(|e| e.to_string())?;
+    let found: heapless::Vec<FoundUnit, 32> =
+        scan_bus(port, UNIT_IDS).map_err(|e| e.to_string())?;
+    for unit in &found {
+        match unit.model() {
+            Some(model) => println!("{}: {model}", unit.unit_id),
+            None => println!("{}: unknown model {}", unit.unit_id, unit.model_raw),
+        }
+    }
+    Ok(())
+}
+
+fn dump(psu: &mut XyPsu<Port>, range: core::ops::Range<u16>) -> CliResult {
+    let dump: heapless::Vec<_, 512> = psu.dump_registers(range).map_err(|e| e.to_string())?;
+    print!("{}", RegisterDump(&dump));
+    Ok(())
+}
+
+fn export_presets(psu: &mut XyPsu<Port>, out: &mut impl io::Write) -> CliResult {
+    for group in PresetGroup::iter() {
+        let preset = psu.read_preset(group).map_err(|e| e.to_string())?;
+        writeln!(out, "{}", format_preset(&preset)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn import_presets(psu: &mut XyPsu<Port>, path: &str) -> CliResult {
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {path}: {e}"))?;
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let preset = parse_preset(line).map_err(|e| format!("{path}:{}: {e}", number + 1))?;
+        preset.write(psu).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Format `preset` as one line of `key=value` pairs, e.g. `group=M1 voltage_mv=5000 current_ma=1000 output=off ...`.
+fn format_preset(preset: &XyPreset) -> String {
+    let protection = preset.protection();
+    let over_temperature = match protection.over_temperature {
+        Temperature::Celsius(t) => format!("{t}C"),
+        Temperature::Fahrenheit(t) => format!("{t}F"),
+    };
+    format!(
+        "group={} voltage_mv={} current_ma={} output={} uvp_mv={} ovp_mv={} ocp_ma={} opp_mw={} ohp_secs={} \
+         oah_mah={} owh_mwh={} otp={over_temperature}",
+        preset.group(),
+        preset.voltage_setting_mv(),
+        preset.current_setting_ma(),
+        preset.output_enable(),
+        protection.under_voltage_mv,
+        protection.over_voltage_mv,
+        protection.over_current_ma,
+        protection.over_power_mw,
+        protection.over_time.as_secs(),
+        protection.over_capacity_mah,
+        protection.over_energy_mwh,
+    )
+}
+
+/// Parse a line written by [`format_preset`].
+fn parse_preset(line: &str) -> CliResult<XyPreset> {
+    let mut builder = XyPresetBuilder::default();
+    let mut protection = ProtectionConfig::default();
+    for pair in line.split_whitespace() {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("Expected key=value, got {pair}"))?;
+        builder = match key {
+            "group" => {
+                let group = value
+                    .strip_prefix('M')
+                    .and_then(|n| n.parse::<u16>().ok())
+                    .and_then(|n| PresetGroup::try_from(n).ok())
+                    .ok_or_else(|| format!("Invalid preset group {value}"))?;
+                builder.for_group(group)
+            }
+            "voltage_mv" => builder.with_set_v(parse_number(value)?),
+            "current_ma" => builder.with_set_i_lim(parse_number(value)?),
+            "output" => builder.with_output(match value {
+                "on" => State::On,
+                "off" => State::Off,
+                _ => return Err(format!("Invalid output state {value}")),
+            }),
+            _ => {
+                match key {
+                    "uvp_mv" => protection.under_voltage_mv = parse_number(value)?,
+                    "ovp_mv" => protection.over_voltage_mv = parse_number(value)?,
+                    "ocp_ma" => protection.over_current_ma = parse_number(value)?,
+                    "opp_mw" => protection.over_power_mw = parse_number(value)?,
+                    "ohp_secs" => protection.over_time = Duration::from_secs(parse_number(value)?),
+                    "oah_mah" => protection.over_capacity_mah = parse_number(value)?,
+                    "owh_mwh" => protection.over_energy_mwh = parse_number(value)?,
+                    "otp" => {
+                        protection.over_temperature =
+                            match value.split_at(value.len().saturating_sub(1)) {
+                                (t, "C") => Temperature::Celsius(parse_number(t)?),
+                                (t, "F") => Temperature::Fahrenheit(parse_number(t)?),
+                                _ => return Err(format!("Invalid temperature {value}")),
+                            }
+                    }
+                    _ => return Err(format!("Unknown key {key}")),
+                }
+                builder
+            }
+        };
+    }
+    builder
+        .with_protections(protection)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// Parse a decimal or `0x` prefixed hexadecimal number.
+fn parse_number<T: TryFrom<u64>>(text: &str) -> CliResult<T> {
+    let value = match text.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    value
+        .ok()
+        .and_then(|value| T::try_from(value).ok())
+        .ok_or_else(|| format!("Invalid number {text}"))
+}
+
+/// Parse a number of volts or amps with up to three decimal places, e.g. `12.5`, into milli-units.
+fn parse_milli(text: &str) -> CliResult<u32> {
+    let invalid = || format!("Invalid value {text}");
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if fraction.len() > 3 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let whole: u32 = whole.parse().map_err(|_| invalid())?;
+    let fraction: u32 = format!("{fraction:0<3}").parse().map_err(|_| invalid())?;
+    whole
+        .checked_mul(1000)
+        .and_then(|milli| milli.checked_add(fraction))
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preset_line_round_trip() {
+        let line = "group=M3 voltage_mv=12500 current_ma=1000 output=on uvp_mv=0 ovp_mv=13000 \
+                    ocp_ma=1500 opp_mw=20000 ohp_secs=3600 oah_mah=0 owh_mwh=0 otp=70C";
+        let preset = parse_preset(line).unwrap();
+        assert_eq!(preset.group(), PresetGroup::Group3);
+        assert_eq!(
+            format_preset(&preset),
+            line.split_whitespace().collect::<Vec<_>>().join(" ")
+        );
+        assert!(parse_preset("group=M10").is_err());
+
+        assert_eq!(parse_milli("12.5"), Ok(12500));
+        assert_eq!(parse_milli("0.125"), Ok(125));
+        assert!(parse_milli("1.2345").is_err());
+        assert_eq!(parse_number::<u16>("0x80"), Ok(0x80));
+    }
+}
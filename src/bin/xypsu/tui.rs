@@ -0,0 +1,266 @@
+//! Live monitor for the `monitor` command, only built with the `tui` feature.
+//!
+//! Graphs the output voltage, current and power from [`XyPsu::poll`], with hotkeys to toggle the output and nudge the
+//! setpoints.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Paragraph, Sparkline},
+};
+use sinilink_xy_psu::{
+    psu::XyPsu,
+    register::{State, Temperature},
+    status::{DeviceStatus, Measurements},
+};
+
+use crate::{CliResult, Port};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Number of samples kept for the graphs, 4 minutes at the poll interval.
+const HISTORY_LEN: usize = 480;
+const VOLTAGE_STEP_MV: i64 = 100;
+const CURRENT_STEP_MA: i64 = 10;
+const HELP: &str =
+    " o: output on/off   up/down: voltage ±0.1V   right/left: current ±0.01A   q: quit";
+
+struct Monitor {
+    history: VecDeque<Measurements>,
+    status: Option<DeviceStatus>,
+    voltage_set_mv: Option<u32>,
+    current_limit_ma: Option<u32>,
+    /// Most recent error, shown until the next successful poll.
+    error: Option<String>,
+}
+
+/// Run the monitor until `q` is pressed.
+pub fn run(psu: &mut XyPsu<Port>) -> CliResult {
+    let mut monitor = Monitor {
+        history: VecDeque::with_capacity(HISTORY_LEN),
+        status: None,
+        voltage_set_mv: None,
+        current_limit_ma: None,
+        error: None,
+    };
+    let mut terminal = ratatui::init();
+    let result = monitor.run(&mut terminal, psu);
+    ratatui::restore();
+    result
+}
+
+impl Monitor {
+    fn run(&mut self, terminal: &mut DefaultTerminal, psu: &mut XyPsu<Port>) -> CliResult {
+        let mut next_poll = Instant::now();
+        loop {
+            if Instant::now() >= next_poll {
+                self.poll(psu);
+                next_poll = Instant::now() + POLL_INTERVAL;
+            }
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(|e| e.to_string())?;
+
+            let timeout = next_poll.saturating_duration_since(Instant::now());
+            if !event::poll(timeout).map_err(|e| e.to_string())? {
+                continue;
+            }
+            let Event::Key(key) = event::read().map_err(|e| e.to_string())? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('o') => self.toggle_output(psu),
+                KeyCode::Up => self.nudge_voltage(psu, VOLTAGE_STEP_MV),
+                KeyCode::Down => self.nudge_voltage(psu, -VOLTAGE_STEP_MV),
+                KeyCode::Right => self.nudge_current(psu, CURRENT_STEP_MA),
+                KeyCode::Left => self.nudge_current(psu, -CURRENT_STEP_MA),
+                _ => {}
+            }
+        }
+    }
+
+    fn poll(&mut self, psu: &mut XyPsu<Port>) {
+        let result = psu.poll().and_then(|polled| {
+            if self.voltage_set_mv.is_none() || self.current_limit_ma.is_none() {
+                self.voltage_set_mv = Some(psu.get_output_voltage_mv()?.0);
+                self.current_limit_ma = Some(psu.get_current_limit_ma()?.0);
+            }
+            Ok(polled)
+        });
+        match result {
+            Ok((measurements, status)) => {
+                if self.history.len() == HISTORY_LEN {
+                    self.history.pop_front();
+                }
+                self.history.push_back(measurements);
+                self.status = Some(status);
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    fn toggle_output(&mut self, psu: &mut XyPsu<Port>) {
+        let Some(status) = &mut self.status else {
+            return;
+        };
+        match psu.set_output_state(!status.output) {
+            Ok(()) => status.output = !status.output,
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    fn nudge_voltage(&mut self, psu: &mut XyPsu<Port>, step_mv: i64) {
+        let Some(voltage_mv) = self.voltage_set_mv.and_then(|v| nudge(v, step_mv)) else {
+            return;
+        };
+        match psu.set_output_voltage_mv(voltage_mv) {
+            Ok(()) => self.voltage_set_mv = Some(voltage_mv),
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    fn nudge_current(&mut self, psu: &mut XyPsu<Port>, step_ma: i64) {
+        let Some(current_ma) = self.current_limit_ma.and_then(|i| nudge(i, step_ma)) else {
+            return;
+        };
+        match psu.set_current_limit_ma(current_ma) {
+            Ok(()) => self.current_limit_ma = Some(current_ma),
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header, voltage, current, power, footer] = Layout::vertical([
+            Constraint::Length(4),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let header_block = Block::bordered().title(" xypsu monitor ");
+        frame.render_widget(Paragraph::new(self.header()).block(header_block), header);
+        self.draw_graph(
+            frame,
+            voltage,
+            "Voltage",
+            "V",
+            |m| m.voltage_mv,
+            Color::Yellow,
+        );
+        self.draw_graph(
+            frame,
+            current,
+            "Current",
+            "A",
+            |m| m.current_ma,
+            Color::Cyan,
+        );
+        self.draw_graph(frame, power, "Power", "W", |m| m.power_mw, Color::Magenta);
+        frame.render_widget(Paragraph::new(HELP), footer);
+    }
+
+    fn header(&self) -> Vec<Line<'static>> {
+        let setpoints = format!(
+            "Set {}V {}A",
+            self.voltage_set_mv.map_or("-".into(), milli),
+            self.current_limit_ma.map_or("-".into(), milli),
+        );
+        let state = match &self.status {
+            Some(status) => format!(
+                "  Output {}  {}  Protection: {}",
+                match status.output {
+                    State::On => "ON",
+                    State::Off => "OFF",
+                },
+                status.control_mode,
+                status.protection
+            ),
+            None => String::new(),
+        };
+        let details = match self.history.back() {
+            Some(m) => format!(
+                "Input {}V  {}mAh  {}mWh  Internal {}  External {}",
+                milli(m.input_voltage_mv),
+                m.capacity_mah,
+                m.energy_mwh,
+                temperature(m.temperature_internal),
+                temperature(m.temperature_external),
+            ),
+            None => String::new(),
+        };
+        let second_line = match &self.error {
+            Some(error) => Line::styled(error.clone(), Style::new().fg(Color::Red)),
+            None => Line::raw(details),
+        };
+        vec![Line::raw(setpoints + &state), second_line]
+    }
+
+    fn draw_graph(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        title: &str,
+        unit: &str,
+        value: fn(&Measurements) -> u32,
+        color: Color,
+    ) {
+        // Only the most recent samples which fit, inside the borders.
+        let width = usize::from(area.width.saturating_sub(2));
+        let data: Vec<u64> = self
+            .history
+            .iter()
+            .skip(self.history.len().saturating_sub(width))
+            .map(|m| u64::from(value(m)))
+            .collect();
+        let latest = self.history.back().map_or("-".into(), |m| milli(value(m)));
+        let block = Block::bordered().title(format!(" {title} {latest}{unit} "));
+        let sparkline = Sparkline::default()
+            .block(block)
+            .data(&data)
+            .style(Style::new().fg(color));
+        frame.render_widget(sparkline, area);
+    }
+}
+
+/// Add `step` to `value`, unless it would go out of range.
+fn nudge(value: u32, step: i64) -> Option<u32> {
+    u32::try_from(i64::from(value) + step).ok()
+}
+
+/// Format a value in thousandths with 3 decimal places, e.g. millivolts `12001` as `12.001`.
+fn milli(value: u32) -> String {
+    format!("{}.{:03}", value / 1000, value % 1000)
+}
+
+fn temperature(temperature: Temperature) -> String {
+    match temperature {
+        Temperature::Celsius(t) => format!("{t}°C"),
+        Temperature::Fahrenheit(t) => format!("{t}°F"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setpoints_nudged_within_range() {
+        assert_eq!(nudge(5000, VOLTAGE_STEP_MV), Some(5100));
+        assert_eq!(nudge(50, -VOLTAGE_STEP_MV), None);
+        assert_eq!(milli(12345), "12.345");
+    }
+}
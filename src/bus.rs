@@ -0,0 +1,153 @@
+//! Several PSUs on one RS-485 bus, e.g. a rack powered by a string of XY supplies.
+//!
+//! [`XyBus`] owns the interface, and hands out a driver for one unit at a time, so transactions can't interleave.
+//! Scaling factors are remembered per unit between handles, so each unit's model is only read once.
+//!
+//! ```ignore
+//! let mut bus: XyBus<_> = XyBus::new(port);
+//! for unit_id in [0x01, 0x02, 0x03] {
+//!     bus.unit(unit_id).set_output_voltage_mv(12000)?;
+//! }
+//! let current_ma = bus.unit(0x02).read_current_ma()?;
+//! ```
+
+use crate::{
+    psu::{CachedScaling, XyPsu},
+    scaling::ScalingRegistry,
+};
+
+/// PSUs sharing one interface. Scaling factors are remembered for up to `N` units.
+pub struct XyBus<
+    S: embedded_io::Read + embedded_io::Write,
+    const L: usize = 128,
+    const N: usize = 8,
+> {
+    interface: S,
+    /// Scaling factors and limits of each unit.
+    scaling: heapless::LinearMap<u8, CachedScaling, N>,
+    /// Given to every unit's driver.
+    scaling_registry: ScalingRegistry,
+}
+
+impl<S: embedded_io::Read + embedded_io::Write, const L: usize, const N: usize> XyBus<S, L, N> {
+    pub fn new(interface: S) -> Self {
+        Self {
+            interface,
+            scaling: heapless::LinearMap::new(),
+            scaling_registry: ScalingRegistry::new(),
+        }
+    }
+
+    /// Return the interface.
+    pub fn into_inner(self) -> S {
+        self.interface
+    }
+
+    /// Add scaling factors for models which aren't known by this crate, for every unit on the bus.
+    pub fn scaling_registry_mut(&mut self) -> &mut ScalingRegistry {
+        &mut self.scaling_registry
+    }
+
+    /// Iterate over the units whose scaling factors are known.
+    pub fn known_units(&self) -> impl Iterator<Item = u8> + '_ {
+        self.scaling.keys().copied()
+    }
+
+    /// Forget the scaling factors of a unit, e.g. after swapping it for another model.
+    pub fn forget_unit(&mut self, unit_id: u8) {
+        self.scaling.remove(&unit_id);
+    }
+
+    /// Return a driver for the unit with `unit_id`.
+    ///
+    /// Its scaling factors are kept when the handle is dropped. If `N` units are already remembered, they are
+    /// detected again each time instead.
+    pub fn unit(&mut self, unit_id: u8) -> BusUnit<'_, S, L, N> {
+        let mut psu = XyPsu::new(&mut self.interface, unit_id);
+        psu.scaling_registry_mut()
+            .clone_from(&self.scaling_registry);
+        if let Some(cached) = self.scaling.get(&unit_id) {
+            psu.restore_scaling(*cached);
+        }
+        BusUnit {
+            psu,
+            scaling: &mut self.scaling,
+        }
+    }
+}
+
+/// Driver for one unit of an [`XyBus`]. Dereferences to [`XyPsu`].
+pub struct BusUnit<'a, S: embedded_io::Read + embedded_io::Write, const L: usize, const N: usize> {
+    psu: XyPsu<&'a mut S, L>,
+    scaling: &'a mut heapless::LinearMap<u8, CachedScaling, N>,
+}
+
+impl<'a, S: embedded_io::Read + embedded_io::Write, const L: usize, const N: usize> core::ops::Deref
+    for BusUnit<'a, S, L, N>
+{
+    type Target = XyPsu<&'a mut S, L>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.psu
+    }
+}
+
+impl<S: embedded_io::Read + embedded_io::Write, const L: usize, const N: usize> core::ops::DerefMut
+    for BusUnit<'_, S, L, N>
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.psu
+    }
+}
+
+impl<S: embedded_io::Read + embedded_io::Write, const L: usize, const N: usize> Drop
+    for BusUnit<'_, S, L, N>
+{
+    fn drop(&mut self) {
+        let unit_id = self.psu.unit_id();
+        match self.psu.cached_scaling() {
+            // Ignore the map being full, the scaling factors will be detected again next time.
+            Some(cached) => _ = self.scaling.insert(unit_id, cached),
+            None => _ = self.scaling.remove(&unit_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_serial::{MockResponse, MockSerial},
+        scaling::ScalingFactors,
+    };
+
+    #[test]
+    fn bus_remembers_scaling_per_unit() {
+        let mut bus: XyBus<MockSerial, 128, 4> = XyBus::new(MockSerial::new());
+
+        // Model read once, for the first scaled call.
+        bus.unit(0x01)
+            .interface_mut()
+            .queue_read_response(0x01, &[0x6500]);
+        bus.unit(0x01)
+            .interface_mut()
+            .queue_read_response(0x01, &[1200]);
+        assert_eq!(bus.unit(0x01).read_output_voltage_mv().unwrap().0, 12000);
+        bus.unit(0x01)
+            .interface_mut()
+            .queue_read_response(0x01, &[1300]);
+        assert_eq!(bus.unit(0x01).read_output_voltage_mv().unwrap().0, 13000);
+
+        let mut unit = bus.unit(0x02);
+        unit.set_scaling_factors(ScalingFactors::new(100, 1, 100, 1, 10).unwrap());
+        unit.interface_mut().queue_response(MockResponse::Echo);
+        unit.set_output_voltage_mv(5000).unwrap();
+        drop(unit);
+
+        assert!(bus.known_units().eq([0x01, 0x02]));
+        assert_eq!(
+            bus.unit(0x02).scaling_factors(),
+            Some(ScalingFactors::new(100, 1, 100, 1, 10).unwrap())
+        );
+    }
+}
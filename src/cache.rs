@@ -0,0 +1,133 @@
+//! Cache of configuration registers which rarely change, so they aren't read again on every call.
+//!
+//! Many methods read the same settings over and over, e.g. [`XyPsu::get_protections`] reads the temperature unit to
+//! decode the over temperature protection. With the cache enabled, each of [`CACHED_REGISTERS`] is read from the device
+//! once, then answered from memory until it is written or the cache is invalidated.
+//!
+//! ```ignore
+//! psu.set_register_cache(true);
+//! let protections = psu.get_protections()?; // Reads the temperature unit.
+//! let protections = psu.get_protections()?; // Doesn't.
+//! // The temperature unit was changed at the front panel.
+//! psu.register_cache_mut().invalidate();
+//! ```
+//!
+//! The cache doesn't see settings changed at the front panel, or written with the raw `write_modbus_*` methods, so
+//! invalidate it when that may have happened. It is cleared along with everything else by [`XyPsu::invalidate_cache`],
+//! e.g. after a power cycle.
+
+#[cfg(any(doc, test))]
+use crate::psu::XyPsu;
+use crate::register::XyRegister;
+
+/// Registers which are cached: the temperature unit, model, firmware version, slave address, baud rate and backlight.
+pub const CACHED_REGISTERS: [XyRegister; 6] = [
+    XyRegister::FC,
+    XyRegister::Model,
+    XyRegister::Version,
+    XyRegister::SlaveAdd,
+    XyRegister::BaudRateL,
+    XyRegister::BLed,
+];
+
+/// Values of [`CACHED_REGISTERS`] read from or written to the device. Disabled by default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegisterCache {
+    enabled: bool,
+    values: [Option<u16>; CACHED_REGISTERS.len()],
+}
+
+impl RegisterCache {
+    /// Whether reads are answered from the cache.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enable or disable the cache. Disabling it also forgets the cached values.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.invalidate();
+        }
+    }
+
+    /// Forget all cached values, so each is read from the device when next needed.
+    pub fn invalidate(&mut self) {
+        self.values = Default::default();
+    }
+
+    /// Forget the cached value of `register`.
+    pub fn invalidate_register(&mut self, register: XyRegister) {
+        if let Some(slot) = self.slot(register) {
+            *slot = None;
+        }
+    }
+
+    /// Return the cached value of `register`, if the cache is enabled and holds it.
+    pub fn get(&self, register: XyRegister) -> Option<u16> {
+        if !self.enabled {
+            return None;
+        }
+        CACHED_REGISTERS
+            .iter()
+            .zip(self.values)
+            .find(|(r, _)| **r == register)
+            .and_then(|(_, value)| value)
+    }
+
+    /// Remember `value` as read from or written to `register`. Ignored if the cache is disabled or the register isn't
+    /// cached.
+    pub(crate) fn store(&mut self, register: XyRegister, value: u16) {
+        if !self.enabled {
+            return;
+        }
+        if let Some(slot) = self.slot(register) {
+            *slot = Some(value);
+        }
+    }
+
+    fn slot(&mut self, register: XyRegister) -> Option<&mut Option<u16>> {
+        CACHED_REGISTERS
+            .iter()
+            .zip(self.values.iter_mut())
+            .find(|(r, _)| **r == register)
+            .map(|(_, slot)| slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_serial::{MockResponse, MockSerial},
+        register::TemperatureUnit,
+    };
+
+    #[test]
+    fn cached_registers_read_once() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::builder(MockSerial::new())
+            .with_register_cache(true)
+            .build();
+        psu.interface_mut().queue_read_response(0x01, &[0]);
+        assert_eq!(
+            psu.get_temperature_unit().unwrap(),
+            TemperatureUnit::Celsius
+        );
+        // Nothing queued, so a read would fail.
+        assert_eq!(
+            psu.get_temperature_unit().unwrap(),
+            TemperatureUnit::Celsius
+        );
+
+        psu.interface_mut().queue_response(MockResponse::Echo);
+        psu.set_temperature_unit(TemperatureUnit::Fahrenheit)
+            .unwrap();
+        assert_eq!(
+            psu.get_temperature_unit().unwrap(),
+            TemperatureUnit::Fahrenheit
+        );
+
+        psu.register_cache_mut().invalidate();
+        assert!(psu.get_temperature_unit().is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! Factory calibration of the voltage and current readback, for boards which read consistently off.
+//!
+//! Writing bad calibration values makes the PSU report, and regulate to, the wrong voltage and current, so this is
+//! only available with the `unsafe_calibration` feature.
+//!
+//! @TODO the calibration registers aren't in the documented register map, and haven't been found yet, so their
+//! addresses have to be given. [`XyPsu::dump_registers`] before and after calibrating from the front panel is a good
+//! way to find them. Please submit a Github ticket with the addresses for your model!
+//!
+//! ```ignore
+//! let addresses = CalibrationAddresses { voltage_gain: 0x..., current_gain: 0x... };
+//! let mut calibration = psu.read_calibration(&addresses)?;
+//! calibration.voltage_gain += 3;
+//! psu.write_calibration(&addresses, calibration)?;
+//! ```
+
+use crate::{
+    error::{Error, Result},
+    psu::XyPsu,
+    register::State,
+    transport::Transport,
+};
+
+/// Addresses of the calibration registers of a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CalibrationAddresses {
+    pub voltage_gain: u16,
+    pub current_gain: u16,
+}
+
+/// Raw readback gains. Their units are unknown, so adjust them in small steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Calibration {
+    pub voltage_gain: u16,
+    pub current_gain: u16,
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Read the calibration from the registers at `addresses`.
+    pub fn read_calibration(
+        &mut self,
+        addresses: &CalibrationAddresses,
+    ) -> Result<Calibration, S::Error> {
+        Ok(Calibration {
+            voltage_gain: self.read_modbus_single(addresses.voltage_gain)?,
+            current_gain: self.read_modbus_single(addresses.current_gain)?,
+        })
+    }
+
+    /// Write `calibration` to the registers at `addresses`, and read it back.
+    ///
+    /// Returns `InvalidRange` if the output is on, as the output would jump if the calibration is wrong. Returns
+    /// [`Error::VerificationFailed`] if the PSU didn't keep the values written.
+    pub fn write_calibration(
+        &mut self,
+        addresses: &CalibrationAddresses,
+        calibration: Calibration,
+    ) -> Result<(), S::Error> {
+        if self.get_output_state()? == State::On {
+            return Err(Error::InvalidRange);
+        }
+        for (address, expected) in [
+            (addresses.voltage_gain, calibration.voltage_gain),
+            (addresses.current_gain, calibration.current_gain),
+        ] {
+            self.write_modbus_single(address, expected)?;
+            let actual = self.read_modbus_single(address)?;
+            if actual != expected {
+                return Err(Error::VerificationFailed { expected, actual });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_serial::{MockResponse, MockSerial};
+
+    #[test]
+    fn calibration_written_with_output_off() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        let addresses = CalibrationAddresses {
+            voltage_gain: 0x70,
+            current_gain: 0x71,
+        };
+        let calibration = Calibration {
+            voltage_gain: 1000,
+            current_gain: 998,
+        };
+
+        psu.interface_mut().queue_read_response(0x01, &[1]);
+        assert!(matches!(
+            psu.write_calibration(&addresses, calibration),
+            Err(Error::InvalidRange)
+        ));
+
+        psu.interface_mut().queue_read_response(0x01, &[0]);
+        psu.interface_mut().queue_response(MockResponse::Echo);
+        psu.interface_mut().queue_read_response(0x01, &[1000]);
+        psu.interface_mut().queue_response(MockResponse::Echo);
+        psu.interface_mut().queue_read_response(0x01, &[998]);
+        psu.write_calibration(&addresses, calibration).unwrap();
+        assert_eq!(
+            psu.last_transaction().request.get(..4),
+            Some([0x01, 0x03, 0x00, 0x71].as_slice())
+        );
+    }
+}
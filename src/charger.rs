@@ -0,0 +1,553 @@
+//! Host-driven CC-CV battery charging, e.g. for lead-acid or lithium packs.
+//!
+//! The PSU charges at the current limit (CC) until the battery reaches the charge voltage, then holds that voltage (CV)
+//! while the current tapers off. [`Charger::poll`] follows the PSU's control mode, and ends the charge by turning the
+//! output off once the current has been below [`ChargeProfile::cutoff_ma`] for [`ChargeProfile::cutoff_readings`] polls
+//! in a row.
+//!
+//! ```ignore
+//! let mut charger = Charger::new(psu, ChargeProfile::new(14_400, 5_000, 250));
+//! charger.start()?;
+//! loop {
+//!     if let ChargePhase::Done { charged_mah } = charger.poll()? {
+//!         println!("Charged {charged_mah}mAh");
+//!         break;
+//!     }
+//!     sleep(Duration::from_secs(5));
+//! }
+//! ```
+//!
+//! With a [`FloatProfile`], the charger keeps the battery topped up after the charge instead of turning the output off:
+//! it drops to the float voltage, and goes back to charging at the charge voltage if the battery voltage sags. Use
+//! [`Charger::poll_event`] to be told about each change of phase, e.g. to log them.
+//!
+//! [`run_charge_test`] runs a whole charge in one call instead, and reports the capacity and energy delivered, e.g. to
+//! measure the capacity of a battery.
+
+use crate::{
+    error::Result,
+    psu::XyPsu,
+    register::{ControlMode, ProtectionStatus, State},
+    transport::Transport,
+};
+
+/// Charge voltage, current, and termination current.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChargeProfile {
+    /// Voltage held during the CV phase, in millivolts.
+    pub voltage_mv: u32,
+    /// Current limit during the CC phase, in milliamps.
+    pub current_ma: u32,
+    /// The charge ends when the current drops below this during the CV phase, in milliamps.
+    pub cutoff_ma: u32,
+    /// Number of polls in a row the current has to be below [`Self::cutoff_ma`] for, so one noisy reading doesn't end
+    /// the charge.
+    pub cutoff_readings: u8,
+    /// Maintenance charge after the charge ends. `None` turns the output off instead.
+    pub float: Option<FloatProfile>,
+}
+
+/// Default for [`ChargeProfile::cutoff_readings`].
+pub const CUTOFF_READINGS: u8 = 3;
+
+impl ChargeProfile {
+    /// Charge at `current_ma` up to `voltage_mv`, then hold that voltage until the current drops below `cutoff_ma`
+    /// for [`CUTOFF_READINGS`] polls in a row, and turn the output off.
+    pub fn new(voltage_mv: u32, current_ma: u32, cutoff_ma: u32) -> Self {
+        Self {
+            voltage_mv,
+            current_ma,
+            cutoff_ma,
+            cutoff_readings: CUTOFF_READINGS,
+            float: None,
+        }
+    }
+
+    /// End the charge after `readings` polls in a row below the cutoff current, rather than [`CUTOFF_READINGS`].
+    /// 0 is treated as 1.
+    pub fn with_cutoff_readings(mut self, readings: u8) -> Self {
+        self.cutoff_readings = readings;
+        self
+    }
+
+    /// Hold the battery at a float voltage after the charge, instead of turning the output off.
+    pub fn with_float(mut self, float: FloatProfile) -> Self {
+        self.float = Some(float);
+        self
+    }
+}
+
+/// Float voltage held after the charge, and when to charge again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloatProfile {
+    /// Voltage held after the charge ends, in millivolts. E.g. 13.6V for a 12V lead-acid battery.
+    pub voltage_mv: u32,
+    /// Charge again at [`ChargeProfile::voltage_mv`] if the output voltage drops below this while floating, in
+    /// millivolts. It only sags below the float voltage when the load takes more than the current limit.
+    pub recharge_mv: u32,
+}
+
+impl FloatProfile {
+    /// Float at `voltage_mv`, and charge again if the output voltage drops below `recharge_mv`.
+    pub fn new(voltage_mv: u32, recharge_mv: u32) -> Self {
+        Self {
+            voltage_mv,
+            recharge_mv,
+        }
+    }
+}
+
+/// Progress of a charge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChargePhase {
+    /// [`Charger::start`] hasn't been called.
+    Idle,
+    /// Charging at the current limit.
+    ConstantCurrent,
+    /// Holding the charge voltage while the current tapers off.
+    ConstantVoltage,
+    /// The current dropped below the cutoff, and the output has been turned off.
+    Done { charged_mah: u32 },
+    /// The current dropped below the cutoff, and the output is holding the float voltage.
+    Float { charged_mah: u32 },
+    /// The output was turned off before the charge finished, e.g. from the front panel or by a protection.
+    Interrupted {
+        charged_mah: u32,
+        protection: ProtectionStatus,
+    },
+}
+
+/// A change of phase, see [`Charger::poll_event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChargeEvent {
+    /// Phase before the poll.
+    pub from: ChargePhase,
+    /// Phase after the poll.
+    pub to: ChargePhase,
+}
+
+/// Runs a CC-CV charge on an [`XyPsu`].
+pub struct Charger<S: Transport, const L: usize = 128> {
+    psu: XyPsu<S, L>,
+    profile: ChargeProfile,
+    phase: ChargePhase,
+    /// Capacity counter when the charge started, in milliamp-hours.
+    start_mah: u32,
+    /// Polls in a row with the current below the cutoff.
+    below_cutoff: u8,
+}
+
+impl<S: Transport, const L: usize> Charger<S, L> {
+    /// Charge with `psu` following `profile`. Nothing is sent to the PSU until [`Self::start`].
+    pub fn new(psu: XyPsu<S, L>, profile: ChargeProfile) -> Self {
+        Self {
+            psu,
+            profile,
+            phase: ChargePhase::Idle,
+            start_mah: 0,
+            below_cutoff: 0,
+        }
+    }
+
+    /// Return the charge profile.
+    pub fn profile(&self) -> &ChargeProfile {
+        &self.profile
+    }
+
+    /// Return the phase as of the last call to [`Self::poll`].
+    pub fn phase(&self) -> ChargePhase {
+        self.phase
+    }
+
+    /// Access the PSU, e.g. to log measurements while charging.
+    pub fn psu_mut(&mut self) -> &mut XyPsu<S, L> {
+        &mut self.psu
+    }
+
+    /// Return the PSU. The output is left as it is.
+    pub fn into_psu(self) -> XyPsu<S, L> {
+        self.psu
+    }
+
+    /// Apply the profile's voltage and current limit, and turn the output on.
+    ///
+    /// Requires known scaling factors for the PSU model.
+    pub fn start(&mut self) -> Result<(), S::Error> {
+        self.psu.set_output_voltage_mv(self.profile.voltage_mv)?;
+        self.psu.set_current_limit_ma(self.profile.current_ma)?;
+        self.psu.set_output_state(State::On)?;
+        self.start_mah = self.psu.read_capacity_mah()?;
+        self.phase = ChargePhase::ConstantCurrent;
+        self.below_cutoff = 0;
+        Ok(())
+    }
+
+    /// Check on the charge, moving to the next phase if needed.
+    ///
+    /// Call periodically, e.g. every few seconds. Once the charge has ended, returns the same phase without talking to
+    /// the PSU. While floating, checks the battery voltage each call.
+    pub fn poll(&mut self) -> Result<ChargePhase, S::Error> {
+        if let ChargePhase::Float { .. } = self.phase {
+            return self.poll_float();
+        }
+        if !matches!(
+            self.phase,
+            ChargePhase::ConstantCurrent | ChargePhase::ConstantVoltage
+        ) {
+            return Ok(self.phase);
+        }
+
+        let status = self.psu.read_status()?;
+        if status.output == State::Off || status.protection.any() {
+            self.psu.set_output_state(State::Off)?;
+            self.phase = ChargePhase::Interrupted {
+                charged_mah: self.charged_mah()?,
+                protection: status.protection,
+            };
+            return Ok(self.phase);
+        }
+
+        if status.control_mode == ControlMode::Cv {
+            self.phase = ChargePhase::ConstantVoltage;
+        }
+        if self.phase == ChargePhase::ConstantVoltage && status.current_ma < self.profile.cutoff_ma
+        {
+            self.below_cutoff = self.below_cutoff.saturating_add(1);
+        } else {
+            self.below_cutoff = 0;
+        }
+        if self.below_cutoff >= self.profile.cutoff_readings.max(1) {
+            match self.profile.float {
+                Some(float) => self.psu.set_output_voltage_mv(float.voltage_mv)?,
+                None => self.psu.set_output_state(State::Off)?,
+            }
+            let charged_mah = self.charged_mah()?;
+            self.phase = match self.profile.float {
+                Some(_) => ChargePhase::Float { charged_mah },
+                None => ChargePhase::Done { charged_mah },
+            };
+        }
+        Ok(self.phase)
+    }
+
+    /// [`Self::poll`], returning the change of phase if there was one.
+    pub fn poll_event(&mut self) -> Result<Option<ChargeEvent>, S::Error> {
+        let from = self.phase;
+        let to = self.poll()?;
+        Ok((to != from).then_some(ChargeEvent { from, to }))
+    }
+
+    /// Hold the float voltage, going back to charging if the battery voltage sags.
+    fn poll_float(&mut self) -> Result<ChargePhase, S::Error> {
+        let status = self.psu.read_status()?;
+        if status.output == State::Off || status.protection.any() {
+            self.psu.set_output_state(State::Off)?;
+            self.phase = ChargePhase::Interrupted {
+                charged_mah: self.charged_mah()?,
+                protection: status.protection,
+            };
+        } else if let Some(float) = self.profile.float
+            && status.voltage_mv < float.recharge_mv
+        {
+            self.psu.set_output_voltage_mv(self.profile.voltage_mv)?;
+            self.phase = ChargePhase::ConstantCurrent;
+            self.below_cutoff = 0;
+        }
+        Ok(self.phase)
+    }
+
+    /// Return the capacity charged since [`Self::start`], in milliamp-hours.
+    pub fn charged_mah(&mut self) -> Result<u32, S::Error> {
+        Ok(self.psu.read_capacity_mah()?.saturating_sub(self.start_mah))
+    }
+}
+
+/// Settings of [`run_charge_test`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChargeTestConfig {
+    /// Charge voltage, in millivolts.
+    pub voltage_mv: u32,
+    /// Charge current limit, in milliamps.
+    pub current_ma: u32,
+    /// The test ends when the current drops below this in CV, in milliamps.
+    pub taper_ma: u32,
+    /// Time between checks, in milliseconds.
+    pub poll_ms: u32,
+}
+
+impl ChargeTestConfig {
+    /// Charge at `voltage_mv` and `current_ma` until the current drops below `taper_ma`, checking every 5s.
+    pub fn new(voltage_mv: u32, current_ma: u32, taper_ma: u32) -> Self {
+        Self {
+            voltage_mv,
+            current_ma,
+            taper_ma,
+            poll_ms: 5000,
+        }
+    }
+}
+
+/// Why a charge test ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChargeTestEnd {
+    /// The current tapered off below [`ChargeTestConfig::taper_ma`].
+    Tapered,
+    /// The PSU's capacity (OAH) or energy (OWH) protection tripped.
+    Limit(ProtectionStatus),
+    /// Another protection tripped.
+    Protection(ProtectionStatus),
+    /// The output was turned off, e.g. from the front panel.
+    OutputOff,
+    /// The delay callback returned `false`.
+    Cancelled,
+}
+
+/// What a charge test delivered, from the PSU's counters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChargeReport {
+    pub end: ChargeTestEnd,
+    /// Capacity delivered, in milliamp-hours.
+    pub charged_mah: u32,
+    /// Energy delivered, in milliwatt-hours.
+    pub charged_mwh: u32,
+    /// Time the output was on, in seconds.
+    pub elapsed_secs: u32,
+}
+
+/// Charge at the configured voltage and current until the current tapers off or a protection trips, then turn the
+/// output off and report what was delivered.
+///
+/// `delay` is called between checks with the time to wait in milliseconds, and returns `false` to stop early. Set the
+/// PSU's capacity or energy protection beforehand to stop at a limit. The output is turned off when the test ends,
+/// however it ends, unless communicating with the PSU fails.
+///
+/// Requires known scaling factors for the PSU model.
+pub fn run_charge_test<S: Transport, const L: usize>(
+    psu: &mut XyPsu<S, L>,
+    config: ChargeTestConfig,
+    mut delay: impl FnMut(u32) -> bool,
+) -> Result<ChargeReport, S::Error> {
+    psu.set_output_voltage_mv(config.voltage_mv)?;
+    psu.set_current_limit_ma(config.current_ma)?;
+    psu.set_output_state(State::On)?;
+    // Read the counters after turning the output on, in case the PSU resets them then.
+    let (start, _) = psu.poll()?;
+
+    let mut latest = start;
+    let end = loop {
+        if !delay(config.poll_ms) {
+            break ChargeTestEnd::Cancelled;
+        }
+        let (measurements, status) = psu.poll()?;
+        latest = measurements;
+        if status.protection.over_capacity() || status.protection.over_energy() {
+            break ChargeTestEnd::Limit(status.protection);
+        } else if status.protection.any() {
+            break ChargeTestEnd::Protection(status.protection);
+        } else if status.output == State::Off {
+            break ChargeTestEnd::OutputOff;
+        } else if status.control_mode == ControlMode::Cv
+            && measurements.current_ma < config.taper_ma
+        {
+            break ChargeTestEnd::Tapered;
+        }
+    };
+    psu.set_output_state(State::Off)?;
+
+    Ok(ChargeReport {
+        end,
+        charged_mah: latest.capacity_mah.saturating_sub(start.capacity_mah),
+        charged_mwh: latest.energy_mwh.saturating_sub(start.energy_mwh),
+        elapsed_secs: latest
+            .output_time_secs
+            .saturating_sub(start.output_time_secs),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_serial::{MockResponse, MockSerial},
+        register::XyRegister,
+        scaling::ScalingFactors,
+    };
+
+    fn queue_status(psu: &mut XyPsu<MockSerial, 128>, current_ma: u16, mode: u16) {
+        let mut block = [0u16; XyRegister::FC as usize + 1];
+        block[XyRegister::IOut as usize] = current_ma;
+        block[XyRegister::CvCc as usize] = mode;
+        block[XyRegister::OnOff as usize] = 1;
+        psu.interface_mut().queue_read_response(0x01, &block);
+    }
+
+    /// Queue a status read while floating, with the output voltage in raw 10mV units.
+    fn queue_status_at(psu: &mut XyPsu<MockSerial, 128>, voltage_raw: u16, current_ma: u16) {
+        let mut block = [0u16; XyRegister::FC as usize + 1];
+        block[XyRegister::VOut as usize] = voltage_raw;
+        block[XyRegister::IOut as usize] = current_ma;
+        block[XyRegister::OnOff as usize] = 1;
+        psu.interface_mut().queue_read_response(0x01, &block);
+    }
+
+    #[test]
+    fn charge_ends_when_current_tapers() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        for _ in 0..3 {
+            psu.interface_mut().queue_response(MockResponse::Echo);
+        }
+        psu.interface_mut().queue_read_response(0x01, &[100]);
+        psu.interface_mut().queue_read_response(0x01, &[0]);
+
+        let mut charger = Charger::new(psu, ChargeProfile::new(14_400, 5_000, 250));
+        charger.start().unwrap();
+
+        queue_status(charger.psu_mut(), 5000, 1);
+        assert_eq!(charger.poll().unwrap(), ChargePhase::ConstantCurrent);
+
+        queue_status(charger.psu_mut(), 1200, 0);
+        assert_eq!(charger.poll().unwrap(), ChargePhase::ConstantVoltage);
+
+        for _ in 1..CUTOFF_READINGS {
+            queue_status(charger.psu_mut(), 240, 0);
+            assert_eq!(charger.poll().unwrap(), ChargePhase::ConstantVoltage);
+        }
+        queue_status(charger.psu_mut(), 240, 0);
+        let psu = charger.psu_mut().interface_mut();
+        psu.queue_response(MockResponse::Echo);
+        psu.queue_read_response(0x01, &[3100]);
+        psu.queue_read_response(0x01, &[0]);
+        assert_eq!(
+            charger.poll().unwrap(),
+            ChargePhase::Done { charged_mah: 3000 }
+        );
+        // Stays done without talking to the PSU.
+        assert_eq!(
+            charger.poll().unwrap(),
+            ChargePhase::Done { charged_mah: 3000 }
+        );
+    }
+
+    #[test]
+    fn noisy_reading_does_not_end_charge() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        for _ in 0..3 {
+            psu.interface_mut().queue_response(MockResponse::Echo);
+        }
+        psu.interface_mut().queue_read_response(0x01, &[0]);
+        psu.interface_mut().queue_read_response(0x01, &[0]);
+
+        let profile = ChargeProfile::new(14_400, 5_000, 250).with_cutoff_readings(2);
+        let mut charger = Charger::new(psu, profile);
+        charger.start().unwrap();
+
+        // One low reading, then back above the cutoff, starts the count again.
+        for current_ma in [240, 300, 240] {
+            queue_status(charger.psu_mut(), current_ma, 0);
+            assert_eq!(charger.poll().unwrap(), ChargePhase::ConstantVoltage);
+        }
+
+        queue_status(charger.psu_mut(), 240, 0);
+        let psu = charger.psu_mut().interface_mut();
+        psu.queue_response(MockResponse::Echo);
+        psu.queue_read_response(0x01, &[1000]);
+        psu.queue_read_response(0x01, &[0]);
+        assert_eq!(
+            charger.poll().unwrap(),
+            ChargePhase::Done { charged_mah: 1000 }
+        );
+    }
+
+    #[test]
+    fn charge_test_reports_delivered() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        for _ in 0..3 {
+            psu.interface_mut().queue_response(MockResponse::Echo);
+        }
+        for (mah, mwh, secs, current_ma, mode) in [
+            (10, 10, 1, 0, 0),
+            (1010, 1_210, 3601, 2000, 1),
+            (2010, 2_510, 7202, 90, 0),
+        ] {
+            let mut block = [0u16; XyRegister::FC as usize + 1];
+            block[XyRegister::AhLow as usize] = mah;
+            block[XyRegister::WhLow as usize] = mwh;
+            block[XyRegister::OutH as usize] = secs / 3600;
+            block[XyRegister::OutS as usize] = secs % 3600;
+            block[XyRegister::IOut as usize] = current_ma;
+            block[XyRegister::CvCc as usize] = mode;
+            block[XyRegister::OnOff as usize] = 1;
+            psu.interface_mut().queue_read_response(0x01, &block);
+        }
+        psu.interface_mut().queue_response(MockResponse::Echo);
+
+        let report = run_charge_test(&mut psu, ChargeTestConfig::new(14_400, 2_000, 100), |_| {
+            true
+        })
+        .unwrap();
+        assert_eq!(
+            report,
+            ChargeReport {
+                end: ChargeTestEnd::Tapered,
+                charged_mah: 2000,
+                charged_mwh: 25_000,
+                elapsed_secs: 7201,
+            }
+        );
+    }
+
+    #[test]
+    fn float_after_charge_and_recharge() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        for _ in 0..3 {
+            psu.interface_mut().queue_response(MockResponse::Echo);
+        }
+        psu.interface_mut().queue_read_response(0x01, &[0]);
+        psu.interface_mut().queue_read_response(0x01, &[0]);
+
+        let profile = ChargeProfile::new(14_400, 5_000, 250)
+            .with_cutoff_readings(1)
+            .with_float(FloatProfile::new(13_600, 12_800));
+        let mut charger = Charger::new(psu, profile);
+        charger.start().unwrap();
+
+        queue_status(charger.psu_mut(), 240, 0);
+        let psu = charger.psu_mut().interface_mut();
+        psu.queue_response(MockResponse::Echo);
+        psu.queue_read_response(0x01, &[2000]);
+        psu.queue_read_response(0x01, &[0]);
+        assert_eq!(
+            charger.poll_event().unwrap(),
+            Some(ChargeEvent {
+                from: ChargePhase::ConstantCurrent,
+                to: ChargePhase::Float { charged_mah: 2000 }
+            })
+        );
+
+        // Holding the float voltage.
+        queue_status_at(charger.psu_mut(), 1360, 10);
+        assert_eq!(charger.poll_event().unwrap(), None);
+
+        // Sagged under a load, so charges again.
+        queue_status_at(charger.psu_mut(), 1250, 5000);
+        charger
+            .psu_mut()
+            .interface_mut()
+            .queue_response(MockResponse::Echo);
+        assert_eq!(
+            charger.poll_event().unwrap(),
+            Some(ChargeEvent {
+                from: ChargePhase::Float { charged_mah: 2000 },
+                to: ChargePhase::ConstantCurrent
+            })
+        );
+        assert_eq!(
+            charger.psu_mut().last_transaction().request.get(2..6),
+            Some([0x00, 0x00, 0x05, 0xA0].as_slice())
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! Tag readings with the host's time, so logs can correlate the PSU's output time with real time.
+//!
+//! The PSU only counts how long its output has been on. [`XyPsu::read_output_time_stamped`] pairs that with a
+//! timestamp from a [`Clock`], which can be anything that counts, e.g. an RTC or a monotonic timer on `no_std` systems.
+//! Closures returning a `u64` are clocks.
+//!
+//! ```ignore
+//! let start = Instant::now();
+//! let stamped = psu.read_output_time_stamped(&mut || start.elapsed().as_millis() as u64)?;
+//! println!("{}ms: on for {:?}", stamped.timestamp, stamped.output_time);
+//! ```
+
+use crate::{error::Result, psu::XyPsu, transport::Transport};
+
+/// Source of host timestamps, in whatever unit it counts in.
+pub trait Clock {
+    fn now(&mut self) -> u64;
+}
+
+impl<F: FnMut() -> u64> Clock for F {
+    fn now(&mut self) -> u64 {
+        self()
+    }
+}
+
+/// Output time reported by the PSU, and when it was read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StampedOutputTime {
+    /// Duration that the output has been enabled.
+    pub output_time: core::time::Duration,
+    /// Time from the clock when the response was received.
+    pub timestamp: u64,
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Return the duration that the output has been enabled, with the time from `clock` when it was read.
+    pub fn read_output_time_stamped(
+        &mut self,
+        clock: &mut impl Clock,
+    ) -> Result<StampedOutputTime, S::Error> {
+        let output_time = self.read_output_duration()?;
+        Ok(StampedOutputTime {
+            output_time,
+            timestamp: clock.now(),
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: embedded_io_async::Read + embedded_io_async::Write, const L: usize>
+    crate::asynch::XyPsuAsync<S, L>
+{
+    /// See [`XyPsu::read_output_time_stamped`].
+    pub async fn read_output_time_stamped(
+        &mut self,
+        clock: &mut impl Clock,
+    ) -> Result<StampedOutputTime, S::Error> {
+        let output_time = self.read_output_duration().await?;
+        Ok(StampedOutputTime {
+            output_time,
+            timestamp: clock.now(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_serial::MockSerial;
+
+    #[test]
+    fn output_time_stamped() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.interface_mut().queue_read_response(0x01, &[1]);
+        psu.interface_mut().queue_read_response(0x01, &[2]);
+        psu.interface_mut().queue_read_response(0x01, &[3]);
+
+        let stamped = psu.read_output_time_stamped(&mut || 42).unwrap();
+        assert_eq!(
+            stamped,
+            StampedOutputTime {
+                output_time: core::time::Duration::from_secs(3723),
+                timestamp: 42,
+            }
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Encoding of the Modbus RTU requests the driver sends, and checking of the responses' framing.
+//!
+//! Only the function codes the XY PSUs need are supported: reading holding or input registers, and writing one or
+//! several holding registers.
+
+use crate::error::{Error, ModbusException, Result};
+
+/// Read holding registers.
+pub(crate) const READ_HOLDING_REGISTERS: u8 = 0x03;
+/// Read input registers, which some clone firmwares use for measurements.
+pub(crate) const READ_INPUT_REGISTERS: u8 = 0x04;
+/// Write a single holding register.
+pub(crate) const WRITE_SINGLE_REGISTER: u8 = 0x06;
+/// Write multiple holding registers.
+pub(crate) const WRITE_MULTIPLE_REGISTERS: u8 = 0x10;
+
+/// Most registers in one read, as the response's byte count is a single byte.
+pub(crate) const MAX_READ_COUNT: u16 = 125;
+/// Most registers in one write, as the request's byte count is a single byte.
+pub(crate) const MAX_WRITE_COUNT: u16 = 123;
+
+/// Set on the function code of a response to report an exception.
+const EXCEPTION_FLAG: u8 = 0x80;
+
+/// Modbus RTU CRC, low byte first.
+pub(crate) fn crc16(data: &[u8]) -> [u8; 2] {
+    let mut crc: u16 = 0xFFFF;
+    for &byte in data {
+        crc ^= u16::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xA001
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc.to_le_bytes()
+}
+
+/// Append `bytes` to `frame`.
+fn push<I: embedded_io::Error, const L: usize>(
+    frame: &mut heapless::Vec<u8, L>,
+    bytes: &[u8],
+) -> Result<(), I> {
+    frame
+        .extend_from_slice(bytes)
+        .map_err(|_| Error::BufferError)
+}
+
+/// Append the CRC of `frame`.
+fn push_crc<I: embedded_io::Error, const L: usize>(
+    frame: &mut heapless::Vec<u8, L>,
+) -> Result<(), I> {
+    let crc = crc16(frame);
+    push(frame, &crc)
+}
+
+/// Encode a read of `count` registers from `start_register`, with `function` choosing holding or input registers.
+pub(crate) fn encode_read<I: embedded_io::Error, const L: usize>(
+    unit_id: u8,
+    function: u8,
+    start_register: u16,
+    count: u16,
+    frame: &mut heapless::Vec<u8, L>,
+) -> Result<(), I> {
+    if !(1..=MAX_READ_COUNT).contains(&count) {
+        return Err(Error::InvalidRange);
+    }
+    push(frame, &[unit_id, function])?;
+    push(frame, &start_register.to_be_bytes())?;
+    push(frame, &count.to_be_bytes())?;
+    push_crc(frame)
+}
+
+/// Encode a write of `value` to a single holding register.
+pub(crate) fn encode_write_single<I: embedded_io::Error, const L: usize>(
+    unit_id: u8,
+    register: u16,
+    value: u16,
+    frame: &mut heapless::Vec<u8, L>,
+) -> Result<(), I> {
+    push(frame, &[unit_id, WRITE_SINGLE_REGISTER])?;
+    push(frame, &register.to_be_bytes())?;
+    push(frame, &value.to_be_bytes())?;
+    push_crc(frame)
+}
+
+/// Encode a write of `data` to sequential holding registers from `start_register`.
+pub(crate) fn encode_write_multiple<I: embedded_io::Error, const L: usize>(
+    unit_id: u8,
+    start_register: u16,
+    data: &[u16],
+    frame: &mut heapless::Vec<u8, L>,
+) -> Result<(), I> {
+    let count = u16::try_from(data.len())?;
+    if !(1..=MAX_WRITE_COUNT).contains(&count) {
+        return Err(Error::InvalidRange);
+    }
+    push(frame, &[unit_id, WRITE_MULTIPLE_REGISTERS])?;
+    push(frame, &start_register.to_be_bytes())?;
+    push(frame, &count.to_be_bytes())?;
+    // At most 246, checked above.
+    push(frame, &[(count * 2) as u8])?;
+    for value in data {
+        push(frame, &value.to_be_bytes())?;
+    }
+    push_crc(frame)
+}
+
+/// Check the CRC, unit ID and function code of `response` to a request with `function` to `unit_id`, returning the
+/// response without its CRC.
+///
+/// Returns [`Error::ModbusError`] if the PSU responded with an exception.
+pub(crate) fn check_response<I: embedded_io::Error>(
+    unit_id: u8,
+    function: u8,
+    response: &[u8],
+) -> Result<&[u8], I> {
+    let Some((body, crc)) = response.split_last_chunk::<2>() else {
+        return Err(Error::InvalidResponse);
+    };
+    let [response_unit_id, response_function, ..] = *body else {
+        return Err(Error::InvalidResponse);
+    };
+    if crc16(body) != *crc {
+        return Err(Error::CrcMismatch);
+    }
+    if response_unit_id != unit_id {
+        return Err(Error::InvalidResponse);
+    }
+    if response_function == function | EXCEPTION_FLAG {
+        let code = body.get(2).ok_or(Error::InvalidResponse)?;
+        return Err(Error::ModbusError(ModbusException::from_code(*code)));
+    }
+    if response_function != function {
+        return Err(Error::InvalidResponse);
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_serial::MockSerialError;
+
+    type Result<T> = crate::error::Result<T, MockSerialError>;
+
+    #[test]
+    fn encode_and_check() {
+        let mut frame: heapless::Vec<u8, 32> = heapless::Vec::new();
+        encode_read::<MockSerialError, 32>(0x01, READ_HOLDING_REGISTERS, 0x0000, 2, &mut frame)
+            .unwrap();
+        assert_eq!(frame, [0x01, 0x03, 0x00, 0x00, 0x00, 0x02, 0xC4, 0x0B]);
+
+        frame.clear();
+        encode_write_multiple::<MockSerialError, 32>(0x01, 0x0050, &[0x04B0, 0x0064], &mut frame)
+            .unwrap();
+        assert_eq!(
+            frame.get(..7),
+            Some([0x01, 0x10, 0x00, 0x50, 0x00, 0x02, 0x04].as_slice())
+        );
+        assert_eq!(frame.len(), 13);
+        let result: Result<()> = encode_write_multiple(0x01, 0x0050, &[], &mut frame);
+        assert!(matches!(result, Err(Error::InvalidRange)));
+
+        let exception = [0x01, 0x83, 0x02, 0xC0, 0xF1];
+        let result: Result<&[u8]> = check_response(0x01, READ_HOLDING_REGISTERS, &exception);
+        assert!(matches!(
+            result,
+            Err(Error::ModbusError(ModbusException::IllegalDataAddress))
+        ));
+        let result: Result<&[u8]> = check_response(0x02, READ_HOLDING_REGISTERS, &exception);
+        assert!(matches!(result, Err(Error::InvalidResponse)));
+        let result: Result<&[u8]> = check_response(
+            0x01,
+            READ_HOLDING_REGISTERS,
+            &[0x01, 0x83, 0x02, 0x00, 0x00],
+        );
+        assert!(matches!(result, Err(Error::CrcMismatch)));
+    }
+}
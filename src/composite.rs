@@ -0,0 +1,209 @@
+//! Several PSUs with their outputs wired in parallel, controlled as one supply with more current.
+//!
+//! Paralleled supplies rarely share current evenly, as their output voltages differ slightly. The unit with the highest
+//! voltage takes most of the load, and may current limit while the others are barely loaded. With [`CurrentShare`]
+//! configured, [`CompositeSupply::balance`] trims each unit's voltage setting a little (software droop) until the load
+//! is shared within a tolerance.
+//!
+//! ```ignore
+//! let mut supply = CompositeSupply::new([psu_a, psu_b]);
+//! supply.set_current_share(Some(CurrentShare::default()));
+//! supply.set_output_voltage_mv(12000)?;
+//! supply.set_current_limit_ma(20000)?;
+//! supply.set_output_state(true)?;
+//! loop {
+//!     supply.balance()?;
+//!     sleep(Duration::from_millis(500));
+//! }
+//! ```
+
+use crate::{error::Result, psu::XyPsu, register::State, transport::Transport};
+
+/// Settings for current sharing between paralleled units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurrentShare {
+    /// Largest allowed difference between a unit's current and the average, in milliamps.
+    pub tolerance_ma: u32,
+    /// Voltage change made to a unit per call to [`CompositeSupply::balance`], in millivolts.
+    pub step_mv: u32,
+    /// Largest voltage trim applied to any unit, in millivolts.
+    pub max_trim_mv: u32,
+}
+
+/// Default allows 100mA of imbalance, trimming in 10mV steps up to 200mV.
+impl Default for CurrentShare {
+    fn default() -> Self {
+        Self {
+            tolerance_ma: 100,
+            step_mv: 10,
+            max_trim_mv: 200,
+        }
+    }
+}
+
+/// `N` PSUs with their outputs in parallel.
+pub struct CompositeSupply<S: Transport, const L: usize, const N: usize> {
+    units: [XyPsu<S, L>; N],
+    /// Output voltage requested for the whole supply, in millivolts.
+    voltage_mv: u32,
+    /// Voltage trim currently applied to each unit, in millivolts.
+    trims_mv: [i32; N],
+    current_share: Option<CurrentShare>,
+}
+
+impl<S: Transport, const L: usize, const N: usize> CompositeSupply<S, L, N> {
+    /// Combine `units` into one supply. Current sharing is off until configured.
+    pub fn new(units: [XyPsu<S, L>; N]) -> Self {
+        Self {
+            units,
+            voltage_mv: 0,
+            trims_mv: [0; N],
+            current_share: None,
+        }
+    }
+
+    /// Configure current sharing, or turn it off with `None`. Turning it off removes any trims on the next voltage
+    /// change.
+    pub fn set_current_share(&mut self, current_share: Option<CurrentShare>) {
+        self.current_share = current_share;
+        if current_share.is_none() {
+            self.trims_mv = [0; N];
+        }
+    }
+
+    /// Access the individual units.
+    pub fn units_mut(&mut self) -> &mut [XyPsu<S, L>; N] {
+        &mut self.units
+    }
+
+    /// Split back into the individual units.
+    pub fn into_units(self) -> [XyPsu<S, L>; N] {
+        self.units
+    }
+
+    /// Return the voltage trim currently applied to each unit, in millivolts.
+    pub fn trims_mv(&self) -> &[i32; N] {
+        &self.trims_mv
+    }
+
+    /// Set the output voltage of every unit, plus any current sharing trim. Value supplied in millivolts.
+    pub fn set_output_voltage_mv(&mut self, voltage_mv: u32) -> Result<(), S::Error> {
+        self.voltage_mv = voltage_mv;
+        for (unit, trim) in self.units.iter_mut().zip(self.trims_mv) {
+            unit.set_output_voltage_mv(voltage_mv.saturating_add_signed(trim))?;
+        }
+        Ok(())
+    }
+
+    /// Set the total current limit, split evenly between the units. Value supplied in milliamps.
+    pub fn set_current_limit_ma(&mut self, current_ma: u32) -> Result<(), S::Error> {
+        let per_unit_ma = current_ma.checked_div(N as u32).unwrap_or(0);
+        for unit in &mut self.units {
+            unit.set_current_limit_ma(per_unit_ma)?;
+        }
+        Ok(())
+    }
+
+    /// Enable/disable the output of every unit.
+    pub fn set_output_state(&mut self, state: impl Into<State>) -> Result<(), S::Error> {
+        let state = state.into();
+        for unit in &mut self.units {
+            unit.set_output_state(state)?;
+        }
+        Ok(())
+    }
+
+    /// Return the measured output current of each unit in milliamps.
+    pub fn read_currents_ma(&mut self) -> Result<[u32; N], S::Error> {
+        let mut currents = [0; N];
+        for (current, unit) in currents.iter_mut().zip(&mut self.units) {
+            *current = unit.read_current_ma()?.0;
+        }
+        Ok(currents)
+    }
+
+    /// Return the total measured output current in milliamps.
+    pub fn read_current_ma(&mut self) -> Result<u32, S::Error> {
+        Ok(self.read_currents_ma()?.iter().sum())
+    }
+
+    /// Take one current sharing step: units above the average current have their voltage trimmed down, and units below
+    /// it trimmed up, by [`CurrentShare::step_mv`].
+    ///
+    /// Call periodically, slowly enough for the units to settle between calls. Returns `true` if the current was already
+    /// shared within tolerance, or current sharing is off.
+    pub fn balance(&mut self) -> Result<bool, S::Error> {
+        let Some(share) = self.current_share else {
+            return Ok(true);
+        };
+        let currents = self.read_currents_ma()?;
+        let average_ma = currents
+            .iter()
+            .sum::<u32>()
+            .checked_div(N as u32)
+            .unwrap_or(0);
+
+        let step = share.step_mv as i32;
+        let max_trim = share.max_trim_mv as i32;
+        let mut balanced = true;
+        for ((unit, trim), current_ma) in
+            self.units.iter_mut().zip(&mut self.trims_mv).zip(currents)
+        {
+            let new_trim = if current_ma > average_ma.saturating_add(share.tolerance_ma) {
+                *trim - step
+            } else if current_ma.saturating_add(share.tolerance_ma) < average_ma {
+                *trim + step
+            } else {
+                continue;
+            }
+            .clamp(-max_trim, max_trim);
+
+            balanced = false;
+            if new_trim != *trim {
+                *trim = new_trim;
+                unit.set_output_voltage_mv(self.voltage_mv.saturating_add_signed(new_trim))?;
+            }
+        }
+        Ok(balanced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_serial::{MockResponse, MockSerial},
+        scaling::ScalingFactors,
+    };
+
+    fn unit() -> XyPsu<MockSerial, 128> {
+        let mut psu = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        psu
+    }
+
+    #[test]
+    fn balance_trims_unit_with_most_current() {
+        let mut supply = CompositeSupply::new([unit(), unit()]);
+        supply.set_current_share(Some(CurrentShare::default()));
+        for unit in supply.units_mut() {
+            unit.interface_mut().queue_response(MockResponse::Echo);
+        }
+        supply.set_output_voltage_mv(12000).unwrap();
+
+        // Unit 0 takes most of the load.
+        let [a, b] = supply.units_mut();
+        a.interface_mut().queue_read_response(0x01, &[3000]);
+        a.interface_mut().queue_response(MockResponse::Echo);
+        b.interface_mut().queue_read_response(0x01, &[1000]);
+        b.interface_mut().queue_response(MockResponse::Echo);
+        assert!(!supply.balance().unwrap());
+        assert_eq!(supply.trims_mv(), &[-10, 10]);
+
+        let [a, b] = supply.units_mut();
+        a.interface_mut().queue_read_response(0x01, &[2020]);
+        b.interface_mut().queue_read_response(0x01, &[1980]);
+        assert!(supply.balance().unwrap());
+        assert_eq!(supply.trims_mv(), &[-10, 10]);
+    }
+}
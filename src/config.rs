@@ -0,0 +1,659 @@
+//! Copy the whole configuration of one PSU to another, e.g. to provision a replacement board identically.
+//!
+//! [`XyPsu::snapshot_config`] reads every setting which persists across power cycles, as raw register values, and
+//! [`XyPsu::restore_config`] writes them back. Raw values are only meaningful to the same model, so check the models
+//! match before restoring onto a different unit.
+//!
+//! ```ignore
+//! let config = old_psu.snapshot_config(false)?;
+//! new_psu.restore_config(&config)?;
+//! ```
+//!
+//! [`XyPsu::apply_config`] instead reads the device first and only writes the settings of a [`DesiredConfig`] which
+//! differ, reporting what it changed.
+//!
+//! With the `config-files` feature, a [`BenchConfig`] holds the settings in millivolts and milliamps rather than raw
+//! values, and is loaded from and saved to TOML, so bench setups can be kept under version control.
+
+#[cfg(feature = "presets")]
+use crate::preset::{PresetGroup, XyPresetOffsets as XPO};
+use crate::{
+    error::{Error, Result},
+    psu::XyPsu,
+    register::{BaudRate, XyRegister},
+    transport::Transport,
+};
+#[cfg(feature = "presets")]
+use strum::EnumCount;
+
+/// Settings registers in a [`DeviceConfig`], in the order they are restored.
+///
+/// The key lock is last, so the rest is written before the panel is locked. The output state, active preset and
+/// protection status are left out, as restoring them could turn the output on or load a preset over the settings.
+pub const CONFIG_REGISTERS: [XyRegister; 14] = [
+    XyRegister::VSet,
+    XyRegister::ISet,
+    XyRegister::FC,
+    XyRegister::BLed,
+    XyRegister::Sleep,
+    XyRegister::TInOffset,
+    XyRegister::TExOffset,
+    XyRegister::Buzzer,
+    XyRegister::MpptSw,
+    XyRegister::MpptK,
+    XyRegister::BatFul,
+    XyRegister::CwSw,
+    XyRegister::Cw,
+    XyRegister::Lock,
+];
+
+/// Modbus settings of a PSU, which only take effect after a power cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommsConfig {
+    pub slave_address: u8,
+    pub baud_rate: BaudRate,
+}
+
+/// Raw values of every persistent setting of a PSU.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceConfig {
+    /// Values of [`CONFIG_REGISTERS`], in the same order. `None` if the firmware doesn't implement the register.
+    pub settings: [Option<u16>; CONFIG_REGISTERS.len()],
+    /// Slave address and baud rate, if included in the snapshot.
+    pub comms: Option<CommsConfig>,
+    /// Registers of each preset group, M0 to M9.
+    #[cfg(feature = "presets")]
+    pub presets: [[u16; XPO::COUNT]; 10],
+}
+
+impl DeviceConfig {
+    /// Return the raw value of a settings register, if it is in the config.
+    pub fn get(&self, register: XyRegister) -> Option<u16> {
+        CONFIG_REGISTERS
+            .iter()
+            .zip(self.settings)
+            .find(|(r, _)| **r == register)
+            .and_then(|(_, value)| value)
+    }
+
+    /// Change the raw value of a settings register. Returns `false` if it isn't one of [`CONFIG_REGISTERS`].
+    pub fn set(&mut self, register: XyRegister, value: Option<u16>) -> bool {
+        match CONFIG_REGISTERS
+            .iter()
+            .zip(self.settings.iter_mut())
+            .find(|(r, _)| **r == register)
+        {
+            Some((_, slot)) => {
+                *slot = value;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Settings a PSU should have, as raw register values. Settings which are `None` are left as they are.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DesiredConfig {
+    /// Values of [`CONFIG_REGISTERS`], in the same order.
+    pub settings: [Option<u16>; CONFIG_REGISTERS.len()],
+    /// Registers of each preset group, M0 to M9.
+    #[cfg(feature = "presets")]
+    pub presets: [Option<[u16; XPO::COUNT]>; 10],
+}
+
+impl DesiredConfig {
+    /// Want `register` to hold `value`. Returns `None` if it isn't one of [`CONFIG_REGISTERS`].
+    pub fn with(mut self, register: XyRegister, value: u16) -> Option<Self> {
+        let slot = CONFIG_REGISTERS
+            .iter()
+            .zip(self.settings.iter_mut())
+            .find(|(r, _)| **r == register)?
+            .1;
+        *slot = Some(value);
+        Some(self)
+    }
+
+    /// Want preset `group` to hold `registers`, all registers of the group.
+    #[cfg(feature = "presets")]
+    pub fn with_preset(mut self, group: PresetGroup, registers: [u16; XPO::COUNT]) -> Self {
+        if let Some(slot) = self.presets.get_mut(group as usize) {
+            *slot = Some(registers);
+        }
+        self
+    }
+}
+
+/// Want everything in a snapshot. The comms settings aren't part of a desired config, see [`XyPsu::restore_config`].
+impl From<&DeviceConfig> for DesiredConfig {
+    fn from(config: &DeviceConfig) -> Self {
+        Self {
+            settings: config.settings,
+            #[cfg(feature = "presets")]
+            presets: config.presets.map(Some),
+        }
+    }
+}
+
+/// A settings register written by [`XyPsu::apply_config`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterChange {
+    pub register: XyRegister,
+    /// Raw value before the change.
+    pub from: u16,
+    /// Raw value written.
+    pub to: u16,
+}
+
+/// What [`XyPsu::apply_config`] wrote.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AppliedChanges {
+    /// Settings registers which changed, in the order they were written.
+    pub settings: heapless::Vec<RegisterChange, { CONFIG_REGISTERS.len() }>,
+    /// Preset groups which were rewritten.
+    #[cfg(feature = "presets")]
+    pub presets: heapless::Vec<PresetGroup, 10>,
+}
+
+impl AppliedChanges {
+    /// Whether the device already matched, and nothing was written.
+    pub fn is_empty(&self) -> bool {
+        #[cfg(feature = "presets")]
+        if !self.presets.is_empty() {
+            return false;
+        }
+        self.settings.is_empty()
+    }
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Read every persistent setting: the settings in [`CONFIG_REGISTERS`] and, with the `presets` feature, all
+    /// preset groups. The slave address and baud rate are only read if `include_comms` is set, since a replacement
+    /// board usually sits on a different bus or address.
+    ///
+    /// Registers the firmware doesn't implement, see [`Quirks`](crate::quirks::Quirks), are left out.
+    pub fn snapshot_config(&mut self, include_comms: bool) -> Result<DeviceConfig, S::Error> {
+        let mut settings = [None; CONFIG_REGISTERS.len()];
+        for (value, register) in settings.iter_mut().zip(CONFIG_REGISTERS) {
+            if register == XyRegister::BatFul && !self.register_map().has_battery_full_current() {
+                continue;
+            }
+            *value = match self.read_register(register) {
+                Ok(value) => Some(value),
+                Err(Error::NotSupported) => None,
+                Err(e) => return Err(e),
+            };
+        }
+
+        let comms = match include_comms {
+            true => Some(CommsConfig {
+                slave_address: self.get_slave_address()?,
+                baud_rate: self.get_baudrate()?,
+            }),
+            false => None,
+        };
+
+        #[cfg(feature = "presets")]
+        let presets = {
+            let mut presets = [[0; XPO::COUNT]; 10];
+            for (registers, group) in presets.iter_mut().zip(PresetGroup::iter()) {
+                let values =
+                    self.read_modbus_bulk(XPO::VSet.address_in_group(group), XPO::COUNT as u16)?;
+                *registers = values
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| Error::InvalidResponse)?;
+            }
+            presets
+        };
+
+        Ok(DeviceConfig {
+            settings,
+            comms,
+            #[cfg(feature = "presets")]
+            presets,
+        })
+    }
+
+    /// Write a configuration read by [`Self::snapshot_config`]: presets first, then the settings, then the slave
+    /// address and baud rate if included. The output state is left as it is.
+    ///
+    /// New comms settings only take effect after a power cycle, see [`Self::pending_baud_rate`].
+    pub fn restore_config(&mut self, config: &DeviceConfig) -> Result<(), S::Error> {
+        #[cfg(feature = "presets")]
+        for (registers, group) in config.presets.iter().zip(PresetGroup::iter()) {
+            let start = XPO::VSet.address_in_group(group);
+            self.write_modbus_bulk(start, registers)?;
+            self.verify_write(start, registers)?;
+        }
+
+        for (value, register) in config.settings.iter().zip(CONFIG_REGISTERS) {
+            if let Some(value) = value {
+                self.write_register(register, *value)?;
+            }
+        }
+
+        if let Some(comms) = config.comms {
+            self.set_slave_address(comms.slave_address)?;
+            self.set_baudrate(comms.baud_rate)?;
+        }
+        Ok(())
+    }
+
+    /// Bring the device to `config`, reading each setting first and only writing the ones which differ. Saves bus
+    /// traffic and wear on the PSU's flash compared with [`Self::restore_config`].
+    ///
+    /// Settings are written in the order of [`CONFIG_REGISTERS`], after any preset groups. A preset group which differs
+    /// is rewritten whole, in one transaction. Returns what was written, which is empty if the device already matched.
+    pub fn apply_config(&mut self, config: &DesiredConfig) -> Result<AppliedChanges, S::Error> {
+        let mut changes = AppliedChanges::default();
+
+        #[cfg(feature = "presets")]
+        for (wanted, group) in config.presets.iter().zip(PresetGroup::iter()) {
+            let Some(wanted) = wanted else {
+                continue;
+            };
+            let start = XPO::VSet.address_in_group(group);
+            let current = self.read_modbus_bulk(start, XPO::COUNT as u16)?;
+            if current.as_slice() != wanted.as_slice() {
+                self.write_modbus_bulk(start, wanted)?;
+                self.verify_write(start, wanted)?;
+                changes
+                    .presets
+                    .push(group)
+                    .map_err(|_| Error::BufferError)?;
+            }
+        }
+
+        for (wanted, register) in config.settings.iter().zip(CONFIG_REGISTERS) {
+            let Some(wanted) = *wanted else {
+                continue;
+            };
+            let current = self.read_register(register)?;
+            if current != wanted {
+                self.write_register(register, wanted)?;
+                changes
+                    .settings
+                    .push(RegisterChange {
+                        register,
+                        from: current,
+                        to: wanted,
+                    })
+                    .map_err(|_| Error::BufferError)?;
+            }
+        }
+        Ok(changes)
+    }
+}
+
+#[cfg(all(feature = "config-files", not(feature = "no_std")))]
+pub use config_file::*;
+
+#[cfg(all(feature = "config-files", not(feature = "no_std")))]
+mod config_file {
+    use std::{io, path::Path, string::String, vec::Vec};
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        error::Result,
+        preset::{PresetDelta, PresetGroup, ProtectionConfig},
+        psu::XyPsu,
+        register::{BacklightBrightness, State, Temperature, TemperatureUnit},
+        transport::Transport,
+    };
+
+    /// Desired settings of a PSU, loaded from a TOML file. Settings which are left out aren't changed.
+    ///
+    /// ```toml
+    /// [output]
+    /// voltage_mv = 12000
+    /// current_ma = 1500
+    ///
+    /// [protections]
+    /// over_voltage_mv = 13000
+    /// over_temperature = { Celsius = 70 }
+    ///
+    /// [ui]
+    /// backlight = 3
+    /// buzzer = false
+    /// temperature_unit = "celsius"
+    ///
+    /// [[presets]]
+    /// group = 1
+    /// voltage_mv = 5000
+    /// current_ma = 1000
+    /// ```
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct BenchConfig {
+        /// Output settings.
+        pub output: Option<OutputSettings>,
+        /// Protections of the active preset group, see [`XyPsu::set_protections`].
+        pub protections: Option<ProtectionSettings>,
+        /// Display and front panel settings.
+        pub ui: Option<UiSettings>,
+        /// Preset groups to change.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub presets: Vec<PresetSettings>,
+    }
+
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct OutputSettings {
+        pub voltage_mv: Option<u32>,
+        pub current_ma: Option<u32>,
+    }
+
+    /// Protection levels, in the units of [`ProtectionConfig`].
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct ProtectionSettings {
+        pub under_voltage_mv: Option<u32>,
+        pub over_voltage_mv: Option<u32>,
+        pub over_current_ma: Option<u32>,
+        pub over_power_mw: Option<u32>,
+        /// The device only stores whole minutes.
+        pub over_time_secs: Option<u64>,
+        pub over_capacity_mah: Option<u32>,
+        pub over_energy_mwh: Option<u32>,
+        pub over_temperature: Option<Temperature>,
+    }
+
+    impl ProtectionSettings {
+        /// Change the levels which are set in `protections`.
+        pub fn apply_to(&self, protections: &mut ProtectionConfig) {
+            fn apply<T: Copy>(value: &mut T, change: Option<T>) {
+                if let Some(change) = change {
+                    *value = change;
+                }
+            }
+            apply(&mut protections.under_voltage_mv, self.under_voltage_mv);
+            apply(&mut protections.over_voltage_mv, self.over_voltage_mv);
+            apply(&mut protections.over_current_ma, self.over_current_ma);
+            apply(&mut protections.over_power_mw, self.over_power_mw);
+            if let Some(secs) = self.over_time_secs {
+                protections.over_time = core::time::Duration::from_secs(secs);
+            }
+            apply(&mut protections.over_capacity_mah, self.over_capacity_mah);
+            apply(&mut protections.over_energy_mwh, self.over_energy_mwh);
+            apply(&mut protections.over_temperature, self.over_temperature);
+        }
+
+        /// Set the protection levels of `delta` to the levels which are set here.
+        fn fill_delta(&self, delta: &mut PresetDelta) {
+            delta.under_voltage_mv = self.under_voltage_mv;
+            delta.over_voltage_mv = self.over_voltage_mv;
+            delta.over_current_ma = self.over_current_ma;
+            delta.over_power_mw = self.over_power_mw;
+            delta.over_time = self.over_time_secs.map(core::time::Duration::from_secs);
+            delta.over_capacity_mah = self.over_capacity_mah;
+            delta.over_energy_mwh = self.over_energy_mwh;
+            delta.over_temperature = self.over_temperature;
+        }
+    }
+
+    impl From<&ProtectionConfig> for ProtectionSettings {
+        fn from(protections: &ProtectionConfig) -> Self {
+            Self {
+                under_voltage_mv: Some(protections.under_voltage_mv),
+                over_voltage_mv: Some(protections.over_voltage_mv),
+                over_current_ma: Some(protections.over_current_ma),
+                over_power_mw: Some(protections.over_power_mw),
+                over_time_secs: Some(protections.over_time.as_secs()),
+                over_capacity_mah: Some(protections.over_capacity_mah),
+                over_energy_mwh: Some(protections.over_energy_mwh),
+                over_temperature: Some(protections.over_temperature),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct UiSettings {
+        /// Backlight brightness, 0 (darkest) to 5.
+        pub backlight: Option<u8>,
+        /// Minutes the screen stays on after the last key press.
+        pub screen_timeout_min: Option<u16>,
+        pub buzzer: Option<bool>,
+        pub key_lock: Option<bool>,
+        pub temperature_unit: Option<TemperatureUnit>,
+    }
+
+    /// Settings of a preset group, with its protections in a `[presets.protections]` table. Settings which are left out
+    /// aren't changed.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct PresetSettings {
+        /// Preset group, 0 to 9.
+        pub group: u8,
+        pub voltage_mv: Option<u32>,
+        pub current_ma: Option<u32>,
+        /// Whether loading the preset turns the output on.
+        pub output_on: Option<bool>,
+        pub protections: Option<ProtectionSettings>,
+    }
+
+    impl BenchConfig {
+        /// Load a bench configuration from a TOML file.
+        pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+            Self::from_toml_str(&std::fs::read_to_string(path)?)
+        }
+
+        /// Save as a TOML file, e.g. the settings read with [`XyPsu::read_bench_config`].
+        pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+            std::fs::write(path, self.to_toml_string()?)
+        }
+
+        /// Parse a bench configuration from a TOML string.
+        pub fn from_toml_str(toml: &str) -> io::Result<Self> {
+            toml::from_str(toml).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        }
+
+        /// Serialise to TOML.
+        pub fn to_toml_string(&self) -> io::Result<String> {
+            toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        }
+    }
+
+    impl<S: Transport, const L: usize> XyPsu<S, L> {
+        /// Change the settings given in `config`. The output state is left as it is.
+        ///
+        /// The temperature unit is set first, as the temperature protections are stored in it, and the key lock last.
+        /// Stops at the first setting which fails, leaving the earlier ones applied.
+        ///
+        /// Requires known scaling factors for the PSU model.
+        pub fn apply_bench_config(&mut self, config: &BenchConfig) -> Result<(), S::Error> {
+            let ui = config.ui.unwrap_or_default();
+            if let Some(unit) = ui.temperature_unit {
+                self.set_temperature_unit(unit)?;
+            }
+
+            for settings in &config.presets {
+                let group = PresetGroup::try_from(u16::from(settings.group))?;
+                let mut delta = PresetDelta {
+                    voltage_setting_mv: settings.voltage_mv,
+                    current_setting_ma: settings.current_ma,
+                    output_enable: settings.output_on.map(State::from),
+                    ..Default::default()
+                };
+                if let Some(protections) = &settings.protections {
+                    protections.fill_delta(&mut delta);
+                }
+                let mut preset = self.read_preset(group)?;
+                preset.apply_delta(&delta)?;
+                preset.write(self)?;
+            }
+
+            if let Some(settings) = &config.protections {
+                let mut protections = self.get_protections()?;
+                settings.apply_to(&mut protections);
+                self.set_protections(protections)?;
+            }
+
+            if let Some(output) = config.output {
+                if let Some(voltage_mv) = output.voltage_mv {
+                    self.set_output_voltage_mv(voltage_mv)?;
+                }
+                if let Some(current_ma) = output.current_ma {
+                    self.set_current_limit_ma(current_ma)?;
+                }
+            }
+
+            if let Some(level) = ui.backlight {
+                self.set_backlight(BacklightBrightness::try_from(u16::from(level))?)?;
+            }
+            if let Some(minutes) = ui.screen_timeout_min {
+                self.set_screen_timeout(core::time::Duration::from_secs(u64::from(minutes) * 60))?;
+            }
+            if let Some(buzzer) = ui.buzzer {
+                self.set_buzzer_enabled(buzzer)?;
+            }
+            if let Some(locked) = ui.key_lock {
+                self.set_lock_state(locked)?;
+            }
+            Ok(())
+        }
+
+        /// Read every setting which a [`BenchConfig`] can hold, including all preset groups.
+        ///
+        /// Requires known scaling factors for the PSU model.
+        pub fn read_bench_config(&mut self) -> Result<BenchConfig, S::Error> {
+            let output = OutputSettings {
+                voltage_mv: Some(self.get_output_voltage_mv()?.0),
+                current_ma: Some(self.get_current_limit_ma()?.0),
+            };
+            let protections = ProtectionSettings::from(&self.get_protections()?);
+            let ui = UiSettings {
+                backlight: Some(self.get_backlight()? as u8),
+                screen_timeout_min: Some((self.get_screen_timeout()?.as_secs() / 60) as u16),
+                buzzer: Some(self.is_buzzer_enabled()?),
+                key_lock: Some(self.is_locked()?),
+                temperature_unit: Some(self.get_temperature_unit()?),
+            };
+            let mut presets = Vec::with_capacity(PresetGroup::ALL.len());
+            for group in PresetGroup::iter() {
+                let preset = self.read_preset(group)?;
+                presets.push(PresetSettings {
+                    group: group as u8,
+                    voltage_mv: Some(preset.voltage_setting_mv()),
+                    current_ma: Some(preset.current_setting_ma()),
+                    output_on: Some(preset.output_enable() == State::On),
+                    protections: Some(ProtectionSettings::from(preset.protection())),
+                });
+            }
+            Ok(BenchConfig {
+                output: Some(output),
+                protections: Some(protections),
+                ui: Some(ui),
+                presets,
+            })
+        }
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::{register::ProductModel, sim::SimulatedXyPsu};
+
+    #[test]
+    fn config_copied_to_replacement() {
+        let mut old = SimulatedXyPsu::for_model(ProductModel::XY6020L, 0x01).unwrap();
+        old.set_register(XyRegister::VSet as u16, 1200);
+        old.set_register(XyRegister::BLed as u16, 3);
+        old.set_register(XyRegister::Lock as u16, 1);
+        old.set_register(0x50 + 0x10 * 9, 500);
+        let mut new = SimulatedXyPsu::for_model(ProductModel::XY6020L, 0x02).unwrap();
+
+        let config = XyPsu::<_, 128>::new(&mut old, 0x01)
+            .snapshot_config(false)
+            .unwrap();
+        assert_eq!(config.get(XyRegister::VSet), Some(1200));
+        assert_eq!(config.get(XyRegister::OnOff), None);
+        XyPsu::<_, 128>::new(&mut new, 0x02)
+            .restore_config(&config)
+            .unwrap();
+
+        for register in [XyRegister::VSet, XyRegister::BLed, XyRegister::Lock] {
+            assert_eq!(new.register(register as u16), old.register(register as u16));
+        }
+        #[cfg(feature = "presets")]
+        assert_eq!(new.register(0x50 + 0x10 * 9), Some(500));
+        // The comms settings weren't copied.
+        assert_eq!(new.register(XyRegister::SlaveAdd as u16), Some(0x02));
+    }
+
+    #[test]
+    fn config_applied_only_where_different() {
+        let mut sim = SimulatedXyPsu::for_model(ProductModel::XY6020L, 0x01).unwrap();
+        sim.set_register(XyRegister::VSet as u16, 1200);
+        sim.set_register(XyRegister::BLed as u16, 1);
+        let mut psu: XyPsu<_, 128> = XyPsu::new(&mut sim, 0x01);
+
+        let desired = DesiredConfig::default()
+            .with(XyRegister::VSet, 1200)
+            .and_then(|config| config.with(XyRegister::BLed, 4))
+            .unwrap();
+        let changes = psu.apply_config(&desired).unwrap();
+        assert_eq!(
+            changes.settings.as_slice(),
+            [RegisterChange {
+                register: XyRegister::BLed,
+                from: 1,
+                to: 4
+            }]
+        );
+        assert!(psu.apply_config(&desired).unwrap().is_empty());
+        assert!(
+            DesiredConfig::default()
+                .with(XyRegister::OnOff, 1)
+                .is_none()
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "config-files", not(feature = "no_std")))]
+    fn bench_config_round_trip() {
+        use crate::register::Temperature;
+
+        let config = BenchConfig::from_toml_str(
+            r#"
+            [output]
+            voltage_mv = 12000
+
+            [ui]
+            backlight = 3
+            buzzer = false
+
+            [[presets]]
+            group = 1
+            voltage_mv = 5000
+            current_ma = 1000
+
+            [presets.protections]
+            over_temperature = { Celsius = 70 }
+            "#,
+        )
+        .unwrap();
+        assert!(BenchConfig::from_toml_str("[output]\nvoltage = 5").is_err());
+
+        let mut sim = SimulatedXyPsu::for_model(ProductModel::XY6020L, 0x01).unwrap();
+        let mut psu: XyPsu<_, 128> = XyPsu::new(&mut sim, 0x01);
+        psu.apply_bench_config(&config).unwrap();
+
+        let read = psu.read_bench_config().unwrap();
+        assert_eq!(read.output.unwrap().voltage_mv, Some(12_000));
+        assert_eq!(read.ui.unwrap().backlight, Some(3));
+        let preset = read.presets[1];
+        assert_eq!(preset.voltage_mv, Some(5_000));
+        assert_eq!(
+            preset.protections.unwrap().over_temperature,
+            Some(Temperature::Celsius(70))
+        );
+        assert_eq!(
+            BenchConfig::from_toml_str(&read.to_toml_string().unwrap()).unwrap(),
+            read
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! Host-side constant power (CW) regulation, for models where the `Cw` register is missing or doesn't work.
+//!
+//! [`ConstantPower::poll`] reads the output, and moves the settings towards the target power: the voltage setting while
+//! the PSU is in CV, e.g. on a resistive load, and the current limit while it is in CC, e.g. charging a battery. Each
+//! adjustment is clamped to the configured limits, and the voltage moves by at most
+//! [`ConstantPowerConfig::max_step_mv`] at a time so the loop doesn't overshoot on a noisy reading.
+//!
+//! ```ignore
+//! let start = Instant::now();
+//! let config = ConstantPowerConfig::new(25_000, 24_000, 3_000, 500);
+//! let mut cw = ConstantPower::new(psu, config, || start.elapsed().as_millis() as u64);
+//! cw.start()?;
+//! loop {
+//!     if let PowerStep::Stopped { protection } = cw.poll()? {
+//!         println!("Stopped: {protection}");
+//!         break;
+//!     }
+//!     sleep(Duration::from_millis(50));
+//! }
+//! ```
+
+use crate::{
+    error::Result,
+    psu::XyPsu,
+    register::{ControlMode, ProtectionStatus, State},
+    transport::Transport,
+};
+
+/// Target power, limits, and loop rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConstantPowerConfig {
+    /// Output power to hold, in milliwatts.
+    pub target_mw: u32,
+    /// Highest voltage setting, in millivolts.
+    pub max_voltage_mv: u32,
+    /// Highest current limit, in milliamps.
+    pub max_current_ma: u32,
+    /// Time between adjustments, in the clock's unit.
+    pub interval: u64,
+    /// Largest change of the voltage setting per adjustment, in millivolts. Default 500mV.
+    pub max_step_mv: u32,
+}
+
+impl ConstantPowerConfig {
+    pub fn new(target_mw: u32, max_voltage_mv: u32, max_current_ma: u32, interval: u64) -> Self {
+        Self {
+            target_mw,
+            max_voltage_mv,
+            max_current_ma,
+            interval,
+            max_step_mv: 500,
+        }
+    }
+}
+
+/// Result of [`ConstantPower::poll`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerStep {
+    /// The interval hasn't passed since the last adjustment.
+    Waiting,
+    /// The output was read, and the settings moved towards the target.
+    Adjusted {
+        /// Output power measured before adjusting, in milliwatts.
+        power_mw: u32,
+        voltage_set_mv: u32,
+        current_limit_ma: u32,
+    },
+    /// The output was turned off, e.g. from the front panel or by a protection. The controller stops adjusting until
+    /// [`ConstantPower::start`] is called again.
+    Stopped { protection: ProtectionStatus },
+}
+
+/// Holds the output of an [`XyPsu`] at a constant power.
+pub struct ConstantPower<S: Transport, C: FnMut() -> u64, const L: usize = 128> {
+    psu: XyPsu<S, L>,
+    config: ConstantPowerConfig,
+    clock: C,
+    last_timestamp: Option<u64>,
+    running: bool,
+}
+
+impl<S: Transport, C: FnMut() -> u64, const L: usize> ConstantPower<S, C, L> {
+    /// Regulate `psu`'s output, timing adjustments with `clock`.
+    pub fn new(psu: XyPsu<S, L>, config: ConstantPowerConfig, clock: C) -> Self {
+        Self {
+            psu,
+            config,
+            clock,
+            last_timestamp: None,
+            running: false,
+        }
+    }
+
+    /// Return the configuration.
+    pub fn config(&self) -> &ConstantPowerConfig {
+        &self.config
+    }
+
+    /// Change the target power, e.g. to step through a load profile.
+    pub fn set_target_mw(&mut self, target_mw: u32) {
+        self.config.target_mw = target_mw;
+    }
+
+    /// Access the PSU, e.g. to log measurements while regulating.
+    pub fn psu_mut(&mut self) -> &mut XyPsu<S, L> {
+        &mut self.psu
+    }
+
+    /// Return the PSU. The output is left as it is.
+    pub fn into_psu(self) -> XyPsu<S, L> {
+        self.psu
+    }
+
+    /// Start from a low voltage setting and the maximum current limit, and turn the output on. The next
+    /// [`Self::poll`] adjusts straight away.
+    ///
+    /// Requires known scaling factors for the PSU model.
+    pub fn start(&mut self) -> Result<(), S::Error> {
+        self.psu
+            .set_output_voltage_mv(self.config.max_step_mv.min(self.config.max_voltage_mv))?;
+        self.psu.set_current_limit_ma(self.config.max_current_ma)?;
+        self.psu.set_output_state(State::On)?;
+        self.last_timestamp = None;
+        self.running = true;
+        Ok(())
+    }
+
+    /// Turn the output off and stop adjusting.
+    pub fn stop(&mut self) -> Result<(), S::Error> {
+        self.running = false;
+        self.psu.set_output_state(State::Off)
+    }
+
+    /// Adjust the settings if at least the interval has passed since the last adjustment.
+    ///
+    /// Call more often than the interval.
+    pub fn poll(&mut self) -> Result<PowerStep, S::Error> {
+        let now = (self.clock)();
+        if !self.running
+            || self
+                .last_timestamp
+                .is_some_and(|last| now.saturating_sub(last) < self.config.interval)
+        {
+            return Ok(PowerStep::Waiting);
+        }
+        self.last_timestamp = Some(now);
+
+        let status = self.psu.read_status()?;
+        if status.output == State::Off || status.protection.any() {
+            self.running = false;
+            return Ok(PowerStep::Stopped {
+                protection: status.protection,
+            });
+        }
+
+        let target_mw = u64::from(self.config.target_mw);
+        let (voltage_set_mv, current_limit_ma) = match status.control_mode {
+            // The load sets the current, so scale the voltage by the square root of the power error, as for a
+            // resistance: P = V²/R.
+            ControlMode::Cv => {
+                let voltage_mv = u64::from(status.voltage_mv.max(1));
+                let wanted_mv = match status.power_mw {
+                    0 => u64::from(status.voltage_set_mv) + u64::from(self.config.max_step_mv),
+                    power_mw => (voltage_mv * voltage_mv * target_mw / u64::from(power_mw)).isqrt(),
+                };
+                let low_mv = status
+                    .voltage_set_mv
+                    .saturating_sub(self.config.max_step_mv);
+                let high_mv = status
+                    .voltage_set_mv
+                    .saturating_add(self.config.max_step_mv);
+                let voltage_set_mv = u32::try_from(wanted_mv)
+                    .unwrap_or(u32::MAX)
+                    .clamp(low_mv, high_mv)
+                    .min(self.config.max_voltage_mv);
+                (voltage_set_mv, self.config.max_current_ma)
+            }
+            // The load holds the voltage, e.g. a battery, so limit the current to the target power at that voltage.
+            ControlMode::Cc => {
+                let current_ma = target_mw * 1000 / u64::from(status.voltage_mv.max(1));
+                let current_limit_ma = u32::try_from(current_ma)
+                    .unwrap_or(u32::MAX)
+                    .min(self.config.max_current_ma);
+                (status.voltage_set_mv, current_limit_ma)
+            }
+        };
+
+        if voltage_set_mv != status.voltage_set_mv {
+            self.psu.set_output_voltage_mv(voltage_set_mv)?;
+        }
+        if current_limit_ma != status.current_limit_ma {
+            self.psu.set_current_limit_ma(current_limit_ma)?;
+        }
+        Ok(PowerStep::Adjusted {
+            power_mw: status.power_mw,
+            voltage_set_mv,
+            current_limit_ma,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_serial::{MockResponse, MockSerial},
+        register::XyRegister,
+        scaling::ScalingFactors,
+    };
+    use core::cell::Cell;
+
+    /// Queue a status read with the settings and measurements in raw values, scaled by 10mV, 1mA and 10mW.
+    fn queue_status(psu: &mut XyPsu<MockSerial, 128>, set: [u16; 2], out: [u16; 3], mode: u16) {
+        let mut block = [0u16; XyRegister::FC as usize + 1];
+        block[XyRegister::VSet as usize] = set[0];
+        block[XyRegister::ISet as usize] = set[1];
+        block[XyRegister::VOut as usize] = out[0];
+        block[XyRegister::IOut as usize] = out[1];
+        block[XyRegister::Power as usize] = out[2];
+        block[XyRegister::CvCc as usize] = mode;
+        block[XyRegister::OnOff as usize] = 1;
+        psu.interface_mut().queue_read_response(0x01, &block);
+    }
+
+    #[test]
+    fn power_regulated_in_cv_and_cc() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 10, 1, 10).unwrap());
+        for _ in 0..3 {
+            psu.interface_mut().queue_response(MockResponse::Echo);
+        }
+        let now = Cell::new(0);
+        let config = ConstantPowerConfig::new(20_000, 24_000, 3_000, 100);
+        let mut cw = ConstantPower::new(psu, config, || now.get());
+        cw.start().unwrap();
+
+        // 10V across 10Ω is 10W, so the voltage should rise to 14.142V, but only by a step.
+        queue_status(cw.psu_mut(), [1000, 3000], [1000, 1000, 1000], 0);
+        cw.psu_mut()
+            .interface_mut()
+            .queue_response(MockResponse::Echo);
+        assert_eq!(
+            cw.poll().unwrap(),
+            PowerStep::Adjusted {
+                power_mw: 10_000,
+                voltage_set_mv: 10_500,
+                current_limit_ma: 3_000
+            }
+        );
+        assert_eq!(cw.poll().unwrap(), PowerStep::Waiting);
+
+        // A 12V battery taking 3A is 36W, so the current limit drops.
+        now.set(100);
+        queue_status(cw.psu_mut(), [1500, 3000], [1200, 3000, 3600], 1);
+        cw.psu_mut()
+            .interface_mut()
+            .queue_response(MockResponse::Echo);
+        assert_eq!(
+            cw.poll().unwrap(),
+            PowerStep::Adjusted {
+                power_mw: 36_000,
+                voltage_set_mv: 15_000,
+                current_limit_ma: 1_666
+            }
+        );
+    }
+}
@@ -0,0 +1,1447 @@
+//! Methods written once for both the blocking [`XyPsu`] and, with the `async` feature, `XyPsuAsync`.
+//!
+//! `dual!` compiles each method body twice, with `io!(..)` around every call to the interface: as-is for the
+//! blocking driver, and followed by `.await` for the async one. `shared!` copies methods which don't talk to the
+//! device into both drivers unchanged.
+//!
+//! The transaction path, scaling detection, quirks, power cycle handling and the register methods are here, so the
+//! drivers can't drift apart on them. Each driver keeps its own `exchange` of frames with the interface,
+//! `invalidate_cache` and `record_write`, and the methods only one of them has.
+
+#[cfg(feature = "async")]
+use crate::asynch::XyPsuAsync;
+#[cfg(feature = "presets")]
+use crate::preset::{PresetGroup, PresetOutputPolicy, ProtectionConfig, XyPreset};
+use crate::{
+    cache::RegisterCache,
+    error::{Error, Result, TransactionFailure},
+    frame::{self, Frame},
+    limits::{ModelLimits, setpoint_to_raw},
+    psu::{
+        CachedScaling, ENERGY_COUNTERS, FAILURES_BEFORE_REINIT, FrameHook, MpptConfig,
+        ReadFunction, RequestGap, RetryPolicy, Transaction, WriteVerification, XyPsu,
+    },
+    quirks::Quirks,
+    register::{
+        BacklightBrightness, BaudRate, ControlMode, DeciTemperature, ProductModel,
+        ProtectionStatus, State, Temperature, TemperatureUnit, XyRegister,
+    },
+    register_map::RegisterMap,
+    scaling::{ScalingFactors, ScalingRegistry},
+    specs::ModelRegistry,
+    transport::Transport,
+    units::{Milliamps, Millivolts, Milliwatts},
+};
+
+/// Leaves the call as-is, for the blocking driver.
+macro_rules! io_blocking {
+    ($call:expr) => {
+        $call
+    };
+}
+
+/// Awaits the call, for the async driver.
+#[cfg(feature = "async")]
+macro_rules! io_async {
+    ($call:expr) => {
+        $call.await
+    };
+}
+
+macro_rules! dual {
+    ($(
+        $(#[$attr:meta])*
+        $vis:vis fn $name:ident($($params:tt)*) $(-> $ret:ty)? $body:block
+    )*) => {
+        impl<S: Transport, const L: usize> XyPsu<S, L> {
+            $(
+                $(#[$attr])*
+                $vis fn $name($($params)*) $(-> $ret)? {
+                    use io_blocking as io;
+                    $body
+                }
+            )*
+        }
+
+        #[cfg(feature = "async")]
+        impl<S: embedded_io_async::Read + embedded_io_async::Write, const L: usize> XyPsuAsync<S, L> {
+            $(
+                $(#[$attr])*
+                $vis async fn $name($($params)*) $(-> $ret)? {
+                    use io_async as io;
+                    $body
+                }
+            )*
+        }
+    };
+}
+
+macro_rules! shared {
+    ($($item:item)*) => {
+        impl<S: Transport, const L: usize> XyPsu<S, L> {
+            $($item)*
+        }
+
+        #[cfg(feature = "async")]
+        impl<S: embedded_io_async::Read + embedded_io_async::Write, const L: usize> XyPsuAsync<S, L> {
+            $($item)*
+        }
+    };
+}
+
+shared! {
+    /// Return the Modbus unit ID used to talk to this PSU.
+    pub fn unit_id(&self) -> u8 {
+        self.unit_id
+    }
+
+    /// Use a custom [`RegisterMap`] for models which have registers at different addresses.
+    pub fn set_register_map(&mut self, register_map: RegisterMap) {
+        self.register_map = register_map;
+    }
+
+    /// Return the [`RegisterMap`] in use.
+    pub fn register_map(&self) -> &RegisterMap {
+        &self.register_map
+    }
+
+    pub(crate) fn register_map_mut(&mut self) -> &mut RegisterMap {
+        &mut self.register_map
+    }
+
+    /// Return the [`RetryPolicy`] in use.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+
+    /// In strict mode, enabling the output while a protection is active returns [`Error::ProtectionActive`], rather
+    /// than commanding a PSU which won't comply. Clear the protection first with [`Self::clear_protections`].
+    ///
+    /// This costs a read of the protection status each time the output is enabled. Off by default.
+    pub fn set_strict_mode(&mut self, strict_mode: bool) {
+        self.strict_mode = strict_mode;
+    }
+
+    /// Whether strict mode is on, see [`Self::set_strict_mode`].
+    pub fn strict_mode(&self) -> bool {
+        self.strict_mode
+    }
+
+    /// Set which Modbus function reads registers. By default holding registers are read.
+    ///
+    /// A few clone firmwares expose the measurements as input registers, and respond to reads of them as holding
+    /// registers with an exception. [`ReadFunction::HoldingWithInputFallback`] works with both, at the cost of a
+    /// second transaction for each such read.
+    pub fn set_read_function(&mut self, read_function: ReadFunction) {
+        self.read_function = read_function;
+    }
+
+    /// Return the [`ReadFunction`] in use.
+    pub fn read_function(&self) -> ReadFunction {
+        self.read_function
+    }
+
+    /// Set whether setters read back the registers they wrote, returning [`Error::VerificationFailed`] if they differ.
+    ///
+    /// Useful as some registers, like the output voltage setting, silently clamp values. Costs a read after each
+    /// write. Writes with [`Self::write_modbus_single`] and [`Self::write_modbus_bulk`] aren't checked. Off by default.
+    pub fn set_write_verification(&mut self, write_verification: WriteVerification) {
+        self.write_verification = write_verification;
+    }
+
+    /// Return the [`WriteVerification`] in use.
+    pub fn write_verification(&self) -> WriteVerification {
+        self.write_verification
+    }
+
+    /// Call `hook` with every frame sent to and received from the PSU, e.g. to record the bus traffic of a new model to
+    /// a file. Responses are passed as received, even if they turn out to be invalid. `None` removes the hook.
+    pub fn set_frame_hook(&mut self, hook: Option<FrameHook>) {
+        self.frame_hook = hook;
+    }
+
+    /// Return the wait between transactions, see [`Self::set_request_gap`].
+    pub fn request_gap(&self) -> Option<RequestGap> {
+        self.request_gap
+    }
+
+    /// Manually set the scaling factors for this PSU.
+    ///
+    /// This allows you to override the automatic scaling factor detection for models
+    /// with unknown or incorrect scaling factors. Once set, these scaling factors will
+    /// be used for all scaled measurement and configuration functions.
+    pub fn set_scaling_factors(&mut self, scaling: ScalingFactors) {
+        self.scaling = Some(scaling);
+        self.scaling_is_manual = true;
+    }
+
+    /// Return the scaling factors currently in use, if they have been loaded or set.
+    pub fn scaling_factors(&self) -> Option<ScalingFactors> {
+        self.scaling
+    }
+
+    /// Set the maximum setpoints, for models whose limits aren't known or when scaling factors are set manually.
+    /// `None` disables the checks, leaving only the register size.
+    ///
+    /// Replaced when the limits are next detected with the scaling factors.
+    pub fn set_limits(&mut self, limits: Option<ModelLimits>) {
+        self.limits = limits;
+    }
+
+    /// Return the maximum setpoints currently enforced, if known.
+    pub fn limits(&self) -> Option<ModelLimits> {
+        self.limits
+    }
+
+    /// Return the cached scaling factors, whether they were set manually, and the limits, to be restored by
+    /// [`Self::restore_scaling`] on another instance for the same device.
+    pub(crate) fn cached_scaling(&self) -> Option<CachedScaling> {
+        self.scaling.map(|scaling| CachedScaling {
+            scaling,
+            is_manual: self.scaling_is_manual,
+            limits: self.limits,
+        })
+    }
+
+    /// Restore scaling factors returned by [`Self::cached_scaling`].
+    pub(crate) fn restore_scaling(&mut self, cached: CachedScaling) {
+        self.scaling = Some(cached.scaling);
+        self.scaling_is_manual = cached.is_manual;
+        self.limits = cached.limits;
+    }
+
+    /// Return the [`ScalingRegistry`] consulted when detecting the scaling factors.
+    pub fn scaling_registry(&self) -> &ScalingRegistry {
+        &self.scaling_registry
+    }
+
+    /// Add scaling factors for models which aren't known by this crate, e.g.
+    /// `psu.scaling_registry_mut().insert(0x6503, scaling)`.
+    ///
+    /// Takes effect the next time scaling factors are detected, so add them before the first scaled measurement.
+    pub fn scaling_registry_mut(&mut self) -> &mut ScalingRegistry {
+        &mut self.scaling_registry
+    }
+
+    /// Return the [`ModelRegistry`] of models described at runtime, see [`XyPsu::register_model`].
+    pub fn model_registry(&self) -> &ModelRegistry {
+        &self.model_registry
+    }
+
+    /// Return the [`ModelRegistry`], e.g. to remove a model.
+    pub fn model_registry_mut(&mut self) -> &mut ModelRegistry {
+        &mut self.model_registry
+    }
+
+    /// Enable or disable the cache of slow-changing registers, e.g. the temperature unit and model. Disabled by
+    /// default, see [`crate::cache`] for when it goes stale.
+    pub fn set_register_cache(&mut self, enabled: bool) {
+        self.register_cache.set_enabled(enabled);
+    }
+
+    /// Return the cache of slow-changing registers.
+    pub fn register_cache(&self) -> &RegisterCache {
+        &self.register_cache
+    }
+
+    /// Access the cache of slow-changing registers, e.g. to invalidate it after a change at the front panel.
+    pub fn register_cache_mut(&mut self) -> &mut RegisterCache {
+        &mut self.register_cache
+    }
+
+    /// Work around `quirks`, e.g. for firmware which isn't in [`QUIRK_TABLE`](crate::quirks::QUIRK_TABLE) yet.
+    ///
+    /// Scaling factors given by the quirks replace detected ones, but not ones set manually.
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        if quirks.battery_full_current_inert {
+            self.register_map_mut().set_battery_full_current(false);
+        }
+        if let Some(scaling) = quirks.scaling
+            && self.cached_scaling().is_none_or(|cached| !cached.is_manual)
+        {
+            self.restore_scaling(CachedScaling {
+                scaling,
+                is_manual: false,
+                limits: self.limits(),
+            });
+        }
+        self.quirks = quirks;
+    }
+
+    /// Return the quirks being worked around.
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// Return the baud rate which the device will switch to after its next power cycle, if it has been changed.
+    ///
+    /// After a power cycle, reconfigure the serial port to this baud rate then call [`Self::reinitialize`].
+    pub fn pending_baud_rate(&self) -> Option<BaudRate> {
+        self.pending_baud_rate
+    }
+
+    /// Keep track of failures so we can tell if the device has been power cycled.
+    fn record_outcome(&mut self, success: bool) {
+        if success {
+            if self.consecutive_failures >= FAILURES_BEFORE_REINIT {
+                self.stale = true;
+            }
+            self.consecutive_failures = 0;
+        } else {
+            self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        }
+    }
+
+    /// Returns `NotSupported` if the firmware doesn't implement `register`, see [`Quirks`].
+    pub(crate) fn check_register(&self, register: XyRegister) -> Result<(), S::Error> {
+        match self.quirks.has_register(register) {
+            true => Ok(()),
+            false => Err(Error::NotSupported),
+        }
+    }
+
+    fn input_current_limit_address(&self) -> Result<u16, S::Error> {
+        self.register_map
+            .input_current_limit_address()
+            .ok_or(Error::NotSupported)
+    }
+
+    fn check_battery_full_current(&self) -> Result<(), S::Error> {
+        match self.register_map.has_battery_full_current() {
+            true => Ok(()),
+            false => Err(Error::NotSupported),
+        }
+    }
+
+    /// Return the request and response frames of the most recent transaction, for debugging.
+    ///
+    /// The response is whatever was received, even if the transaction failed, e.g. with `InvalidResponse`. Frames longer
+    /// than the buffer size `L` are truncated.
+    pub fn last_transaction(&self) -> Transaction<'_> {
+        Transaction {
+            request: &self.last_request,
+            response: &self.last_response,
+        }
+    }
+}
+
+dual! {
+    /// Enable/disable the key lock.
+    pub fn set_lock_state(&mut self, state: impl Into<State>) -> Result<(), S::Error> {
+        io!(self.write_register(XyRegister::Lock, state.into() as u16))
+    }
+
+    /// Get the current state of the key lock.
+    pub fn get_lock_state(&mut self) -> Result<State, S::Error> {
+        let value = io!(self.read_register(XyRegister::Lock))?;
+        Ok(State::from(value != 0))
+    }
+
+    /// Return whether the key lock is on. See [`get_lock_state`](Self::get_lock_state).
+    pub fn is_locked(&mut self) -> Result<bool, S::Error> {
+        Ok(io!(self.get_lock_state())? == State::On)
+    }
+
+    /// Get the currently active control mode. (CV or CC.)
+    pub fn get_current_control_mode(&mut self) -> Result<ControlMode, S::Error> {
+        let value = io!(self.read_register(XyRegister::CvCc))?;
+        Ok(ControlMode::from(value))
+    }
+
+    /// Read whether the output is enabled or disabled.
+    pub fn get_output_state(&mut self) -> Result<State, S::Error> {
+        let value = io!(self.read_register(XyRegister::OnOff))?;
+        Ok(State::from(value != 0))
+    }
+
+    /// Return whether the output is enabled. See [`get_output_state`](Self::get_output_state).
+    pub fn is_output_enabled(&mut self) -> Result<bool, S::Error> {
+        Ok(io!(self.get_output_state())? == State::On)
+    }
+
+    /// Enable/disable the buzzer.
+    pub fn set_buzzer_enabled(&mut self, state: impl Into<State>) -> Result<(), S::Error> {
+        io!(self.write_register(XyRegister::Buzzer, state.into() as u16))
+    }
+
+    /// Get the current buzzer enable state.
+    pub fn get_buzzer_enabled(&mut self) -> Result<State, S::Error> {
+        let value = io!(self.read_register(XyRegister::Buzzer))?;
+        Ok(State::from(value != 0))
+    }
+
+    /// Return whether the buzzer is enabled. See [`get_buzzer_enabled`](Self::get_buzzer_enabled).
+    pub fn is_buzzer_enabled(&mut self) -> Result<bool, S::Error> {
+        Ok(io!(self.get_buzzer_enabled())? == State::On)
+    }
+
+    /// Enter or exit sleep mode. (Screen off, ON/OFF button fading in and out red.)
+    pub fn set_sleep_state(&mut self, activate_sleep: impl Into<State>) -> Result<(), S::Error> {
+        io!(self.write_register(XyRegister::Device, !activate_sleep.into() as u16))
+    }
+
+    /// Get whether the device is currently in sleep mode.
+    pub fn get_sleep_state(&mut self) -> Result<State, S::Error> {
+        // The register is on while awake, see `set_sleep_state`.
+        let value = io!(self.read_register(XyRegister::Device))?;
+        Ok(State::from(value == 0))
+    }
+
+    /// Return whether the device is in sleep mode. See [`get_sleep_state`](Self::get_sleep_state).
+    pub fn is_sleeping(&mut self) -> Result<bool, S::Error> {
+        Ok(io!(self.get_sleep_state())? == State::On)
+    }
+
+    /// Read the current firmware version.
+    ///
+    /// Decimal value of `136` -> `v1.3.6`.
+    pub fn get_firmware_version(&mut self) -> Result<u16, S::Error> {
+        io!(self.read_register(XyRegister::Version))
+    }
+
+    /// Set the Modbus unit ID of this PSU.
+    ///
+    /// Appears to only be applied after a power cycle. Until then the old address is used; after
+    /// [`FAILURES_BEFORE_REINIT`] failed transactions in a row the new one is tried, and used from then on if the device
+    /// answers on it.
+    ///
+    /// Returns [`Error::InvalidRange`] if the address is outside of the 1-247 range.
+    pub fn set_slave_address(&mut self, address: u8) -> Result<(), S::Error> {
+        // Only 1-247 range is suitable ID for single Modbus device.
+        if !(1..=247).contains(&address) {
+            return Err(Error::InvalidRange);
+        }
+        io!(self.write_register(XyRegister::SlaveAdd, address as u16))?;
+        self.pending_unit_id = (address != self.unit_id).then_some(address);
+        Ok(())
+    }
+
+    /// Get the current Modbus unit ID of this PSU.
+    pub fn get_slave_address(&mut self) -> Result<u8, S::Error> {
+        let value = io!(self.read_register(XyRegister::SlaveAdd))?;
+        Ok(u8::try_from(value)?)
+    }
+
+    /// Sets the configured baud rate on the PSU.
+    ///
+    /// Appears to only be applied after a power cycle.
+    pub fn set_baudrate(&mut self, baud_rate: BaudRate) -> Result<(), S::Error> {
+        io!(self.write_register(XyRegister::BaudRateL, baud_rate as u16))?;
+        self.pending_baud_rate = Some(baud_rate);
+        Ok(())
+    }
+
+    /// Reads the configured baud rate on the PSU.
+    pub fn get_baudrate(&mut self) -> Result<BaudRate, S::Error> {
+        let value = io!(self.read_register(XyRegister::BaudRateL))?;
+        Ok(BaudRate::try_from(value)?)
+    }
+
+    /// Configure the baud rate of the PSU.
+    pub fn set_baud_rate(&mut self, baud_rate: BaudRate) -> Result<(), S::Error> {
+        io!(self.set_baudrate(baud_rate))
+    }
+
+    /// Returns the raw register values for "MODEL" -> product model
+    ///
+    /// See [Self::get_product_model] for a method which tries to interpret this data.
+    pub fn get_product_model_raw(&mut self) -> Result<u16, S::Error> {
+        io!(self.read_register(XyRegister::Model))
+    }
+
+    /// Returns the interpreted product model.
+    ///
+    /// Only models where the ID has been observed are recognised, others are returned as [`ProductModel::Unknown`].
+    /// The raw API works on them regardless, and the scaled methods once scaling factors are given.
+    ///
+    /// If you have a model which is not supported, please submit a Github
+    /// ticket with information so we can add it!
+    pub fn get_product_model(&mut self) -> Result<ProductModel, S::Error> {
+        let raw = io!(self.get_product_model_raw())?;
+        Ok(ProductModel::from(raw))
+    }
+
+    /// Read the model and firmware version, and work around the [`Quirks`] of that firmware.
+    ///
+    /// Returns the quirks found, which are kept until [`Self::set_quirks`] is called.
+    pub fn identify(&mut self) -> Result<Quirks, S::Error> {
+        let model_raw = io!(self.get_product_model_raw())?;
+        let firmware_version = io!(self.get_firmware_version())?;
+        let quirks = Quirks::lookup(model_raw, firmware_version);
+        self.set_quirks(quirks);
+        Ok(quirks)
+    }
+
+    /// Ensure scaling factors are loaded for this PSU model.
+    ///
+    /// This is called automatically by scaled measurement functions. The [`ModelRegistry`] and [`ScalingRegistry`] are
+    /// consulted first.
+    /// If the model's scaling factors are unknown, returns `ScalingNotAvailable` error.
+    ///
+    /// Returns a copy of the scaling factors so that self can be borrowed mutably afterwards.
+    pub(crate) fn ensure_scaling(&mut self) -> Result<ScalingFactors, S::Error> {
+        // The device may have been power cycled, don't trust anything we have cached.
+        if self.stale {
+            self.invalidate_cache();
+        }
+
+        // If already cached, return a copy
+        if let Some(scaling) = self.scaling {
+            return Ok(scaling);
+        }
+
+        // Otherwise, fetch model and lookup scaling factors
+        let raw = io!(self.get_product_model_raw())?;
+        let registered = self.model_registry.get(raw);
+        let scaling = match (registered, self.quirks.scaling) {
+            (Some(info), _) => info.scaling,
+            (None, Some(scaling)) => scaling,
+            (None, None) => self.scaling_registry.resolve(raw)?,
+        };
+
+        // Cache for future use
+        self.scaling = Some(scaling);
+        self.scaling_is_manual = false;
+        self.limits = match registered {
+            Some(info) => info.specs.limits,
+            None => ProductModel::from_raw(raw).and_then(|model| model.limits()),
+        };
+        Ok(scaling)
+    }
+
+    /// Check whether the device appears to have been power cycled, and re-initialize if so.
+    ///
+    /// A power cycle is detected by either:
+    /// * The link recovering after [`FAILURES_BEFORE_REINIT`] or more failed transactions.
+    /// * The output time counter going backwards since the last check. Note that some firmware also resets this
+    ///   counter when the output is turned off and on again, which causes an unnecessary, but harmless, re-initialization.
+    ///
+    /// Call this periodically, e.g. alongside reading measurements. Returns `true` if the device was re-initialized.
+    pub fn check_power_cycle(&mut self) -> Result<bool, S::Error> {
+        if self.stale {
+            io!(self.reinitialize())?;
+            return Ok(true);
+        }
+
+        let start = self.register_map.address(XyRegister::OutH);
+        let registers = io!(self.read_modbus_bulk(start, 3))?;
+        let [hours, minutes, seconds] =
+            <[u16; 3]>::try_from(registers.as_slice()).map_err(|_| Error::InvalidResponse)?;
+        let output_time_secs = hours as u32 * 3600 + minutes as u32 * 60 + seconds as u32;
+        let power_cycled = self
+            .last_output_time_secs
+            .is_some_and(|last| output_time_secs < last);
+        if power_cycled {
+            io!(self.reinitialize())?;
+        }
+        self.last_output_time_secs = Some(output_time_secs);
+        Ok(power_cycled)
+    }
+
+    /// Re-initialize after the device has been power cycled.
+    ///
+    /// Any slave address set with [`Self::set_slave_address`] is now in use, so we switch to it. The new baud rate
+    /// from [`Self::set_baudrate`] is also in use, but the serial port has to be reconfigured by the caller, see
+    /// [`Self::pending_baud_rate`].
+    ///
+    /// Cached state is invalidated and the product model is read again to check the device is responding.
+    pub fn reinitialize(&mut self) -> Result<(), S::Error> {
+        if let Some(unit_id) = self.pending_unit_id.take() {
+            self.unit_id = unit_id;
+        }
+        self.pending_baud_rate = None;
+        self.invalidate_cache();
+        io!(self.get_product_model_raw())?;
+        Ok(())
+    }
+
+    /// Return the measured output voltage in millivolts.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown.
+    ///
+    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
+    /// specify scaling factors.
+    pub fn read_output_voltage_mv(&mut self) -> Result<Millivolts, S::Error> {
+        let scaling = io!(self.ensure_scaling())?;
+        let raw = io!(self.read_register(XyRegister::VOut))?;
+        Ok(Millivolts(scaling.raw_to_voltage_mv(raw)))
+    }
+
+    /// Return the measured supply input voltage in millivolts.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown.
+    ///
+    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
+    /// specify scaling factors.
+    pub fn read_input_voltage_mv(&mut self) -> Result<Millivolts, S::Error> {
+        let scaling = io!(self.ensure_scaling())?;
+        let raw = io!(self.read_register(XyRegister::UIn))?;
+        Ok(Millivolts(scaling.raw_to_voltage_mv(raw)))
+    }
+
+    /// Return the measured output current in milliamps.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown.
+    ///
+    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
+    /// specify scaling factors.
+    pub fn read_current_ma(&mut self) -> Result<Milliamps, S::Error> {
+        let scaling = io!(self.ensure_scaling())?;
+        let raw = io!(self.read_register(XyRegister::IOut))?;
+        Ok(Milliamps(scaling.raw_to_current_ma(raw)))
+    }
+
+    /// Return the measured output power in milliwatts.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown.
+    ///
+    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
+    /// specify scaling factors.
+    pub fn read_power_mw(&mut self) -> Result<Milliwatts, S::Error> {
+        let scaling = io!(self.ensure_scaling())?;
+        let raw = io!(self.read_register(XyRegister::Power))?;
+        Ok(Milliwatts(scaling.raw_to_power_mw(raw)))
+    }
+
+    /// Return the measured output energy in milliwatt-hours.
+    ///
+    /// Requires known scaling factors for the PSU model, as for [`Self::get_protections`]. Returns
+    /// `ScalingNotAvailable` error if the model's scaling factors are unknown.
+    ///
+    /// To read all of the counters consistently in one transaction, see [`XyPsu::read_accumulators`].
+    pub fn read_energy_mwh(&mut self) -> Result<u32, S::Error> {
+        let scaling = io!(self.ensure_scaling())?;
+        let raw = io!(self.read_counter(XyRegister::WhLow, XyRegister::WhHigh))?;
+        Ok(scaling.raw_to_energy_mwh(raw))
+    }
+
+    /// Return the measured output capacity in milliamp-hours.
+    ///
+    /// Requires known scaling factors for the PSU model, as for [`Self::get_protections`]. Returns
+    /// `ScalingNotAvailable` error if the model's scaling factors are unknown.
+    ///
+    /// To read all of the counters consistently in one transaction, see [`XyPsu::read_accumulators`].
+    pub fn read_capacity_mah(&mut self) -> Result<u32, S::Error> {
+        let scaling = io!(self.ensure_scaling())?;
+        let raw = io!(self.read_counter(XyRegister::AhLow, XyRegister::AhHigh))?;
+        Ok(scaling.raw_to_capacity_mah(raw))
+    }
+
+    /// Return the unscaled 32-bit value of a counter split across `low` and `high` registers.
+    fn read_counter(&mut self, low: XyRegister, high: XyRegister) -> Result<u32, S::Error> {
+        let low = io!(self.read_register(low))? as u32;
+        let high = io!(self.read_register(high))? as u32;
+        Ok(low + (high << 16))
+    }
+
+    /// Zero the capacity and energy counters, e.g. before each run of a battery test.
+    ///
+    /// The counter registers are documented as read only, so this writes zero to them and checks that they went down.
+    /// The counters are compared unscaled, so this works without known scaling factors.
+    /// Returns `NotSupported` if the firmware ignored the write.
+    /// @TODO confirm which firmware accepts the write.
+    pub fn reset_energy_counters(&mut self) -> Result<(), S::Error> {
+        let before = (
+            io!(self.read_counter(XyRegister::AhLow, XyRegister::AhHigh))?,
+            io!(self.read_counter(XyRegister::WhLow, XyRegister::WhHigh))?,
+        );
+        match self.register_map.contiguous_start(&ENERGY_COUNTERS) {
+            Some(start) => io!(self.write_modbus_bulk(start, [0; ENERGY_COUNTERS.len()]))?,
+            None => {
+                for register in ENERGY_COUNTERS {
+                    io!(self.write_register(register, 0u16))?;
+                }
+            }
+        }
+        let after = (
+            io!(self.read_counter(XyRegister::AhLow, XyRegister::AhHigh))?,
+            io!(self.read_counter(XyRegister::WhLow, XyRegister::WhHigh))?,
+        );
+        match before != (0, 0) && after.0 >= before.0 && after.1 >= before.1 {
+            true => Err(Error::NotSupported),
+            false => Ok(()),
+        }
+    }
+
+    /// Return the duration that the output has been enabled.
+    ///
+    /// Only available with the `fugit` feature, see [`Self::read_output_duration`] and
+    /// [`Self::read_output_time_secs`].
+    #[cfg(feature = "fugit")]
+    pub fn read_output_time(&mut self) -> Result<fugit::Duration<u32, 1, 1>, S::Error> {
+        let secs = io!(self.read_output_time_secs())?;
+        Ok(fugit::Duration::<u32, 1, 1>::secs(secs))
+    }
+
+    /// Return the duration that the output has been enabled.
+    pub fn read_output_duration(&mut self) -> Result<core::time::Duration, S::Error> {
+        let secs = io!(self.read_output_time_secs())?;
+        Ok(core::time::Duration::from_secs(secs as u64))
+    }
+
+    /// Return the duration that the output has been enabled, as a [`std::time::Duration`].
+    ///
+    /// Not available with the `no_std` feature. Equivalent to [`Self::read_output_duration`], as
+    /// [`std::time::Duration`] is [`core::time::Duration`].
+    #[cfg(not(feature = "no_std"))]
+    pub fn read_output_time_std(&mut self) -> Result<std::time::Duration, S::Error> {
+        io!(self.read_output_duration())
+    }
+
+    /// Return the duration that the output has been enabled, in seconds.
+    ///
+    /// To read all of the counters consistently in one transaction, see [`XyPsu::read_accumulators`].
+    pub fn read_output_time_secs(&mut self) -> Result<u32, S::Error> {
+        let time_h = io!(self.read_register(XyRegister::OutH))? as u32;
+        let time_m = io!(self.read_register(XyRegister::OutM))? as u32;
+        let time_s = io!(self.read_register(XyRegister::OutS))? as u32;
+        Ok(time_h * 3600 + time_m * 60 + time_s)
+    }
+
+    /// Return the measured internal temperature.
+    ///
+    /// Unit of measurement depends on setting.
+    pub fn read_temperature_internal(&mut self) -> Result<Temperature, S::Error> {
+        let unit = io!(self.get_temperature_unit())?;
+        let temp_internal_raw = io!(self.read_register(XyRegister::TIn))?;
+        Ok(Temperature::from_centi(temp_internal_raw, unit))
+    }
+
+    /// Return the measured external temperature sensor.
+    ///
+    /// Unit of measurement depends on setting. See [Self::set_temperature_unit].
+    ///
+    /// @TODO test with external temp sensor.
+    pub fn read_temperature_external(&mut self) -> Result<Temperature, S::Error> {
+        let unit = io!(self.get_temperature_unit())?;
+        let temp_external_raw = io!(self.read_register(XyRegister::TEx))?;
+        Ok(Temperature::from_centi(temp_external_raw, unit))
+    }
+
+    /// Return the measured internal temperature in tenths of a degree, see [`Self::read_temperature_internal`].
+    pub fn read_temperature_internal_deci(&mut self) -> Result<DeciTemperature, S::Error> {
+        let unit = io!(self.get_temperature_unit())?;
+        let raw = io!(self.read_register(XyRegister::TIn))?;
+        Ok(DeciTemperature::new(raw, unit))
+    }
+
+    /// Return the measured external temperature in tenths of a degree, see [`Self::read_temperature_external`].
+    pub fn read_temperature_external_deci(&mut self) -> Result<DeciTemperature, S::Error> {
+        let unit = io!(self.get_temperature_unit())?;
+        let raw = io!(self.read_register(XyRegister::TEx))?;
+        Ok(DeciTemperature::new(raw, unit))
+    }
+
+    /// Enable/disable the output.
+    ///
+    /// In [strict mode](Self::set_strict_mode), returns `ProtectionActive` when enabling while a protection is active.
+    pub fn set_output_state(&mut self, state: impl Into<State>) -> Result<(), S::Error> {
+        let state = state.into();
+        if self.strict_mode && state == State::On {
+            let status = io!(self.get_protection_status())?;
+            if status.any() {
+                return Err(Error::ProtectionActive(status));
+            }
+        }
+        io!(self.write_register(XyRegister::OnOff, state as u16))?;
+        Ok(())
+    }
+
+    /// Set the temperature unit to use.
+    pub fn set_temperature_unit(&mut self, unit: TemperatureUnit) -> Result<(), S::Error> {
+        io!(self.write_register(XyRegister::FC, unit as u16))?;
+        Ok(())
+    }
+
+    /// Return the temperature unit in use.
+    pub fn get_temperature_unit(&mut self) -> Result<TemperatureUnit, S::Error> {
+        let value = io!(self.read_register(XyRegister::FC))?;
+        let unit = TemperatureUnit::try_from(value)?;
+        Ok(unit)
+    }
+
+    /// Set the output target voltage. Value supplied in millivolts.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown.
+    ///
+    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
+    /// specify scaling factors.
+    ///
+    /// Returns `InvalidRange` if the voltage is above the model's [`limits`](Self::limits).
+    pub fn set_output_voltage_mv(
+        &mut self,
+        voltage_mv: impl Into<Millivolts>,
+    ) -> Result<(), S::Error> {
+        let voltage_mv = voltage_mv.into().0;
+        let scaling = io!(self.ensure_scaling())?;
+        let max = self.limits.map(|limits| limits.max_voltage_mv);
+        let raw =
+            setpoint_to_raw(voltage_mv, max, scaling.voltage_divisor).ok_or(Error::InvalidRange)?;
+        io!(self.write_register(XyRegister::VSet, raw))?;
+        Ok(())
+    }
+
+    /// Get the current output target voltage. Value returned in millivolts.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown.
+    ///
+    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
+    /// specify scaling factors.
+    pub fn get_output_voltage_mv(&mut self) -> Result<Millivolts, S::Error> {
+        let scaling = io!(self.ensure_scaling())?;
+        let raw = io!(self.read_register(XyRegister::VSet))?;
+        Ok(Millivolts(scaling.raw_to_voltage_mv(raw)))
+    }
+
+    /// Set the output current limit. Value supplied in milliamps.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown.
+    ///
+    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
+    /// specify scaling factors.
+    ///
+    /// Returns `InvalidRange` if the current is above the model's [`limits`](Self::limits).
+    pub fn set_current_limit_ma(
+        &mut self,
+        current_ma: impl Into<Milliamps>,
+    ) -> Result<(), S::Error> {
+        let current_ma = current_ma.into().0;
+        let scaling = io!(self.ensure_scaling())?;
+        let max = self.limits.map(|limits| limits.max_current_ma);
+        let raw =
+            setpoint_to_raw(current_ma, max, scaling.current_divisor).ok_or(Error::InvalidRange)?;
+        io!(self.write_register(XyRegister::ISet, raw))?;
+        Ok(())
+    }
+
+    /// Get the current output current limit value. Value returned in milliamps.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown.
+    ///
+    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
+    /// specify scaling factors.
+    pub fn get_current_limit_ma(&mut self) -> Result<Milliamps, S::Error> {
+        let scaling = io!(self.ensure_scaling())?;
+        let raw = io!(self.read_register(XyRegister::ISet))?;
+        Ok(Milliamps(scaling.raw_to_current_ma(raw)))
+    }
+
+    /// Return which protections have been triggered, if any.
+    pub fn get_protection_status(&mut self) -> Result<ProtectionStatus, S::Error> {
+        let raw = io!(self.read_register(XyRegister::Protect))?;
+        let bytes = raw.to_le_bytes();
+        let status = ProtectionStatus::from_bytes(bytes);
+        Ok(status)
+    }
+
+    /// Clear any active protection flags.
+    pub fn clear_protections(&mut self) -> Result<(), S::Error> {
+        io!(self.write_register(XyRegister::Protect, 0x00_u16))?;
+        Ok(())
+    }
+
+    /// Set the backlight brightness level.
+    pub fn set_backlight(&mut self, level: BacklightBrightness) -> Result<(), S::Error> {
+        io!(self.write_register(XyRegister::BLed, level as u16))?;
+        Ok(())
+    }
+
+    /// Get the current backlight brightness level.
+    pub fn get_backlight(&mut self) -> Result<BacklightBrightness, S::Error> {
+        let value = io!(self.read_register(XyRegister::BLed))?;
+        let level = BacklightBrightness::try_from(value)?;
+        Ok(level)
+    }
+
+    /// Set how long the screen stays on after the last key press. The register is written in whole minutes, so the
+    /// timeout is rounded down.
+    ///
+    /// __Unconfirmed:__ minutes are the unit of the "rest screen time" menu setting, but the register hasn't been
+    /// tested on hardware yet, and neither has what 0 does. Use [`Self::write_modbus_single`] on
+    /// [`XyRegister::Sleep`] for values outside of what this assumes.
+    ///
+    /// Returns `InvalidRange` if the timeout is too long for the register.
+    pub fn set_screen_timeout(&mut self, timeout: core::time::Duration) -> Result<(), S::Error> {
+        let minutes = u16::try_from(timeout.as_secs() / 60).map_err(|_| Error::InvalidRange)?;
+        io!(self.write_register(XyRegister::Sleep, minutes))?;
+        Ok(())
+    }
+
+    /// Get how long the screen stays on after the last key press.
+    pub fn get_screen_timeout(&mut self) -> Result<core::time::Duration, S::Error> {
+        let minutes = io!(self.read_register(XyRegister::Sleep))?;
+        Ok(core::time::Duration::from_secs(minutes as u64 * 60))
+    }
+
+    /// Activate preset by index.
+    ///
+    /// Only available with the `presets` feature.
+    #[cfg(feature = "presets")]
+    pub fn set_active_preset(&mut self, group: impl Into<PresetGroup>) -> Result<(), S::Error> {
+        io!(self.write_register(XyRegister::ExtractM, group.into() as u16))?;
+        Ok(())
+    }
+
+    /// Activate a preset group, with `policy` deciding whether the output is switched on or off.
+    ///
+    /// Only available with the `presets` feature.
+    ///
+    /// Unlike [`Self::set_active_preset`], the output switch is written after the group has been loaded, so it ends up
+    /// as `policy` says whether or not the firmware applies the group's `SIni` setting.
+    #[cfg(feature = "presets")]
+    pub fn load_preset(
+        &mut self,
+        group: impl Into<PresetGroup>,
+        policy: PresetOutputPolicy,
+    ) -> Result<(), S::Error> {
+        use crate::preset::XyPresetOffsets as XPO;
+
+        let group = group.into();
+        let output = match policy {
+            PresetOutputPolicy::KeepCurrentOutputState => io!(self.read_register(XyRegister::OnOff))?,
+            PresetOutputPolicy::ForceOff => {
+                // Switch off first, so the output never sees the group's settings.
+                io!(self.write_register(XyRegister::OnOff, 0u16))?;
+                0
+            }
+            PresetOutputPolicy::UsePresetSIni => {
+                io!(self.read_modbus_single(XPO::SIni.address_in_group(group)))?
+            }
+        };
+        io!(self.set_active_preset(group))?;
+        io!(self.write_register(XyRegister::OnOff, State::from(output != 0) as u16))
+    }
+
+    /// Get the currently active preset group.
+    ///
+    /// Only available with the `presets` feature.
+    ///
+    /// Returns the preset group (0-9) that is currently active on the PSU.
+    #[cfg(feature = "presets")]
+    pub fn get_active_preset(&mut self) -> Result<PresetGroup, S::Error> {
+        let value = io!(self.read_register(XyRegister::ExtractM))?;
+        let group = PresetGroup::try_from(value)?;
+        Ok(group)
+    }
+
+    /// Set the raw correction value used for the internal temperature sensor.
+    ///
+    /// The units of this register have not been confirmed, so the value is written as-is.
+    pub fn set_temperature_offset_internal_raw(&mut self, offset: u16) -> Result<(), S::Error> {
+        io!(self.write_register(XyRegister::TInOffset, offset))?;
+        Ok(())
+    }
+
+    /// Get the raw correction value used for the internal temperature sensor.
+    pub fn get_temperature_offset_internal_raw(&mut self) -> Result<u16, S::Error> {
+        io!(self.read_register(XyRegister::TInOffset))
+    }
+
+    /// Set the raw correction value used for the external temperature sensor.
+    ///
+    /// The units of this register have not been confirmed, so the value is written as-is.
+    pub fn set_temperature_offset_external_raw(&mut self, offset: u16) -> Result<(), S::Error> {
+        io!(self.write_register(XyRegister::TExOffset, offset))?;
+        Ok(())
+    }
+
+    /// Get the raw correction value used for the external temperature sensor.
+    pub fn get_temperature_offset_external_raw(&mut self) -> Result<u16, S::Error> {
+        io!(self.read_register(XyRegister::TExOffset))
+    }
+
+    /// Enable or disable MPPT functionality.
+    pub fn set_mppt_enabled(&mut self, activate_sleep: impl Into<State>) -> Result<(), S::Error> {
+        io!(self.write_register(XyRegister::MpptSw, activate_sleep.into() as u16))?;
+        Ok(())
+    }
+
+    /// Get whether MPPT is currently in enabled or disabled.
+    pub fn get_mppt_enabled(&mut self) -> Result<State, S::Error> {
+        let value = io!(self.read_register(XyRegister::MpptSw))?;
+        let state = State::from(value != 0);
+        Ok(state)
+    }
+
+    /// Set the MPPT coefficient. Recommended [`75` - `85`]
+    ///
+    /// Note: Value passed in is 100x bigger than shown on screen.
+    pub fn set_mppt_k_value(&mut self, mppt_k: u16) -> Result<(), S::Error> {
+        io!(self.write_register(XyRegister::MpptK, mppt_k))?;
+        Ok(())
+    }
+
+    /// Get the current MPPT coefficient. Default value of `80`.
+    ///
+    /// Value returned is 100x what is shown on the display.
+    ///
+    /// E.g. `0.75` on display => `75` as retuned by this function.
+    pub fn get_mppt_k_value(&mut self) -> Result<u16, S::Error> {
+        let value = io!(self.read_register(XyRegister::MpptK))?;
+        Ok(value)
+    }
+
+    /// Read the MPPT switch, coefficient and battery full current in one transaction.
+    ///
+    /// The battery full current is only read on models where it works, see [`Self::set_battery_full_current_ma`], and
+    /// is `None` otherwise. If the register map has moved the registers apart, they are read individually instead.
+    /// Requires known scaling factors for the PSU model.
+    pub fn get_mppt_config(&mut self) -> Result<MpptConfig, S::Error> {
+        let scaling = io!(self.ensure_scaling())?;
+        let registers = MpptConfig::registers(self.register_map.has_battery_full_current());
+        let mut values = heapless::Vec::<u16, 3>::new();
+        match self.register_map.contiguous_start(registers) {
+            Some(start) => {
+                let read = io!(self.read_modbus_bulk(start, registers.len() as u16))?;
+                values = heapless::Vec::from_slice(&read).map_err(|_| Error::InvalidResponse)?;
+            }
+            None => {
+                for register in registers {
+                    values
+                        .push(io!(self.read_register(*register))?)
+                        .map_err(|_| Error::BufferError)?;
+                }
+            }
+        }
+        if values.len() != registers.len() {
+            return Err(Error::InvalidResponse);
+        }
+        Ok(MpptConfig::from_registers(&values, &scaling))
+    }
+
+    /// Write the MPPT switch, coefficient and battery full current in one transaction, so they take effect together.
+    ///
+    /// A battery full current of `None` leaves the register as it is. Setting one on a model where it doesn't work
+    /// returns `NotSupported`, see [`Self::set_battery_full_current_ma`]. If the register map has moved the registers
+    /// apart, they are written individually instead. Requires known scaling factors for the PSU model, and returns
+    /// `InvalidRange` if the battery full current is above the model's [`limits`](Self::limits).
+    pub fn set_mppt_config(&mut self, config: MpptConfig) -> Result<(), S::Error> {
+        if config.battery_full_current_ma.is_some() {
+            self.check_battery_full_current()?;
+        }
+        let scaling = io!(self.ensure_scaling())?;
+        let registers = MpptConfig::registers(config.battery_full_current_ma.is_some());
+        let max = self.limits.map(|limits| limits.max_current_ma);
+        let values = config
+            .to_registers(&scaling, max)
+            .ok_or(Error::InvalidRange)?;
+        match self.register_map.contiguous_start(registers) {
+            Some(start) => {
+                io!(self.write_modbus_bulk(start, &values))?;
+                io!(self.verify_write(start, &values))
+            }
+            None => {
+                for (value, register) in values.into_iter().zip(registers) {
+                    io!(self.write_register(*register, value))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Set the input current limit (ICP). Value supplied in milliamps.
+    ///
+    /// Only available on models whose [`RegisterMap`] gives the address of the input current limit register, see
+    /// [`RegisterMap::set_input_current_limit_address`]. Returns `NotSupported` otherwise, which is the case for all of
+    /// the built-in models, as none of them has a confirmed address for it.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown, or `InvalidRange` if the current is too large for the register.
+    pub fn set_input_current_limit_ma(&mut self, current_ma: u32) -> Result<(), S::Error> {
+        let address = self.input_current_limit_address()?;
+        let scaling = io!(self.ensure_scaling())?;
+        // The model's limits are for the output, so only the register bounds the input current.
+        let raw =
+            setpoint_to_raw(current_ma, None, scaling.current_divisor).ok_or(Error::InvalidRange)?;
+        io!(self.write_modbus_single(address, raw))?;
+        io!(self.verify_write(address, &[raw]))
+    }
+
+    /// Get the input current limit (ICP). Value returned in milliamps.
+    ///
+    /// See [`Self::set_input_current_limit_ma`] for which models support this.
+    pub fn get_input_current_limit_ma(&mut self) -> Result<u32, S::Error> {
+        let address = self.input_current_limit_address()?;
+        let scaling = io!(self.ensure_scaling())?;
+        let raw = io!(self.read_modbus_single(address))?;
+        Ok(scaling.raw_to_current_ma(raw))
+    }
+
+    /// Set the battery full current, at which MPPT charging is considered complete. Value supplied in milliamps.
+    ///
+    /// Only available on models whose [`RegisterMap::has_battery_full_current`] is set, as the register didn't appear
+    /// to work on the firmware tested. Returns `NotSupported` otherwise.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown, or `InvalidRange` if the current is above the model's
+    /// [`limits`](Self::limits).
+    pub fn set_battery_full_current_ma(&mut self, current_ma: u32) -> Result<(), S::Error> {
+        self.check_battery_full_current()?;
+        let scaling = io!(self.ensure_scaling())?;
+        let max = self.limits.map(|limits| limits.max_current_ma);
+        let raw =
+            setpoint_to_raw(current_ma, max, scaling.current_divisor).ok_or(Error::InvalidRange)?;
+        io!(self.write_register(XyRegister::BatFul, raw))?;
+        Ok(())
+    }
+
+    /// Get the battery full current. Value returned in milliamps.
+    ///
+    /// See [`Self::set_battery_full_current_ma`] for which models support this.
+    pub fn get_battery_full_current_ma(&mut self) -> Result<u32, S::Error> {
+        self.check_battery_full_current()?;
+        let scaling = io!(self.ensure_scaling())?;
+        let raw = io!(self.read_register(XyRegister::BatFul))?;
+        Ok(scaling.raw_to_current_ma(raw))
+    }
+
+    /// Enable or disable constant power mode.
+    pub fn set_constant_power_enabled(
+        &mut self,
+        activate_sleep: impl Into<State>,
+    ) -> Result<(), S::Error> {
+        io!(self.write_register(XyRegister::CwSw, activate_sleep.into() as u16))?;
+        Ok(())
+    }
+
+    /// Get whether constant power mode is currently enabled or disabled.
+    pub fn get_constant_power_enabled(&mut self) -> Result<State, S::Error> {
+        let value = io!(self.read_register(XyRegister::CwSw))?;
+        let state = State::from(value != 0);
+        Ok(state)
+    }
+
+    /// Set the constant power power level. Units of watts.
+    ///
+    /// This can be set without enabling constant power mode.
+    pub fn set_constant_power_level(&mut self, mppt_k: u16) -> Result<(), S::Error> {
+        io!(self.write_register(XyRegister::Cw, mppt_k))?;
+        Ok(())
+    }
+
+    /// Get the current constant power power level. Units of watts.
+    ///
+    /// This can be read without enabling constant power mode.
+    pub fn get_constant_power_level(&mut self) -> Result<u16, S::Error> {
+        let value = io!(self.read_register(XyRegister::Cw))?;
+        Ok(value)
+    }
+
+    /// Set the constant power level. Value supplied in milliwatts.
+    ///
+    /// Unlike [`Self::set_constant_power_level`], this applies the model's power scaling, like the other scaled
+    /// setters. This can be set without enabling constant power mode.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown, or `InvalidRange` if the power is above the model's
+    /// [`limits`](Self::limits).
+    pub fn set_constant_power_mw(&mut self, power_mw: u32) -> Result<(), S::Error> {
+        let scaling = io!(self.ensure_scaling())?;
+        let max = self.limits.and_then(|limits| limits.max_power_mw);
+        let raw =
+            setpoint_to_raw(power_mw, max, scaling.power_divisor).ok_or(Error::InvalidRange)?;
+        io!(self.write_register(XyRegister::Cw, raw))?;
+        Ok(())
+    }
+
+    /// Get the constant power level. Value returned in milliwatts.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown.
+    pub fn get_constant_power_mw(&mut self) -> Result<u32, S::Error> {
+        let scaling = io!(self.ensure_scaling())?;
+        let raw = io!(self.read_register(XyRegister::Cw))?;
+        Ok(scaling.raw_to_power_mw(raw))
+    }
+
+    /// Get the current protection configuration from the active preset.
+    ///
+    /// Only available with the `presets` feature.
+    ///
+    /// This reads the protection settings from the currently active preset group
+    /// and returns them as a `ProtectionConfig` struct.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown.
+    ///
+    /// # For Unknown Models
+    ///
+    /// If your PSU model has unknown scaling factors, use [`set_scaling_factors`](Self::set_scaling_factors)
+    /// to manually specify them before calling this method:
+    ///
+    /// ```ignore
+    /// // Set custom scaling factors for unknown model
+    /// let scaling = ScalingFactors::new(10, 10, 100, 10, 10).unwrap();
+    /// psu.set_scaling_factors(scaling);
+    ///
+    /// // Now get_protections will use your custom scaling
+    /// let protections = psu.get_protections()?;
+    /// ```
+    #[cfg(feature = "presets")]
+    pub fn get_protections(&mut self) -> Result<ProtectionConfig, S::Error> {
+        // Ensure scaling factors are loaded
+        let scaling = io!(self.ensure_scaling())?;
+        use crate::preset::XyPresetOffsets as XPO;
+
+        // Get currently active preset group
+        let group = io!(self.get_active_preset())?;
+
+        // Calculate the starting address for protection registers
+        let start_address = XPO::SLvp.address_in_group(group);
+
+        // Read all protection-related registers (SLvp through SEtp)
+        // That's registers 0x02 through 0x0E in the preset group (13 registers)
+        let registers = io!(self.read_modbus_bulk(start_address, 13))?;
+
+        let registers =
+            <[u16; 13]>::try_from(registers.as_slice()).map_err(|_| Error::InvalidResponse)?;
+
+        let temp_unit = io!(self.get_temperature_unit())?;
+        Ok(ProtectionConfig::from_registers(
+            registers, scaling, temp_unit,
+        ))
+    }
+
+    /// Read back all settings of any preset group, M0 - M9.
+    ///
+    /// Only available with the `presets` feature.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown.
+    #[cfg(feature = "presets")]
+    pub fn read_preset(&mut self, group: impl Into<PresetGroup>) -> Result<XyPreset, S::Error> {
+        use crate::preset::XyPresetOffsets as XPO;
+        use strum::EnumCount;
+
+        let group = group.into();
+        let scaling = io!(self.ensure_scaling())?;
+        let registers =
+            io!(self.read_modbus_bulk(XPO::VSet.address_in_group(group), XPO::COUNT as u16))?;
+        let registers = <[u16; XPO::COUNT]>::try_from(registers.as_slice())
+            .map_err(|_| Error::InvalidResponse)?;
+
+        let temp_unit = io!(self.get_temperature_unit())?;
+        Ok(XyPreset::from_registers(
+            group, registers, scaling, temp_unit,
+        ))
+    }
+
+    /// Set protection levels of the power supply.
+    ///
+    /// Only available with the `presets` feature.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown.
+    ///
+    /// # For Unknown Models
+    ///
+    /// If your PSU model has unknown scaling factors, use [`set_scaling_factors`](Self::set_scaling_factors)
+    /// to manually specify them before calling this method:
+    ///
+    /// ```ignore
+    /// // Set custom scaling factors for unknown model
+    /// let scaling = ScalingFactors::new(10, 10, 100, 10, 10).unwrap();
+    /// psu.set_scaling_factors(scaling);
+    ///
+    /// // Now set_protections will use your custom scaling
+    /// psu.set_protections(protection_config)?;
+    /// ```
+    ///
+    /// __Note:__ This works by modifying the active preset group. The group is read first and only its protection
+    /// levels are changed, its voltage, current, output and external temperature settings are written back as they were.
+    #[cfg(feature = "presets")]
+    pub fn set_protections(
+        &mut self,
+        protection_settings: ProtectionConfig,
+    ) -> Result<(), S::Error> {
+        use crate::preset::XyPresetOffsets as XPO;
+        use strum::EnumCount;
+
+        // Ensure scaling factors are loaded
+        let scaling = io!(self.ensure_scaling())?;
+        // Get currently active preset group so we can write values to the active group.
+        let group = io!(self.get_active_preset())?;
+
+        // Read the whole group, so only the protections change.
+        let start_address = XPO::VSet.address_in_group(group);
+        let registers = io!(self.read_modbus_bulk(start_address, XPO::COUNT as u16))?;
+        let mut registers = <[u16; XPO::COUNT]>::try_from(registers.as_slice())
+            .map_err(|_| Error::InvalidResponse)?;
+
+        let temp_unit = io!(self.get_temperature_unit())?;
+        protection_settings.apply_to_registers(&mut registers, scaling, temp_unit)?;
+
+        io!(self.write_modbus_bulk(start_address, registers))?;
+        io!(self.verify_write(start_address, &registers))
+    }
+
+    /// Read a named register, at the address given by the register map.
+    pub(crate) fn read_register(&mut self, register: XyRegister) -> Result<u16, S::Error> {
+        self.check_register(register)?;
+        if let Some(value) = self.register_cache.get(register) {
+            return Ok(value);
+        }
+        let address = self.register_map.address(register);
+        let value = io!(self.read_modbus_single(address))?;
+        self.register_cache.store(register, value);
+        Ok(value)
+    }
+
+    /// Write a named register, at the address given by the register map.
+    pub(crate) fn write_register(
+        &mut self,
+        register: XyRegister,
+        data: impl Into<u16>,
+    ) -> Result<(), S::Error> {
+        self.check_register(register)?;
+        let address = self.register_map.address(register);
+        let data = data.into();
+        io!(self.write_modbus_single(address, data))?;
+        self.record_write(register, data);
+        if let Err(e) = io!(self.verify_write(address, &[data])) {
+            self.register_cache.invalidate_register(register);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Read back `data` written from `address`, with [`WriteVerification::ReadBack`].
+    pub(crate) fn verify_write(&mut self, address: u16, data: &[u16]) -> Result<(), S::Error> {
+        if self.write_verification != WriteVerification::ReadBack {
+            return Ok(());
+        }
+        let actual = io!(self.read_modbus_bulk(address, u16::try_from(data.len())?))?;
+        match data
+            .iter()
+            .zip(&actual)
+            .find(|(expected, actual)| expected != actual)
+        {
+            Some((&expected, &actual)) => Err(Error::VerificationFailed { expected, actual }),
+            None => Ok(()),
+        }
+    }
+
+    /// Write to a single register of the PSU.
+    pub fn write_modbus_single(
+        &mut self,
+        register: impl Into<u16>,
+        data: impl Into<u16>,
+    ) -> Result<(), S::Error> {
+        let frame = Frame::write_single(self.unit_id, register.into(), data.into())?;
+        io!(self.transact(&frame))?;
+        Ok(())
+    }
+
+    /// Write to multiple, sequential PSU registers.
+    pub fn write_modbus_bulk(
+        &mut self,
+        start_register: impl Into<u16>,
+        data: impl AsRef<[u16]>,
+    ) -> Result<(), S::Error> {
+        let frame = Frame::write_bulk(self.unit_id, start_register.into(), data.as_ref())?;
+        io!(self.transact(&frame))?;
+        Ok(())
+    }
+
+    /// Read a single register from the PSU.
+    pub fn read_modbus_single(&mut self, register: impl Into<u16>) -> Result<u16, S::Error> {
+        io!(self.read_registers(register.into(), 1))?
+            .first()
+            .copied()
+            .ok_or(Error::InvalidResponse)
+    }
+
+    /// Read multiple registers from the PSU.
+    ///
+    /// Returns a vector of u16 values representing the register contents.
+    pub fn read_modbus_bulk(
+        &mut self,
+        start_register: u16,
+        count: u16,
+    ) -> Result<heapless::Vec<u16, 64>, S::Error> {
+        io!(self.read_registers(start_register, count))
+    }
+
+    /// Read registers with the [`ReadFunction`] in use.
+    fn read_registers(
+        &mut self,
+        start_register: u16,
+        count: u16,
+    ) -> Result<heapless::Vec<u16, 64>, S::Error> {
+        let frame = match self.read_function {
+            ReadFunction::Input => Frame::read_input(self.unit_id, start_register, count)?,
+            _ => Frame::read(self.unit_id, start_register, count)?,
+        };
+        match io!(self.transact(&frame)) {
+            Err(Error::Transaction {
+                failure: TransactionFailure::Exception(_),
+                ..
+            }) if self.read_function == ReadFunction::HoldingWithInputFallback => {
+                let frame = Frame::read_input(self.unit_id, start_register, count)?;
+                io!(self.transact(&frame))
+            }
+            result => result,
+        }
+    }
+
+    /// Send a request frame, read back the response and check it.
+    ///
+    /// Every Modbus transaction goes through here, so this is where the health of the link is tracked.
+    fn transact(&mut self, frame: &Frame<L>) -> Result<heapless::Vec<u16, 64>, S::Error> {
+        let mut result = io!(self.exchange(frame)).and_then(|()| frame.parse(&self.last_response));
+        for retry in 1..self.retry_policy.attempts {
+            if !result.as_ref().is_err_and(frame::is_transient) {
+                break;
+            }
+            // `XyPsuAsync` refuses a backoff, so this only ever blocks the blocking driver.
+            if let Some(backoff) = self.retry_policy.backoff {
+                backoff(retry);
+            }
+            result = io!(self.exchange(frame)).and_then(|()| frame.parse(&self.last_response));
+        }
+        self.record_outcome(result.is_ok());
+        if self.consecutive_failures >= FAILURES_BEFORE_REINIT {
+            io!(self.probe_pending_unit_id())?;
+        }
+        result.map_err(|e| e.in_transaction(frame.context))
+    }
+
+    /// Switch to the slave address set with [`Self::set_slave_address`] if the device answers on it.
+    ///
+    /// A new slave address takes effect after a power cycle, after which the old one stops responding. The old one
+    /// also stops responding if e.g. the cable is loose, so the new one is only used once the "MODEL" register has been
+    /// read from it.
+    fn probe_pending_unit_id(&mut self) -> Result<(), S::Error> {
+        let Some(unit_id) = self.pending_unit_id else {
+            return Ok(());
+        };
+        let address = self.register_map.address(XyRegister::Model);
+        let frame = match self.read_function {
+            ReadFunction::Input => Frame::read_input(unit_id, address, 1)?,
+            _ => Frame::read(unit_id, address, 1)?,
+        };
+        // Keep the failed transaction for `last_transaction`, rather than the probe.
+        let request = core::mem::take(&mut self.last_request);
+        let response = core::mem::take(&mut self.last_response);
+        // Exchanged directly rather than through `transact`, so a failed probe isn't counted as a failure of the link.
+        let answered = io!(self.exchange(&frame))
+            .and_then(|()| frame.parse(&self.last_response))
+            .is_ok();
+        self.last_request = request;
+        self.last_response = response;
+        if answered {
+            self.unit_id = unit_id;
+            self.pending_unit_id = None;
+            self.record_outcome(true);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+//! Read the raw values of a range of registers, for investigating models this crate doesn't know yet.
+//!
+//! If your model isn't supported, please paste a dump of its registers into a Github ticket:
+//!
+//! ```ignore
+//! let dump: heapless::Vec<_, 128> = psu.dump_registers(0x00..0x80)?;
+//! println!("{}", RegisterDump(&dump));
+//! ```
+
+use crate::{
+    error::{Error, Result, TransactionFailure},
+    psu::XyPsu,
+    transport::Transport,
+};
+
+/// Number of registers read by each bulk read of a dump. Small enough for the response to fit a 128 byte buffer.
+pub const DUMP_CHUNK: u16 = 32;
+
+/// Formats a dump as one `ADDRESS: VALUE (DECIMAL)` line per register, e.g. `0x0000: 0x04B0 (1200)`.
+#[derive(Debug, Clone, Copy)]
+pub struct RegisterDump<'a>(pub &'a [(u16, u16)]);
+
+impl core::fmt::Display for RegisterDump<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (address, value) in self.0 {
+            writeln!(f, "0x{address:04X}: 0x{value:04X} ({value})")?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Read the raw value of every register in `range`, as `(address, value)` pairs.
+    ///
+    /// Registers are read in chunks of [`DUMP_CHUNK`]. If the device rejects a chunk, e.g. because it includes an
+    /// address it doesn't implement, the chunk is read one register at a time and the rejected registers are left out.
+    /// Returns [`Error::BufferError`] if the range has more than `N` registers.
+    pub fn dump_registers<const N: usize>(
+        &mut self,
+        range: core::ops::Range<u16>,
+    ) -> Result<heapless::Vec<(u16, u16), N>, S::Error> {
+        if range.len() > N {
+            return Err(Error::BufferError);
+        }
+        let mut dump = heapless::Vec::new();
+        for start in range.clone().step_by(DUMP_CHUNK as usize) {
+            let count = DUMP_CHUNK.min(range.end - start);
+            match self.read_modbus_bulk(start, count) {
+                Ok(values) => {
+                    for (address, value) in (start..).zip(values) {
+                        dump.push((address, value))
+                            .map_err(|_| Error::BufferError)?;
+                    }
+                }
+                Err(Error::Transaction {
+                    failure: TransactionFailure::Exception(_),
+                    ..
+                }) => {
+                    for address in start..start + count {
+                        match self.read_modbus_single(address) {
+                            Ok(value) => dump
+                                .push((address, value))
+                                .map_err(|_| Error::BufferError)?,
+                            Err(Error::Transaction {
+                                failure: TransactionFailure::Exception(_),
+                                ..
+                            }) => {}
+                            Err(e) => return Err(e),
+                        }
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(dump)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_serial::{MockResponse, MockSerial};
+
+    #[test]
+    fn dump_read_in_chunks() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        let first: [u16; 32] = core::array::from_fn(|i| i as u16 * 10);
+        psu.interface_mut().queue_read_response(0x01, &first);
+        psu.interface_mut()
+            .queue_read_response(0x01, &[0x6502, 113]);
+
+        let dump: heapless::Vec<_, 40> = psu.dump_registers(0x00..0x22).unwrap();
+        assert_eq!(dump.len(), 34);
+        assert_eq!(dump.get(3), Some(&(0x03, 30)));
+        assert_eq!(dump.last(), Some(&(0x21, 113)));
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([0x00, 0x20, 0x00, 0x02].as_slice())
+        );
+
+        // Bulk read rejected, so the registers are read one at a time.
+        let exception = || {
+            let mut response: heapless::Vec<u8, 256> =
+                heapless::Vec::from_slice(&[0x01, 0x83, 0x02]).unwrap();
+            let crc = crate::mock_serial::crc16(&response);
+            response.extend_from_slice(&crc).unwrap();
+            MockResponse::Data(response)
+        };
+        psu.interface_mut().queue_response(exception());
+        psu.interface_mut().queue_read_response(0x01, &[7]);
+        psu.interface_mut().queue_response(exception());
+        let skipped: heapless::Vec<_, 2> = psu.dump_registers(0x40..0x42).unwrap();
+        assert_eq!(skipped, [(0x40, 7)]);
+
+        let mut text: heapless::String<64> = heapless::String::new();
+        core::fmt::Write::write_fmt(
+            &mut text,
+            format_args!("{}", RegisterDump(dump.get(..2).unwrap())),
+        )
+        .unwrap();
+        assert_eq!(text, "0x0000: 0x0000 (0)\n0x0001: 0x000A (10)\n");
+    }
+}
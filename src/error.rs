@@ -9,29 +9,177 @@ pub type Result<T, I> = core::result::Result<T, Error<I>>;
 pub enum Error<I: embedded_io::Error> {
     #[error("Serial communication error")]
     SerialError(I),
-    #[error("Modbus protocol error: {0}")]
-    ModbusError(rmodbus::ErrorKind),
+    #[error("Modbus exception: {0}")]
+    ModbusError(ModbusException),
     #[error("Communication timeout")]
     Timeout,
     #[error("Invalid range")]
     InvalidRange,
     #[error("Invalid modbus response received")]
     InvalidResponse,
+    #[error("Response CRC did not match")]
+    CrcMismatch,
     #[error("heapless::Vec full?")]
     BufferError,
     #[error("Passed value was too large to convert to u16.")]
     IntTooBig,
+    #[error(
+        "The raw product model ID 0x{0:04X} | {0} is not currently recognised by this library."
+    )]
+    UnknownModel(u16),
     #[error(
         "Scaling factors not available for this PSU model. You can use the *_raw() methods instead an apply scaling manually."
     )]
     ScalingNotAvailable,
+    #[error("Not supported by this PSU model.")]
+    NotSupported,
+    #[error("A protection is active, so the PSU won't comply: {0:?}")]
+    ProtectionActive(crate::register::ProtectionStatus),
+    #[error("Register read back as {actual} after writing {expected}")]
+    VerificationFailed { expected: u16, actual: u16 },
+    #[error("{failure} in {context}")]
+    Transaction {
+        context: TransactionContext,
+        failure: TransactionFailure,
+    },
     #[error("Other, non-descriptive error...")]
     Other,
 }
 
-impl<I: embedded_io::Error> From<rmodbus::ErrorKind> for Error<I> {
-    fn from(err: rmodbus::ErrorKind) -> Self {
-        Error::ModbusError(err)
+impl<I: embedded_io::Error> Error<I> {
+    /// Return the transaction this error happened in, if it came from a failed transaction.
+    pub fn context(&self) -> Option<TransactionContext> {
+        match self {
+            Error::Transaction { context, .. } => Some(*context),
+            _ => None,
+        }
+    }
+
+    /// Attach `context` to a failed transaction. Other errors are returned unchanged.
+    pub(crate) fn in_transaction(self, context: TransactionContext) -> Self {
+        let failure = match self {
+            Error::InvalidResponse => TransactionFailure::InvalidResponse,
+            Error::CrcMismatch => TransactionFailure::CrcMismatch,
+            Error::Timeout => TransactionFailure::Timeout,
+            Error::ModbusError(kind) => TransactionFailure::Exception(kind),
+            other => return other,
+        };
+        Error::Transaction { context, failure }
+    }
+}
+
+/// Whether a transaction read or wrote registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Operation {
+    Read,
+    Write,
+}
+
+/// The transaction an [`Error::Transaction`] happened in, to tell which device and register failed on a busy bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TransactionContext {
+    pub unit_id: u8,
+    pub operation: Operation,
+    /// Address of the first register.
+    pub address: u16,
+    /// Number of registers.
+    pub count: u16,
+}
+
+/// Formats as e.g. `read of 3 registers at 0x0010 on unit 1`.
+impl core::fmt::Display for TransactionContext {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let operation = match self.operation {
+            Operation::Read => "read",
+            Operation::Write => "write",
+        };
+        let plural = if self.count == 1 { "" } else { "s" };
+        write!(
+            f,
+            "{operation} of {} register{plural} at 0x{:04X} on unit {}",
+            self.count, self.address, self.unit_id
+        )
+    }
+}
+
+/// How a transaction failed, see [`Error::Transaction`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionFailure {
+    #[error("Invalid modbus response received")]
+    InvalidResponse,
+    #[error("Response CRC did not match")]
+    CrcMismatch,
+    #[error("Communication timeout")]
+    Timeout,
+    /// The PSU responded with a Modbus exception, e.g. for an address it doesn't implement.
+    #[error("Modbus exception: {0}")]
+    Exception(ModbusException),
+}
+
+/// Exception code with which the PSU refused a request.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ModbusException {
+    #[error("Illegal function")]
+    IllegalFunction,
+    /// The PSU doesn't implement the register, e.g. on another model or firmware.
+    #[error("Illegal data address")]
+    IllegalDataAddress,
+    #[error("Illegal data value")]
+    IllegalDataValue,
+    #[error("Slave device failure")]
+    SlaveDeviceFailure,
+    #[error("Acknowledge")]
+    Acknowledge,
+    #[error("Slave device busy")]
+    SlaveDeviceBusy,
+    #[error("Negative acknowledge")]
+    NegativeAcknowledge,
+    #[error("Memory parity error")]
+    MemoryParityError,
+    #[error("Gateway path unavailable")]
+    GatewayPathUnavailable,
+    #[error("Gateway target device failed to respond")]
+    GatewayTargetFailed,
+    #[error("Unknown exception code 0x{0:02X}")]
+    Unknown(u8),
+}
+
+impl ModbusException {
+    /// Interpret an exception code from a response.
+    pub fn from_code(code: u8) -> Self {
+        match code {
+            0x01 => ModbusException::IllegalFunction,
+            0x02 => ModbusException::IllegalDataAddress,
+            0x03 => ModbusException::IllegalDataValue,
+            0x04 => ModbusException::SlaveDeviceFailure,
+            0x05 => ModbusException::Acknowledge,
+            0x06 => ModbusException::SlaveDeviceBusy,
+            0x07 => ModbusException::NegativeAcknowledge,
+            0x08 => ModbusException::MemoryParityError,
+            0x0A => ModbusException::GatewayPathUnavailable,
+            0x0B => ModbusException::GatewayTargetFailed,
+            other => ModbusException::Unknown(other),
+        }
+    }
+
+    /// Return the exception code as sent on the wire.
+    pub fn code(&self) -> u8 {
+        match self {
+            ModbusException::IllegalFunction => 0x01,
+            ModbusException::IllegalDataAddress => 0x02,
+            ModbusException::IllegalDataValue => 0x03,
+            ModbusException::SlaveDeviceFailure => 0x04,
+            ModbusException::Acknowledge => 0x05,
+            ModbusException::SlaveDeviceBusy => 0x06,
+            ModbusException::NegativeAcknowledge => 0x07,
+            ModbusException::MemoryParityError => 0x08,
+            ModbusException::GatewayPathUnavailable => 0x0A,
+            ModbusException::GatewayTargetFailed => 0x0B,
+            ModbusException::Unknown(code) => *code,
+        }
     }
 }
 
@@ -46,3 +194,41 @@ impl<I: embedded_io::Error> From<()> for Error<I> {
         Error::Other
     }
 }
+
+/// The PSU reported a preset group which doesn't exist.
+#[cfg(feature = "presets")]
+impl<I: embedded_io::Error> From<crate::preset::InvalidPresetGroup> for Error<I> {
+    fn from(_: crate::preset::InvalidPresetGroup) -> Self {
+        Error::InvalidResponse
+    }
+}
+
+#[cfg(feature = "presets")]
+impl<I: embedded_io::Error> From<crate::preset::XyPresetBuilderError> for Error<I> {
+    fn from(_: crate::preset::XyPresetBuilderError) -> Self {
+        Error::InvalidRange
+    }
+}
+
+impl<I: embedded_io::Error> From<crate::scaling::ScalingFactorsError> for Error<I> {
+    fn from(_: crate::scaling::ScalingFactorsError) -> Self {
+        Error::InvalidRange
+    }
+}
+
+/// Serial errors are formatted with the [`core::fmt::Debug`] output of their kind, as it doesn't support defmt.
+#[cfg(feature = "defmt")]
+impl<I: embedded_io::Error> defmt::Format for Error<I> {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Error::SerialError(e) => {
+                defmt::write!(f, "SerialError({})", defmt::Debug2Format(&e.kind()))
+            }
+            Error::ModbusError(kind) => {
+                defmt::write!(f, "ModbusError({})", kind)
+            }
+            Error::UnknownModel(raw) => defmt::write!(f, "UnknownModel({=u16:#06x})", raw),
+            other => defmt::write!(f, "{}", defmt::Debug2Format(other)),
+        }
+    }
+}
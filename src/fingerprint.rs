@@ -0,0 +1,73 @@
+//! Identify physically identical devices so stored settings can be matched to the right unit.
+//!
+//! The product model and firmware version alone can't tell two units of the same model apart, so a [`Fingerprint`]
+//! can also include a "tag": the value of a register which the user has set to something unique for each device.
+//! A register from an otherwise unused preset group is a good choice, e.g. the voltage setting of group 9.
+
+use crate::{error::Result, psu::XyPsu, transport::Transport};
+
+/// Combination of values which identify a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fingerprint {
+    /// Raw value of the "MODEL" register.
+    pub model_raw: u16,
+    /// Raw value of the firmware "VERSION" register.
+    pub firmware_version: u16,
+    /// Value of the user chosen tag register, if one was used.
+    pub tag: Option<u16>,
+}
+
+/// Formats as `MODEL-VERSION` or `MODEL-VERSION-TAG`, with each part as 4 hex digits. E.g. `6500-0047-002A`.
+impl core::fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:04X}-{:04X}", self.model_raw, self.firmware_version)?;
+        if let Some(tag) = self.tag {
+            write!(f, "-{tag:04X}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Read the [`Fingerprint`] of this device.
+    ///
+    /// `tag_register` is the address of the register used to tag this device, or `None` to not use a tag.
+    ///
+    /// ```ignore
+    /// // Use the voltage setting of preset group 9 as the tag.
+    /// let tag_register = XyPresetOffsets::VSet.address_in_group(PresetGroup::Group9);
+    /// psu.write_modbus_single(tag_register, 42u16)?;
+    /// let fingerprint = psu.fingerprint(Some(tag_register))?;
+    /// ```
+    pub fn fingerprint(&mut self, tag_register: Option<u16>) -> Result<Fingerprint, S::Error> {
+        let model_raw = self.get_product_model_raw()?;
+        let firmware_version = self.get_firmware_version()?;
+        let tag = match tag_register {
+            Some(register) => Some(self.read_modbus_single(register)?),
+            None => None,
+        };
+        Ok(Fingerprint {
+            model_raw,
+            firmware_version,
+            tag,
+        })
+    }
+}
+
+#[cfg(all(test, not(feature = "no_std")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_display() {
+        let mut fingerprint = Fingerprint {
+            model_raw: 0x6500,
+            firmware_version: 71,
+            tag: None,
+        };
+        assert_eq!(format!("{fingerprint}"), "6500-0047");
+
+        fingerprint.tag = Some(42);
+        assert_eq!(format!("{fingerprint}"), "6500-0047-002A");
+    }
+}
@@ -0,0 +1,118 @@
+//! Volts, amps and watts as fixed-point numbers in the API, for fractional units on targets without an FPU.
+//!
+//! Only available with the `fixed` feature. Each method wraps the integer method of the same name with a unit suffix,
+//! e.g. [`XyPsu::set_output_voltage_fixed`] wraps [`XyPsu::set_output_voltage_mv`]. Values are [`I16F16`], which
+//! holds up to 32767 with a resolution far finer than the PSU's. They are rounded to the nearest millivolt/milliamp,
+//! and negative values are treated as zero.
+//!
+//! ```ignore
+//! use fixed::types::I16F16;
+//!
+//! psu.set_output_voltage_fixed(I16F16::from_num(12.5))?;
+//! psu.set_current_limit_fixed(I16F16::lit("1.25"))?;
+//! let power: Watts = psu.read_power_fixed()?;
+//! ```
+
+use crate::{error::Result, psu::XyPsu, transport::Transport};
+pub use fixed::types::I16F16;
+
+/// Volts as a fixed-point number.
+pub type Volts = I16F16;
+/// Amps as a fixed-point number.
+pub type Amps = I16F16;
+/// Watts as a fixed-point number.
+pub type Watts = I16F16;
+
+/// Convert thousandths of a unit, e.g. millivolts, into the unit. Saturates at [`I16F16::MAX`].
+pub const fn from_milli(value: u32) -> I16F16 {
+    let bits = (((value as i64) << 16) + 500) / 1000;
+    if bits > i32::MAX as i64 {
+        I16F16::MAX
+    } else {
+        I16F16::from_bits(bits as i32)
+    }
+}
+
+/// Convert a value into thousandths of its unit, e.g. volts into millivolts, rounding to the nearest. Negative
+/// values become 0.
+pub const fn to_milli(value: I16F16) -> u32 {
+    let bits = value.to_bits();
+    if bits <= 0 {
+        return 0;
+    }
+    ((bits as i64 * 1000 + (1 << 15)) >> 16) as u32
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// See [`Self::read_output_voltage_mv`].
+    pub fn read_output_voltage_fixed(&mut self) -> Result<Volts, S::Error> {
+        self.read_output_voltage_mv()
+            .map(|value| from_milli(value.0))
+    }
+
+    /// See [`Self::read_input_voltage_mv`].
+    pub fn read_input_voltage_fixed(&mut self) -> Result<Volts, S::Error> {
+        self.read_input_voltage_mv()
+            .map(|value| from_milli(value.0))
+    }
+
+    /// See [`Self::read_current_ma`].
+    pub fn read_current_fixed(&mut self) -> Result<Amps, S::Error> {
+        self.read_current_ma().map(|value| from_milli(value.0))
+    }
+
+    /// See [`Self::read_power_mw`].
+    pub fn read_power_fixed(&mut self) -> Result<Watts, S::Error> {
+        self.read_power_mw().map(|value| from_milli(value.0))
+    }
+
+    /// See [`Self::set_output_voltage_mv`].
+    pub fn set_output_voltage_fixed(&mut self, voltage: Volts) -> Result<(), S::Error> {
+        self.set_output_voltage_mv(to_milli(voltage))
+    }
+
+    /// See [`Self::get_output_voltage_mv`].
+    pub fn get_output_voltage_fixed(&mut self) -> Result<Volts, S::Error> {
+        self.get_output_voltage_mv()
+            .map(|value| from_milli(value.0))
+    }
+
+    /// See [`Self::set_current_limit_ma`].
+    pub fn set_current_limit_fixed(&mut self, current: Amps) -> Result<(), S::Error> {
+        self.set_current_limit_ma(to_milli(current))
+    }
+
+    /// See [`Self::get_current_limit_ma`].
+    pub fn get_current_limit_fixed(&mut self) -> Result<Amps, S::Error> {
+        self.get_current_limit_ma().map(|value| from_milli(value.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_serial::{MockResponse, MockSerial},
+        scaling::ScalingFactors,
+    };
+
+    #[test]
+    fn fixed_point_converted() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+
+        psu.interface_mut().queue_response(MockResponse::Echo);
+        psu.set_output_voltage_fixed(I16F16::lit("12.34")).unwrap();
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([0x00, 0x00, 0x04, 0xD2].as_slice())
+        );
+
+        psu.interface_mut().queue_read_response(0x01, &[1500]);
+        assert_eq!(psu.read_current_fixed().unwrap(), I16F16::lit("1.5"));
+
+        assert_eq!(to_milli(I16F16::lit("-1")), 0);
+        assert_eq!(to_milli(from_milli(12_345)), 12_345);
+        assert_eq!(from_milli(u32::MAX), I16F16::MAX);
+    }
+}
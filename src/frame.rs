@@ -0,0 +1,204 @@
+//! Modbus RTU request frames and checking of their responses, independent of how the bytes are sent.
+//!
+//! Shared by the blocking [`XyPsu`](crate::psu::XyPsu) and, with the `async` feature, `XyPsuAsync`.
+
+use crate::{
+    codec,
+    error::{Error, Operation, Result, TransactionContext},
+};
+
+/// Length of the response to a write: unit ID, function, address, value or count, and CRC.
+const WRITE_RESPONSE_LEN: usize = 8;
+/// Length of an exception response: unit ID, function with the top bit set, exception code, and CRC.
+const EXCEPTION_RESPONSE_LEN: usize = 5;
+
+/// What a request expects back from the PSU.
+#[derive(Debug, Clone, Copy)]
+enum Expect {
+    /// An echo of the register address and value for single register writes, or the start register address and
+    /// number of registers for multiple register writes.
+    Write,
+    /// Register values.
+    Registers,
+}
+
+/// An encoded request, and how to check its response.
+pub(crate) struct Frame<const L: usize> {
+    /// Bytes to send.
+    pub(crate) bytes: heapless::Vec<u8, L>,
+    expect: Expect,
+    /// Attached to errors if the transaction fails.
+    pub(crate) context: TransactionContext,
+}
+
+impl<const L: usize> Frame<L> {
+    /// Write `value` to a single register.
+    pub(crate) fn write_single<I: embedded_io::Error>(
+        unit_id: u8,
+        register: u16,
+        value: u16,
+    ) -> Result<Self, I> {
+        let mut bytes = heapless::Vec::new();
+        codec::encode_write_single(unit_id, register, value, &mut bytes)?;
+        Ok(Self {
+            bytes,
+            expect: Expect::Write,
+            context: TransactionContext {
+                unit_id,
+                operation: Operation::Write,
+                address: register,
+                count: 1,
+            },
+        })
+    }
+
+    /// Write `data` to sequential registers from `start_register`.
+    pub(crate) fn write_bulk<I: embedded_io::Error>(
+        unit_id: u8,
+        start_register: u16,
+        data: &[u16],
+    ) -> Result<Self, I> {
+        let mut bytes = heapless::Vec::new();
+        codec::encode_write_multiple(unit_id, start_register, data, &mut bytes)?;
+        Ok(Self {
+            bytes,
+            expect: Expect::Write,
+            context: TransactionContext {
+                unit_id,
+                operation: Operation::Write,
+                address: start_register,
+                count: u16::try_from(data.len()).unwrap_or(u16::MAX),
+            },
+        })
+    }
+
+    /// Read `count` sequential holding registers from `start_register`.
+    pub(crate) fn read<I: embedded_io::Error>(
+        unit_id: u8,
+        start_register: u16,
+        count: u16,
+    ) -> Result<Self, I> {
+        Self::read_with(
+            codec::READ_HOLDING_REGISTERS,
+            unit_id,
+            start_register,
+            count,
+        )
+    }
+
+    /// Read `count` sequential input registers from `start_register`.
+    pub(crate) fn read_input<I: embedded_io::Error>(
+        unit_id: u8,
+        start_register: u16,
+        count: u16,
+    ) -> Result<Self, I> {
+        Self::read_with(codec::READ_INPUT_REGISTERS, unit_id, start_register, count)
+    }
+
+    fn read_with<I: embedded_io::Error>(
+        function: u8,
+        unit_id: u8,
+        start_register: u16,
+        count: u16,
+    ) -> Result<Self, I> {
+        let mut bytes = heapless::Vec::new();
+        codec::encode_read(unit_id, function, start_register, count, &mut bytes)?;
+        Ok(Self {
+            bytes,
+            expect: Expect::Registers,
+            context: TransactionContext {
+                unit_id,
+                operation: Operation::Read,
+                address: start_register,
+                count,
+            },
+        })
+    }
+
+    /// Length of the response, once enough of it has been received to tell.
+    ///
+    /// Exception responses are shorter than normal ones, and register reads give their length in the third byte.
+    pub(crate) fn response_len(&self, response: &[u8]) -> Option<usize> {
+        let function = *response.get(1)?;
+        if function & 0x80 != 0 {
+            return Some(EXCEPTION_RESPONSE_LEN);
+        }
+        match self.expect {
+            Expect::Write => Some(WRITE_RESPONSE_LEN),
+            // Unit ID, function, byte count, data and CRC.
+            Expect::Registers => response.get(2).map(|&count| 5 + count as usize),
+        }
+    }
+
+    /// Add received `bytes` to `response`. Returns `true` once the whole response has been received.
+    pub(crate) fn receive<I: embedded_io::Error, const M: usize>(
+        &self,
+        response: &mut heapless::Vec<u8, M>,
+        bytes: &[u8],
+    ) -> Result<bool, I> {
+        response
+            .extend_from_slice(bytes)
+            .map_err(|_| Error::BufferError)?;
+        Ok(self
+            .response_len(response)
+            .is_some_and(|len| response.len() >= len))
+    }
+
+    /// Check `response`, returning the register values it holds, if any.
+    pub(crate) fn parse<I: embedded_io::Error>(
+        &self,
+        response: &[u8],
+    ) -> Result<heapless::Vec<u16, 64>, I> {
+        let [unit_id, function, ..] = *self.bytes else {
+            return Err(Error::BufferError);
+        };
+        if self.response_len(response) != Some(response.len()) {
+            return Err(Error::InvalidResponse);
+        }
+        let body = codec::check_response(unit_id, function, response)?;
+        let mut values = heapless::Vec::new();
+        match self.expect {
+            Expect::Write => {
+                if body.len() != WRITE_RESPONSE_LEN - 2 || self.bytes.get(2..6) != body.get(2..6) {
+                    return Err(Error::InvalidResponse);
+                }
+            }
+            Expect::Registers => {
+                let data = body.get(3..).ok_or(Error::InvalidResponse)?;
+                if body.get(2).map(|&count| count as usize) != Some(data.len())
+                    || data.len() != self.context.count as usize * 2
+                {
+                    return Err(Error::InvalidResponse);
+                }
+                for value in data.chunks_exact(2) {
+                    let [high, low] = *value else {
+                        return Err(Error::InvalidResponse);
+                    };
+                    values
+                        .push(u16::from_be_bytes([high, low]))
+                        .map_err(|_| Error::BufferError)?;
+                }
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// Whether a read error just marks the end of a response, rather than a failure.
+///
+/// If WouldBlock and we have some data, break and try to parse.
+pub(crate) fn ends_response(kind: embedded_io::ErrorKind, received: usize) -> bool {
+    matches!(
+        kind,
+        embedded_io::ErrorKind::Other | embedded_io::ErrorKind::TimedOut
+    ) && received > 0
+}
+
+/// Whether a transaction failed in a way which may succeed if tried again, e.g. due to line noise.
+pub(crate) fn is_transient<I: embedded_io::Error>(error: &Error<I>) -> bool {
+    match error {
+        Error::InvalidResponse | Error::CrcMismatch | Error::Timeout => true,
+        Error::SerialError(e) => e.kind() == embedded_io::ErrorKind::TimedOut,
+        _ => false,
+    }
+}
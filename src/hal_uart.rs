@@ -0,0 +1,195 @@
+//! HAL UARTs for [`XyPsuAsync`](crate::asynch::XyPsuAsync), without an embedded-io shim.
+//!
+//! embedded-hal-async has no UART traits, so HALs expose their UARTs for async use in one of two ways:
+//! - Embassy HALs (embassy-stm32, embassy-rp, embassy-nrf, ...) implement [`embedded_io_async::Read`] &
+//!   [`embedded_io_async::Write`] on their async UARTs, which are passed to `XyPsuAsync` as they are.
+//! - Most other HALs implement the non-blocking [`embedded_hal_nb::serial`] traits. [`HalUart`] adapts those, polling
+//!   the UART and yielding to the executor while it has no data.
+//!
+//! ```ignore
+//! let uart = hal::uart::Uart::new(peripherals.UART1, pins, 115_200.Hz());
+//! let mut psu: XyPsuAsync<_> = XyPsuAsync::new(HalUart::new(uart), 0x01);
+//! let voltage_mv = psu.read_output_voltage_mv().await?;
+//! ```
+//!
+//! Reads wait until the UART receives something, so set a response timeout with the `embassy` feature, see
+//! `XyPsuAsync::set_response_timeout`, or the driver waits forever for a PSU which doesn't answer.
+//!
+//! Only available with the `hal-uart` feature.
+
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use embedded_hal_nb::{
+    nb,
+    serial::{self, ErrorKind},
+};
+
+/// Error of a UART wrapped by [`HalUart`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UartError<E>(pub E);
+
+impl<E: serial::Error> embedded_io::Error for UartError<E> {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self.0.kind() {
+            ErrorKind::Overrun | ErrorKind::FrameFormat | ErrorKind::Parity | ErrorKind::Noise => {
+                embedded_io::ErrorKind::InvalidData
+            }
+            _ => embedded_io::ErrorKind::Other,
+        }
+    }
+}
+
+impl<E: core::fmt::Debug> core::fmt::Display for UartError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "UART error: {:?}", self.0)
+    }
+}
+
+impl<E: core::fmt::Debug> core::error::Error for UartError<E> {}
+
+/// Async interface over a UART implementing [`embedded_hal_nb::serial::Read`] & [`embedded_hal_nb::serial::Write`].
+///
+/// The UART is polled, yielding to the executor each time it would block, so other tasks run while a response is
+/// awaited. The task is woken again straight away, so the executor doesn't sleep while a transaction is in progress.
+#[derive(Debug)]
+pub struct HalUart<U> {
+    uart: U,
+}
+
+impl<U> HalUart<U> {
+    /// Wrap `uart`, which must already be configured as the PSUs expect: 8 data bits, no parity and 1 stop bit.
+    pub fn new(uart: U) -> Self {
+        Self { uart }
+    }
+
+    /// Unwrap the UART.
+    pub fn into_inner(self) -> U {
+        self.uart
+    }
+}
+
+impl<U: serial::ErrorType> embedded_io::ErrorType for HalUart<U> {
+    type Error = UartError<U::Error>;
+}
+
+impl<U: serial::Read + serial::ErrorType> embedded_io_async::Read for HalUart<U> {
+    /// Wait for at least one byte, then return those received without waiting for more.
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let mut read = 0;
+        for slot in buf.iter_mut() {
+            let byte = match self.uart.read() {
+                Ok(byte) => byte,
+                Err(nb::Error::WouldBlock) if read > 0 => break,
+                Err(nb::Error::WouldBlock) => poll(|| self.uart.read()).await?,
+                Err(nb::Error::Other(e)) => return Err(UartError(e)),
+            };
+            *slot = byte;
+            read += 1;
+        }
+        Ok(read)
+    }
+}
+
+impl<U: serial::Write + serial::ErrorType> embedded_io_async::Write for HalUart<U> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        for &byte in buf {
+            poll(|| self.uart.write(byte)).await?;
+        }
+        Ok(buf.len())
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        poll(|| self.uart.flush()).await
+    }
+}
+
+/// Call `f` until it stops returning `WouldBlock`, yielding to the executor between calls.
+async fn poll<T, E>(mut f: impl FnMut() -> nb::Result<T, E>) -> Result<T, UartError<E>> {
+    loop {
+        match f() {
+            Ok(value) => return Ok(value),
+            Err(nb::Error::WouldBlock) => YieldNow(false).await,
+            Err(nb::Error::Other(e)) => return Err(UartError(e)),
+        }
+    }
+}
+
+/// Future which is pending once, waking its task straight away.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asynch::XyPsuAsync;
+    use embassy_futures::block_on;
+
+    /// UART which blocks on every other call, and answers a read of one register holding 0x1234.
+    #[derive(Default)]
+    struct NbUart {
+        received: usize,
+        ready: bool,
+    }
+
+    impl NbUart {
+        fn ready(&mut self) -> nb::Result<(), core::convert::Infallible> {
+            self.ready = !self.ready;
+            if self.ready {
+                Ok(())
+            } else {
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
+
+    impl serial::ErrorType for NbUart {
+        type Error = core::convert::Infallible;
+    }
+
+    impl serial::Read for NbUart {
+        fn read(&mut self) -> nb::Result<u8, Self::Error> {
+            self.ready()?;
+            let response = [0x01, 0x03, 0x02, 0x12, 0x34, 0xB5, 0x33];
+            let byte = response.get(self.received).ok_or(nb::Error::WouldBlock)?;
+            self.received += 1;
+            Ok(*byte)
+        }
+    }
+
+    impl serial::Write for NbUart {
+        fn write(&mut self, _word: u8) -> nb::Result<(), Self::Error> {
+            self.ready()
+        }
+
+        fn flush(&mut self) -> nb::Result<(), Self::Error> {
+            self.ready()
+        }
+    }
+
+    #[test]
+    fn read_over_nb_uart() {
+        let mut psu: XyPsuAsync<_, 128> = XyPsuAsync::new(HalUart::new(NbUart::default()), 0x01);
+        assert_eq!(block_on(psu.read_modbus_single(0x00_u16)).unwrap(), 0x1234);
+        assert_eq!(
+            psu.last_transaction().request,
+            [0x01, 0x03, 0x00, 0x00, 0x00, 0x01, 0x84, 0x0A]
+        );
+    }
+}
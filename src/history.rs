@@ -0,0 +1,129 @@
+//! Keep the last few [`PsuStatus`] snapshots in RAM.
+//!
+//! Useful for drawing simple graphs on small displays, or dumping what led up to a protection trip.
+//!
+//! ```ignore
+//! let mut history: StatusHistory<64> = StatusHistory::new();
+//! loop {
+//!     let status = history.record_from(&mut psu)?;
+//!     if status.protection.any() {
+//!         for status in history.oldest_ordered() {
+//!             log_status(status);
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::{error::Result, psu::XyPsu, status::PsuStatus, transport::Transport};
+
+/// The last `N` [`PsuStatus`] snapshots.
+pub struct StatusHistory<const N: usize> {
+    buffer: heapless::HistoryBuf<PsuStatus, N>,
+}
+
+impl<const N: usize> Default for StatusHistory<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> StatusHistory<N> {
+    /// Create an empty history.
+    pub const fn new() -> Self {
+        Self {
+            buffer: heapless::HistoryBuf::new(),
+        }
+    }
+
+    /// Add a snapshot, dropping the oldest if full.
+    pub fn record(&mut self, status: PsuStatus) {
+        self.buffer.write(status);
+    }
+
+    /// Read a snapshot from `psu`, record it, and return it.
+    pub fn record_from<S: Transport, const L: usize>(
+        &mut self,
+        psu: &mut XyPsu<S, L>,
+    ) -> Result<PsuStatus, S::Error> {
+        let status = psu.read_status()?;
+        self.record(status);
+        Ok(status)
+    }
+
+    /// Return the most recent snapshot.
+    pub fn latest(&self) -> Option<&PsuStatus> {
+        self.buffer.recent()
+    }
+
+    /// Iterate over snapshots, oldest first.
+    pub fn oldest_ordered(&self) -> impl Iterator<Item = &PsuStatus> {
+        self.buffer.oldest_ordered()
+    }
+
+    /// Iterate over one value of each snapshot, oldest first. E.g. `history.series(|s| s.voltage_mv)` for a sparkline.
+    pub fn series<'a>(
+        &'a self,
+        value: impl Fn(&PsuStatus) -> u32 + 'a,
+    ) -> impl Iterator<Item = u32> + 'a {
+        self.oldest_ordered().map(value)
+    }
+
+    /// Return the smallest and largest of one value over all snapshots, e.g. to scale a graph.
+    pub fn range(&self, value: impl Fn(&PsuStatus) -> u32) -> Option<(u32, u32)> {
+        self.series(value).fold(None, |range, v| match range {
+            None => Some((v, v)),
+            Some((min, max)) => Some((min.min(v), max.max(v))),
+        })
+    }
+
+    /// Number of snapshots held.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Whether no snapshots are held.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Forget all snapshots.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::register::{ControlMode, ProtectionStatus, State, Temperature};
+
+    fn status(voltage_mv: u32) -> PsuStatus {
+        PsuStatus {
+            voltage_set_mv: 12000,
+            current_limit_ma: 1000,
+            voltage_mv,
+            current_ma: 500,
+            power_mw: 6000,
+            input_voltage_mv: 24000,
+            control_mode: ControlMode::Cv,
+            output: State::On,
+            lock: State::Off,
+            protection: ProtectionStatus::default(),
+            temperature_internal: Temperature::Celsius(30),
+        }
+    }
+
+    #[test]
+    fn history_keeps_most_recent() {
+        let mut history: StatusHistory<3> = StatusHistory::new();
+        assert_eq!(history.range(|s| s.voltage_mv), None);
+
+        for voltage_mv in [1000, 4000, 2000, 3000] {
+            history.record(status(voltage_mv));
+        }
+        assert_eq!(history.len(), 3);
+        assert!(history.series(|s| s.voltage_mv).eq([4000, 2000, 3000]));
+        assert_eq!(history.range(|s| s.voltage_mv), Some((2000, 4000)));
+        assert_eq!(history.latest().map(|s| s.voltage_mv), Some(3000));
+    }
+}
@@ -0,0 +1,434 @@
+//! Home Assistant MQTT discovery, so a PSU shows up in Home Assistant without any YAML.
+//!
+//! Only available with the `home-assistant` feature. The crate has no MQTT client of its own, so this builds the topics
+//! and payloads for the application to publish with whichever client it uses:
+//!
+//! ```ignore
+//! let discovery = Discovery::new("xypsu_bench", "Bench PSU", "xypsu/bench", psu.get_product_model()?);
+//! for entity in Entity::ALL {
+//!     let mut topic = heapless::String::<128>::new();
+//!     discovery.config_topic(entity, &mut topic)?;
+//!     let len = discovery.config_payload(entity, &mut buf)?;
+//!     mqtt.publish(&topic, &buf[..len], /* retain */ true)?;
+//!     if entity.is_controllable() {
+//!         topic.clear();
+//!         discovery.command_topic(entity, &mut topic)?;
+//!         mqtt.subscribe(&topic)?;
+//!     }
+//! }
+//! loop {
+//!     // Publish the state, a PsuStatus as telemetry JSON.
+//!     let len = telemetry::to_slice(&psu.read_status()?, &mut buf)?;
+//!     mqtt.publish(&state_topic, &buf[..len], false)?;
+//!     // Act on commands from Home Assistant.
+//!     if let Some(command) = discovery.parse_command(&message.topic, &message.payload) {
+//!         command.apply(&mut psu)?;
+//!     }
+//! }
+//! ```
+//!
+//! The entities are numbers for the voltage and current setpoints, a switch for the output, and sensors for the
+//! measured output voltage, current and power and the input voltage. Their state is read from the
+//! [`PsuStatus`](crate::status::PsuStatus) published to [`Discovery::state_topic`], and commands are received on
+//! `<base topic>/<entity>/set`.
+
+use core::fmt::{self, Write};
+
+use serde::{Serialize, Serializer};
+
+use crate::{
+    error::Result,
+    psu::XyPsu,
+    register::{ProductModel, State},
+    telemetry,
+    transport::Transport,
+};
+
+/// Prefix of the discovery topics, Home Assistant's default.
+const DISCOVERY_PREFIX: &str = "homeassistant";
+
+/// An entity of the PSU in Home Assistant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entity {
+    /// Number, the output voltage setting in volts.
+    VoltageSet,
+    /// Number, the output current limit in amps.
+    CurrentLimit,
+    /// Switch, whether the output is on.
+    Output,
+    /// Sensor, the measured output voltage.
+    Voltage,
+    /// Sensor, the measured output current.
+    Current,
+    /// Sensor, the measured output power.
+    Power,
+    /// Sensor, the measured input voltage.
+    InputVoltage,
+}
+
+impl Entity {
+    /// Every entity, to publish the discovery of each.
+    pub const ALL: [Entity; 7] = [
+        Entity::VoltageSet,
+        Entity::CurrentLimit,
+        Entity::Output,
+        Entity::Voltage,
+        Entity::Current,
+        Entity::Power,
+        Entity::InputVoltage,
+    ];
+
+    /// Home Assistant component of the entity, e.g. `sensor`.
+    pub const fn component(&self) -> &'static str {
+        match self {
+            Entity::VoltageSet | Entity::CurrentLimit => "number",
+            Entity::Output => "switch",
+            Entity::Voltage | Entity::Current | Entity::Power | Entity::InputVoltage => "sensor",
+        }
+    }
+
+    /// ID of the entity within the PSU, used in its topics and unique ID.
+    pub const fn object_id(&self) -> &'static str {
+        match self {
+            Entity::VoltageSet => "voltage_set",
+            Entity::CurrentLimit => "current_limit",
+            Entity::Output => "output",
+            Entity::Voltage => "voltage",
+            Entity::Current => "current",
+            Entity::Power => "power",
+            Entity::InputVoltage => "input_voltage",
+        }
+    }
+
+    /// Whether Home Assistant sends commands for the entity.
+    pub const fn is_controllable(&self) -> bool {
+        matches!(
+            self,
+            Entity::VoltageSet | Entity::CurrentLimit | Entity::Output
+        )
+    }
+
+    const fn name(&self) -> &'static str {
+        match self {
+            Entity::VoltageSet => "Voltage setpoint",
+            Entity::CurrentLimit => "Current limit",
+            Entity::Output => "Output",
+            Entity::Voltage => "Voltage",
+            Entity::Current => "Current",
+            Entity::Power => "Power",
+            Entity::InputVoltage => "Input voltage",
+        }
+    }
+
+    /// Template extracting the entity's state from the [`PsuStatus`](crate::status::PsuStatus) JSON.
+    const fn value_template(&self) -> &'static str {
+        match self {
+            Entity::VoltageSet => "{{ value_json.voltage_set_mv / 1000 }}",
+            Entity::CurrentLimit => "{{ value_json.current_limit_ma / 1000 }}",
+            Entity::Output => "{{ value_json.output }}",
+            Entity::Voltage => "{{ value_json.voltage_mv / 1000 }}",
+            Entity::Current => "{{ value_json.current_ma / 1000 }}",
+            Entity::Power => "{{ value_json.power_mw / 1000 }}",
+            Entity::InputVoltage => "{{ value_json.input_voltage_mv / 1000 }}",
+        }
+    }
+
+    /// Device class and unit.
+    const fn measurement(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Entity::VoltageSet | Entity::Voltage | Entity::InputVoltage => Some(("voltage", "V")),
+            Entity::CurrentLimit | Entity::Current => Some(("current", "A")),
+            Entity::Power => Some(("power", "W")),
+            Entity::Output => None,
+        }
+    }
+}
+
+/// A command from Home Assistant, see [`Discovery::parse_command`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    SetVoltageMv(u32),
+    SetCurrentMa(u32),
+    SetOutput(State),
+}
+
+impl Command {
+    /// Carry out the command on `psu`.
+    pub fn apply<S: Transport, const L: usize>(
+        self,
+        psu: &mut XyPsu<S, L>,
+    ) -> Result<(), S::Error> {
+        match self {
+            Command::SetVoltageMv(voltage_mv) => psu.set_output_voltage_mv(voltage_mv),
+            Command::SetCurrentMa(current_ma) => psu.set_current_limit_ma(current_ma),
+            Command::SetOutput(state) => psu.set_output_state(state),
+        }
+    }
+}
+
+/// Discovery topics and payloads for one PSU.
+#[derive(Debug)]
+pub struct Discovery<'a> {
+    node_id: &'a str,
+    name: &'a str,
+    base_topic: &'a str,
+    model: ProductModel,
+}
+
+impl<'a> Discovery<'a> {
+    /// Describe a PSU.
+    ///
+    /// * `node_id` - Unique ID of the PSU, e.g. `xypsu_bench`. Only letters, digits, `_` and `-` are allowed.
+    /// * `name` - Name of the device shown in Home Assistant.
+    /// * `base_topic` - Topic prefix for its state and commands, e.g. `xypsu/bench`.
+    /// * `model` - Model of the PSU, which sets the range of the setpoints.
+    pub fn new(node_id: &'a str, name: &'a str, base_topic: &'a str, model: ProductModel) -> Self {
+        Self {
+            node_id,
+            name,
+            base_topic,
+            model,
+        }
+    }
+
+    /// Write the topic to publish `entity`'s discovery payload to, with retain set.
+    pub fn config_topic(&self, entity: Entity, out: &mut impl Write) -> fmt::Result {
+        write!(
+            out,
+            "{DISCOVERY_PREFIX}/{}/{}/{}/config",
+            entity.component(),
+            self.node_id,
+            entity.object_id()
+        )
+    }
+
+    /// Write the topic to publish the [`PsuStatus`](crate::status::PsuStatus) to, encoded with
+    /// [`telemetry::to_slice`].
+    pub fn state_topic(&self, out: &mut impl Write) -> fmt::Result {
+        write!(out, "{}/state", self.base_topic)
+    }
+
+    /// Write the topic to subscribe to for `entity`'s commands. Does nothing if it has none.
+    pub fn command_topic(&self, entity: Entity, out: &mut impl Write) -> fmt::Result {
+        if !entity.is_controllable() {
+            return Ok(());
+        }
+        write!(out, "{}/{}/set", self.base_topic, entity.object_id())
+    }
+
+    /// Encode `entity`'s discovery payload into `buf`, returning its length. 1024 bytes fits any entity.
+    pub fn config_payload(
+        &self,
+        entity: Entity,
+        buf: &mut [u8],
+    ) -> core::result::Result<usize, telemetry::Error> {
+        let (max_mv, decimals) = match entity {
+            Entity::VoltageSet => (
+                self.model.limits().map(|l| l.max_voltage_mv),
+                self.model.specs().precision.map(|p| p.voltage_decimals),
+            ),
+            Entity::CurrentLimit => (
+                self.model.limits().map(|l| l.max_current_ma),
+                self.model.specs().precision.map(|p| p.current_decimals),
+            ),
+            _ => (None, None),
+        };
+        let number = matches!(entity, Entity::VoltageSet | Entity::CurrentLimit);
+
+        let config = Config {
+            name: entity.name(),
+            unique_id: Joined(self.node_id, "_", entity.object_id()),
+            state_topic: Joined(self.base_topic, "/", "state"),
+            value_template: entity.value_template(),
+            command_topic: entity
+                .is_controllable()
+                .then_some(Joined(self.base_topic, "/", entity.object_id()))
+                .map(CommandTopic),
+            device_class: entity.measurement().map(|(class, _)| class),
+            unit_of_measurement: entity.measurement().map(|(_, unit)| unit),
+            state_class: (entity.component() == "sensor").then_some("measurement"),
+            min: number.then_some(0.0),
+            max: max_mv.map(|max| max as f32 / 1000.0),
+            step: number.then(|| step(decimals.unwrap_or(2))),
+            mode: number.then_some("box"),
+            state_on: (entity == Entity::Output).then_some("on"),
+            state_off: (entity == Entity::Output).then_some("off"),
+            device: Device {
+                identifiers: [self.node_id],
+                name: self.name,
+                manufacturer: "Sinilink",
+                model: &self.model,
+            },
+        };
+        telemetry::to_slice(&config, buf)
+    }
+
+    /// Parse a message received on one of the command topics. Returns `None` if it isn't a valid command.
+    pub fn parse_command(&self, topic: &str, payload: &[u8]) -> Option<Command> {
+        let object_id = topic
+            .strip_prefix(self.base_topic)?
+            .strip_prefix('/')?
+            .strip_suffix("/set")?;
+        let payload = core::str::from_utf8(payload).ok()?.trim();
+        match Entity::ALL
+            .into_iter()
+            .find(|e| e.object_id() == object_id)?
+        {
+            Entity::VoltageSet => parse_milli(payload).map(Command::SetVoltageMv),
+            Entity::CurrentLimit => parse_milli(payload).map(Command::SetCurrentMa),
+            Entity::Output => match payload {
+                "ON" => Some(Command::SetOutput(State::On)),
+                "OFF" => Some(Command::SetOutput(State::Off)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Smallest step of a value shown with `decimals` decimals, e.g. 0.01 for 2.
+fn step(decimals: u8) -> f32 {
+    (0..decimals).fold(1.0, |step, _| step / 10.0)
+}
+
+/// Parse a decimal number in whole units, e.g. volts `12.5`, into thousandths, rounding off any further decimals.
+fn parse_milli(text: &str) -> Option<u32> {
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut milli = whole.parse::<u32>().ok()?.checked_mul(1000)?;
+    let mut digits = fraction.bytes().map(|b| u32::from(b - b'0'));
+    for scale in [100, 10, 1] {
+        milli = milli.checked_add(digits.next().unwrap_or(0) * scale)?;
+    }
+    if digits.next().is_some_and(|digit| digit >= 5) {
+        milli = milli.checked_add(1)?;
+    }
+    Some(milli)
+}
+
+/// Discovery payload, with the fields Home Assistant understands.
+#[derive(Serialize)]
+struct Config<'a> {
+    name: &'static str,
+    unique_id: Joined<'a>,
+    state_topic: Joined<'a>,
+    value_template: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command_topic: Option<CommandTopic<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device_class: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit_of_measurement: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state_class: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    step: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mode: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state_on: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state_off: Option<&'static str>,
+    device: Device<'a>,
+}
+
+#[derive(Serialize)]
+struct Device<'a> {
+    identifiers: [&'a str; 1],
+    name: &'a str,
+    manufacturer: &'static str,
+    #[serde(serialize_with = "serialize_display")]
+    model: &'a ProductModel,
+}
+
+/// Two strings joined by a separator, serialized as one string without allocating.
+struct Joined<'a>(&'a str, &'static str, &'static str);
+
+impl Serialize for Joined<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("{}{}{}", self.0, self.1, self.2))
+    }
+}
+
+/// The command topic of an entity, `<base topic>/<entity>/set`.
+struct CommandTopic<'a>(Joined<'a>);
+
+impl Serialize for CommandTopic<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        let Joined(base, separator, object_id) = &self.0;
+        serializer.collect_str(&format_args!("{base}{separator}{object_id}/set"))
+    }
+}
+
+fn serialize_display<S: Serializer>(
+    value: &&ProductModel,
+    serializer: S,
+) -> core::result::Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discovery_payloads_and_commands() {
+        let discovery = Discovery::new(
+            "xypsu_bench",
+            "Bench PSU",
+            "xypsu/bench",
+            ProductModel::XY6020L,
+        );
+
+        let mut topic = heapless::String::<128>::new();
+        discovery
+            .config_topic(Entity::VoltageSet, &mut topic)
+            .unwrap();
+        assert_eq!(topic, "homeassistant/number/xypsu_bench/voltage_set/config");
+
+        let mut buf = [0; 1024];
+        let len = discovery
+            .config_payload(Entity::VoltageSet, &mut buf)
+            .unwrap();
+        assert_eq!(
+            core::str::from_utf8(buf.get(..len).unwrap()).unwrap(),
+            concat!(
+                r#"{"name":"Voltage setpoint","unique_id":"xypsu_bench_voltage_set","state_topic":"xypsu/bench/state","#,
+                r#""value_template":"{{ value_json.voltage_set_mv / 1000 }}","command_topic":"xypsu/bench/voltage_set/set","#,
+                r#""device_class":"voltage","unit_of_measurement":"V","min":0.0,"max":60.0,"step":0.01,"mode":"box","#,
+                r#""device":{"identifiers":["xypsu_bench"],"name":"Bench PSU","manufacturer":"Sinilink","model":"XY6020L"}}"#
+            )
+        );
+        // Every entity fits.
+        for entity in Entity::ALL {
+            discovery.config_payload(entity, &mut buf).unwrap();
+        }
+
+        assert_eq!(
+            discovery.parse_command("xypsu/bench/voltage_set/set", b"12.5"),
+            Some(Command::SetVoltageMv(12500))
+        );
+        assert_eq!(
+            discovery.parse_command("xypsu/bench/current_limit/set", b"0.30000000000000004"),
+            Some(Command::SetCurrentMa(300))
+        );
+        assert_eq!(
+            discovery.parse_command("xypsu/bench/output/set", b"ON"),
+            Some(Command::SetOutput(State::On))
+        );
+        assert_eq!(
+            discovery.parse_command("xypsu/bench/voltage/set", b"1"),
+            None
+        );
+        assert_eq!(
+            discovery.parse_command("xypsu/other/output/set", b"ON"),
+            None
+        );
+    }
+}
@@ -2,7 +2,50 @@
 //!
 //! It supports `no_std` environments by use of the `no_std` feature flag.
 //!
-//! @TODO add table including electrical spec.
+//! No part of the driver panics, all failures are reported through [`error::Error`]. This is enforced with clippy
+//! lints on the crate.
+//!
+//! Preset groups and protection configuration are behind the default `presets` feature. Disabling it leaves just
+//! measurement and basic control, which saves a meaningful amount of flash on small MCUs.
+//!
+//! Durations use [`fugit`](https://docs.rs/fugit) with the default `fugit` feature. Without it, durations are available
+//! as [`core::time::Duration`] or plain seconds.
+//!
+//! Voltages, currents and power are given and returned as [`units`] newtypes, so they can't be mixed up, and plain
+//! `u32` values are still accepted.
+//!
+//! Voltages, currents and power can be given and returned as [`uom`](https://docs.rs/uom) quantities with the `uom`
+//! feature, see [`quantity`]. On targets without an FPU, they can be fixed-point numbers instead with the `fixed`
+//! feature, see `fixed_point`.
+//!
+//! An async driver, [`asynch::XyPsuAsync`], is available with the `async` feature. With the `tokio` feature,
+//! `tokio_adapter::TokioSerial` connects it to a serial port in tokio applications. With the `hal-uart` feature,
+//! `hal_uart::HalUart` connects it to HAL UARTs implementing the embedded-hal-nb serial traits.
+//!
+//! The PSU can be reached over any [`transport::Transport`], e.g. a serial port or a TCP gateway. Firmware which
+//! receives by DMA or interrupts can drive each transaction itself with [`split::XyPsuSplit`].
+//!
+//! Modbus RTU inter-frame silence can be kept with an `embedded-hal` delay, see `timing::RtuTiming`, and RS485
+//! transceivers without automatic direction driven with an `embedded-hal` pin, see `rs485::Rs485`, with the
+//! `embedded-hal` feature.
+//!
+//! Measurements and status can be encoded as compact JSON for dashboards with the `telemetry` feature, see
+//! `telemetry`. With the `home-assistant` feature, `home_assistant` builds the MQTT discovery payloads which make a PSU
+//! show up in Home Assistant.
+//!
+//! A whole device configuration can be copied to a replacement PSU, see [`config`]. With the `config-files` feature,
+//! bench setups are loaded from and saved to TOML.
+//!
+//! Every frame sent and received can be logged, through `log` or `defmt`, with the `trace-frames` feature.
+//!
+//! The `cli` feature builds `xypsu`, a command line bench tool for a PSU on a serial port. With the `tui` feature, its
+//! `monitor` command graphs the output live.
+//!
+//! Applications can be tested without hardware against `sim::SimulatedXyPsu`, with the `test-util` feature.
+//!
+//! Ratings, display precision and supported features of each model are available from
+//! [`ProductModel::specs`](register::ProductModel::specs). Boards which aren't known yet can be described at runtime,
+//! see [`XyPsu::register_model`](psu::XyPsu::register_model).
 //!
 //! Example PSU model numbers which this should work with:
 //! * XY6506
@@ -28,12 +71,91 @@
 //! * Parity: None
 
 #![cfg_attr(feature = "no_std", no_std)]
+// The driver is used in safety relevant firmware (e.g. battery chargers), so it must never panic.
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::panic,
+        clippy::unimplemented,
+        clippy::todo,
+        clippy::unreachable,
+        clippy::indexing_slicing
+    )
+)]
 
+#[cfg(feature = "async")]
+pub mod asynch;
+pub mod batch;
+pub mod battery;
+pub mod bus;
+pub mod cache;
+#[cfg(feature = "unsafe_calibration")]
+pub mod calibration;
+pub mod charger;
+pub mod clock;
+mod codec;
+pub mod composite;
+pub mod config;
+pub mod constant_power;
+mod dual;
+pub mod dump;
 pub mod error;
+pub mod fingerprint;
+#[cfg(feature = "fixed")]
+pub mod fixed_point;
+mod frame;
+#[cfg(feature = "hal-uart")]
+pub mod hal_uart;
+pub mod history;
+#[cfg(feature = "home-assistant")]
+pub mod home_assistant;
+pub mod limits;
+pub mod link;
+pub mod logger;
+pub mod monitor;
+pub mod mppt;
+pub mod panel;
+#[cfg(feature = "presets")]
 pub mod preset;
+pub mod protection;
 pub mod psu;
+#[cfg(feature = "uom")]
+pub mod quantity;
+pub mod quirks;
+pub mod ramp;
 pub mod register;
+pub mod register_map;
+#[cfg(feature = "embedded-hal")]
+pub mod rs485;
+pub mod scaled;
 pub mod scaling;
+pub mod scan;
+pub mod sequence;
+pub mod shared;
+#[cfg(feature = "test-util")]
+pub mod sim;
+pub mod specs;
+pub mod split;
+pub mod status;
+#[cfg(not(feature = "no_std"))]
+pub mod store;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+#[cfg(feature = "embedded-hal")]
+pub mod timing;
+#[cfg(all(feature = "tokio", not(feature = "no_std")))]
+pub mod tokio_adapter;
+#[cfg(feature = "trace-frames")]
+mod trace;
+pub mod transport;
+pub mod typed;
+pub mod units;
+pub mod watchdog;
+pub mod waveform;
+#[cfg(feature = "embedded-graphics")]
+pub mod widget;
 
 #[cfg(test)]
 mod mock_serial;
@@ -42,10 +164,8 @@ mod mock_serial;
 // * Determine units of all values and protections, based on setting and reading over modbus.
 //      * Update protection defaults to reflect this.
 // * Do we need a lookup table to establish bounds checking on values set?
-//     * Will loading presets enable/disable the output?
 // Unify use of get/read/set/write
 // * General support for presets.
-// * Make use of https://github.com/alttch/rmodbus?tab=readme-ov-file#custom-type-representations-in-u16-sized-registers ?
 // * Expose all functions/registers
 // Add conditional methods using float behind f32 feature flag
 
@@ -0,0 +1,80 @@
+//! Electrical limits of each model, so setpoints the hardware can't deliver are rejected rather than written.
+//!
+//! Limits are looked up alongside the scaling factors, when the model is first read. Scaled setters such as
+//! [`XyPsu::set_output_voltage_mv`](crate::psu::XyPsu::set_output_voltage_mv) return
+//! [`Error::InvalidRange`](crate::error::Error::InvalidRange) for values above them, or too large for the register.
+//! For models without known limits, or with scaling factors set manually, use
+//! [`XyPsu::set_limits`](crate::psu::XyPsu::set_limits).
+
+use crate::register::ProductModel;
+
+/// Maximum setpoints of a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ModelLimits {
+    /// Maximum output voltage in millivolts.
+    pub max_voltage_mv: u32,
+    /// Maximum output current in milliamps.
+    pub max_current_ma: u32,
+    /// Maximum output power in milliwatts, if known.
+    pub max_power_mw: Option<u32>,
+}
+
+impl ModelLimits {
+    pub const fn new(max_voltage_mv: u32, max_current_ma: u32, max_power_mw: Option<u32>) -> Self {
+        Self {
+            max_voltage_mv,
+            max_current_ma,
+            max_power_mw,
+        }
+    }
+}
+
+impl ProductModel {
+    /// Get the electrical limits of this product model.
+    ///
+    /// Voltage and current are the ratings in the model name, e.g. 60 V and 20 A for the XY6020L.
+    /// @TODO confirm the power ratings of the other models.
+    pub const fn limits(&self) -> Option<ModelLimits> {
+        match self {
+            ProductModel::XY3606B => Some(ModelLimits::new(36_000, 6_000, None)),
+            ProductModel::XY3607F => Some(ModelLimits::new(36_000, 7_000, None)),
+            ProductModel::XY6506 | ProductModel::XY6506S => {
+                Some(ModelLimits::new(65_000, 6_000, None))
+            }
+            ProductModel::XY6509 | ProductModel::XY6509X => {
+                Some(ModelLimits::new(65_000, 9_000, None))
+            }
+            ProductModel::XY7025 => Some(ModelLimits::new(70_000, 25_000, Some(1_750_000))),
+            ProductModel::XY12522 => Some(ModelLimits::new(125_000, 22_000, None)),
+            ProductModel::XY6020L => Some(ModelLimits::new(60_000, 20_000, Some(1_200_000))),
+            // The ratings aren't in the model name.
+            ProductModel::XYSK60S
+            | ProductModel::XYSK120S
+            | ProductModel::XYSK150S
+            | ProductModel::Unknown(_) => None,
+        }
+    }
+}
+
+/// Divide a setpoint down to its raw register value, or `None` if it is above `max`, or too large for the register.
+pub(crate) fn setpoint_to_raw(value: u32, max: Option<u32>, divisor: u32) -> Option<u16> {
+    if max.is_some_and(|max| value > max) {
+        return None;
+    }
+    u16::try_from(value.checked_div(divisor).unwrap_or(0)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setpoint_checked_against_limit_and_register() {
+        assert_eq!(setpoint_to_raw(60_000, Some(60_000), 10), Some(6000));
+        assert_eq!(setpoint_to_raw(60_010, Some(60_000), 10), None);
+        // No limit, but doesn't fit in the register.
+        assert_eq!(setpoint_to_raw(655_360, None, 10), None);
+        assert_eq!(setpoint_to_raw(655_350, None, 10), Some(u16::MAX));
+    }
+}
@@ -0,0 +1,108 @@
+//! Notice a lost link to the PSU quickly, e.g. an unplugged RS485 cable, without waiting for the next real command to
+//! fail.
+//!
+//! [`XyPsu::heartbeat`] reads the model register, which changes nothing on the PSU, and keeps count of how the link is
+//! doing. Call it every second or so from supervisory code.
+//!
+//! ```ignore
+//! loop {
+//!     if psu.heartbeat() == LinkHealth::Down {
+//!         alarm("PSU not responding");
+//!     }
+//!     sleep(Duration::from_secs(1));
+//! }
+//! ```
+
+use crate::{
+    psu::{FAILURES_BEFORE_REINIT, XyPsu},
+    transport::Transport,
+};
+
+/// State of the link, judged from the heartbeats in a row which failed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LinkHealth {
+    /// The last heartbeat succeeded, or none have been sent yet.
+    #[default]
+    Ok,
+    /// The last heartbeat failed, but fewer than [`FAILURES_BEFORE_REINIT`] have failed in a row, e.g. due to noise.
+    Degraded,
+    /// [`FAILURES_BEFORE_REINIT`] or more heartbeats have failed in a row.
+    Down,
+}
+
+/// Counts of the heartbeats, see [`XyPsu::link_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LinkStats {
+    pub health: LinkHealth,
+    /// Heartbeats sent.
+    pub heartbeats: u32,
+    /// Heartbeats which failed.
+    pub failures: u32,
+    /// Heartbeats which failed since the last one which succeeded.
+    pub consecutive_failures: u8,
+}
+
+impl LinkStats {
+    fn record(&mut self, success: bool) -> LinkHealth {
+        self.heartbeats = self.heartbeats.saturating_add(1);
+        if success {
+            self.consecutive_failures = 0;
+            self.health = LinkHealth::Ok;
+        } else {
+            self.failures = self.failures.saturating_add(1);
+            self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+            self.health = if self.consecutive_failures >= FAILURES_BEFORE_REINIT {
+                LinkHealth::Down
+            } else {
+                LinkHealth::Degraded
+            };
+        }
+        self.health
+    }
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Read the model register to check the PSU is responding, and return the link's health.
+    ///
+    /// Errors aren't returned, they count as failed heartbeats.
+    pub fn heartbeat(&mut self) -> LinkHealth {
+        let success = self.get_product_model_raw().is_ok();
+        self.link.record(success)
+    }
+
+    /// Return the counts of the heartbeats so far.
+    pub fn link_stats(&self) -> &LinkStats {
+        &self.link
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_serial::MockSerial;
+
+    #[test]
+    fn heartbeat_tracks_health() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+
+        psu.interface_mut().queue_read_response(0x01, &[25858]);
+        assert_eq!(psu.heartbeat(), LinkHealth::Ok);
+        // Nothing queued, so no response.
+        for health in [LinkHealth::Degraded, LinkHealth::Degraded, LinkHealth::Down] {
+            assert_eq!(psu.heartbeat(), health);
+        }
+        psu.interface_mut().queue_read_response(0x01, &[25858]);
+        assert_eq!(psu.heartbeat(), LinkHealth::Ok);
+        assert_eq!(
+            psu.link_stats(),
+            &LinkStats {
+                health: LinkHealth::Ok,
+                heartbeats: 5,
+                failures: 3,
+                consecutive_failures: 0,
+            }
+        );
+    }
+}
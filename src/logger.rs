@@ -0,0 +1,328 @@
+//! Log chosen registers at a fixed interval, keeping the last few samples in RAM.
+//!
+//! Unlike [`StatusHistory`](crate::history::StatusHistory), which keeps whole snapshots, [`PsuLogger`] keeps just the
+//! raw values of the registers it is given, so more samples fit in the same memory. Samples are timestamped with a
+//! user-provided clock, in whatever unit it counts in.
+//!
+//! ```ignore
+//! let start = Instant::now();
+//! let mut logger: PsuLogger<_, 2, 256> =
+//!     PsuLogger::new([XyRegister::VOut, XyRegister::IOut], 1000, || start.elapsed().as_millis() as u64);
+//! loop {
+//!     logger.poll(&mut psu)?;
+//!     sleep(Duration::from_millis(100));
+//! }
+//! ```
+//!
+//! With std, samples can be written as CSV by a [`CsvWriter`], e.g. to a file for a spreadsheet or pandas:
+//!
+//! ```ignore
+//! let scaling = CsvUnits::Scaled(psu.scaling_factors().unwrap_or_default());
+//! let mut csv = CsvWriter::new(File::create("session.csv")?, logger.registers(), &[XyRegister::VOut], scaling)?;
+//! if let Some(sample) = logger.poll(&mut psu)? {
+//!     csv.write_sample(sample)?;
+//! }
+//! ```
+
+use crate::{
+    error::{Error, Result},
+    psu::XyPsu,
+    register::XyRegister,
+    transport::Transport,
+};
+#[cfg(not(feature = "no_std"))]
+use crate::{scaling::ScalingFactors, status::Milli, typed::Unit};
+
+/// Raw register values read at one time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample<const R: usize> {
+    /// Time from the logger's clock when the sample was taken.
+    pub timestamp: u64,
+    /// Raw value of each register, in the order given to [`PsuLogger::new`].
+    pub values: [u16; R],
+}
+
+/// Logs `R` registers, keeping the last `N` samples.
+pub struct PsuLogger<C: FnMut() -> u64, const R: usize, const N: usize> {
+    registers: [XyRegister; R],
+    interval: u64,
+    clock: C,
+    last_timestamp: Option<u64>,
+    samples: heapless::HistoryBuf<Sample<R>, N>,
+}
+
+impl<C: FnMut() -> u64, const R: usize, const N: usize> PsuLogger<C, R, N> {
+    /// Log `registers` every `interval`, timestamping with `clock`. `interval` is in the clock's unit.
+    pub fn new(registers: [XyRegister; R], interval: u64, clock: C) -> Self {
+        Self {
+            registers,
+            interval,
+            clock,
+            last_timestamp: None,
+            samples: heapless::HistoryBuf::new(),
+        }
+    }
+
+    /// Return the logged registers.
+    pub fn registers(&self) -> &[XyRegister; R] {
+        &self.registers
+    }
+
+    /// Take a sample if at least the interval has passed since the last one, dropping the oldest sample if full.
+    ///
+    /// Call more often than the interval. Returns the new sample, if one was taken.
+    pub fn poll<S: Transport, const L: usize>(
+        &mut self,
+        psu: &mut XyPsu<S, L>,
+    ) -> Result<Option<&Sample<R>>, S::Error> {
+        let now = (self.clock)();
+        if let Some(last) = self.last_timestamp
+            && now.saturating_sub(last) < self.interval
+        {
+            return Ok(None);
+        }
+        self.sample_at(psu, now).map(Some)
+    }
+
+    /// Take a sample now, regardless of the interval.
+    pub fn sample<S: Transport, const L: usize>(
+        &mut self,
+        psu: &mut XyPsu<S, L>,
+    ) -> Result<&Sample<R>, S::Error> {
+        let now = (self.clock)();
+        self.sample_at(psu, now)
+    }
+
+    fn sample_at<S: Transport, const L: usize>(
+        &mut self,
+        psu: &mut XyPsu<S, L>,
+        timestamp: u64,
+    ) -> Result<&Sample<R>, S::Error> {
+        let mut values = [0; R];
+        for (value, register) in values.iter_mut().zip(self.registers) {
+            *value = psu.read_register(register)?;
+        }
+        self.last_timestamp = Some(timestamp);
+        self.samples.write(Sample { timestamp, values });
+        self.samples.recent().ok_or(Error::BufferError)
+    }
+
+    /// Return the most recent sample.
+    pub fn latest(&self) -> Option<&Sample<R>> {
+        self.samples.recent()
+    }
+
+    /// Iterate over samples, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &Sample<R>> {
+        self.samples.oldest_ordered()
+    }
+
+    /// Number of samples held.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Whether no samples are held.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Forget all samples. The next [`Self::poll`] samples straight away.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.last_timestamp = None;
+    }
+}
+
+/// How [`CsvWriter`] writes register values.
+#[cfg(not(feature = "no_std"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvUnits {
+    /// Raw register values, as read.
+    Raw,
+    /// Volts, amps and watts converted with the given scaling factors, and temperatures in degrees. Other values are
+    /// raw.
+    Scaled(ScalingFactors),
+}
+
+/// Writes [`Sample`]s as CSV rows: the timestamp, then the chosen columns.
+#[cfg(not(feature = "no_std"))]
+#[derive(Debug)]
+pub struct CsvWriter<W: std::io::Write> {
+    out: W,
+    /// Index into the sample's values and register of each column.
+    columns: Vec<(usize, XyRegister)>,
+    units: CsvUnits,
+}
+
+#[cfg(not(feature = "no_std"))]
+impl<W: std::io::Write> CsvWriter<W> {
+    /// Write the header row to `out`, for `columns` out of the `logged` registers, e.g. `timestamp,VOut (V),IOut (A)`.
+    ///
+    /// Returns an `InvalidInput` error if a column isn't logged.
+    pub fn new(
+        mut out: W,
+        logged: &[XyRegister],
+        columns: &[XyRegister],
+        units: CsvUnits,
+    ) -> std::io::Result<Self> {
+        let columns = columns
+            .iter()
+            .map(|column| {
+                let index = logged
+                    .iter()
+                    .position(|logged| logged == column)
+                    .ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!("{column:?} isn't logged"),
+                        )
+                    })?;
+                Ok((index, *column))
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        write!(out, "timestamp")?;
+        for (_, register) in &columns {
+            match (units, Self::unit_suffix(*register)) {
+                (CsvUnits::Scaled(_), Some(suffix)) => write!(out, ",{register:?} ({suffix})")?,
+                _ => write!(out, ",{register:?}")?,
+            }
+        }
+        writeln!(out)?;
+        Ok(Self {
+            out,
+            columns,
+            units,
+        })
+    }
+
+    /// Write one row for `sample`.
+    pub fn write_sample<const R: usize>(&mut self, sample: &Sample<R>) -> std::io::Result<()> {
+        write!(self.out, "{}", sample.timestamp)?;
+        for (index, register) in &self.columns {
+            let raw = sample.values.get(*index).copied().unwrap_or_default();
+            let CsvUnits::Scaled(scaling) = self.units else {
+                write!(self.out, ",{raw}")?;
+                continue;
+            };
+            match Unit::of(*register) {
+                Unit::Millivolts => write!(self.out, ",{}", Milli(scaling.raw_to_voltage_mv(raw)))?,
+                Unit::Milliamps => write!(self.out, ",{}", Milli(scaling.raw_to_current_ma(raw)))?,
+                Unit::Milliwatts => write!(self.out, ",{}", Milli(scaling.raw_to_power_mw(raw)))?,
+                Unit::CentiDegrees => write!(self.out, ",{}.{:02}", raw / 100, raw % 100)?,
+                _ => write!(self.out, ",{raw}")?,
+            }
+        }
+        writeln!(self.out)
+    }
+
+    /// Write a row for every sample held by `logger`, oldest first.
+    pub fn write_all<C: FnMut() -> u64, const R: usize, const N: usize>(
+        &mut self,
+        logger: &PsuLogger<C, R, N>,
+    ) -> std::io::Result<()> {
+        logger
+            .iter()
+            .try_for_each(|sample| self.write_sample(sample))
+    }
+
+    /// Flush and unwrap the output.
+    pub fn into_inner(mut self) -> std::io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    /// Unit shown in the header of a scaled column, if any.
+    fn unit_suffix(register: XyRegister) -> Option<&'static str> {
+        match Unit::of(register) {
+            Unit::Millivolts => Some("V"),
+            Unit::Milliamps => Some("A"),
+            Unit::Milliwatts | Unit::Watts => Some("W"),
+            Unit::Hours => Some("h"),
+            Unit::Minutes => Some("min"),
+            Unit::Seconds => Some("s"),
+            Unit::CentiDegrees => Some("°"),
+            Unit::None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_serial::MockSerial;
+    use core::cell::Cell;
+
+    #[test]
+    fn logger_samples_at_interval() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        let now = Cell::new(0);
+        let mut logger: PsuLogger<_, 2, 2> =
+            PsuLogger::new([XyRegister::VOut, XyRegister::IOut], 100, || now.get());
+
+        for (time, voltage) in [(0, 1200), (50, 0), (100, 1201), (250, 1202)] {
+            now.set(time);
+            if voltage != 0 {
+                psu.interface_mut().queue_read_response(0x01, &[voltage]);
+                psu.interface_mut().queue_read_response(0x01, &[500]);
+            }
+            let sampled = logger.poll(&mut psu).unwrap().is_some();
+            assert_eq!(sampled, voltage != 0);
+        }
+
+        // Oldest dropped.
+        assert_eq!(logger.len(), 2);
+        assert!(
+            logger
+                .iter()
+                .map(|s| (s.timestamp, s.values))
+                .eq([(100, [1201, 500]), (250, [1202, 500])])
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "no_std"))]
+    fn csv_columns_and_units() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        let mut logger: PsuLogger<_, 2, 4> =
+            PsuLogger::new([XyRegister::VOut, XyRegister::IOut], 100, || 1500);
+        psu.interface_mut().queue_read_response(0x01, &[1201]);
+        psu.interface_mut().queue_read_response(0x01, &[534]);
+        logger.sample(&mut psu).unwrap();
+
+        let scaled = CsvUnits::Scaled(ScalingFactors::new(10, 1, 10, 1, 10).unwrap());
+        let mut csv = CsvWriter::new(
+            Vec::new(),
+            logger.registers(),
+            &[XyRegister::IOut, XyRegister::VOut],
+            scaled,
+        )
+        .unwrap();
+        csv.write_all(&logger).unwrap();
+        assert_eq!(
+            String::from_utf8(csv.into_inner().unwrap()).unwrap(),
+            "timestamp,IOut (A),VOut (V)\n1500,0.534,12.010\n"
+        );
+
+        let mut csv = CsvWriter::new(
+            Vec::new(),
+            logger.registers(),
+            &[XyRegister::VOut],
+            CsvUnits::Raw,
+        )
+        .unwrap();
+        csv.write_all(&logger).unwrap();
+        assert_eq!(csv.into_inner().unwrap(), b"timestamp,VOut\n1500,1201\n");
+
+        assert!(
+            CsvWriter::new(
+                Vec::new(),
+                logger.registers(),
+                &[XyRegister::Power],
+                CsvUnits::Raw
+            )
+            .is_err()
+        );
+    }
+}
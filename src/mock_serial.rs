@@ -1,5 +1,14 @@
 //! We use this mocking module in unit tests to emulate a serial port.
 
+/// A response the mock will give to the next request written to it.
+#[allow(clippy::large_enum_variant)]
+pub enum MockResponse {
+    /// Respond with exactly what was written, as the PSU does for single register writes.
+    Echo,
+    /// Respond with these bytes.
+    Data(heapless::Vec<u8, 256>),
+}
+
 /// Our mock type used to emulate a serial port.
 pub struct MockSerial {
     /// Buffer to store data written to the mock serial port
@@ -12,6 +21,8 @@ pub struct MockSerial {
     should_error_on_write: bool,
     /// Flag to simulate read errors
     should_error_on_read: bool,
+    /// Responses which replace the read buffer each time a request is written.
+    responses: heapless::Deque<MockResponse, 16>,
 }
 
 #[derive(Debug)]
@@ -77,6 +88,14 @@ impl embedded_io::Write for MockSerial {
                 .map_err(|_| MockSerialError::BufferOverflow)?;
         }
 
+        // Load the response to this request, if one has been queued.
+        if let Some(response) = self.responses.pop_front() {
+            match response {
+                MockResponse::Echo => self.set_read_data(buf)?,
+                MockResponse::Data(data) => self.set_read_data(&data)?,
+            }
+        }
+
         Ok(buf.len())
     }
 
@@ -101,15 +120,33 @@ impl embedded_io::Read for MockSerial {
         let available_bytes = self.read_buffer.len() - self.read_position;
         let bytes_to_read = core::cmp::min(buf.len(), available_bytes);
 
-        for i in 0..bytes_to_read {
-            buf[i] = self.read_buffer[self.read_position + i];
-        }
+        buf[..bytes_to_read].copy_from_slice(
+            &self.read_buffer[self.read_position..self.read_position + bytes_to_read],
+        );
 
         self.read_position += bytes_to_read;
         Ok(bytes_to_read)
     }
 }
 
+#[cfg(feature = "async")]
+impl embedded_io_async::Write for MockSerial {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        embedded_io::Write::write(self, buf)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        embedded_io::Write::flush(self)
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_io_async::Read for MockSerial {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        embedded_io::Read::read(self, buf)
+    }
+}
+
 impl MockSerial {
     /// Create a new MockSerial instance with empty buffers
     pub fn new() -> Self {
@@ -119,9 +156,24 @@ impl MockSerial {
             read_position: 0,
             should_error_on_write: false,
             should_error_on_read: false,
+            responses: heapless::Deque::new(),
         }
     }
 
+    /// Queue a response to be given to the next request which is written.
+    ///
+    /// Queued responses are used in order, one per write.
+    pub fn queue_response(&mut self, response: MockResponse) {
+        if self.responses.push_back(response).is_err() {
+            panic!("Too many mock responses queued");
+        }
+    }
+
+    /// Queue a valid "read holding registers" response containing `values`.
+    pub fn queue_read_response(&mut self, unit_id: u8, values: &[u16]) {
+        self.queue_response(MockResponse::Data(read_response(unit_id, values)));
+    }
+
     /// Set the data that will be returned when read() is called
     pub fn set_read_data(&mut self, data: &[u8]) -> Result<(), MockSerialError> {
         self.read_buffer.clear();
@@ -162,6 +214,36 @@ impl MockSerial {
     }
 }
 
+/// Calculate the Modbus RTU CRC of `data`, in the order it is sent on the wire.
+pub fn crc16(data: &[u8]) -> [u8; 2] {
+    let mut crc: u16 = 0xFFFF;
+    for &byte in data {
+        crc ^= byte as u16;
+        for _ in 0..8 {
+            if crc & 1 != 0 {
+                crc = (crc >> 1) ^ 0xA001;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    crc.to_le_bytes()
+}
+
+/// Build a valid "read holding registers" response frame containing `values`.
+pub fn read_response(unit_id: u8, values: &[u16]) -> heapless::Vec<u8, 256> {
+    let mut frame: heapless::Vec<u8, 256> = heapless::Vec::new();
+    frame.push(unit_id).unwrap();
+    frame.push(0x03).unwrap();
+    frame.push((values.len() * 2) as u8).unwrap();
+    for value in values {
+        frame.extend_from_slice(&value.to_be_bytes()).unwrap();
+    }
+    let crc = crc16(&frame);
+    frame.extend_from_slice(&crc).unwrap();
+    frame
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -172,8 +254,8 @@ mod tests {
         let mock = MockSerial::new();
         assert_eq!(mock.written_data().len(), 0);
         assert_eq!(mock.read_position, 0);
-        assert_eq!(mock.should_error_on_write, false);
-        assert_eq!(mock.should_error_on_read, false);
+        assert!(!mock.should_error_on_write);
+        assert!(!mock.should_error_on_read);
     }
 
     #[test]
@@ -416,6 +498,35 @@ mod tests {
         assert_eq!(&buffer[..6], b"second");
     }
 
+    #[test]
+    fn test_crc16() {
+        // Known good frame from the PSU tests.
+        assert_eq!(crc16(&[0x01, 0x03, 0x02, 0x56, 0x78]), [0x87, 0xC6]);
+        assert_eq!(
+            read_response(0x01, &[500]).as_slice(),
+            [0x01, 0x03, 0x02, 0x01, 0xF4, 0xB8, 0x53]
+        );
+    }
+
+    #[test]
+    fn test_queued_responses() {
+        let mut mock = MockSerial::new();
+        mock.queue_response(MockResponse::Echo);
+        mock.queue_response(MockResponse::Data(
+            heapless::Vec::from_slice(b"second").unwrap(),
+        ));
+
+        let mut buffer = [0u8; 10];
+        mock.write(b"first").unwrap();
+        assert_eq!(mock.read(&mut buffer).unwrap(), 5);
+        assert_eq!(&buffer[..5], b"first");
+        assert!(mock.read(&mut buffer).is_err());
+
+        mock.write(b"request").unwrap();
+        assert_eq!(mock.read(&mut buffer).unwrap(), 6);
+        assert_eq!(&buffer[..6], b"second");
+    }
+
     #[test]
     fn test_error_flags_toggle() {
         let mut mock = MockSerial::new();
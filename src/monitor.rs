@@ -0,0 +1,150 @@
+//! Read-only handle to a PSU, for telemetry.
+//!
+//! A [`MonitorPsu`] only exposes methods which read from the PSU, so a telemetry task can be handed one without any
+//! chance of it changing setpoints or turning the output on or off.
+//!
+//! ```ignore
+//! let psu = XyPsu::<_, 128>::new(serial, 0x01);
+//! let mut monitor = MonitorPsu::from(psu);
+//! let voltage_mv = monitor.read_output_voltage_mv()?;
+//! ```
+
+#[cfg(feature = "presets")]
+use crate::preset::{PresetGroup, ProtectionConfig, XyPreset};
+use crate::{
+    error::Result,
+    fingerprint::Fingerprint,
+    panel::PanelChange,
+    psu::{MpptConfig, XyPsu},
+    register::{
+        BacklightBrightness, BaudRate, ControlMode, ProductModel, ProtectionStatus, State,
+        Temperature, TemperatureUnit,
+    },
+    scaling::ScalingFactors,
+    transport::Transport,
+    typed::Register,
+    units::{Milliamps, Millivolts, Milliwatts},
+};
+
+/// Read-only wrapper around an [`XyPsu`].
+///
+/// There is deliberately no way to get the [`XyPsu`] back out.
+pub struct MonitorPsu<S: Transport, const L: usize = 128> {
+    psu: XyPsu<S, L>,
+}
+
+impl<S: Transport, const L: usize> From<XyPsu<S, L>> for MonitorPsu<S, L> {
+    fn from(psu: XyPsu<S, L>) -> Self {
+        Self { psu }
+    }
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Convert into a read-only [`MonitorPsu`].
+    pub fn into_monitor(self) -> MonitorPsu<S, L> {
+        MonitorPsu::from(self)
+    }
+}
+
+/// Forward read-only methods to the wrapped [`XyPsu`].
+macro_rules! forward {
+    ($($(#[$attr:meta])* fn $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty;)*) => {
+        $(
+            #[doc = concat!("See [`XyPsu::", stringify!($name), "`].")]
+            $(#[$attr])*
+            pub fn $name(&mut self, $($arg: $ty),*) -> Result<$ret, S::Error> {
+                self.psu.$name($($arg),*)
+            }
+        )*
+    };
+}
+
+impl<S: Transport, const L: usize> MonitorPsu<S, L> {
+    /// Return the Modbus unit ID used to talk to this PSU.
+    pub fn unit_id(&self) -> u8 {
+        self.psu.unit_id()
+    }
+
+    /// Return the scaling factors in use, if they are known yet.
+    pub fn scaling_factors(&self) -> Option<ScalingFactors> {
+        self.psu.scaling_factors()
+    }
+
+    /// See [`XyPsu::get`].
+    pub fn get<R: Register>(&mut self) -> Result<R::Value, S::Error> {
+        self.psu.get::<R>()
+    }
+
+    forward! {
+        fn read_output_voltage_mv() -> Millivolts;
+        fn read_input_voltage_mv() -> Millivolts;
+        fn read_current_ma() -> Milliamps;
+        fn read_power_mw() -> Milliwatts;
+        fn read_energy_mwh() -> u32;
+        fn read_capacity_mah() -> u32;
+        #[cfg(feature = "fugit")]
+        fn read_output_time() -> fugit::Duration<u32, 1, 1>;
+        fn read_output_duration() -> core::time::Duration;
+        #[cfg(not(feature = "no_std"))]
+        fn read_output_time_std() -> std::time::Duration;
+        fn read_output_time_secs() -> u32;
+        fn read_temperature_internal() -> Temperature;
+        fn read_temperature_external() -> Temperature;
+        fn get_lock_state() -> State;
+        fn is_locked() -> bool;
+        fn get_current_control_mode() -> ControlMode;
+        fn get_output_state() -> State;
+        fn is_output_enabled() -> bool;
+        fn get_firmware_version() -> u16;
+        fn get_slave_address() -> u8;
+        fn get_baudrate() -> BaudRate;
+        fn get_temperature_unit() -> TemperatureUnit;
+        fn get_output_voltage_mv() -> Millivolts;
+        fn get_current_limit_ma() -> Milliamps;
+        fn get_product_model_raw() -> u16;
+        fn get_product_model() -> ProductModel;
+        fn get_protection_status() -> ProtectionStatus;
+        fn get_backlight() -> BacklightBrightness;
+        fn get_buzzer_enabled() -> State;
+        fn is_buzzer_enabled() -> bool;
+        fn get_screen_timeout() -> core::time::Duration;
+        #[cfg(feature = "presets")]
+        fn get_active_preset() -> PresetGroup;
+        #[cfg(feature = "presets")]
+        fn get_protections() -> ProtectionConfig;
+        #[cfg(feature = "presets")]
+        fn read_preset(group: PresetGroup) -> XyPreset;
+        fn get_sleep_state() -> State;
+        fn is_sleeping() -> bool;
+        fn get_temperature_offset_internal_raw() -> u16;
+        fn get_temperature_offset_external_raw() -> u16;
+        fn get_mppt_enabled() -> State;
+        fn get_mppt_k_value() -> u16;
+        fn get_constant_power_enabled() -> State;
+        fn get_constant_power_level() -> u16;
+        fn get_constant_power_mw() -> u32;
+        fn get_input_current_limit_ma() -> u32;
+        fn get_battery_full_current_ma() -> u32;
+        fn get_mppt_config() -> MpptConfig;
+        fn fingerprint(tag_register: Option<u16>) -> Fingerprint;
+        fn poll_panel_changes() -> heapless::Vec<PanelChange, 5>;
+        fn read_modbus_single(register: u16) -> u16;
+        fn read_modbus_bulk(start_register: u16, count: u16) -> heapless::Vec<u16, 64>;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_serial::MockSerial;
+
+    #[test]
+    fn monitor_reads_through() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.interface_mut().queue_read_response(0x01, &[0x6500]);
+
+        let mut monitor = psu.into_monitor();
+        assert_eq!(monitor.get_product_model_raw().unwrap(), 0x6500);
+        assert_eq!(monitor.unit_id(), 0x01);
+    }
+}
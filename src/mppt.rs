@@ -0,0 +1,152 @@
+//! Find the best MPPT coefficient for a solar panel, instead of guessing between 0.75 and 0.85.
+//!
+//! [`XyPsu::sweep_mppt_k`] steps the coefficient over a range, lets the output settle at each value, and averages the
+//! harvested power. Best run in steady sunlight, since a cloud passing during the sweep skews the result.
+//!
+//! ```ignore
+//! let best = psu.sweep_mppt_k(
+//!     MpptSweep::new(70, 90),
+//!     |ms| {
+//!         sleep(Duration::from_millis(ms.into()));
+//!         true
+//!     },
+//!     |point| println!("K {:.2}: {}mW", point.k_value as f32 / 100.0, point.power_mw),
+//! )?;
+//! ```
+
+use crate::{error::Result, psu::XyPsu, transport::Transport};
+
+/// Range and timing of a sweep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MpptSweep {
+    /// First coefficient tried, 100x what is shown on the display.
+    pub from_k: u16,
+    /// Last coefficient tried, 100x what is shown on the display.
+    pub to_k: u16,
+    /// Change of the coefficient between points. Default 1, a value of 0 is treated as 1.
+    pub step: u16,
+    /// Time to let the output settle after changing the coefficient, in milliseconds. Default 5s.
+    pub settle_ms: u32,
+    /// Number of power readings averaged at each coefficient. Default 5, a value of 0 is treated as 1.
+    pub samples: u8,
+    /// Time between power readings, in milliseconds. Default 1s.
+    pub sample_interval_ms: u32,
+}
+
+impl MpptSweep {
+    /// Sweep from `from_k` to `to_k`, with the default step and timing.
+    pub fn new(from_k: u16, to_k: u16) -> Self {
+        Self {
+            from_k,
+            to_k,
+            step: 1,
+            settle_ms: 5000,
+            samples: 5,
+            sample_interval_ms: 1000,
+        }
+    }
+}
+
+/// Average power harvested with a coefficient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MpptPoint {
+    /// Coefficient, 100x what is shown on the display.
+    pub k_value: u16,
+    /// Average output power in milliwatts.
+    pub power_mw: u32,
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Try each MPPT coefficient in `sweep`, passing the power harvested with each to `log`, then apply the best.
+    ///
+    /// `delay` is called with the time to wait in milliseconds, and returns `false` to cancel the sweep, which restores
+    /// the coefficient set before it. Returns the best point, or `None` if cancelled. MPPT must be enabled and the
+    /// output on for the readings to mean anything.
+    ///
+    /// Requires known scaling factors for the PSU model.
+    pub fn sweep_mppt_k(
+        &mut self,
+        sweep: MpptSweep,
+        mut delay: impl FnMut(u32) -> bool,
+        mut log: impl FnMut(MpptPoint),
+    ) -> Result<Option<MpptPoint>, S::Error> {
+        let original_k = self.get_mppt_k_value()?;
+        let step = usize::from(sweep.step.max(1));
+        let samples = u32::from(sweep.samples.max(1));
+
+        let mut best: Option<MpptPoint> = None;
+        for k_value in (sweep.from_k..=sweep.to_k).step_by(step) {
+            self.set_mppt_k_value(k_value)?;
+            if !delay(sweep.settle_ms) {
+                self.set_mppt_k_value(original_k)?;
+                return Ok(None);
+            }
+            let mut total_mw = 0u64;
+            for i in 0..samples {
+                if i > 0 && !delay(sweep.sample_interval_ms) {
+                    self.set_mppt_k_value(original_k)?;
+                    return Ok(None);
+                }
+                total_mw += u64::from(self.read_power_mw()?.0);
+            }
+            let point = MpptPoint {
+                k_value,
+                power_mw: (total_mw / u64::from(samples)) as u32,
+            };
+            log(point);
+            if best.is_none_or(|best| point.power_mw > best.power_mw) {
+                best = Some(point);
+            }
+        }
+
+        self.set_mppt_k_value(best.map_or(original_k, |best| best.k_value))?;
+        Ok(best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_serial::{MockResponse, MockSerial},
+        scaling::ScalingFactors,
+    };
+
+    #[test]
+    fn sweep_applies_best_k() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        let serial = psu.interface_mut();
+        serial.queue_read_response(0x01, &[80]);
+        // Two readings each at 0.76, 0.78 and 0.80, in units of 100mW.
+        for power in [[100, 110], [130, 126], [120, 120]] {
+            serial.queue_response(MockResponse::Echo);
+            for raw in power {
+                serial.queue_read_response(0x01, &[raw]);
+            }
+        }
+        serial.queue_response(MockResponse::Echo);
+
+        let sweep = MpptSweep {
+            step: 2,
+            samples: 2,
+            ..MpptSweep::new(76, 81)
+        };
+        let mut logged = heapless::Vec::<MpptPoint, 4>::new();
+        let best = psu
+            .sweep_mppt_k(sweep, |_| true, |point| logged.push(point).unwrap())
+            .unwrap();
+        assert_eq!(
+            best,
+            Some(MpptPoint {
+                k_value: 78,
+                power_mw: 12_800
+            })
+        );
+        assert_eq!(logged.len(), 3);
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([0x00, 0x20, 0x00, 78].as_slice())
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Detect settings which were changed at the front panel, rather than by the driver.
+//!
+//! Call [`XyPsu::poll_panel_changes`] periodically. It reads the settings an operator can change at the panel, and
+//! reports any which differ from what was last seen or written by the driver. Supervisory software can then log the
+//! change, or revert it.
+
+use crate::{error::Result, psu::XyPsu, register::XyRegister, transport::Transport};
+
+/// A setting which can be changed at the front panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PanelSetting {
+    /// Output voltage setting, raw register value.
+    VoltageSet,
+    /// Output current limit, raw register value.
+    CurrentLimit,
+    /// Output on/off.
+    Output,
+    /// Key lock.
+    Lock,
+    /// Backlight brightness level.
+    Backlight,
+}
+
+impl PanelSetting {
+    /// All settings which are watched, in the order they are read.
+    pub const ALL: [PanelSetting; 5] = [
+        PanelSetting::VoltageSet,
+        PanelSetting::CurrentLimit,
+        PanelSetting::Output,
+        PanelSetting::Lock,
+        PanelSetting::Backlight,
+    ];
+
+    /// Register holding this setting.
+    pub const fn register(self) -> XyRegister {
+        match self {
+            PanelSetting::VoltageSet => XyRegister::VSet,
+            PanelSetting::CurrentLimit => XyRegister::ISet,
+            PanelSetting::Output => XyRegister::OnOff,
+            PanelSetting::Lock => XyRegister::Lock,
+            PanelSetting::Backlight => XyRegister::BLed,
+        }
+    }
+
+    /// Find the setting held in `register`, if it is watched.
+    pub fn from_register(register: XyRegister) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.register() == register)
+    }
+}
+
+/// A setting which changed without the driver writing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PanelChange {
+    /// Which setting changed.
+    pub setting: PanelSetting,
+    /// Raw register value before the change.
+    pub previous: u16,
+    /// Raw register value after the change.
+    pub current: u16,
+}
+
+/// Raw values of all watched settings, in the order of [`PanelSetting::ALL`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct PanelSettings([u16; 5]);
+
+impl PanelSettings {
+    fn get_mut(&mut self, setting: PanelSetting) -> Option<&mut u16> {
+        let index = PanelSetting::ALL.iter().position(|s| *s == setting)?;
+        self.0.get_mut(index)
+    }
+
+    /// Record a value written by the driver, so it isn't reported as a panel change.
+    pub(crate) fn record_write(&mut self, register: XyRegister, value: u16) {
+        if let Some(slot) = PanelSetting::from_register(register).and_then(|s| self.get_mut(s)) {
+            *slot = value;
+        }
+    }
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Read the front panel settings, and return any which changed without the driver writing them.
+    ///
+    /// The first call after creating the [`XyPsu`], re-initialization or activating a preset just records the current
+    /// settings, and returns no changes.
+    pub fn poll_panel_changes(&mut self) -> Result<heapless::Vec<PanelChange, 5>, S::Error> {
+        let mut current = PanelSettings::default();
+        for (setting, value) in PanelSetting::ALL.into_iter().zip(current.0.iter_mut()) {
+            *value = self.read_register(setting.register())?;
+        }
+
+        let mut changes = heapless::Vec::new();
+        if let Some(previous) = self.panel_baseline.replace(current) {
+            for ((setting, previous), current) in PanelSetting::ALL
+                .into_iter()
+                .zip(previous.0)
+                .zip(current.0)
+                .filter(|((_, previous), current)| previous != current)
+            {
+                // Can't overflow, there are only as many settings as the capacity.
+                let _ = changes.push(PanelChange {
+                    setting,
+                    previous,
+                    current,
+                });
+            }
+        }
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_serial::{MockResponse, MockSerial};
+
+    fn queue_settings(psu: &mut XyPsu<MockSerial, 128>, values: [u16; 5]) {
+        for value in values {
+            psu.interface_mut().queue_read_response(0x01, &[value]);
+        }
+    }
+
+    #[test]
+    fn panel_changes_ignore_driver_writes() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+
+        queue_settings(&mut psu, [500, 100, 0, 0, 3]);
+        assert!(psu.poll_panel_changes().unwrap().is_empty());
+
+        // The driver turns the output on, then the operator turns the voltage up.
+        psu.interface_mut().queue_response(MockResponse::Echo);
+        psu.set_output_state(true).unwrap();
+        queue_settings(&mut psu, [1200, 100, 1, 0, 3]);
+        let changes = psu.poll_panel_changes().unwrap();
+        assert_eq!(
+            changes.as_slice(),
+            &[PanelChange {
+                setting: PanelSetting::VoltageSet,
+                previous: 500,
+                current: 1200,
+            }]
+        );
+
+        queue_settings(&mut psu, [1200, 100, 1, 0, 3]);
+        assert!(psu.poll_panel_changes().unwrap().is_empty());
+    }
+}
@@ -1,16 +1,19 @@
-use fugit::Duration;
+use core::time::Duration;
 use strum::EnumCount;
 use strum_macros::EnumCount as EnumCountMacro;
 use thiserror::Error;
 
 use crate::{
     error::Error,
+    limits::ModelLimits,
     psu::XyPsu,
     register::{State, Temperature, TemperatureUnit},
     scaling::ScalingFactors,
+    transport::Transport,
 };
 
-/// Use [`XyPresetBuilder`] to create a preset.
+/// Use [`XyPresetBuilder`] to create a preset, or [`XyPsu::read_preset`] to read one back.
+#[derive(Debug)]
 pub struct XyPreset {
     /// Index number of this preset group (0 - 9).
     group: PresetGroup,
@@ -24,6 +27,107 @@ pub struct XyPreset {
 }
 
 impl XyPreset {
+    /// Decode all registers of preset `group`, `VSet` through `SEtp`.
+    pub(crate) fn from_registers(
+        group: PresetGroup,
+        registers: [u16; XyPresetOffsets::COUNT],
+        scaling: ScalingFactors,
+        temperature_unit: TemperatureUnit,
+    ) -> Self {
+        let [v_set, i_set, protection @ ..] = registers;
+        let [.., s_ini, _s_etp] = registers;
+        XyPreset {
+            group,
+            voltage_setting_mv: scaling.raw_to_voltage_mv(v_set),
+            current_setting_ma: scaling.raw_to_current_ma(i_set),
+            protection: ProtectionConfig::from_registers(protection, scaling, temperature_unit),
+            output_enable: State::from(s_ini != 0),
+        }
+    }
+
+    /// Return the preset group this preset is for.
+    pub fn group(&self) -> PresetGroup {
+        self.group
+    }
+
+    /// Return the output voltage setting in millivolts.
+    pub fn voltage_setting_mv(&self) -> u32 {
+        self.voltage_setting_mv
+    }
+
+    /// Return the output current limit in milliamps.
+    pub fn current_setting_ma(&self) -> u32 {
+        self.current_setting_ma
+    }
+
+    /// Return the protection configuration levels.
+    pub fn protection(&self) -> &ProtectionConfig {
+        &self.protection
+    }
+
+    /// Return what state the output is put in when the preset is loaded.
+    pub fn output_enable(&self) -> State {
+        self.output_enable
+    }
+
+    /// Whether the voltage and current settings are within `limits`.
+    pub fn is_within(&self, limits: &ModelLimits) -> bool {
+        self.voltage_setting_mv <= limits.max_voltage_mv
+            && self.current_setting_ma <= limits.max_current_ma
+    }
+
+    /// Return the settings of `other` which differ from this preset, e.g. to show what writing `other` would change on
+    /// the device. The group isn't compared.
+    pub fn diff(&self, other: &XyPreset) -> PresetDelta {
+        fn changed<T: PartialEq + Copy>(from: T, to: T) -> Option<T> {
+            (from != to).then_some(to)
+        }
+        let (from, to) = (&self.protection, &other.protection);
+        PresetDelta {
+            voltage_setting_mv: changed(self.voltage_setting_mv, other.voltage_setting_mv),
+            current_setting_ma: changed(self.current_setting_ma, other.current_setting_ma),
+            output_enable: changed(self.output_enable, other.output_enable),
+            under_voltage_mv: changed(from.under_voltage_mv, to.under_voltage_mv),
+            over_voltage_mv: changed(from.over_voltage_mv, to.over_voltage_mv),
+            over_current_ma: changed(from.over_current_ma, to.over_current_ma),
+            over_power_mw: changed(from.over_power_mw, to.over_power_mw),
+            over_time: changed(from.over_time, to.over_time),
+            over_capacity_mah: changed(from.over_capacity_mah, to.over_capacity_mah),
+            over_energy_mwh: changed(from.over_energy_mwh, to.over_energy_mwh),
+            over_temperature: changed(from.over_temperature, to.over_temperature),
+        }
+    }
+
+    /// Change the settings given in `delta`, e.g. to merge a partial edit into a preset read from the device.
+    ///
+    /// The preset is left unchanged if the result wouldn't be valid.
+    pub fn apply_delta(&mut self, delta: &PresetDelta) -> Result<(), XyPresetBuilderError> {
+        if delta
+            .over_time
+            .is_some_and(|over_time| over_time.as_secs() / 3600 > u16::MAX as u64)
+        {
+            return Err(XyPresetBuilderError::OverTimeTooLong);
+        }
+        fn apply<T: Copy>(value: &mut T, change: Option<T>) {
+            if let Some(change) = change {
+                *value = change;
+            }
+        }
+        let protection = &mut self.protection;
+        apply(&mut self.voltage_setting_mv, delta.voltage_setting_mv);
+        apply(&mut self.current_setting_ma, delta.current_setting_ma);
+        apply(&mut self.output_enable, delta.output_enable);
+        apply(&mut protection.under_voltage_mv, delta.under_voltage_mv);
+        apply(&mut protection.over_voltage_mv, delta.over_voltage_mv);
+        apply(&mut protection.over_current_ma, delta.over_current_ma);
+        apply(&mut protection.over_power_mw, delta.over_power_mw);
+        apply(&mut protection.over_time, delta.over_time);
+        apply(&mut protection.over_capacity_mah, delta.over_capacity_mah);
+        apply(&mut protection.over_energy_mwh, delta.over_energy_mwh);
+        apply(&mut protection.over_temperature, delta.over_temperature);
+        Ok(())
+    }
+
     /// Write this preset to the device using adaptive scaling based on PSU model.
     ///
     /// This will automatically detect the PSU model and apply appropriate scaling factors.
@@ -33,56 +137,127 @@ impl XyPreset {
     ///
     /// If your PSU model has unknown scaling factors, use [`XyPsu::set_scaling_factors`]
     /// to manually specify them before calling this method..
-    pub fn write<S: embedded_io::Read + embedded_io::Write, const L: usize>(
+    ///
+    /// Returns `InvalidRange` if the settings are above the PSU's [`limits`](XyPsu::limits).
+    pub fn write<S: Transport, const L: usize>(
         &self,
         interface: &mut XyPsu<S, L>,
     ) -> Result<(), Error<S::Error>> {
         // Ensure scaling is loaded (lazy load on first call)
         let scaling = interface.ensure_scaling()?;
+        if interface
+            .limits()
+            .is_some_and(|limits| !self.is_within(&limits))
+        {
+            return Err(Error::InvalidRange);
+        }
         let unit = interface.get_temperature_unit()?;
-        let (start_address, write_buffer) = self.generate_write_data_and_offset(unit, scaling);
+        let (start_address, write_buffer) = self.generate_write_data_and_offset(unit, scaling)?;
 
-        interface.write_modbus_bulk(start_address, write_buffer)
+        interface.write_modbus_bulk(start_address, write_buffer)?;
+        interface.verify_write(start_address, &write_buffer)
     }
 
     /// Generate write data with scaling factors applied.
+    ///
+    /// Returns `OutOfRange` if a voltage, current or power is too large for its register once scaled.
     pub fn generate_write_data_and_offset(
         &self,
         temperature_unit: impl Into<TemperatureUnit>,
         scaling: ScalingFactors,
-    ) -> (u16, [u16; XyPresetOffsets::COUNT]) {
+    ) -> Result<(u16, [u16; XyPresetOffsets::COUNT]), XyPresetBuilderError> {
         use XyPresetOffsets as XPO;
 
         let temperature_unit = temperature_unit.into();
         let mut write_buffer: [u16; _] = [0x00; XPO::COUNT];
-
-        write_buffer[XPO::VSet as usize] = scaling.voltage_mv_to_raw(self.voltage_setting_mv);
-        write_buffer[XPO::ISet as usize] = scaling.current_ma_to_raw(self.current_setting_ma);
-        write_buffer[XPO::SLvp as usize] =
-            scaling.voltage_mv_to_raw(self.protection.under_voltage_mv);
-        write_buffer[XPO::SOvp as usize] =
-            scaling.voltage_mv_to_raw(self.protection.over_voltage_mv);
-        write_buffer[XPO::SOcp as usize] =
-            scaling.current_ma_to_raw(self.protection.over_current_ma);
-        write_buffer[XPO::SOpp as usize] = scaling.power_mw_to_raw(self.protection.over_power_mw);
-        write_buffer[XPO::SOhpH as usize] =
-            u16::try_from(self.protection.over_time.to_hours()).unwrap();
-        write_buffer[XPO::SoHpM as usize] =
-            u16::try_from(self.protection.over_time.to_minutes() % 60).unwrap();
-        let scaled_capacity = self.protection.over_capacity_mah / scaling.capacity_divisor;
-        write_buffer[XPO::SOahL as usize] = scaled_capacity as u16;
-        write_buffer[XPO::SOahH as usize] = (scaled_capacity >> 16) as u16;
-        let scaled_energy = self.protection.over_energy_mwh / scaling.energy_divisor;
-        write_buffer[XPO::SOwhL as usize] = scaled_energy as u16;
-        write_buffer[XPO::SOwhH as usize] = (scaled_energy >> 16) as u16;
-        write_buffer[XPO::SOtp as usize] =
-            self.protection.over_temperature.as_unit(temperature_unit);
-        write_buffer[XPO::SIni as usize] = self.output_enable as u16;
-        write_buffer[XPO::SEtp as usize] =
-            self.protection.over_temperature.as_unit(temperature_unit);
+        // Every offset is less than `XPO::COUNT`, so this always finds a slot.
+        let mut set = |register: XPO, value: u16| {
+            if let Some(slot) = write_buffer.get_mut(register as usize) {
+                *slot = value;
+            }
+        };
+        let fit = |raw: Option<u16>| raw.ok_or(XyPresetBuilderError::OutOfRange);
+
+        set(
+            XPO::VSet,
+            fit(scaling.voltage_mv_to_raw(self.voltage_setting_mv))?,
+        );
+        set(
+            XPO::ISet,
+            fit(scaling.current_ma_to_raw(self.current_setting_ma))?,
+        );
+        set(
+            XPO::SLvp,
+            fit(scaling.voltage_mv_to_raw(self.protection.under_voltage_mv))?,
+        );
+        set(
+            XPO::SOvp,
+            fit(scaling.voltage_mv_to_raw(self.protection.over_voltage_mv))?,
+        );
+        set(
+            XPO::SOcp,
+            fit(scaling.current_ma_to_raw(self.protection.over_current_ma))?,
+        );
+        set(
+            XPO::SOpp,
+            fit(scaling.power_mw_to_raw(self.protection.over_power_mw))?,
+        );
+        // Over time is validated to fit in the register when the preset is built.
+        let over_time_secs = self.protection.over_time.as_secs();
+        set(
+            XPO::SOhpH,
+            u16::try_from(over_time_secs / 3600).unwrap_or(u16::MAX),
+        );
+        set(XPO::SoHpM, (over_time_secs / 60 % 60) as u16);
+        let scaled_capacity = self
+            .protection
+            .over_capacity_mah
+            .checked_div(scaling.capacity_divisor)
+            .unwrap_or(0);
+        set(XPO::SOahL, scaled_capacity as u16);
+        set(XPO::SOahH, (scaled_capacity >> 16) as u16);
+        let scaled_energy = self
+            .protection
+            .over_energy_mwh
+            .checked_div(scaling.energy_divisor)
+            .unwrap_or(0);
+        set(XPO::SOwhL, scaled_energy as u16);
+        set(XPO::SOwhH, (scaled_energy >> 16) as u16);
+        set(
+            XPO::SOtp,
+            self.protection.over_temperature.as_unit(temperature_unit),
+        );
+        set(XPO::SIni, self.output_enable as u16);
+        set(
+            XPO::SEtp,
+            self.protection.over_temperature.as_unit(temperature_unit),
+        );
 
         let start_address = XPO::VSet.address_in_group(self.group);
-        (start_address, write_buffer)
+        Ok((start_address, write_buffer))
+    }
+}
+
+/// Changes to the settings of a preset, see [`XyPreset::diff`]. `None` leaves a setting as it is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PresetDelta {
+    pub voltage_setting_mv: Option<u32>,
+    pub current_setting_ma: Option<u32>,
+    pub output_enable: Option<State>,
+    pub under_voltage_mv: Option<u32>,
+    pub over_voltage_mv: Option<u32>,
+    pub over_current_ma: Option<u32>,
+    pub over_power_mw: Option<u32>,
+    pub over_time: Option<Duration>,
+    pub over_capacity_mah: Option<u32>,
+    pub over_energy_mwh: Option<u32>,
+    pub over_temperature: Option<Temperature>,
+}
+
+impl PresetDelta {
+    /// Whether there are no changes.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
     }
 }
 
@@ -98,6 +273,8 @@ pub struct XyPresetBuilder {
     protection: ProtectionConfig,
     /// What state the output should be in when the preset is loaded.
     output_enable: State,
+    /// Maximum settings, checked when the preset is built.
+    limits: Option<ModelLimits>,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -109,6 +286,7 @@ impl Default for XyPresetBuilder {
             current_setting_ma: 0,
             protection: ProtectionConfig::default(),
             output_enable: State::default(),
+            limits: None,
         }
     }
 }
@@ -131,17 +309,30 @@ impl XyPresetBuilder {
 
     /// Let's build it!
     pub fn build(self) -> Result<XyPreset, XyPresetBuilderError> {
-        if let Some(group_idx) = self.group {
-            Ok(XyPreset {
-                group: group_idx,
-                voltage_setting_mv: self.voltage_setting_mv,
-                current_setting_ma: self.current_setting_ma,
-                protection: self.protection,
-                output_enable: self.output_enable,
-            })
-        } else {
-            Err(XyPresetBuilderError::InvalidGroupIndex)
+        if self.protection.over_time.as_secs() / 3600 > u16::MAX as u64 {
+            return Err(XyPresetBuilderError::OverTimeTooLong);
+        }
+        let Some(group_idx) = self.group else {
+            return Err(XyPresetBuilderError::InvalidGroupIndex);
+        };
+        let preset = XyPreset {
+            group: group_idx,
+            voltage_setting_mv: self.voltage_setting_mv,
+            current_setting_ma: self.current_setting_ma,
+            protection: self.protection,
+            output_enable: self.output_enable,
+        };
+        if self.limits.is_some_and(|limits| !preset.is_within(&limits)) {
+            return Err(XyPresetBuilderError::OutOfRange);
         }
+        Ok(preset)
+    }
+
+    /// Reject voltage and current settings above `limits` when building, e.g. from
+    /// [`ProductModel::limits`](crate::register::ProductModel::limits).
+    pub fn with_limits(mut self, limits: ModelLimits) -> Self {
+        self.limits = Some(limits);
+        self
     }
 
     /// Set output state.
@@ -168,6 +359,22 @@ impl XyPresetBuilder {
         self
     }
 
+    /// Set output voltage level.
+    ///
+    /// Only available with the `uom` feature, see [`Self::with_set_v`].
+    #[cfg(feature = "uom")]
+    pub fn with_set_voltage(self, voltage: uom::si::f32::ElectricPotential) -> Self {
+        self.with_set_v(crate::quantity::voltage_mv(voltage))
+    }
+
+    /// Set output current limit.
+    ///
+    /// Only available with the `uom` feature, see [`Self::with_set_i_lim`].
+    #[cfg(feature = "uom")]
+    pub fn with_set_current_limit(self, current: uom::si::f32::ElectricCurrent) -> Self {
+        self.with_set_i_lim(crate::quantity::current_ma(current))
+    }
+
     /// Set output state.
     pub fn with_output(mut self, output_enable: impl Into<State>) -> Self {
         self.output_enable = output_enable.into();
@@ -199,11 +406,26 @@ impl XyPresetBuilder {
     }
 
     /// Set over time protection level in preset.
-    pub fn with_ohp(mut self, duration: Duration<u32, 1, 1>) -> Self {
+    ///
+    /// Only available with the `fugit` feature, see [`Self::with_ohp_duration`] and [`Self::with_ohp_secs`].
+    #[cfg(feature = "fugit")]
+    pub fn with_ohp(self, duration: fugit::Duration<u32, 1, 1>) -> Self {
+        self.with_ohp_secs(duration.to_secs())
+    }
+
+    /// Set over time protection level in preset. The device only stores whole minutes.
+    ///
+    /// Takes a `std::time::Duration` too, as it is the same type.
+    pub fn with_ohp_duration(mut self, duration: Duration) -> Self {
         self.protection.over_time = duration;
         self
     }
 
+    /// Set over time protection level in preset, in seconds. The device only stores whole minutes.
+    pub fn with_ohp_secs(self, secs: u32) -> Self {
+        self.with_ohp_duration(Duration::from_secs(secs as u64))
+    }
+
     /// Set over capacity protection level in preset. Units: mAh.
     pub fn with_oahp(mut self, capacity_mah: u32) -> Self {
         self.protection.over_capacity_mah = capacity_mah;
@@ -227,6 +449,10 @@ impl XyPresetBuilder {
 pub enum XyPresetBuilderError {
     #[error("Preset group no not set")]
     InvalidGroupIndex,
+    #[error("Over time protection is too long to be stored on the device")]
+    OverTimeTooLong,
+    #[error("Setting is above the model's limits, or too large for its register")]
+    OutOfRange,
 }
 
 /// This struct is used to define the configuration of the protection features. E.g. over-voltage protection.
@@ -241,7 +467,7 @@ pub struct ProtectionConfig {
     /// Over-power protection level in milli-watts.
     pub over_power_mw: u32,
     /// Over-time protection duration.
-    pub over_time: Duration<u32, 1, 1>,
+    pub over_time: Duration,
     /// Over capacity protection level in milli-amp hours.
     pub over_capacity_mah: u32,
     /// Over energy protection level in milli-watt hours.
@@ -259,7 +485,7 @@ impl Default for ProtectionConfig {
             over_voltage_mv: 100_000,
             over_current_ma: 50_000,
             over_power_mw: 99999,
-            over_time: Duration::<u32, _, _>::hours(0),
+            over_time: Duration::ZERO,
             over_capacity_mah: 0,
             over_energy_mwh: 0,
             over_temperature: Temperature::Celsius(0),
@@ -267,6 +493,70 @@ impl Default for ProtectionConfig {
     }
 }
 
+impl ProtectionConfig {
+    /// Decode the protection registers of a preset group, `SLvp` through `SEtp`.
+    pub(crate) fn from_registers(
+        registers: [u16; 13],
+        scaling: ScalingFactors,
+        temperature_unit: TemperatureUnit,
+    ) -> Self {
+        // Register layout (from XyPresetOffsets), starting from SLvp:
+        let [
+            s_lvp,   // Under voltage
+            s_ovp,   // Over voltage
+            s_ocp,   // Over current
+            s_opp,   // Over power
+            s_ohp_h, // Over time hours
+            s_ohp_m, // Over time minutes
+            s_oah_l, // Over capacity low
+            s_oah_h, // Over capacity high
+            s_owh_l, // Over energy low
+            s_owh_h, // Over energy high
+            s_otp,   // Over temperature
+            _s_ini,  // Output enable - skip
+            _s_etp,  // External temperature
+        ] = registers;
+
+        ProtectionConfig {
+            under_voltage_mv: scaling.raw_to_voltage_mv(s_lvp),
+            over_voltage_mv: scaling.raw_to_voltage_mv(s_ovp),
+            over_current_ma: scaling.raw_to_current_ma(s_ocp),
+            over_power_mw: scaling.raw_to_power_mw(s_opp),
+            over_time: Duration::from_secs(s_ohp_h as u64 * 3600 + s_ohp_m as u64 * 60),
+            over_capacity_mah: scaling
+                .raw_to_capacity_mah((s_oah_l as u32) | ((s_oah_h as u32) << 16)),
+            over_energy_mwh: scaling.raw_to_energy_mwh((s_owh_l as u32) | ((s_owh_h as u32) << 16)),
+            over_temperature: Temperature::new(s_otp, temperature_unit),
+        }
+    }
+
+    /// Encode these protections into `registers`, all registers of a preset group, `VSet` through `SEtp`.
+    ///
+    /// Only `SLvp` through `SOtp` are changed, the settings, output switch and external temperature protection are
+    /// left as they are.
+    pub(crate) fn apply_to_registers(
+        self,
+        registers: &mut [u16; XyPresetOffsets::COUNT],
+        scaling: ScalingFactors,
+        temperature_unit: TemperatureUnit,
+    ) -> Result<(), XyPresetBuilderError> {
+        use XyPresetOffsets as XPO;
+
+        let preset = XyPresetBuilder::new(PresetGroup::Group0, 0, 0)
+            .with_protections(self)
+            .build()?;
+        let (_, encoded) = preset.generate_write_data_and_offset(temperature_unit, scaling)?;
+        let protections = XPO::SLvp as usize..=XPO::SOtp as usize;
+        if let (Some(target), Some(source)) = (
+            registers.get_mut(protections.clone()),
+            encoded.get(protections),
+        ) {
+            target.copy_from_slice(source);
+        }
+        Ok(())
+    }
+}
+
 // impl ProtectionConfig {
 //     pub fn write
 // }
@@ -328,8 +618,23 @@ impl XyPresetOffsets {
     }
 }
 
+/// What happens to the output when a preset group is loaded, see [`XyPsu::load_preset`].
+///
+/// Loading a group also loads its `SIni` setting into the output switch, so without a policy the output can be
+/// switched on or off as a side effect of changing settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PresetOutputPolicy {
+    /// Leave the output on or off as it was before the group was loaded.
+    KeepCurrentOutputState,
+    /// Switch the output off before the group is loaded, and leave it off.
+    ForceOff,
+    /// Switch the output as the group's `SIni` setting says.
+    UsePresetSIni,
+}
+
 /// This enum represents all possible preset groups.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u16)]
 pub enum PresetGroup {
     Group0 = 0x00,
@@ -344,33 +649,53 @@ pub enum PresetGroup {
     Group9 = 0x09,
 }
 
-impl TryFrom<u16> for PresetGroup {
-    type Error = ();
+impl PresetGroup {
+    /// All preset groups, M0 to M9.
+    pub const ALL: [PresetGroup; 10] = [
+        PresetGroup::Group0,
+        PresetGroup::Group1,
+        PresetGroup::Group2,
+        PresetGroup::Group3,
+        PresetGroup::Group4,
+        PresetGroup::Group5,
+        PresetGroup::Group6,
+        PresetGroup::Group7,
+        PresetGroup::Group8,
+        PresetGroup::Group9,
+    ];
+
+    /// Iterate over all preset groups, M0 to M9.
+    pub fn iter() -> impl Iterator<Item = PresetGroup> {
+        Self::ALL.into_iter()
+    }
+}
 
-    fn try_from(value: u16) -> Result<Self, Self::Error> {
-        PresetGroup::try_from(value as u32)
+/// Formats as the front panel names the group, e.g. `M3`.
+impl core::fmt::Display for PresetGroup {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "M{}", *self as u16)
     }
 }
 
-impl TryFrom<u32> for PresetGroup {
-    // @TODO should probably have own error type, but I am lazy.
-    type Error = ();
-
-    fn try_from(value: u32) -> Result<Self, Self::Error> {
-        use PresetGroup as PG;
-        match value {
-            0 => Ok(PG::Group0),
-            1 => Ok(PG::Group1),
-            2 => Ok(PG::Group2),
-            3 => Ok(PG::Group3),
-            4 => Ok(PG::Group4),
-            5 => Ok(PG::Group5),
-            6 => Ok(PG::Group6),
-            7 => Ok(PG::Group7),
-            8 => Ok(PG::Group8),
-            9 => Ok(PG::Group9),
-            _ => Err(()),
-        }
+impl From<PresetGroup> for u16 {
+    fn from(value: PresetGroup) -> Self {
+        value as u16
+    }
+}
+
+/// A preset group number above 9.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("There is no preset group {0}, only 0 to 9.")]
+pub struct InvalidPresetGroup(pub u16);
+
+impl TryFrom<u16> for PresetGroup {
+    type Error = InvalidPresetGroup;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        Self::ALL
+            .get(usize::from(value))
+            .copied()
+            .ok_or(InvalidPresetGroup(value))
     }
 }
 
@@ -399,20 +724,21 @@ mod test {
         let preset = XyPresetBuilder::new(PresetGroup::Group3, 5000, 1000)
             .with_output(true)
             .with_uvp(1000)
-            .with_ohp(Duration::<u32, _, _>::hours(10u32) + Duration::<u32, _, _>::minutes(10u32))
+            .with_ohp_secs(10 * 3600 + 10 * 60)
             .with_otp(Temperature::from_centi(1000, TemperatureUnit::Celsius))
-            .with_opp(0xFFFFFF)
+            .with_opp(655_350)
             .with_owhp(0xFFFFFF)
             .with_oahp(0xFFFFFF)
             .build()
             .unwrap();
 
         // Create manual scaling factors (equivalent to the hardcoded /10 scaling).
-        let scaling = ScalingFactors::new(10, 10, 10, 10, 10);
+        let scaling = ScalingFactors::new(10, 10, 10, 10, 10).unwrap();
 
         // Generate payload using manual scaling factors.
-        let (start_address, write_buffer) =
-            preset.generate_write_data_and_offset(TemperatureUnit::Celsius, scaling);
+        let (start_address, write_buffer) = preset
+            .generate_write_data_and_offset(TemperatureUnit::Celsius, scaling)
+            .unwrap();
 
         // Check start address is as expected.
         assert_eq!(start_address, 0x80);
@@ -421,5 +747,60 @@ mod test {
         for double in write_buffer {
             assert_ne!(double, 0);
         }
+
+        // Too large for the register once scaled, rather than wrapping around.
+        let mut preset = preset;
+        preset.protection.over_power_mw = 655_360;
+        assert!(matches!(
+            preset.generate_write_data_and_offset(TemperatureUnit::Celsius, scaling),
+            Err(XyPresetBuilderError::OutOfRange)
+        ));
+    }
+
+    #[test]
+    fn preset_group_conversions() {
+        assert_eq!(PresetGroup::try_from(7), Ok(PresetGroup::Group7));
+        assert_eq!(PresetGroup::try_from(10), Err(InvalidPresetGroup(10)));
+        assert_eq!(PresetGroup::iter().count(), 10);
+        assert!(
+            PresetGroup::iter()
+                .enumerate()
+                .all(|(i, group)| u16::from(group) == i as u16)
+        );
+
+        let mut text: heapless::String<8> = heapless::String::new();
+        core::fmt::Write::write_fmt(&mut text, format_args!("{}", PresetGroup::Group3)).unwrap();
+        assert_eq!(text, "M3");
+    }
+
+    #[test]
+    fn preset_diff_and_merge() {
+        let mut on_device = XyPresetBuilder::new(PresetGroup::Group1, 5000, 1000)
+            .with_ovp(6000)
+            .build()
+            .unwrap();
+        let edited = XyPresetBuilder::new(PresetGroup::Group1, 12000, 1000)
+            .with_ovp(13000)
+            .build()
+            .unwrap();
+
+        let delta = on_device.diff(&edited);
+        assert_eq!(
+            delta,
+            PresetDelta {
+                voltage_setting_mv: Some(12000),
+                over_voltage_mv: Some(13000),
+                ..Default::default()
+            }
+        );
+
+        on_device.apply_delta(&delta).unwrap();
+        assert!(on_device.diff(&edited).is_empty());
+
+        let too_long = PresetDelta {
+            over_time: Some(Duration::from_secs(u64::MAX)),
+            ..Default::default()
+        };
+        assert!(on_device.apply_delta(&too_long).is_err());
     }
 }
@@ -0,0 +1,68 @@
+//! Get notified when a protection trips, without hand-rolling edge detection around
+//! [`XyPsu::get_protection_status`].
+//!
+//! The observer is passed to each [`XyPsu::poll_protections`] call rather than stored in the [`XyPsu`], so it can
+//! borrow local state without the driver needing an allocator. Closures taking a [`ProtectionStatus`] are observers.
+//!
+//! ```ignore
+//! loop {
+//!     psu.poll_protections(&mut |tripped: ProtectionStatus| {
+//!         for name in tripped.triggered() {
+//!             println!("{name} tripped");
+//!         }
+//!     })?;
+//!     sleep(Duration::from_millis(500));
+//! }
+//! ```
+
+use crate::{error::Result, psu::XyPsu, register::ProtectionStatus, transport::Transport};
+
+/// Notified of protections tripping.
+pub trait ProtectionObserver {
+    /// Called with the protections which have gone from clear to set since the last poll. `status` holds every
+    /// protection currently set.
+    fn protection_tripped(&mut self, tripped: ProtectionStatus, status: ProtectionStatus);
+}
+
+impl<F: FnMut(ProtectionStatus)> ProtectionObserver for F {
+    fn protection_tripped(&mut self, tripped: ProtectionStatus, _status: ProtectionStatus) {
+        self(tripped)
+    }
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Read the protection status, and notify `observer` if any protection has tripped since the last call.
+    ///
+    /// Protections set before the first call count as tripped. Returns every protection currently set.
+    pub fn poll_protections(
+        &mut self,
+        observer: &mut impl ProtectionObserver,
+    ) -> Result<ProtectionStatus, S::Error> {
+        let status = self.get_protection_status()?;
+        let tripped = status.newly_set(self.protections_seen);
+        self.protections_seen = status;
+        if tripped.any() {
+            observer.protection_tripped(tripped, status);
+        }
+        Ok(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_serial::MockSerial;
+
+    #[test]
+    fn observer_notified_on_rising_edge() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        let mut trips: heapless::Vec<u16, 4> = heapless::Vec::new();
+        let mut observer = |tripped: ProtectionStatus| trips.push(tripped.raw()).unwrap();
+
+        for raw in [0b00, 0b01, 0b01, 0b11, 0b00, 0b10] {
+            psu.interface_mut().queue_read_response(0x01, &[raw]);
+            psu.poll_protections(&mut observer).unwrap();
+        }
+        assert_eq!(trips, [0b01, 0b10, 0b10]);
+    }
+}
@@ -1,882 +1,1202 @@
 use crate::{
+    cache::RegisterCache,
     error::{Error, Result},
-    preset::{PresetGroup, ProtectionConfig, XyPresetBuilder},
-    register::{
-        BacklightBrightness, BaudRate, ControlMode, ProductModel, ProtectionStatus, State,
-        Temperature, TemperatureUnit, XyRegister,
-    },
-    scaling::ScalingFactors,
+    frame::{self, Frame},
+    limits::{ModelLimits, setpoint_to_raw},
+    link::LinkStats,
+    panel::PanelSettings,
+    quirks::Quirks,
+    register::{BaudRate, ProtectionStatus, State, XyRegister},
+    register_map::RegisterMap,
+    scaling::{ScalingFactors, ScalingRegistry},
+    specs::ModelRegistry,
+    transport::Transport,
 };
 use embedded_io::Error as _;
-use fugit::Duration;
 
-/// You can create a XyPsu using any interface which implements [embedded_io::Read] & [embedded_io::Write].
+/// You can create a XyPsu using any interface which implements [embedded_io::Read] & [embedded_io::Write], or any other
+/// [`Transport`].
 ///
 /// For it's methods, we generally use the nomenclature that "set" meant to write a configuration and "get" means to read
 /// back a configuration value. Where as "read" means to get a measured value.
-pub struct XyPsu<S: embedded_io::Read + embedded_io::Write, const L: usize = 128> {
+pub struct XyPsu<S: Transport, const L: usize = 128> {
     interface: S,
     /// Default for PSU is 0x01.
-    unit_id: u8,
+    pub(crate) unit_id: u8,
     /// Scaling factors for this PSU model. Lazily loaded on first use of scaled functions.
-    scaling: Option<ScalingFactors>,
+    pub(crate) scaling: Option<ScalingFactors>,
+    /// Addresses of registers which differ from the defaults on this model.
+    pub(crate) register_map: RegisterMap,
+    /// Whether the scaling factors were set by the user, rather than detected from the model.
+    pub(crate) scaling_is_manual: bool,
+    /// Maximum setpoints, detected with the scaling factors or set by the user.
+    pub(crate) limits: Option<ModelLimits>,
+    /// Scaling factors of models added at runtime.
+    pub(crate) scaling_registry: ScalingRegistry,
+    /// Models described at runtime, see [`Self::register_model`].
+    pub(crate) model_registry: ModelRegistry,
+    /// Firmware quirks being worked around, see [`Self::identify`].
+    pub(crate) quirks: Quirks,
+    /// Number of transactions which have failed in a row.
+    pub(crate) consecutive_failures: u8,
+    /// Set when the link recovers after repeated failures, as the device may have been power cycled.
+    pub(crate) stale: bool,
+    /// Output time seen by the last call to [`Self::check_power_cycle`], in seconds.
+    pub(crate) last_output_time_secs: Option<u32>,
+    /// Slave address written to the device, which only takes effect after a power cycle.
+    pub(crate) pending_unit_id: Option<u8>,
+    /// Baud rate written to the device, which only takes effect after a power cycle.
+    pub(crate) pending_baud_rate: Option<BaudRate>,
+    /// Front panel settings last seen or written, see [`Self::poll_panel_changes`].
+    pub(crate) panel_baseline: Option<PanelSettings>,
+    /// Protections set at the last call to [`Self::poll_protections`].
+    pub(crate) protections_seen: ProtectionStatus,
+    /// Outcome of the heartbeats so far, see [`Self::heartbeat`].
+    pub(crate) link: LinkStats,
+    /// Slow-changing registers, see [`Self::set_register_cache`].
+    pub(crate) register_cache: RegisterCache,
+    /// Request frame of the most recent transaction.
+    pub(crate) last_request: heapless::Vec<u8, L>,
+    /// Response frame of the most recent transaction.
+    pub(crate) last_response: heapless::Vec<u8, L>,
+    /// How failed transactions are retried.
+    pub(crate) retry_policy: RetryPolicy,
+    /// Whether to refuse to enable the output while a protection is active, see [`Self::set_strict_mode`].
+    pub(crate) strict_mode: bool,
+    /// Longest wait for each part of a response, passed to the transport.
+    response_timeout: Option<core::time::Duration>,
+    /// How registers are read, see [`Self::set_read_function`].
+    pub(crate) read_function: ReadFunction,
+    /// Whether setters read back what they wrote, see [`Self::set_write_verification`].
+    pub(crate) write_verification: WriteVerification,
+    /// Called with every frame sent and received, see [`Self::set_frame_hook`].
+    pub(crate) frame_hook: Option<FrameHook>,
+    /// Wait between transactions, see [`Self::set_request_gap`].
+    pub(crate) request_gap: Option<RequestGap>,
+    /// Whether a transaction has been made since the last wait for the request gap.
+    gap_pending: bool,
 }
 
-impl<S: embedded_io::Read + embedded_io::Write, const L: usize> XyPsu<S, L> {
-    /// Create a new XyPsu instance with the given interface and unit ID
-    ///
-    /// Scaling factors are lazily loaded on first use of scaled measurement functions.
-    /// You can manually specify scaling factors using [`Self::set_scaling_factors`].
-    pub fn new(interface: S, unit_id: u8) -> Self {
-        Self {
-            interface,
-            unit_id,
-            scaling: None,
+/// Raw frames of a Modbus transaction, see [`XyPsu::last_transaction`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transaction<'a> {
+    /// Bytes written to the PSU.
+    pub request: &'a [u8],
+    /// Bytes received from the PSU.
+    pub response: &'a [u8],
+}
+
+/// Which way a frame went, see [`XyPsu::set_frame_hook`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Direction {
+    /// Request sent to the PSU.
+    Tx,
+    /// Response received from the PSU.
+    Rx,
+}
+
+/// Called with every frame sent and received, see [`XyPsu::set_frame_hook`].
+pub type FrameHook = fn(Direction, &[u8]);
+
+/// MPPT settings, read and written together by [`XyPsu::get_mppt_config`] and [`XyPsu::set_mppt_config`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MpptConfig {
+    /// Whether MPPT is enabled.
+    pub enabled: State,
+    /// MPPT coefficient, 100x what is shown on the display, see [`XyPsu::get_mppt_k_value`].
+    pub k_value: u16,
+    /// Battery full current in milliamps, or `None` on models where the register doesn't work, see
+    /// [`XyPsu::set_battery_full_current_ma`]. `None` leaves the register as it is when written.
+    pub battery_full_current_ma: Option<u32>,
+}
+
+impl MpptConfig {
+    /// Registers holding the settings, in order, with or without the battery full current.
+    pub(crate) const fn registers(battery_full_current: bool) -> &'static [XyRegister] {
+        match battery_full_current {
+            true => &[XyRegister::MpptSw, XyRegister::MpptK, XyRegister::BatFul],
+            false => &[XyRegister::MpptSw, XyRegister::MpptK],
         }
     }
 
-    /// Manually set the scaling factors for this PSU.
-    ///
-    /// This allows you to override the automatic scaling factor detection for models
-    /// with unknown or incorrect scaling factors. Once set, these scaling factors will
-    /// be used for all scaled measurement and configuration functions.
-    pub fn set_scaling_factors(&mut self, scaling: ScalingFactors) {
-        self.scaling = Some(scaling);
+    /// Decode the values of [`Self::registers`]. The battery full current is `None` if it wasn't read.
+    pub(crate) fn from_registers(registers: &[u16], scaling: &ScalingFactors) -> Self {
+        let value = |i: usize| registers.get(i).copied();
+        Self {
+            enabled: State::from(value(0).unwrap_or(0)),
+            k_value: value(1).unwrap_or(0),
+            battery_full_current_ma: value(2).map(|raw| scaling.raw_to_current_ma(raw)),
+        }
     }
 
-    /// Ensure scaling factors are loaded for this PSU model.
+    /// Encode into the values of [`Self::registers`], with the battery full current if it is set.
     ///
-    /// This is called automatically by scaled measurement functions.
-    /// If the model's scaling factors are unknown, returns `ScalingNotAvailable` error.
-    ///
-    /// Returns a copy of the scaling factors so that self can be borrowed mutably afterwards.
-    pub(crate) fn ensure_scaling(&mut self) -> Result<ScalingFactors, S::Error> {
-        // If already cached, return a copy
-        if let Some(scaling) = self.scaling {
-            return Ok(scaling);
+    /// Returns `None` if the battery full current is above `max_current_ma`, or too large for the register.
+    pub(crate) fn to_registers(
+        self,
+        scaling: &ScalingFactors,
+        max_current_ma: Option<u32>,
+    ) -> Option<heapless::Vec<u16, 3>> {
+        let mut registers = heapless::Vec::from_array([self.enabled.into(), self.k_value]);
+        if let Some(current_ma) = self.battery_full_current_ma {
+            let raw = setpoint_to_raw(current_ma, max_current_ma, scaling.current_divisor)?;
+            // There is room for all three registers.
+            registers.push(raw).ok();
         }
+        Some(registers)
+    }
+}
 
-        // Otherwise, fetch model and lookup scaling factors
-        let model = self.get_product_model()?;
-        let scaling = model.scaling_factors().ok_or(Error::ScalingNotAvailable)?;
+/// How transactions which fail due to line noise are retried, see [`XyPsu::set_retry_policy`].
+///
+/// Corrupted or mismatched responses ([`Error::CrcMismatch`], [`Error::InvalidResponse`]) and timeouts are retried.
+/// Other errors are returned straight away.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total number of attempts at each transaction, including the first. `0` is treated as `1`.
+    pub attempts: u8,
+    /// Called before each retry with the retry number, starting from `1`, e.g. to sleep for a while. Not supported by
+    /// the async driver, see [`XyPsuAsync::set_retry_policy`](crate::asynch::XyPsuAsync::set_retry_policy).
+    pub backoff: Option<fn(u8)>,
+}
 
-        // Cache for future use
-        self.scaling = Some(scaling);
-        Ok(scaling)
+/// Default makes a single attempt, without retrying.
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 1,
+            backoff: None,
+        }
     }
+}
 
-    /// Return the measured output voltage in millivolts.
-    ///
-    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
-    /// error if the model's scaling factors are unknown.
-    ///
-    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
-    /// specify scaling factors.
-    pub fn read_output_voltage_mv(&mut self) -> Result<u32, S::Error> {
-        let scaling = self.ensure_scaling()?;
-        let raw = self.read_modbus_single(XyRegister::VOut)?;
-        Ok(scaling.raw_to_voltage_mv(raw))
-    }
+/// Least time between transactions, for firmware which drops requests sent back-to-back, see
+/// [`XyPsu::set_request_gap`].
+#[derive(Debug, Clone, Copy)]
+pub struct RequestGap {
+    /// Time to wait after each transaction before starting the next, including retries.
+    pub min_gap: core::time::Duration,
+    /// Called with `min_gap` to wait, e.g. `std::thread::sleep`. The async driver waits on a timer instead, see
+    /// [`XyPsuAsync::set_request_gap`](crate::asynch::XyPsuAsync::set_request_gap).
+    pub delay: fn(core::time::Duration),
+}
 
-    /// Return the measured supply input voltage in millivolts.
-    ///
-    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
-    /// error if the model's scaling factors are unknown.
-    ///
-    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
-    /// specify scaling factors.
-    pub fn read_input_voltage_mv(&mut self) -> Result<u32, S::Error> {
-        let scaling = self.ensure_scaling()?;
-        let raw = self.read_modbus_single(XyRegister::UIn)?;
-        Ok(scaling.raw_to_voltage_mv(raw))
-    }
+/// Whether setters check their writes took effect, see [`XyPsu::set_write_verification`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WriteVerification {
+    /// Trust the PSU's acknowledgement of the write.
+    #[default]
+    Off,
+    /// Read the registers back after writing them.
+    ReadBack,
+}
 
-    /// Return the measured output current in milliamps.
-    ///
-    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
-    /// error if the model's scaling factors are unknown.
-    ///
-    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
-    /// specify scaling factors.
-    pub fn read_current_ma(&mut self) -> Result<u32, S::Error> {
-        let scaling = self.ensure_scaling()?;
-        let raw = self.read_modbus_single(XyRegister::IOut)?;
-        Ok(scaling.raw_to_current_ma(raw))
-    }
+/// Which Modbus function reads registers, see [`XyPsu::set_read_function`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReadFunction {
+    /// Read holding registers (FC03), which genuine firmware uses for everything.
+    #[default]
+    Holding,
+    /// Read input registers (FC04).
+    Input,
+    /// Read holding registers, and if the PSU responds with an exception, read the same registers as input registers.
+    HoldingWithInputFallback,
+}
 
-    /// Return the measured output power in milliwatts.
-    ///
-    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
-    /// error if the model's scaling factors are unknown.
-    ///
-    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
-    /// specify scaling factors.
-    pub fn read_power_mw(&mut self) -> Result<u32, S::Error> {
-        let scaling = self.ensure_scaling()?;
-        let raw = self.read_modbus_single(XyRegister::Power)?;
-        Ok(scaling.raw_to_power_mw(raw))
-    }
+/// Use this type to configure an [`XyPsu`] before creating it, see [`XyPsu::builder`].
+///
+/// Options which aren't set keep the defaults of [`XyPsu::new`], with unit ID `0x01`.
+#[derive(Debug)]
+pub struct XyPsuBuilder<S: Transport, const L: usize = 128> {
+    interface: S,
+    unit_id: u8,
+    response_timeout: Option<core::time::Duration>,
+    retry_policy: RetryPolicy,
+    scaling: Option<ScalingFactors>,
+    write_verification: WriteVerification,
+    read_function: ReadFunction,
+    strict_mode: bool,
+    frame_hook: Option<FrameHook>,
+    register_cache: bool,
+    request_gap: Option<RequestGap>,
+}
 
-    /// Return the measured output energy in milliwatt-hours.
-    pub fn read_energy_mwh(&mut self) -> Result<u32, S::Error> {
-        let energy_mwh_lower = self.read_modbus_single(XyRegister::WhLow)? as u32;
-        let energy_mwh_upper = self.read_modbus_single(XyRegister::WhHigh)? as u32;
-        // @TODO confirm raw value in milli-wattshours.
-        Ok(energy_mwh_lower + (energy_mwh_upper << 16))
+impl<S: Transport, const L: usize> XyPsuBuilder<S, L> {
+    /// Set the Modbus unit ID of the PSU.
+    pub fn with_unit_id(mut self, unit_id: u8) -> Self {
+        self.unit_id = unit_id;
+        self
     }
 
-    /// Return the measured output capacity in milliamp-hours.
-    pub fn read_capacity_mah(&mut self) -> Result<u32, S::Error> {
-        let energy_mah_lower = self.read_modbus_single(XyRegister::AhLow)? as u32;
-        let energy_mah_upper = self.read_modbus_single(XyRegister::AhHigh)? as u32;
-        // @TODO confirm raw value in milli-amphours.
-        Ok(energy_mah_lower + (energy_mah_upper << 16))
+    /// Set the response timeout, see [`XyPsu::set_response_timeout`].
+    pub fn with_response_timeout(mut self, timeout: core::time::Duration) -> Self {
+        self.response_timeout = Some(timeout);
+        self
     }
 
-    /// Return the duration that the output has been enabled.
-    ///
-    /// @TODO create std version of this method.
-    pub fn read_output_time(&mut self) -> Result<Duration<u32, 1, 1>, S::Error> {
-        let time_h = self.read_modbus_single(XyRegister::OutH)? as u32;
-        let time_m = self.read_modbus_single(XyRegister::OutM)? as u32;
-        let time_s = self.read_modbus_single(XyRegister::OutS)? as u32;
-        let duration = Duration::<u32, 1, 1>::hours(time_h)
-            + Duration::<u32, 1, 1>::minutes(time_m)
-            + Duration::<u32, 1, 1>::secs(time_s);
-        Ok(duration)
+    /// Set how failed transactions are retried, see [`XyPsu::set_retry_policy`].
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
     }
 
-    /// Return the measured internal temperature.
-    ///
-    /// Unit of measurement depends on setting.
-    pub fn read_temperature_internal(&mut self) -> Result<Temperature, S::Error> {
-        let unit = self.get_temperature_unit()?;
-        let temp_internal_raw = self.read_modbus_single(XyRegister::TIn)?;
-        Ok(Temperature::from_centi(temp_internal_raw, unit))
+    /// Use these scaling factors rather than detecting them, see [`XyPsu::set_scaling_factors`].
+    pub fn with_scaling_factors(mut self, scaling: ScalingFactors) -> Self {
+        self.scaling = Some(scaling);
+        self
     }
 
-    /// Return the measured external temperature sensor.
-    ///
-    /// Unit of measurement depends on setting. See [Self::set_temperature_unit].
-    ///
-    /// @TODO test with external temp sensor.
-    pub fn read_temperature_external(&mut self) -> Result<Temperature, S::Error> {
-        let unit = self.get_temperature_unit()?;
-        let temp_external_raw = self.read_modbus_single(XyRegister::TEx)?;
-        Ok(Temperature::from_centi(temp_external_raw, unit))
+    /// Set whether setters read back what they wrote, see [`XyPsu::set_write_verification`].
+    pub fn with_write_verification(mut self, write_verification: WriteVerification) -> Self {
+        self.write_verification = write_verification;
+        self
     }
 
-    /// Enable/disable the key lock.
-    pub fn set_lock_state(&mut self, state: impl Into<State>) -> Result<(), S::Error> {
-        self.write_modbus_single(XyRegister::Lock, state.into() as u16)?;
-        Ok(())
+    /// Set which Modbus function reads registers, see [`XyPsu::set_read_function`].
+    pub fn with_read_function(mut self, read_function: ReadFunction) -> Self {
+        self.read_function = read_function;
+        self
     }
 
-    /// Get the current state of the key lock.
-    pub fn get_lock_state(&mut self) -> Result<State, S::Error> {
-        let value = self.read_modbus_single(XyRegister::Lock)?;
-        let state = State::from(value != 0);
-        Ok(state)
+    /// Set strict mode, see [`XyPsu::set_strict_mode`].
+    pub fn with_strict_mode(mut self, strict_mode: bool) -> Self {
+        self.strict_mode = strict_mode;
+        self
     }
 
-    /// Get the currently active control mode. (CV or CC.)
-    pub fn get_current_control_mode(&mut self) -> Result<ControlMode, S::Error> {
-        let value = self.read_modbus_single(XyRegister::CvCc)?;
-        let state = ControlMode::from(value);
-        Ok(state)
+    /// Call `hook` with every frame sent and received, see [`XyPsu::set_frame_hook`].
+    pub fn with_frame_hook(mut self, hook: FrameHook) -> Self {
+        self.frame_hook = Some(hook);
+        self
     }
 
-    /// Enable/disable the output.
-    pub fn set_output_state(&mut self, state: impl Into<State>) -> Result<(), S::Error> {
-        self.write_modbus_single(XyRegister::OnOff, state.into() as u16)?;
-        Ok(())
+    /// Cache slow-changing registers, see [`XyPsu::set_register_cache`].
+    pub fn with_register_cache(mut self, enabled: bool) -> Self {
+        self.register_cache = enabled;
+        self
     }
 
-    /// Read whether the output is enabled or disabled.
-    pub fn get_output_state(&mut self) -> Result<State, S::Error> {
-        let value = self.read_modbus_single(XyRegister::OnOff)?;
-        let state = State::from(value != 0);
-        Ok(state)
+    /// Wait at least `min_gap` between transactions, calling `delay` to wait, see [`XyPsu::set_request_gap`].
+    pub fn with_min_request_gap(
+        mut self,
+        min_gap: core::time::Duration,
+        delay: fn(core::time::Duration),
+    ) -> Self {
+        self.request_gap = Some(RequestGap { min_gap, delay });
+        self
     }
 
-    /// Read the current firmware version.
-    ///
-    /// Decimal value of `136` -> `v1.3.6`.
-    pub fn get_firmware_version(&mut self) -> Result<u16, S::Error> {
-        let value = self.read_modbus_single(XyRegister::Version)?;
-        Ok(value)
+    /// Create the [`XyPsu`]. Nothing is sent to the PSU yet.
+    pub fn build(self) -> XyPsu<S, L> {
+        let mut psu = XyPsu::new(self.interface, self.unit_id);
+        psu.set_response_timeout(self.response_timeout);
+        psu.set_retry_policy(self.retry_policy);
+        if let Some(scaling) = self.scaling {
+            psu.set_scaling_factors(scaling);
+        }
+        psu.set_write_verification(self.write_verification);
+        psu.set_read_function(self.read_function);
+        psu.set_strict_mode(self.strict_mode);
+        psu.set_frame_hook(self.frame_hook);
+        psu.set_register_cache(self.register_cache);
+        psu.set_request_gap(self.request_gap);
+        psu
     }
+}
 
-    /// Set the Modbus unit ID of this PSU.
-    ///
-    /// Appears to only be applied after a power cycle.
-    pub fn set_slave_address(&mut self, address: u8) -> Result<(), S::Error> {
-        // Only 1-247 range is suitable ID for single Modbus device.
-        assert!(address <= 247);
-        self.write_modbus_single(XyRegister::SlaveAdd, address as u16)?;
-        Ok(())
-    }
+/// Scaling factors and limits cached by an [`XyPsu`], see [`XyPsu::cached_scaling`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CachedScaling {
+    pub scaling: ScalingFactors,
+    /// Whether the scaling factors were set manually.
+    pub is_manual: bool,
+    pub limits: Option<ModelLimits>,
+}
 
-    /// Get the current Modbus unit ID of this PSU.
-    pub fn get_slave_address(&mut self) -> Result<u8, S::Error> {
-        let value = self.read_modbus_single(XyRegister::SlaveAdd)?;
-        let address = u8::try_from(value)?;
-        Ok(address)
-    }
+/// Capacity and energy counter registers, zeroed by [`XyPsu::reset_energy_counters`].
+pub(crate) const ENERGY_COUNTERS: [XyRegister; 4] = [
+    XyRegister::AhLow,
+    XyRegister::AhHigh,
+    XyRegister::WhLow,
+    XyRegister::WhHigh,
+];
+
+/// Number of failed transactions in a row after which the device is assumed to have been power cycled.
+pub const FAILURES_BEFORE_REINIT: u8 = 3;
 
-    /// Sets the configured baud rate on the PSU.
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Create a new XyPsu instance with the given interface and unit ID
     ///
-    /// Appears to only be applied after a power cycle.
-    pub fn set_baudrate(&mut self, baud_rate: BaudRate) -> Result<(), S::Error> {
-        self.write_modbus_single(XyRegister::BaudRateL, baud_rate as u16)?;
-        Ok(())
+    /// Scaling factors are lazily loaded on first use of scaled measurement functions.
+    /// You can manually specify scaling factors using [`Self::set_scaling_factors`].
+    pub fn new(interface: S, unit_id: u8) -> Self {
+        Self {
+            interface,
+            unit_id,
+            scaling: None,
+            register_map: RegisterMap::new(),
+            scaling_is_manual: false,
+            limits: None,
+            scaling_registry: ScalingRegistry::new(),
+            model_registry: ModelRegistry::new(),
+            quirks: Quirks::default(),
+            consecutive_failures: 0,
+            stale: false,
+            last_output_time_secs: None,
+            pending_unit_id: None,
+            pending_baud_rate: None,
+            panel_baseline: None,
+            protections_seen: ProtectionStatus::default(),
+            link: LinkStats::default(),
+            register_cache: RegisterCache::default(),
+            last_request: heapless::Vec::new(),
+            last_response: heapless::Vec::new(),
+            retry_policy: RetryPolicy::default(),
+            strict_mode: false,
+            response_timeout: None,
+            read_function: ReadFunction::default(),
+            write_verification: WriteVerification::default(),
+            frame_hook: None,
+            request_gap: None,
+            gap_pending: false,
+        }
     }
 
-    /// Reads the configured baud rate on the PSU.
-    pub fn get_baudrate(&mut self) -> Result<BaudRate, S::Error> {
-        let value = self.read_modbus_single(XyRegister::BaudRateL)?;
-        let baudrate = BaudRate::try_from(value)?;
-        Ok(baudrate)
+    /// Configure a new XyPsu on `interface`, for options beyond the unit ID.
+    ///
+    /// ```ignore
+    /// let mut psu: XyPsu<_> = XyPsu::builder(serial)
+    ///     .with_unit_id(0x02)
+    ///     .with_response_timeout(Duration::from_millis(200))
+    ///     .with_write_verification(WriteVerification::ReadBack)
+    ///     .build();
+    /// ```
+    pub fn builder(interface: S) -> XyPsuBuilder<S, L> {
+        XyPsuBuilder {
+            interface,
+            unit_id: 0x01,
+            response_timeout: None,
+            retry_policy: RetryPolicy::default(),
+            scaling: None,
+            write_verification: WriteVerification::default(),
+            read_function: ReadFunction::default(),
+            strict_mode: false,
+            frame_hook: None,
+            register_cache: false,
+            request_gap: None,
+        }
     }
 
-    /// Set the temperature unit to use.
-    pub fn set_temperature_unit(&mut self, unit: TemperatureUnit) -> Result<(), S::Error> {
-        self.write_modbus_single(XyRegister::FC, unit as u16)?;
-        Ok(())
+    /// Access the underlying interface, e.g. to queue mock responses.
+    #[cfg(test)]
+    pub(crate) fn interface_mut(&mut self) -> &mut S {
+        &mut self.interface
     }
 
-    /// Return the temperature unit in use.
-    pub fn get_temperature_unit(&mut self) -> Result<TemperatureUnit, S::Error> {
-        let value = self.read_modbus_single(XyRegister::FC)?;
-        let unit = TemperatureUnit::try_from(value)?;
-        Ok(unit)
+    /// Set how transactions which fail due to line noise are retried. By default they are not.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
     }
 
-    /// Set the output target voltage. Value supplied in millivolts.
-    ///
-    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
-    /// error if the model's scaling factors are unknown.
+    /// Wait between transactions, for firmware which drops requests sent back-to-back, instead of sleeping around
+    /// every call. `None`, the default, sends each request straight away.
     ///
-    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
-    /// specify scaling factors.
-    pub fn set_output_voltage_mv(&mut self, voltage_mv: u32) -> Result<(), S::Error> {
-        let scaling = self.ensure_scaling()?;
-        let raw = scaling.voltage_mv_to_raw(voltage_mv);
-        self.write_modbus_single(XyRegister::VSet, raw)?;
-        Ok(())
+    /// The whole gap is waited before every transaction but the first, as the driver has no clock to tell how long
+    /// the application took between calls.
+    pub fn set_request_gap(&mut self, gap: Option<RequestGap>) {
+        self.request_gap = gap;
     }
 
-    /// Get the current output target voltage. Value returned in millivolts.
-    ///
-    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
-    /// error if the model's scaling factors are unknown.
+    /// Set the longest wait for each part of a response, which is passed to [`Transport::receive`]. `None`, the
+    /// default, waits as long as the transport does.
     ///
-    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
-    /// specify scaling factors.
-    pub fn get_output_voltage_mv(&mut self) -> Result<u32, S::Error> {
-        let scaling = self.ensure_scaling()?;
-        let raw = self.read_modbus_single(XyRegister::VSet)?;
-        Ok(scaling.raw_to_voltage_mv(raw))
+    /// Interfaces used through `embedded-io` ignore this, as it has no timeouts.
+    pub fn set_response_timeout(&mut self, timeout: Option<core::time::Duration>) {
+        self.response_timeout = timeout;
     }
 
-    /// Set the output current limit. Value supplied in milliamps.
+    /// Use the register map and scaling factors from a [`ModelDescription`](crate::register_map::ModelDescription)
+    /// loaded at runtime.
     ///
-    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
-    /// error if the model's scaling factors are unknown.
-    ///
-    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
-    /// specify scaling factors.
-    pub fn set_current_limit_ma(&mut self, current_ma: u32) -> Result<(), S::Error> {
-        let scaling = self.ensure_scaling()?;
-        let raw = scaling.current_ma_to_raw(current_ma);
-        self.write_modbus_single(XyRegister::ISet, raw)?;
+    /// Returns [`Error::BufferError`] if the description moves too many registers.
+    #[cfg(all(feature = "model-files", not(feature = "no_std")))]
+    pub fn apply_model_description(
+        &mut self,
+        description: &crate::register_map::ModelDescription,
+    ) -> Result<(), S::Error> {
+        self.register_map = description.register_map().map_err(|_| Error::BufferError)?;
+        self.set_scaling_factors(description.scaling);
         Ok(())
     }
 
-    /// Get the current output current limit value. Value returned in milliamps.
-    ///
-    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
-    /// error if the model's scaling factors are unknown.
-    ///
-    /// For unknown models, use [`set_scaling_factors`](Self::set_scaling_factors) to manually
-    /// specify scaling factors.
-    pub fn get_current_limit_ma(&mut self) -> Result<u32, S::Error> {
-        let scaling = self.ensure_scaling()?;
-        let raw = self.read_modbus_single(XyRegister::ISet)?;
-        Ok(scaling.raw_to_current_ma(raw))
-    }
-
-    /// Returns the raw register values for "MODEL" -> product model
+    /// Forget everything cached about the device, so it is read again when next needed.
     ///
-    /// See [Self::get_product_model] for a method which tries to interpret this data.
-    pub fn get_product_model_raw(&mut self) -> Result<u16, S::Error> {
-        self.read_modbus_single(XyRegister::Model)
+    /// Scaling factors set with [`Self::set_scaling_factors`] are kept.
+    pub fn invalidate_cache(&mut self) {
+        if !self.scaling_is_manual {
+            self.scaling = None;
+        }
+        self.last_output_time_secs = None;
+        self.panel_baseline = None;
+        self.register_cache.invalidate();
+        self.stale = false;
     }
 
-    /// Returns the interpreted product model.
-    ///
-    /// Only models where the ID has been observed are supported.
-    ///
-    /// If you have a model which is not supported, please submit a Github
-    /// ticket with information so we can add it!
-    pub fn get_product_model(&mut self) -> Result<ProductModel, S::Error> {
-        use ProductModel as PM;
-
-        let raw = self.get_product_model_raw()?;
-
-        match raw {
-            x if x == PM::XY6020L as u16 => Ok(PM::XY6020L),
-            x if x == PM::XY12522 as u16 => Ok(PM::XY12522),
-            x if x == PM::XY7025 as u16 => Ok(PM::XY7025),
-            x if x == PM::XY3607F as u16 => Ok(PM::XY3607F),
-            // x if x == PM::XYSK60S as u16 => Ok(PM::XYSK60S),
-            // x if x == PM::XYSK120S as u16 => Ok(PM::XYSK120S),
-            // x if x == PM::XYSK150S as u16 => Ok(PM::XYSK150S),
-            // x if x == PM::XY3606B as u16 => Ok(PM::XY3606B),
-            // x if x == PM::XY6506 as u16 => Ok(PM::XY6506),
-            // x if x == PM::XY6506S as u16 => Ok(PM::XY6506S),
-            // x if x == PM::XY6509 as u16 => Ok(PM::XY6509),
-            // x if x == PM::XY6509X as u16 => Ok(PM::XY6509X),
-            _ => unimplemented!(
-                "The raw ID  0x{:04X} | {} is not currently recognised by this library.",
-                raw,
-                raw
-            ),
+    /// Note that `register` was written with `data`, for the panel baseline and register cache.
+    pub(crate) fn record_write(&mut self, register: XyRegister, data: u16) {
+        match (register, &mut self.panel_baseline) {
+            // A preset replaces the voltage and current settings.
+            (XyRegister::ExtractM, baseline) => *baseline = None,
+            (_, Some(baseline)) => baseline.record_write(register, data),
+            (_, None) => {}
         }
+        self.register_cache.store(register, data);
     }
 
-    /// Configure the baud rate of the PSU.
-    pub fn set_baud_rate(&mut self, baud_rate: BaudRate) -> Result<(), S::Error> {
-        self.write_modbus_single(XyRegister::BaudRateL, baud_rate)
+    /// Returns `NotSupported` if the firmware doesn't implement `register`, and its address otherwise.
+    pub(crate) fn register_address(&self, register: XyRegister) -> Result<u16, S::Error> {
+        self.check_register(register)?;
+        Ok(self.register_map.address(register))
     }
 
-    /// Return which protections have been triggered, if any.
-    pub fn get_protection_status(&mut self) -> Result<ProtectionStatus, S::Error> {
-        let raw = self.read_modbus_single(XyRegister::Protect)?;
-        let bytes = raw.to_le_bytes();
-        let status = ProtectionStatus::from_bytes(bytes);
-        Ok(status)
-    }
+    /// Write the request frame and read the response frame into `last_response`.
+    pub(crate) fn exchange(&mut self, frame: &Frame<L>) -> Result<(), S::Error> {
+        self.last_request.clone_from(&frame.bytes);
+        self.last_response.clear();
 
-    /// Clear any active protection flags.
-    pub fn clear_protections(&mut self) -> Result<(), S::Error> {
-        self.write_modbus_single(XyRegister::Protect, 0x00_u16)?;
-        Ok(())
-    }
+        if let Some(gap) = self.request_gap.filter(|_| self.gap_pending) {
+            (gap.delay)(gap.min_gap);
+        }
+        self.gap_pending = true;
 
-    /// Set the backlight brightness level.
-    pub fn set_backlight(&mut self, level: BacklightBrightness) -> Result<(), S::Error> {
-        self.write_modbus_single(XyRegister::BLed, level as u16)?;
+        #[cfg(feature = "trace-frames")]
+        let sent = crate::trace::sent(&frame.context, &frame.bytes);
+        if let Some(hook) = self.frame_hook {
+            hook(Direction::Tx, &frame.bytes);
+        }
+        self.interface
+            .send(&frame.bytes)
+            .map_err(Error::SerialError)?;
+
+        // Read the response - keep reading until we get WouldBlock or have enough data
+        let mut temp_buf = [0u8; 16];
+        loop {
+            match self.interface.receive(&mut temp_buf, self.response_timeout) {
+                Ok(bytes_read) => {
+                    let bytes = temp_buf.get(..bytes_read).ok_or(Error::BufferError)?;
+                    if frame.receive(&mut self.last_response, bytes)? {
+                        break;
+                    }
+                }
+                Err(e) if frame::ends_response(e.kind(), self.last_response.len()) => break,
+                // Other errors should be propagated
+                Err(e) => return Err(Error::SerialError(e)),
+            }
+        }
+        #[cfg(feature = "trace-frames")]
+        crate::trace::received(&frame.context, &self.last_response, sent);
+        if let Some(hook) = self.frame_hook {
+            hook(Direction::Rx, &self.last_response);
+        }
         Ok(())
     }
+}
 
-    /// Get the current backlight brightness level.
-    pub fn get_backlight(&mut self) -> Result<BacklightBrightness, S::Error> {
-        let value = self.read_modbus_single(XyRegister::BLed)?;
-        let level = BacklightBrightness::try_from(value)?;
-        Ok(level)
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::{ModbusException, TransactionFailure},
+        mock_serial::{MockResponse, MockSerial},
+        register::ProductModel,
+    };
+    #[cfg(feature = "presets")]
+    use crate::{
+        preset::{PresetGroup, PresetOutputPolicy, ProtectionConfig},
+        register::Temperature,
+    };
 
-    /// Enable/disable the buzzer..
-    pub fn set_buzzer_enabled(&mut self, state: impl Into<State>) -> Result<(), S::Error> {
-        self.write_modbus_single(XyRegister::Buzzer, state.into() as u16)?;
-        Ok(())
+    #[test]
+    fn test_last_transaction_kept_on_error() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        let garbage = [0x01, 0x03, 0x02, 0x56, 0x78, 0x00, 0x00];
+        psu.interface.queue_response(MockResponse::Data(
+            heapless::Vec::from_slice(&garbage).unwrap(),
+        ));
+
+        let error = psu.read_modbus_single(0x20_u16).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Transaction {
+                failure: TransactionFailure::CrcMismatch,
+                ..
+            }
+        ));
+        assert_eq!(
+            error.context(),
+            Some(crate::error::TransactionContext {
+                unit_id: 0x01,
+                operation: crate::error::Operation::Read,
+                address: 0x20,
+                count: 1,
+            })
+        );
+        let transaction = psu.last_transaction();
+        assert_eq!(transaction.request, psu.interface.written_data());
+        assert_eq!(transaction.response, garbage.as_slice());
     }
 
-    /// Get the current buzzer enable state.
-    pub fn get_buzzer_enabled(&mut self) -> Result<State, S::Error> {
-        let value = self.read_modbus_single(XyRegister::Buzzer)?;
-        let state = State::from(value != 0);
-        Ok(state)
+    #[test]
+    fn test_read_function_fallback() {
+        let frame = |bytes: &[u8]| {
+            let mut response: heapless::Vec<u8, 256> = heapless::Vec::from_slice(bytes).unwrap();
+            let crc = crate::mock_serial::crc16(&response);
+            response.extend_from_slice(&crc).unwrap();
+            MockResponse::Data(response)
+        };
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_read_function(ReadFunction::HoldingWithInputFallback);
+        psu.interface.queue_response(frame(&[0x01, 0x83, 0x02]));
+        psu.interface
+            .queue_response(frame(&[0x01, 0x04, 0x02, 0x04, 0xB0]));
+        assert_eq!(psu.read_modbus_single(0x02_u16).unwrap(), 1200);
+        assert_eq!(psu.last_transaction().request.get(1), Some(&0x04));
+
+        // Without the fallback, the exception is returned.
+        psu.set_read_function(ReadFunction::Holding);
+        psu.interface.queue_response(frame(&[0x01, 0x83, 0x02]));
+        assert!(matches!(
+            psu.read_modbus_single(0x02_u16),
+            Err(Error::Transaction {
+                failure: TransactionFailure::Exception(ModbusException::IllegalDataAddress),
+                ..
+            })
+        ));
     }
 
-    /// Activate preset by index.
-    pub fn set_active_preset(&mut self, group: impl Into<PresetGroup>) -> Result<(), S::Error> {
-        self.write_modbus_single(XyRegister::ExtractM, group.into() as u16)?;
-        Ok(())
+    #[test]
+    fn test_write_verification() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        psu.set_write_verification(WriteVerification::ReadBack);
+
+        psu.interface.queue_response(MockResponse::Echo);
+        psu.interface.queue_read_response(0x01, &[1200]);
+        psu.set_output_voltage_mv(12000).unwrap();
+
+        // The PSU clamped the setting.
+        psu.interface.queue_response(MockResponse::Echo);
+        psu.interface.queue_read_response(0x01, &[3000]);
+        assert!(matches!(
+            psu.set_output_voltage_mv(40000),
+            Err(Error::VerificationFailed {
+                expected: 4000,
+                actual: 3000
+            })
+        ));
     }
 
-    /// Get the currently active preset group.
-    ///
-    /// Returns the preset group (0-9) that is currently active on the PSU.
-    pub fn get_active_preset(&mut self) -> Result<PresetGroup, S::Error> {
-        let value = self.read_modbus_single(XyRegister::ExtractM)?;
-        let group = PresetGroup::try_from(value)?;
-        Ok(group)
+    #[test]
+    fn test_retry_policy() {
+        static BACKOFFS: core::sync::atomic::AtomicU8 = core::sync::atomic::AtomicU8::new(0);
+        let garbage: heapless::Vec<u8, 256> =
+            heapless::Vec::from_slice(&[0x01, 0x03, 0x02, 0x56, 0x78, 0x00, 0x00]).unwrap();
+
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.interface
+            .queue_response(MockResponse::Data(garbage.clone()));
+        psu.interface.queue_read_response(0x01, &[0x5678]);
+        assert!(matches!(
+            psu.read_modbus_single(0x20_u16),
+            Err(Error::Transaction {
+                failure: TransactionFailure::CrcMismatch,
+                ..
+            })
+        ));
+
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_retry_policy(RetryPolicy {
+            attempts: 3,
+            backoff: Some(|_| {
+                BACKOFFS.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+            }),
+        });
+        psu.interface.queue_response(MockResponse::Data(garbage));
+        psu.interface.queue_read_response(0x01, &[0x5678]);
+        assert_eq!(psu.read_modbus_single(0x20_u16).unwrap(), 0x5678);
+        assert_eq!(BACKOFFS.load(core::sync::atomic::Ordering::Relaxed), 1);
+        assert_eq!(psu.consecutive_failures, 0);
     }
 
-    /// Enter or exit sleep mode. (Screen off, ON/OFF button fading in and out red.)
-    pub fn set_sleep_state(&mut self, activate_sleep: impl Into<State>) -> Result<(), S::Error> {
-        self.write_modbus_single(XyRegister::Device, !activate_sleep.into() as u16)?;
-        Ok(())
+    #[test]
+    fn test_scaling_registry_consulted() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.interface.queue_read_response(0x01, &[0x6503]);
+        assert!(matches!(
+            psu.read_output_voltage_mv(),
+            Err(Error::UnknownModel(0x6503))
+        ));
+
+        let scaling = ScalingFactors::new(10, 10, 1000, 10, 100).unwrap();
+        psu.scaling_registry_mut().insert(0x6503, scaling).unwrap();
+        psu.interface.queue_read_response(0x01, &[0x6503]);
+        psu.interface.queue_read_response(0x01, &[1234]);
+        assert_eq!(psu.read_output_voltage_mv().unwrap().0, 12340);
+        assert_eq!(psu.scaling_factors(), Some(scaling));
     }
 
-    /// Get whether the device is currently in sleep mode.
-    pub fn get_sleep_state(&mut self) -> Result<State, S::Error> {
-        let value = self.read_modbus_single(XyRegister::Device)?;
-        let state = State::from(value != 0);
-        Ok(state)
+    #[test]
+    fn test_unknown_model_identified() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.interface.queue_read_response(0x01, &[0x6503]);
+        let model = psu.get_product_model().unwrap();
+        assert_eq!(model, ProductModel::Unknown(0x6503));
+        assert_eq!(model.raw(), 0x6503);
+        assert_eq!(ProductModel::from(0x6502), ProductModel::XY6020L);
+
+        // Only the scaled methods need the scaling factors.
+        psu.interface.queue_read_response(0x01, &[1234]);
+        assert_eq!(psu.read_register(XyRegister::VOut).unwrap(), 1234);
+        psu.set_scaling_factors(ScalingFactors::new(10, 10, 1000, 10, 100).unwrap());
+        psu.interface.queue_read_response(0x01, &[1234]);
+        assert_eq!(psu.read_output_voltage_mv().unwrap().0, 12340);
     }
 
-    // /// Set the offset used for the internal temperature sensor.
-    // pub fn set_temperature_offset_input(&mut self, offset: impl Into<Temperature>) -> Result<(), S::Error> {
-    //     let unit = self.get_temperature_unit()?;
-    //     let temperature_in_unit = offset.into().as_unit(unit);
-    //     println!("Offset: {}", temperature_in_unit);
-    //     self.write_modbus_single(XyRegister::TInOffset, temperature_in_unit * 10)?;
-    //     Ok(())
-    // }
-
-    // /// Get the current offset used for the internal temperature sensor.
-    // pub fn get_temperature_offset_input(&mut self) -> Result<Temperature, S::Error> {
-    //     let unit = self.get_temperature_unit()?;
-    //     let value = self.read_modbus_single(XyRegister::TInOffset)?;
-    //     let temp_offset = Temperature::from_centi(value, unit);
-    //     Ok(temp_offset)
-    // }
-
-    /// Enable or disable MPPT functionality.
-    pub fn set_mppt_enabled(&mut self, activate_sleep: impl Into<State>) -> Result<(), S::Error> {
-        self.write_modbus_single(XyRegister::MpptSw, activate_sleep.into() as u16)?;
-        Ok(())
+    #[test]
+    fn test_input_current_limit_requires_register() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        assert!(matches!(
+            psu.get_input_current_limit_ma(),
+            Err(Error::NotSupported)
+        ));
+
+        let mut map = RegisterMap::new();
+        map.set_input_current_limit_address(Some(0x24));
+        psu.set_register_map(map);
+        psu.interface.queue_read_response(0x01, &[2500]);
+        assert_eq!(psu.get_input_current_limit_ma().unwrap(), 2500);
+
+        assert!(matches!(
+            psu.set_input_current_limit_ma(65_536),
+            Err(Error::InvalidRange)
+        ));
     }
 
-    /// Get whether MPPT is currently in enabled or disabled.
-    pub fn get_mppt_enabled(&mut self) -> Result<State, S::Error> {
-        let value = self.read_modbus_single(XyRegister::MpptSw)?;
-        let state = State::from(value != 0);
-        Ok(state)
+    #[test]
+    fn test_battery_full_current_requires_support() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 10, 100, 10, 10).unwrap());
+        assert!(matches!(
+            psu.set_battery_full_current_ma(500),
+            Err(Error::NotSupported)
+        ));
+
+        let mut map = RegisterMap::new();
+        map.set_battery_full_current(true);
+        psu.set_register_map(map);
+        psu.interface.queue_response(MockResponse::Echo);
+        psu.set_battery_full_current_ma(500).unwrap();
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([0x00, 0x21, 0x00, 0x32].as_slice())
+        );
     }
 
-    /// Set the MPPT coefficient. Recommended [`75` - `85`]
-    ///
-    /// Note: Value passed in is 10x bigger than shown on screen.
-    pub fn set_mppt_k_value(&mut self, mppt_k: u16) -> Result<(), S::Error> {
-        self.write_modbus_single(XyRegister::MpptK, mppt_k)?;
-        Ok(())
+    #[test]
+    fn test_strict_mode() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_strict_mode(true);
+
+        psu.interface.queue_read_response(0x01, &[0b10]);
+        assert!(matches!(
+            psu.set_output_state(true),
+            Err(Error::ProtectionActive(status)) if status.over_current()
+        ));
+
+        // Turning off is never refused.
+        psu.interface.queue_response(MockResponse::Echo);
+        psu.set_output_state(false).unwrap();
+
+        psu.interface.queue_read_response(0x01, &[0]);
+        psu.interface.queue_response(MockResponse::Echo);
+        psu.set_output_state(true).unwrap();
     }
 
-    /// Get the current MPPT coefficient. Default value of `80`.
-    ///
-    /// Value returned is 10x what is shown on the display.
-    ///
-    /// E.g. `0.75` on display => `75` as retuned by this function.
-    pub fn get_mppt_k_value(&mut self) -> Result<u16, S::Error> {
-        let value = self.read_modbus_single(XyRegister::MpptK)?;
-        Ok(value)
-    }
+    #[test]
+    fn test_reset_energy_counters() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        let bulk_write_response = || {
+            let mut response: heapless::Vec<u8, 256> =
+                heapless::Vec::from_slice(&[0x01, 0x10, 0x00, 0x06, 0x00, 0x04]).unwrap();
+            let crc = crate::mock_serial::crc16(&response);
+            response.extend_from_slice(&crc).unwrap();
+            MockResponse::Data(response)
+        };
+
+        for value in [1200, 0, 5000, 0] {
+            psu.interface.queue_read_response(0x01, &[value]);
+        }
+        psu.interface.queue_response(bulk_write_response());
+        for value in [0, 0, 1, 0] {
+            psu.interface.queue_read_response(0x01, &[value]);
+        }
+        psu.reset_energy_counters().unwrap();
 
-    // MPPT max charging current doesn't appear to work. Normal current limit value does seem to work.
-    // /// Set the MPPT maximum charging current in units of milli-amps.
-    // pub fn set_mppt_max_current_ma(&mut self, current_ma: u32) -> Result<(), S::Error> {
-    //     let current_raw: u16 = (current_ma / 10).try_into()?;
-    //     self.write_modbus_single(XyRegister::BatFul, current_raw)?;
-    //     Ok(())
-    // }
-
-    // /// Get the MPPT maximum charging current in units of milli-amps.
-    // pub fn get_mppt_max_current_ma(&mut self) -> Result<u32, S::Error> {
-    //     let value = self.read_modbus_single(XyRegister::BatFul)?;
-    //     Ok(value as u32 * 10)
-    // }
-
-    /// Enable or disable constant power mode.
-    pub fn set_constant_power_enabled(
-        &mut self,
-        activate_sleep: impl Into<State>,
-    ) -> Result<(), S::Error> {
-        self.write_modbus_single(XyRegister::CwSw, activate_sleep.into() as u16)?;
-        Ok(())
+        // Write ignored.
+        for value in [1200, 0, 5000, 0] {
+            psu.interface.queue_read_response(0x01, &[value]);
+        }
+        psu.interface.queue_response(bulk_write_response());
+        for value in [1200, 0, 5001, 0] {
+            psu.interface.queue_read_response(0x01, &[value]);
+        }
+        assert!(matches!(
+            psu.reset_energy_counters(),
+            Err(Error::NotSupported)
+        ));
     }
 
-    /// Get whether constant power mode is currently enabled or disabled.
-    pub fn get_constant_power_enabled(&mut self) -> Result<State, S::Error> {
-        let value = self.read_modbus_single(XyRegister::CwSw)?;
-        let state = State::from(value != 0);
-        Ok(state)
+    #[test]
+    fn test_request_gap_between_transactions() {
+        use core::sync::atomic::{AtomicU64, Ordering};
+        static WAITED_MS: AtomicU64 = AtomicU64::new(0);
+
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::builder(MockSerial::new())
+            .with_min_request_gap(core::time::Duration::from_millis(20), |gap| {
+                WAITED_MS.fetch_add(gap.as_millis() as u64, Ordering::Relaxed);
+            })
+            .build();
+        for _ in 0..3 {
+            psu.interface.queue_read_response(0x01, &[0]);
+        }
+        for _ in 0..3 {
+            psu.get_firmware_version().unwrap();
+        }
+        // Not before the first.
+        assert_eq!(WAITED_MS.load(Ordering::Relaxed), 40);
     }
 
-    /// Set the constant power power level. Units of watts.
-    ///
-    /// This can be set without enabling constant power mode.
-    pub fn set_constant_power_level(&mut self, mppt_k: u16) -> Result<(), S::Error> {
-        self.write_modbus_single(XyRegister::Cw, mppt_k)?;
-        Ok(())
+    #[cfg(feature = "presets")]
+    #[test]
+    fn test_set_protections_keeps_settings() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 10, 100, 10, 10).unwrap());
+
+        psu.interface.queue_read_response(0x01, &[2]);
+        let mut group = [0; 15];
+        group[0] = 1234;
+        group[1] = 567;
+        group[13] = 1;
+        group[14] = 77;
+        psu.interface.queue_read_response(0x01, &group);
+        psu.interface.queue_read_response(0x01, &[0]);
+        let mut response: heapless::Vec<u8, 256> =
+            heapless::Vec::from_slice(&[0x01, 0x10, 0x00, 0x70, 0x00, 0x0F]).unwrap();
+        let crc = crate::mock_serial::crc16(&response);
+        response.extend_from_slice(&crc).unwrap();
+        psu.interface.queue_response(MockResponse::Data(response));
+
+        psu.set_protections(ProtectionConfig {
+            over_voltage_mv: 30000,
+            ..Default::default()
+        })
+        .unwrap();
+        let request = psu.last_transaction().request;
+        // Settings, output switch and external temperature as read.
+        assert_eq!(
+            request.get(7..11),
+            Some([0x04, 0xD2, 0x02, 0x37].as_slice())
+        );
+        assert_eq!(
+            request.get(33..37),
+            Some([0x00, 0x01, 0x00, 0x4D].as_slice())
+        );
+        // Over voltage protection as given.
+        assert_eq!(request.get(13..15), Some([0x0B, 0xB8].as_slice()));
     }
 
-    /// Get the current constant power power level. Units of watts.
-    ///
-    /// This can be read without enabling constant power mode.
-    pub fn get_constant_power_level(&mut self) -> Result<u16, S::Error> {
-        let value = self.read_modbus_single(XyRegister::Cw)?;
-        Ok(value)
+    #[test]
+    fn test_mppt_config_bulk() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 10, 100, 10, 10).unwrap());
+        let mut map = RegisterMap::new();
+        map.set_battery_full_current(true);
+        psu.set_register_map(map);
+
+        psu.interface.queue_read_response(0x01, &[1, 80, 50]);
+        let config = psu.get_mppt_config().unwrap();
+        assert_eq!(
+            config,
+            MpptConfig {
+                enabled: State::On,
+                k_value: 80,
+                battery_full_current_ma: Some(500),
+            }
+        );
+        assert_eq!(
+            psu.last_transaction().request.get(..6),
+            Some([0x01, 0x03, 0x00, 0x1F, 0x00, 0x03].as_slice())
+        );
+
+        let mut response: heapless::Vec<u8, 256> =
+            heapless::Vec::from_slice(&[0x01, 0x10, 0x00, 0x1F, 0x00, 0x03]).unwrap();
+        let crc = crate::mock_serial::crc16(&response);
+        response.extend_from_slice(&crc).unwrap();
+        psu.interface.queue_response(MockResponse::Data(response));
+        psu.set_mppt_config(MpptConfig {
+            k_value: 75,
+            ..config
+        })
+        .unwrap();
+        assert_eq!(
+            psu.last_transaction().request.get(..13),
+            Some(
+                [
+                    0x01, 0x10, 0x00, 0x1F, 0x00, 0x03, 0x06, 0x00, 0x01, 0x00, 0x4B, 0x00, 0x32
+                ]
+                .as_slice()
+            )
+        );
     }
 
-    /// Write to a single register of the PSU.
-    pub fn write_modbus_single(
-        &mut self,
-        register: impl Into<u16>,
-        data: impl Into<u16>,
-    ) -> Result<(), S::Error> {
-        // @TODO we could directly compare the incoming bytes to our buffer in sequence without storing all the RX'd bytes a second buffer.
-        let mut buff_1: heapless::Vec<u8, L> = heapless::Vec::new();
-        let mut buff_2: heapless::Vec<u8, L> = heapless::Vec::new();
+    #[test]
+    fn test_mppt_config_without_battery_full_current() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 10, 100, 10, 10).unwrap());
+
+        psu.interface.queue_read_response(0x01, &[1, 80]);
+        let config = psu.get_mppt_config().unwrap();
+        assert_eq!(config.battery_full_current_ma, None);
+        assert_eq!(
+            psu.last_transaction().request.get(..6),
+            Some([0x01, 0x03, 0x00, 0x1F, 0x00, 0x02].as_slice())
+        );
+
+        assert!(matches!(
+            psu.set_mppt_config(MpptConfig {
+                battery_full_current_ma: Some(500),
+                ..config
+            }),
+            Err(Error::NotSupported)
+        ));
+
+        let mut response: heapless::Vec<u8, 256> =
+            heapless::Vec::from_slice(&[0x01, 0x10, 0x00, 0x1F, 0x00, 0x02]).unwrap();
+        let crc = crate::mock_serial::crc16(&response);
+        response.extend_from_slice(&crc).unwrap();
+        psu.interface.queue_response(MockResponse::Data(response));
+        psu.set_mppt_config(config).unwrap();
+        // Only MpptSw and MpptK are written.
+        assert_eq!(
+            psu.last_transaction().request.get(..11),
+            Some(
+                [
+                    0x01, 0x10, 0x00, 0x1F, 0x00, 0x02, 0x04, 0x00, 0x01, 0x00, 0x50
+                ]
+                .as_slice()
+            )
+        );
+    }
 
-        let mut req = rmodbus::client::ModbusRequest::new(self.unit_id, rmodbus::ModbusProto::Rtu);
-        req.generate_set_holding(register.into(), data.into(), &mut buff_1)?;
+    #[test]
+    fn test_constant_power_scaled() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 10, 100, 10, 10).unwrap());
+
+        psu.interface.queue_response(MockResponse::Echo);
+        psu.set_constant_power_mw(50_000).unwrap();
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([0x00, 0x23, 0x01, 0xF4].as_slice())
+        );
+
+        psu.interface.queue_read_response(0x01, &[250]);
+        assert_eq!(psu.get_constant_power_mw().unwrap(), 25_000);
+    }
 
-        self.interface
-            .write_all(&buff_1)
-            .map_err(crate::error::Error::SerialError)?;
+    #[test]
+    fn test_setpoint_limits() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+
+        // Limits detected with the model, an XY6020L.
+        psu.interface.queue_read_response(0x01, &[25858]);
+        assert!(matches!(
+            psu.set_output_voltage_mv(60_100),
+            Err(Error::InvalidRange)
+        ));
+        assert!(matches!(
+            psu.set_current_limit_ma(20_001),
+            Err(Error::InvalidRange)
+        ));
+        psu.interface.queue_response(MockResponse::Echo);
+        psu.set_output_voltage_mv(60_000).unwrap();
+
+        // Without limits, only values too large for the register are rejected.
+        psu.set_limits(None);
+        assert!(matches!(
+            psu.set_output_voltage_mv(700_000),
+            Err(Error::InvalidRange)
+        ));
+    }
 
-        // Read the response - keep reading until we get WouldBlock or have enough data
-        let mut temp_buf = [0u8; 8]; // Temporary buffer for single reads
-        loop {
-            match self.interface.read(&mut temp_buf) {
-                Ok(bytes_read) => {
-                    // Add the read bytes to our buffer
-                    if buff_2.extend_from_slice(&temp_buf[0..bytes_read]).is_err() {
-                        return Err(crate::error::Error::BufferError);
-                    }
-                    // Check if we have enough data for a minimal response (unit_id + function + byte_count + at least 2 data bytes + 2 CRC)
-                    if buff_2.len() >= 8 {
-                        break;
-                    }
-                }
-                Err(e) => {
-                    // If WouldBlock and we have some data, break and try to parse
-                    if matches!(
-                        e.kind(),
-                        embedded_io::ErrorKind::Other | embedded_io::ErrorKind::TimedOut
-                    ) && !buff_2.is_empty()
-                    {
-                        break;
-                    }
-                    // Other errors should be propagated
-                    return Err(crate::error::Error::SerialError(e));
-                }
-            }
-        }
-        if buff_1.as_slice() != buff_2.as_slice() {
-            Err(crate::error::Error::InvalidResponse)
-        } else {
-            Ok(())
-        }
+    #[cfg(feature = "presets")]
+    #[test]
+    fn test_preset_limits() {
+        let limits = ProductModel::XY3607F.limits().unwrap();
+        assert!(matches!(
+            crate::preset::XyPresetBuilder::new(crate::preset::PresetGroup::Group1, 40_000, 1_000)
+                .with_limits(limits)
+                .build(),
+            Err(crate::preset::XyPresetBuilderError::OutOfRange)
+        ));
+        assert!(
+            crate::preset::XyPresetBuilder::new(crate::preset::PresetGroup::Group1, 36_000, 7_000)
+                .with_limits(limits)
+                .build()
+                .is_ok()
+        );
     }
 
-    /// Write to multiple, sequential PSU registers.
-    pub fn write_modbus_bulk(
-        &mut self,
-        start_register: impl Into<u16>,
-        data: impl AsRef<[u16]>,
-    ) -> Result<(), S::Error> {
-        let start_register = start_register.into();
-        let data = data.as_ref();
+    #[test]
+    fn test_screen_timeout() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.interface.queue_response(MockResponse::Echo);
+        psu.set_screen_timeout(core::time::Duration::from_secs(150))
+            .unwrap();
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([0x00, 0x15, 0x00, 0x02].as_slice())
+        );
+
+        psu.interface.queue_read_response(0x01, &[5]);
+        assert_eq!(
+            psu.get_screen_timeout().unwrap(),
+            core::time::Duration::from_secs(300)
+        );
+    }
 
-        // @TODO we could directly compare the incoming bytes to our buffer in sequence without storing all the RX'd bytes a second buffer.
-        let mut buff_1: heapless::Vec<u8, L> = heapless::Vec::new();
-        let mut buff_2: heapless::Vec<u8, L> = heapless::Vec::new();
+    #[test]
+    fn test_builder() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::builder(MockSerial::new())
+            .with_unit_id(0x02)
+            .with_retry_policy(RetryPolicy {
+                attempts: 3,
+                backoff: None,
+            })
+            .with_scaling_factors(ScalingFactors::new(10, 10, 10, 1, 1).unwrap())
+            .with_write_verification(WriteVerification::ReadBack)
+            .build();
+        assert_eq!(psu.unit_id(), 0x02);
+        assert_eq!(psu.retry_policy().attempts, 3);
+        assert_eq!(psu.write_verification(), WriteVerification::ReadBack);
+        assert!(psu.scaling_factors().is_some());
+        assert_eq!(psu.read_function(), ReadFunction::Holding);
+
+        psu.interface.queue_read_response(0x02, &[1]);
+        assert!(psu.is_output_enabled().unwrap());
+    }
 
-        let mut req = rmodbus::client::ModbusRequest::new(self.unit_id, rmodbus::ModbusProto::Rtu);
-        req.generate_set_holdings_bulk(start_register, data, &mut buff_1)?;
+    #[test]
+    fn test_frame_hook() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static BYTES_SENT: AtomicUsize = AtomicUsize::new(0);
+        static BYTES_RECEIVED: AtomicUsize = AtomicUsize::new(0);
+
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_frame_hook(Some(|direction, frame| {
+            let counter = match direction {
+                Direction::Tx => &BYTES_SENT,
+                Direction::Rx => &BYTES_RECEIVED,
+            };
+            counter.fetch_add(frame.len(), Ordering::Relaxed);
+        }));
+        psu.interface.queue_read_response(0x01, &[1, 2]);
+        psu.read_modbus_bulk(0x00_u16, 2).unwrap();
+        assert_eq!(BYTES_SENT.load(Ordering::Relaxed), 8);
+        assert_eq!(BYTES_RECEIVED.load(Ordering::Relaxed), 9);
+    }
 
-        self.interface
-            .write_all(&buff_1)
-            .map_err(crate::error::Error::SerialError)?;
+    #[test]
+    fn test_bool_getters() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.interface.queue_read_response(0x01, &[1]);
+        assert!(psu.is_output_enabled().unwrap());
+        psu.interface.queue_read_response(0x01, &[0]);
+        assert!(!psu.is_locked().unwrap());
+        psu.interface.queue_read_response(0x01, &[1]);
+        assert!(psu.is_buzzer_enabled().unwrap());
+        // The device register is on while awake.
+        psu.interface.queue_read_response(0x01, &[1]);
+        assert!(!psu.is_sleeping().unwrap());
+    }
 
-        // Read the response - keep reading until we get WouldBlock or have enough data
-        let mut temp_buf = [0u8; 8]; // Temporary buffer for single reads
-        loop {
-            match self.interface.read(&mut temp_buf) {
-                Ok(bytes_read) => {
-                    // Add the read bytes to our buffer
-                    if buff_2.extend_from_slice(&temp_buf[0..bytes_read]).is_err() {
-                        return Err(crate::error::Error::BufferError);
-                    }
-                    // Check if we have enough data for a minimal response
-                    if buff_2.len() >= 8 {
-                        break;
-                    }
-                }
-                Err(e) => {
-                    // If WouldBlock and we have some data, break and try to parse
-                    if matches!(
-                        e.kind(),
-                        embedded_io::ErrorKind::Other | embedded_io::ErrorKind::TimedOut
-                    ) && !buff_2.is_empty()
-                    {
-                        break;
-                    }
-                    // Other errors should be propagated
-                    return Err(crate::error::Error::SerialError(e));
-                }
-            }
-        }
-        // @TODO Check CRC?
-        if buff_1.as_slice()[0..=5] != buff_2.as_slice()[0..=5] {
-            // First 6 bytes of message sent should match.
-            Err(crate::error::Error::InvalidResponse)
-        } else {
-            Ok(())
+    #[test]
+    fn test_read_output_time() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        for value in [1, 2, 3] {
+            psu.interface.queue_read_response(0x01, &[value]);
         }
+        assert_eq!(
+            psu.read_output_duration().unwrap(),
+            core::time::Duration::from_secs(3723)
+        );
     }
 
-    /// Read a single register from the PSU.
-    pub fn read_modbus_single(&mut self, register: impl Into<u16>) -> Result<u16, S::Error> {
-        let mut buff: heapless::Vec<u8, L> = heapless::Vec::new();
-        let mut req = rmodbus::client::ModbusRequest::new(self.unit_id, rmodbus::ModbusProto::Rtu);
-
-        // @TODO check that 1 is one register, not one byte?
-        req.generate_get_holdings(register.into(), 1, &mut buff)?;
-
-        self.interface
-            .write_all(&buff)
-            .map_err(crate::error::Error::SerialError)?;
+    #[cfg(feature = "presets")]
+    #[test]
+    fn test_read_preset() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        psu.interface.queue_read_response(
+            0x01,
+            &[
+                1200, 500, 1000, 1500, 2000, 300, 1, 30, 0, 1, 5, 0, 80, 1, 80,
+            ],
+        );
+        psu.interface.queue_read_response(0x01, &[0]);
+
+        let preset = psu.read_preset(PresetGroup::Group3).unwrap();
+        assert_eq!(preset.voltage_setting_mv(), 12000);
+        assert_eq!(preset.current_setting_ma(), 500);
+        assert_eq!(preset.output_enable(), State::On);
+        let protection = preset.protection();
+        assert_eq!(protection.under_voltage_mv, 10000);
+        assert_eq!(protection.over_power_mw, 30000);
+        assert_eq!(protection.over_time, core::time::Duration::from_secs(5400));
+        assert_eq!(protection.over_capacity_mah, 0x10000);
+        assert_eq!(protection.over_energy_mwh, 50);
+        assert_eq!(protection.over_temperature, Temperature::Celsius(80));
+    }
 
-        // Reuse same buffer when reading back
-        buff.clear();
+    #[cfg(feature = "presets")]
+    #[test]
+    fn test_load_preset_output_policy() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        let on_off = (XyRegister::OnOff as u16).to_be_bytes();
+
+        // SIni of M2 is on.
+        psu.interface.queue_read_response(0x01, &[1]);
+        psu.interface.queue_response(MockResponse::Echo);
+        psu.interface.queue_response(MockResponse::Echo);
+        psu.load_preset(PresetGroup::Group2, PresetOutputPolicy::UsePresetSIni)
+            .unwrap();
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([on_off[0], on_off[1], 0x00, 0x01].as_slice())
+        );
 
-        // Read the response - keep reading until we get WouldBlock or have enough data
-        let mut temp_buf = [0u8; 8]; // Temporary buffer for single reads
-        loop {
-            match self.interface.read(&mut temp_buf) {
-                Ok(bytes_read) => {
-                    // Add the read bytes to our buffer
-                    if buff.extend_from_slice(&temp_buf[0..bytes_read]).is_err() {
-                        return Err(crate::error::Error::BufferError);
-                    }
-                    // Check if we have enough data for a minimal response (unit_id + function + byte_count + at least 2 data bytes + 2 CRC)
-                    if buff.len() >= 8 {
-                        break;
-                    }
-                }
-                Err(e) => {
-                    // If WouldBlock and we have some data, break and try to parse
-                    if matches!(
-                        e.kind(),
-                        embedded_io::ErrorKind::Other | embedded_io::ErrorKind::TimedOut
-                    ) && !buff.is_empty()
-                    {
-                        break;
-                    }
-                    // Other errors should be propagated
-                    return Err(crate::error::Error::SerialError(e));
-                }
-            }
+        for _ in 0..3 {
+            psu.interface.queue_response(MockResponse::Echo);
         }
-
-        // Parse the response using rmodbus
-        let mut parsed_data: heapless::Vec<u16, 64> = heapless::Vec::new();
-        req.parse_u16(&buff, &mut parsed_data)
-            .map_err(|_| crate::error::Error::InvalidResponse)?;
-
-        // Return the first register value
-        parsed_data
-            .first()
-            .copied()
-            .ok_or(crate::error::Error::InvalidResponse)
+        psu.load_preset(PresetGroup::Group2, PresetOutputPolicy::ForceOff)
+            .unwrap();
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([on_off[0], on_off[1], 0x00, 0x00].as_slice())
+        );
     }
 
-    /// Read multiple registers from the PSU.
-    ///
-    /// Returns a vector of u16 values representing the register contents.
-    fn read_modbus_bulk(
-        &mut self,
-        start_register: u16,
-        count: u16,
-    ) -> Result<heapless::Vec<u16, 64>, S::Error> {
-        let mut buff: heapless::Vec<u8, L> = heapless::Vec::new();
-        let mut req = rmodbus::client::ModbusRequest::new(self.unit_id, rmodbus::ModbusProto::Rtu);
-
-        req.generate_get_holdings(start_register, count, &mut buff)?;
-
-        self.interface
-            .write_all(&buff)
-            .map_err(crate::error::Error::SerialError)?;
-
-        // Reuse same buffer when reading back
-        buff.clear();
+    #[test]
+    fn test_power_cycle_detected_after_failures() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.scaling = Some(ScalingFactors::new(100, 1000, 100, 1000, 1000).unwrap());
 
-        // Read the response - keep reading until we get WouldBlock or have enough data
-        let expected_response_size = 5 + (count as usize * 2) + 2; // unit_id + func + byte_count + data + CRC
-        let mut temp_buf = [0u8; 64];
-        loop {
-            match self.interface.read(&mut temp_buf) {
-                Ok(bytes_read) => {
-                    if buff.extend_from_slice(&temp_buf[0..bytes_read]).is_err() {
-                        return Err(crate::error::Error::BufferError);
-                    }
-                    if buff.len() >= expected_response_size {
-                        break;
-                    }
-                }
-                Err(e) => {
-                    if matches!(
-                        e.kind(),
-                        embedded_io::ErrorKind::Other | embedded_io::ErrorKind::TimedOut
-                    ) && !buff.is_empty()
-                    {
-                        break;
-                    }
-                    return Err(crate::error::Error::SerialError(e));
-                }
-            }
+        psu.interface.set_read_error(true);
+        for _ in 0..FAILURES_BEFORE_REINIT {
+            assert!(psu.read_modbus_single(0x00_u16).is_err());
         }
+        psu.interface.set_read_error(false);
+
+        // The first successful transaction marks the cached state as stale.
+        psu.interface.queue_read_response(0x01, &[0x1234]);
+        assert_eq!(psu.read_modbus_single(0x00_u16).unwrap(), 0x1234);
+        assert!(psu.stale);
+
+        // Re-initializing reads the model again and forgets the detected scaling.
+        psu.interface.queue_read_response(0x01, &[0x6500]);
+        assert!(psu.check_power_cycle().unwrap());
+        assert!(!psu.stale);
+        assert!(psu.scaling.is_none());
+    }
 
-        // Parse the response using rmodbus
-        let mut parsed_data: heapless::Vec<u16, 64> = heapless::Vec::new();
-        req.parse_u16(&buff, &mut parsed_data)
-            .map_err(|_| crate::error::Error::InvalidResponse)?;
-
-        Ok(parsed_data)
+    #[test]
+    fn test_power_cycle_detected_by_output_time() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        let scaling = ScalingFactors::new(10, 1, 100, 1, 10).unwrap();
+        psu.set_scaling_factors(scaling);
+
+        psu.interface.queue_read_response(0x01, &[1, 2, 3]);
+        assert!(!psu.check_power_cycle().unwrap());
+        psu.interface.queue_read_response(0x01, &[1, 2, 4]);
+        assert!(!psu.check_power_cycle().unwrap());
+
+        // Output time went backwards.
+        psu.interface.queue_read_response(0x01, &[0, 0, 1]);
+        psu.interface.queue_read_response(0x01, &[0x6500]);
+        assert!(psu.check_power_cycle().unwrap());
+
+        // Manually set scaling factors survive re-initialization.
+        assert_eq!(psu.scaling, Some(scaling));
+        assert_eq!(psu.last_output_time_secs, Some(1));
     }
 
-    /// Get the current protection configuration from the active preset.
-    ///
-    /// This reads the protection settings from the currently active preset group
-    /// and returns them as a `ProtectionConfig` struct.
-    ///
-    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
-    /// error if the model's scaling factors are unknown.
-    ///
-    /// # For Unknown Models
-    ///
-    /// If your PSU model has unknown scaling factors, use [`set_scaling_factors`](Self::set_scaling_factors)
-    /// to manually specify them before calling this method:
-    ///
-    /// ```ignore
-    /// // Set custom scaling factors for unknown model
-    /// let scaling = ScalingFactors::new(10, 10, 100, 10, 10);
-    /// psu.set_scaling_factors(scaling);
-    ///
-    /// // Now get_protections will use your custom scaling
-    /// let protections = psu.get_protections()?;
-    /// ```
-    pub fn get_protections(&mut self) -> Result<ProtectionConfig, S::Error> {
-        // Ensure scaling factors are loaded
-        let scaling = self.ensure_scaling()?;
-        use crate::preset::XyPresetOffsets as XPO;
-
-        /// Helper function to calculate array index relative to the starting register (SLvp)
-        #[inline]
-        fn idx(register: XPO) -> usize {
-            (register as usize) - (XPO::SLvp as usize)
+    #[test]
+    fn test_slave_address_out_of_range() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+
+        // Rejected before anything is sent, rather than panicking.
+        for address in [0, 248, u8::MAX] {
+            assert!(matches!(
+                psu.set_slave_address(address),
+                Err(Error::InvalidRange)
+            ));
         }
-
-        // Get currently active preset group
-        let group = self.get_active_preset()?;
-
-        // Calculate the starting address for protection registers
-        let start_address = XPO::SLvp.address_in_group(group);
-
-        // Read all protection-related registers (SLvp through SEtp)
-        // That's registers 0x02 through 0x0E in the preset group (13 registers)
-        let registers = self.read_modbus_bulk(start_address, 13)?;
-
-        let temp_unit = self.get_temperature_unit()?;
-
-        // Parse the registers into a ProtectionConfig
-        // Register layout (from XyPresetOffsets):
-        // We read starting from SLvp, so indices are relative to SLvp (not VSet)
-        // 0: SLvp (under voltage) = XPO::SLvp - XPO::SLvp = 0
-        // 1: SOvp (over voltage) = XPO::SOvp - XPO::SLvp = 1
-        // 2: SOcp (over current) = XPO::SOcp - XPO::SLvp = 2
-        // 3: SOpp (over power) = XPO::SOpp - XPO::SLvp = 3
-        // 4: SOhpH (over time hours) = XPO::SOhpH - XPO::SLvp = 4
-        // 5: SoHpM (over time minutes) = XPO::SoHpM - XPO::SLvp = 5
-        // 6: SOahL (over capacity low) = XPO::SOahL - XPO::SLvp = 6
-        // 7: SOahH (over capacity high) = XPO::SOahH - XPO::SLvp = 7
-        // 8: SOwhL (over energy low) = XPO::SOwhL - XPO::SLvp = 8
-        // 9: SOwhH (over energy high) = XPO::SOwhH - XPO::SLvp = 9
-        // 10: SOtp (over temperature) = XPO::SOtp - XPO::SLvp = 10
-        // 11: SIni (output enable - skip) = XPO::SIni - XPO::SLvp = 11
-        // 12: SEtp (external temperature) = XPO::SEtp - XPO::SLvp = 12
-
-        let under_voltage_mv = scaling.raw_to_voltage_mv(registers[idx(XPO::SLvp)]);
-        let over_voltage_mv = scaling.raw_to_voltage_mv(registers[idx(XPO::SOvp)]);
-        let over_current_ma = scaling.raw_to_current_ma(registers[idx(XPO::SOcp)]);
-        let over_power_mw = scaling.raw_to_power_mw(registers[idx(XPO::SOpp)]);
-        let over_time = Duration::<u32, 1, 1>::hours(registers[idx(XPO::SOhpH)] as u32)
-            + Duration::<u32, 1, 1>::minutes(registers[idx(XPO::SoHpM)] as u32);
-        let over_capacity_mah = ((registers[idx(XPO::SOahL)] as u32)
-            | ((registers[idx(XPO::SOahH)] as u32) << 16))
-            * scaling.capacity_divisor;
-        let over_energy_mwh = ((registers[idx(XPO::SOwhL)] as u32)
-            | ((registers[idx(XPO::SOwhH)] as u32) << 16))
-            * scaling.energy_divisor;
-        let over_temperature = Temperature::new(registers[idx(XPO::SOtp)], temp_unit);
-
-        Ok(ProtectionConfig {
-            under_voltage_mv,
-            over_voltage_mv,
-            over_current_ma,
-            over_power_mw,
-            over_time,
-            over_capacity_mah,
-            over_energy_mwh,
-            over_temperature,
-        })
+        assert!(psu.last_transaction().request.is_empty());
     }
 
-    /// Set protection levels of the power supply.
-    ///
-    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
-    /// error if the model's scaling factors are unknown.
-    ///
-    /// # For Unknown Models
-    ///
-    /// If your PSU model has unknown scaling factors, use [`set_scaling_factors`](Self::set_scaling_factors)
-    /// to manually specify them before calling this method:
-    ///
-    /// ```ignore
-    /// // Set custom scaling factors for unknown model
-    /// let scaling = ScalingFactors::new(10, 10, 100, 10, 10);
-    /// psu.set_scaling_factors(scaling);
-    ///
-    /// // Now set_protections will use your custom scaling
-    /// psu.set_protections(protection_config)?;
-    /// ```
-    ///
-    /// __Note:__ This works by modifying the active preset group. This
-    /// could cause unintended modifications to preset groups if not careful.
-    pub fn set_protections(
-        &mut self,
-        protection_settings: ProtectionConfig,
-    ) -> Result<(), S::Error> {
-        // Ensure scaling factors are loaded
-        let scaling = self.ensure_scaling()?;
-        // Get currently active preset group so we can write values to the active group.
-        let group = self.get_active_preset()?;
-
-        // Get current voltage and current settings (read raw and convert using scaling)
-        let set_voltage_raw = self.read_modbus_single(XyRegister::VSet)?;
-        let set_current_raw = self.read_modbus_single(XyRegister::ISet)?;
-
-        let set_voltage = scaling.raw_to_voltage_mv(set_voltage_raw);
-        let set_current = scaling.raw_to_current_ma(set_current_raw);
-
-        // Get current output state
-        let set_output_state = self.read_modbus_single(XyRegister::OnOff)?;
-
-        let preset = XyPresetBuilder::new(group, set_voltage, set_current)
-            .with_protections(protection_settings)
-            .with_output(set_output_state != 0)
-            .build()
-            .unwrap();
-
-        // Get temperature unit for writing
-        let temp_unit = self.get_temperature_unit()?;
-        let (start_address, write_buffer) =
-            preset.generate_write_data_and_offset(temp_unit, scaling);
-
-        self.write_modbus_bulk(start_address, write_buffer)
+    #[test]
+    fn test_pending_slave_address_used_after_failures() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+
+        psu.interface.queue_response(MockResponse::Echo);
+        psu.set_slave_address(0x05).unwrap();
+        psu.interface.queue_response(MockResponse::Echo);
+        psu.set_baudrate(BaudRate::_9600).unwrap();
+        assert_eq!(psu.unit_id(), 0x01);
+        assert_eq!(psu.pending_baud_rate(), Some(BaudRate::_9600));
+
+        // Failures alone, e.g. from a loose cable, don't switch address as the new one doesn't respond either.
+        psu.interface.set_read_error(true);
+        for _ in 0..FAILURES_BEFORE_REINIT {
+            assert!(psu.read_modbus_single(0x00_u16).is_err());
+        }
+        psu.interface.set_read_error(false);
+        assert_eq!(psu.unit_id(), 0x01);
+
+        // The old address stops responding after a power cycle, and the new one answers the probe.
+        psu.interface
+            .queue_response(MockResponse::Data(heapless::Vec::new()));
+        psu.interface.queue_read_response(0x05, &[0x6500]);
+        assert!(psu.read_modbus_single(0x00_u16).is_err());
+        assert_eq!(psu.unit_id(), 0x05);
+        // The failed read is kept for debugging, not the probe.
+        assert_eq!(
+            psu.last_transaction().request.get(..4),
+            Some([0x01, 0x03, 0x00, 0x00].as_slice())
+        );
+        assert!(psu.last_transaction().response.is_empty());
+
+        psu.interface.queue_read_response(0x05, &[0x6500]);
+        psu.interface.queue_read_response(0x05, &[0x6500]);
+        assert_eq!(psu.get_product_model_raw().unwrap(), 0x6500);
+        assert!(psu.check_power_cycle().unwrap());
+        assert_eq!(psu.pending_baud_rate(), None);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::mock_serial::MockSerial;
 
     #[test]
     fn test_write_modbus_single() {
@@ -887,7 +1207,7 @@ mod tests {
         let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(mock_serial, 0x01);
 
         // Test writing to register 0x10 with value 0x1234
-        let result = psu.write_modbus_single(0x10 as u16, 0x1234u16);
+        let result = psu.write_modbus_single(0x10_u16, 0x1234u16);
         assert!(result.is_ok());
 
         // Check that the correct Modbus RTU frame was written
@@ -897,6 +1217,37 @@ mod tests {
         assert_eq!(written_data.len(), 8); // Total frame length
     }
 
+    #[test]
+    fn test_write_modbus_single_checks_response() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+
+        let bad_crc = [0x01, 0x06, 0x00, 0x10, 0x12, 0x34, 0x85, 0x79];
+        psu.interface.set_read_data(&bad_crc).unwrap();
+        assert!(matches!(
+            psu.write_modbus_single(0x10_u16, 0x1234u16),
+            Err(Error::Transaction {
+                failure: TransactionFailure::CrcMismatch,
+                ..
+            })
+        ));
+
+        // Valid frame, but echoing a different value.
+        let mut wrong_value =
+            heapless::Vec::from_slice(&[0x01, 0x06, 0x00, 0x10, 0x12, 0x35]).unwrap();
+        wrong_value
+            .extend_from_slice(&crate::mock_serial::crc16(&wrong_value))
+            .unwrap();
+        psu.interface
+            .queue_response(MockResponse::Data(wrong_value));
+        assert!(matches!(
+            psu.write_modbus_single(0x10_u16, 0x1234u16),
+            Err(Error::Transaction {
+                failure: TransactionFailure::InvalidResponse,
+                ..
+            })
+        ));
+    }
+
     #[test]
     fn test_read_modbus_single_bad_crc() {
         let mut mock_serial = MockSerial::new();
@@ -908,7 +1259,7 @@ mod tests {
 
         let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(mock_serial, 0x01);
 
-        let result = psu.read_modbus_single(0x20 as u16);
+        let result = psu.read_modbus_single(0x20_u16);
 
         // Check that the request was written correctly
         let written_data = psu.interface.written_data();
@@ -925,13 +1276,16 @@ mod tests {
         assert_eq!(written_data.len(), 8); // Total frame length
 
         // The result might be an error due to invalid CRC, but at least it shouldn't panic
-        // If we get an error, it should be InvalidResponse due to CRC mismatch
+        // If we get an error, it should be CrcMismatch
         match result {
             Ok(value) => {
                 // If parsing somehow succeeds, verify we got the expected value
                 assert_eq!(value, 0x5678);
             }
-            Err(crate::error::Error::InvalidResponse) => {
+            Err(Error::Transaction {
+                failure: TransactionFailure::CrcMismatch,
+                ..
+            }) => {
                 // This is expected due to invalid CRC in our test data
             }
             Err(other) => {
@@ -951,7 +1305,7 @@ mod tests {
 
         let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(mock_serial, 0x01);
 
-        let result = psu.read_modbus_single(0x20 as u16);
+        let result = psu.read_modbus_single(0x20_u16);
 
         // Check that the request was written correctly
         let written_data = psu.interface.written_data();
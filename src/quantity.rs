@@ -0,0 +1,134 @@
+//! [`uom`](https://docs.rs/uom) quantities in the API, so mixing up e.g. millivolts and volts is a compile error.
+//!
+//! Only available with the `uom` feature. Each method wraps the integer method of the same name with a unit suffix,
+//! e.g. [`XyPsu::set_output_voltage`] wraps [`XyPsu::set_output_voltage_mv`]. Quantities are rounded to the nearest
+//! millivolt/milliamp, and negative values are treated as zero.
+//!
+//! ```ignore
+//! use uom::si::{electric_current::ampere, electric_potential::volt, f32::*};
+//!
+//! psu.set_output_voltage(ElectricPotential::new::<volt>(12.0))?;
+//! psu.set_current_limit(ElectricCurrent::new::<ampere>(1.5))?;
+//! let power: Power = psu.read_power()?;
+//! ```
+
+use crate::{
+    error::Result,
+    psu::XyPsu,
+    register::Temperature,
+    transport::Transport,
+    units::{Milliamps, Millivolts, Milliwatts},
+};
+use uom::si::{
+    electric_current::milliampere,
+    electric_potential::millivolt,
+    f32::{ElectricCurrent, ElectricPotential, Power, ThermodynamicTemperature},
+    power::milliwatt,
+    thermodynamic_temperature::{degree_celsius, degree_fahrenheit},
+};
+
+/// Round a non-negative value to the nearest integer, saturating. Negative values become 0.
+fn round(value: f32) -> u32 {
+    (value + 0.5) as u32
+}
+
+pub(crate) fn voltage_mv(voltage: ElectricPotential) -> u32 {
+    round(voltage.get::<millivolt>())
+}
+
+pub(crate) fn current_ma(current: ElectricCurrent) -> u32 {
+    round(current.get::<milliampere>())
+}
+
+fn voltage(Millivolts(voltage_mv): Millivolts) -> ElectricPotential {
+    ElectricPotential::new::<millivolt>(voltage_mv as f32)
+}
+
+fn current(Milliamps(current_ma): Milliamps) -> ElectricCurrent {
+    ElectricCurrent::new::<milliampere>(current_ma as f32)
+}
+
+impl From<Temperature> for ThermodynamicTemperature {
+    fn from(temperature: Temperature) -> Self {
+        match temperature {
+            Temperature::Celsius(t) => Self::new::<degree_celsius>(t as f32),
+            Temperature::Fahrenheit(t) => Self::new::<degree_fahrenheit>(t as f32),
+        }
+    }
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// See [`Self::read_output_voltage_mv`].
+    pub fn read_output_voltage(&mut self) -> Result<ElectricPotential, S::Error> {
+        self.read_output_voltage_mv().map(voltage)
+    }
+
+    /// See [`Self::read_input_voltage_mv`].
+    pub fn read_input_voltage(&mut self) -> Result<ElectricPotential, S::Error> {
+        self.read_input_voltage_mv().map(voltage)
+    }
+
+    /// See [`Self::read_current_ma`].
+    pub fn read_current(&mut self) -> Result<ElectricCurrent, S::Error> {
+        self.read_current_ma().map(current)
+    }
+
+    /// See [`Self::read_power_mw`].
+    pub fn read_power(&mut self) -> Result<Power, S::Error> {
+        self.read_power_mw()
+            .map(|Milliwatts(power_mw)| Power::new::<milliwatt>(power_mw as f32))
+    }
+
+    /// See [`Self::set_output_voltage_mv`].
+    pub fn set_output_voltage(&mut self, voltage: ElectricPotential) -> Result<(), S::Error> {
+        self.set_output_voltage_mv(voltage_mv(voltage))
+    }
+
+    /// See [`Self::get_output_voltage_mv`].
+    pub fn get_output_voltage(&mut self) -> Result<ElectricPotential, S::Error> {
+        self.get_output_voltage_mv().map(voltage)
+    }
+
+    /// See [`Self::set_current_limit_ma`].
+    pub fn set_current_limit(&mut self, current: ElectricCurrent) -> Result<(), S::Error> {
+        self.set_current_limit_ma(current_ma(current))
+    }
+
+    /// See [`Self::get_current_limit_ma`].
+    pub fn get_current_limit(&mut self) -> Result<ElectricCurrent, S::Error> {
+        self.get_current_limit_ma().map(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_serial::{MockResponse, MockSerial},
+        scaling::ScalingFactors,
+    };
+    use uom::si::{
+        electric_current::ampere, electric_potential::volt, thermodynamic_temperature::kelvin,
+    };
+
+    #[test]
+    fn quantities_converted() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+
+        psu.interface_mut().queue_response(MockResponse::Echo);
+        psu.set_output_voltage(ElectricPotential::new::<volt>(12.34))
+            .unwrap();
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([0x00, 0x00, 0x04, 0xD2].as_slice())
+        );
+
+        psu.interface_mut().queue_read_response(0x01, &[1500]);
+        assert_eq!(current_ma(psu.read_current().unwrap()), 1500);
+
+        assert_eq!(current_ma(ElectricCurrent::new::<ampere>(-1.0)), 0);
+        let temperature = ThermodynamicTemperature::from(Temperature::Celsius(27));
+        assert!((temperature.get::<kelvin>() - 300.15).abs() < 0.01);
+    }
+}
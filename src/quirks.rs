@@ -0,0 +1,90 @@
+//! Differences between firmware revisions of the same model, which the driver works around.
+//!
+//! Call [`XyPsu::identify`], or the same method of the async driver, once after connecting. It reads the model and firmware version, looks them up in
+//! [`QUIRK_TABLE`], and adjusts the driver, e.g. named registers the firmware doesn't implement return
+//! [`Error::NotSupported`](crate::error::Error::NotSupported) rather than garbage.
+//!
+//! If you find a firmware bug, please submit a Github ticket so it can be added to the table!
+
+#[cfg(any(doc, test))]
+use crate::psu::XyPsu;
+use crate::{
+    register::{ProductModel, XyRegister},
+    scaling::ScalingFactors,
+};
+
+/// Behaviour of a firmware revision which differs from what the register documentation says.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Quirks {
+    /// The battery full current ([`XyRegister::BatFul`]) register is documented, but writing it has no effect.
+    pub battery_full_current_inert: bool,
+    /// Registers which aren't implemented.
+    pub missing_registers: &'static [XyRegister],
+    /// Scaling factors which differ from the model's usual ones.
+    pub scaling: Option<ScalingFactors>,
+}
+
+/// Quirks of the firmware versions `min_firmware..=max_firmware` of a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuirkEntry {
+    /// Raw value of the "MODEL" register.
+    pub model_raw: u16,
+    pub min_firmware: u16,
+    pub max_firmware: u16,
+    pub quirks: Quirks,
+}
+
+/// Known quirks. The first matching entry is used.
+pub const QUIRK_TABLE: &[QuirkEntry] = &[QuirkEntry {
+    model_raw: ProductModel::XY6020L.raw(),
+    min_firmware: 0,
+    max_firmware: u16::MAX,
+    quirks: Quirks {
+        battery_full_current_inert: true,
+        missing_registers: &[],
+        scaling: None,
+    },
+}];
+
+impl Quirks {
+    /// Look up the quirks of a model and firmware version in [`QUIRK_TABLE`]. Returns no quirks if there is no entry.
+    pub fn lookup(model_raw: u16, firmware_version: u16) -> Self {
+        QUIRK_TABLE
+            .iter()
+            .find(|entry| {
+                entry.model_raw == model_raw
+                    && (entry.min_firmware..=entry.max_firmware).contains(&firmware_version)
+            })
+            .map(|entry| entry.quirks)
+            .unwrap_or_default()
+    }
+
+    /// Whether `register` is implemented.
+    pub fn has_register(&self, register: XyRegister) -> bool {
+        !self.missing_registers.contains(&register)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::Error, mock_serial::MockSerial};
+
+    #[test]
+    fn quirks_applied_after_identify() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.register_map_mut().set_battery_full_current(true);
+
+        psu.interface_mut()
+            .queue_read_response(0x01, &[ProductModel::XY6020L.raw()]);
+        psu.interface_mut().queue_read_response(0x01, &[113]);
+        assert!(psu.identify().unwrap().battery_full_current_inert);
+        assert!(!psu.register_map().has_battery_full_current());
+
+        psu.set_quirks(Quirks {
+            missing_registers: &[XyRegister::Sleep],
+            ..Default::default()
+        });
+        assert!(matches!(psu.get_screen_timeout(), Err(Error::NotSupported)));
+    }
+}
@@ -0,0 +1,131 @@
+//! Change the output voltage gradually, to avoid inrush current and relay chatter on sensitive loads.
+//!
+//! ```ignore
+//! // Ramp to 24V at 2V/s, stopping early if the stop button is pressed.
+//! let applied_mv = psu.ramp_output_voltage_mv(24_000, 2_000, |ms| {
+//!     sleep(Duration::from_millis(ms.into()));
+//!     !stop_button.is_pressed()
+//! })?;
+//! ```
+//!
+//! [`XyPsu::enable_output_soft`] does the same from 0V when turning the output on, so capacitive loads don't trip OCP.
+
+use crate::{error::Result, psu::XyPsu, register::State, transport::Transport};
+
+/// Time between voltage steps while ramping, in milliseconds.
+pub const RAMP_STEP_MS: u32 = 100;
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Move the output voltage setting from its current value to `target_mv`, at `rate_mv_per_s`.
+    ///
+    /// The setting is changed in steps, every [`RAMP_STEP_MS`]. `delay` is called between steps with the time to wait
+    /// in milliseconds, and returns `false` to cancel the ramp, leaving the setting where it is. Returns the last
+    /// voltage setting applied, in millivolts.
+    ///
+    /// A `rate_mv_per_s` of 0 is treated as 1mV/s. Requires known scaling factors for the PSU model.
+    pub fn ramp_output_voltage_mv(
+        &mut self,
+        target_mv: u32,
+        rate_mv_per_s: u32,
+        mut delay: impl FnMut(u32) -> bool,
+    ) -> Result<u32, S::Error> {
+        let step_mv = (rate_mv_per_s.saturating_mul(RAMP_STEP_MS) / 1000).max(1);
+        let mut applied_mv = self.get_output_voltage_mv()?.0;
+        while applied_mv != target_mv {
+            if !delay(RAMP_STEP_MS) {
+                break;
+            }
+            applied_mv = if applied_mv < target_mv {
+                applied_mv.saturating_add(step_mv).min(target_mv)
+            } else {
+                applied_mv.saturating_sub(step_mv).max(target_mv)
+            };
+            self.set_output_voltage_mv(applied_mv)?;
+        }
+        Ok(applied_mv)
+    }
+
+    /// Turn the output on at 0V, then ramp up to the voltage setting at `rate_mv_per_s`, limiting the inrush current
+    /// into capacitive loads.
+    ///
+    /// `delay` is as for [`Self::ramp_output_voltage_mv`], and cancelling leaves the output on at the voltage reached.
+    /// Returns the last voltage setting applied, in millivolts.
+    ///
+    /// Requires known scaling factors for the PSU model.
+    pub fn enable_output_soft(
+        &mut self,
+        rate_mv_per_s: u32,
+        delay: impl FnMut(u32) -> bool,
+    ) -> Result<u32, S::Error> {
+        let target_mv = self.get_output_voltage_mv()?.0;
+        self.set_output_voltage_mv(0)?;
+        self.set_output_state(State::On)?;
+        self.ramp_output_voltage_mv(target_mv, rate_mv_per_s, delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_serial::{MockResponse, MockSerial},
+        scaling::ScalingFactors,
+    };
+
+    #[test]
+    fn ramp_steps_to_target() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        psu.interface_mut().queue_read_response(0x01, &[500]);
+        for _ in 0..3 {
+            psu.interface_mut().queue_response(MockResponse::Echo);
+        }
+
+        let mut delays = 0;
+        let applied_mv = psu
+            .ramp_output_voltage_mv(5500, 2000, |ms| {
+                delays += ms;
+                true
+            })
+            .unwrap();
+        assert_eq!(applied_mv, 5500);
+        assert_eq!(delays, 300);
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([0x00, 0x00, 0x02, 0x26].as_slice())
+        );
+    }
+
+    #[test]
+    fn ramp_cancelled() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        psu.interface_mut().queue_read_response(0x01, &[1200]);
+        psu.interface_mut().queue_response(MockResponse::Echo);
+
+        let mut steps = 0;
+        let applied_mv = psu
+            .ramp_output_voltage_mv(0, 1000, |_| {
+                steps += 1;
+                steps < 2
+            })
+            .unwrap();
+        assert_eq!(applied_mv, 11900);
+    }
+
+    #[test]
+    fn soft_enable_ramps_from_zero() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        psu.interface_mut().queue_read_response(0x01, &[500]);
+        psu.interface_mut().queue_response(MockResponse::Echo);
+        psu.interface_mut().queue_response(MockResponse::Echo);
+        psu.interface_mut().queue_read_response(0x01, &[0]);
+        for _ in 0..5 {
+            psu.interface_mut().queue_response(MockResponse::Echo);
+        }
+
+        let applied_mv = psu.enable_output_soft(10_000, |_| true).unwrap();
+        assert_eq!(applied_mv, 5000);
+    }
+}
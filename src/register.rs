@@ -1,8 +1,7 @@
 //! This module is used to define the registers on the XY PSUs.
 
-use modular_bitfield::prelude::*;
-
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u16)]
 pub enum XyRegister {
     /// __R/W__ - Voltage setting.
@@ -65,6 +64,9 @@ pub enum XyRegister {
     /// 0 is darkest, and 5 is the brightest.
     BLed = 0x14,
     /// __R/W__ - Rest screen time.
+    ///
+    /// Thought to be in minutes, but not yet tested, see
+    /// [`XyPsu::set_screen_timeout`](crate::psu::XyPsu::set_screen_timeout).
     Sleep = 0x15,
     /// __R__ - Product model.
     Model = 0x16,
@@ -106,6 +108,9 @@ pub enum XyRegister {
     /// Manual suggests this should be between 0.75 - 0.85?
     MpptK = 0x20,
     /// __R/W__ - Full current current. (When in MPPT?)
+    ///
+    /// Only used on models where it is known to work, see
+    /// [`RegisterMap::set_battery_full_current`](crate::register_map::RegisterMap::set_battery_full_current).
     BatFul = 0x21,
     /// __R/W__ - Constant power switch. (When in MPPT?)
     CwSw = 0x22,
@@ -120,8 +125,8 @@ impl From<XyRegister> for u16 {
 }
 
 /// This enum represents all possible product model versions.
-#[derive(Debug)]
-#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ProductModel {
     /// This model's "MODEL" register value has not been confirmed.
     XYSK60S,
@@ -132,7 +137,7 @@ pub enum ProductModel {
     /// This model's "MODEL" register value has not been confirmed.
     XY3606B,
     /// Confirmed.
-    XY3607F = 22869,
+    XY3607F,
     /// This model's "MODEL" register value has not been confirmed.
     XY6506,
     /// This model's "MODEL" register value has not been confirmed.
@@ -142,15 +147,116 @@ pub enum ProductModel {
     /// This model's "MODEL" register value has not been confirmed.
     XY6509X,
     /// Confirmed.
-    XY7025 = 25856,
+    XY7025,
     /// Confirmed.
-    XY12522 = 25857,
+    XY12522,
     /// Confirmed on a XY-6020L V5.1 board.
-    XY6020L = 25858,
+    XY6020L,
+    /// A "MODEL" register value which isn't recognised.
+    ///
+    /// The raw register API works as for any other model. Scaled methods need scaling factors, given with
+    /// [`XyPsu::set_scaling_factors`](crate::psu::XyPsu::set_scaling_factors) or added to the
+    /// [`ScalingRegistry`](crate::scaling::ScalingRegistry).
+    Unknown(u16),
+}
+
+impl ProductModel {
+    /// Interpret the "MODEL" register value. Only models where the ID has been observed are recognised.
+    pub(crate) fn from_raw(raw: u16) -> Option<Self> {
+        match Self::from(raw) {
+            Self::Unknown(_) => None,
+            model => Some(model),
+        }
+    }
+
+    /// Return the "MODEL" register value of this model.
+    ///
+    /// Models whose value hasn't been confirmed return a placeholder, which is never recognised.
+    pub const fn raw(&self) -> u16 {
+        match *self {
+            Self::XYSK60S => 0,
+            Self::XYSK120S => 1,
+            Self::XYSK150S => 2,
+            Self::XY3606B => 3,
+            Self::XY3607F => 22869,
+            Self::XY6506 => 22870,
+            Self::XY6506S => 22871,
+            Self::XY6509 => 22872,
+            Self::XY6509X => 22873,
+            Self::XY7025 => 25856,
+            Self::XY12522 => 25857,
+            Self::XY6020L => 25858,
+            Self::Unknown(raw) => raw,
+        }
+    }
+
+    /// Whether this is a recognised model, rather than [`Self::Unknown`].
+    pub const fn is_known(&self) -> bool {
+        !matches!(self, Self::Unknown(_))
+    }
+
+    /// Return the name printed on the board, e.g. `XY-SK60S` or `XY6020L`, or `unknown` for [`Self::Unknown`].
+    pub const fn name(&self) -> &'static str {
+        match self {
+            ProductModel::XYSK60S => "XY-SK60S",
+            ProductModel::XYSK120S => "XY-SK120S",
+            ProductModel::XYSK150S => "XY-SK150S",
+            ProductModel::XY3606B => "XY3606B",
+            ProductModel::XY3607F => "XY3607F",
+            ProductModel::XY6506 => "XY6506",
+            ProductModel::XY6506S => "XY6506S",
+            ProductModel::XY6509 => "XY6509",
+            ProductModel::XY6509X => "XY6509X",
+            ProductModel::XY7025 => "XY7025",
+            ProductModel::XY12522 => "XY12522",
+            ProductModel::XY6020L => "XY6020L",
+            ProductModel::Unknown(_) => "unknown",
+        }
+    }
+}
+
+/// Interpret the "MODEL" register value, as [`ProductModel::Unknown`] if it isn't recognised.
+impl From<u16> for ProductModel {
+    fn from(raw: u16) -> Self {
+        use ProductModel as PM;
+
+        match raw {
+            x if x == PM::XY6020L.raw() => PM::XY6020L,
+            x if x == PM::XY12522.raw() => PM::XY12522,
+            x if x == PM::XY7025.raw() => PM::XY7025,
+            x if x == PM::XY3607F.raw() => PM::XY3607F,
+            // x if x == PM::XYSK60S.raw() => PM::XYSK60S,
+            // x if x == PM::XYSK120S.raw() => PM::XYSK120S,
+            // x if x == PM::XYSK150S.raw() => PM::XYSK150S,
+            // x if x == PM::XY3606B.raw() => PM::XY3606B,
+            // x if x == PM::XY6506.raw() => PM::XY6506,
+            // x if x == PM::XY6506S.raw() => PM::XY6506S,
+            // x if x == PM::XY6509.raw() => PM::XY6509,
+            // x if x == PM::XY6509X.raw() => PM::XY6509X,
+            raw => PM::Unknown(raw),
+        }
+    }
+}
+
+/// Formats as the name printed on the board, e.g. `XY-SK60S` or `XY6020L`. Unknown models format as their "MODEL"
+/// register value, e.g. `unknown model 0x6503`.
+impl core::fmt::Display for ProductModel {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ProductModel::Unknown(raw) => write!(f, "unknown model {raw:#06x}"),
+            model => f.write_str(model.name()),
+        }
+    }
 }
 
 /// Represents the two possible power supply control modes.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "UPPERCASE")
+)]
 pub enum ControlMode {
     /// Constant voltage regulation mode.
     Cv,
@@ -168,9 +274,25 @@ impl From<u16> for ControlMode {
     }
 }
 
+/// Formats as shown on the PSU display, `CV` or `CC`.
+impl core::fmt::Display for ControlMode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            ControlMode::Cv => "CV",
+            ControlMode::Cc => "CC",
+        })
+    }
+}
+
 /// Used for setting and reading unit used for temperature readings.
 // @TODO read value from device to find out what value is what.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 #[repr(u16)]
 pub enum TemperatureUnit {
     Celsius = 0x00,
@@ -188,6 +310,12 @@ impl TryFrom<u16> for TemperatureUnit {
     }
 }
 
+impl From<TemperatureUnit> for u16 {
+    fn from(value: TemperatureUnit) -> Self {
+        value as u16
+    }
+}
+
 impl From<ControlMode> for u16 {
     fn from(value: ControlMode) -> Self {
         match value {
@@ -198,7 +326,7 @@ impl From<ControlMode> for u16 {
 }
 
 /// All possible baud rates supported by the XY PSUs.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u16)]
 pub enum BaudRate {
     _9600 = 0,
@@ -215,12 +343,38 @@ pub enum BaudRate {
     _4800 = 8,
 }
 
+impl BaudRate {
+    /// Return the baud rate in bits per second.
+    ///
+    /// `_5600` and `_576000` are 56000 and 57600 baud.
+    pub const fn bits_per_second(&self) -> u32 {
+        match self {
+            BaudRate::_9600 => 9600,
+            BaudRate::_14400 => 14400,
+            BaudRate::_19200 => 19200,
+            BaudRate::_38400 => 38400,
+            BaudRate::_5600 => 56000,
+            BaudRate::_576000 => 57600,
+            BaudRate::_115200 => 115200,
+            BaudRate::_2400 => 2400,
+            BaudRate::_4800 => 4800,
+        }
+    }
+}
+
 impl From<BaudRate> for u16 {
     fn from(value: BaudRate) -> Self {
         value as u16
     }
 }
 
+/// Formats as the bits per second, e.g. `115200 baud`.
+impl core::fmt::Display for BaudRate {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} baud", self.bits_per_second())
+    }
+}
+
 impl TryFrom<u16> for BaudRate {
     type Error = ();
 
@@ -243,7 +397,13 @@ impl TryFrom<u16> for BaudRate {
 
 /// Used to be less ambiguous and whether something is on or off.
 #[repr(u16)]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum State {
     /// Disabled.
     // @TODO Check value of on and off in registers.
@@ -264,12 +424,29 @@ impl core::ops::Not for State {
     }
 }
 
+/// Formats as `on` or `off`.
+impl core::fmt::Display for State {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            State::Off => "off",
+            State::On => "on",
+        })
+    }
+}
+
 impl From<State> for u16 {
     fn from(value: State) -> Self {
         value as u16
     }
 }
 
+/// Any non-zero value is on.
+impl From<u16> for State {
+    fn from(value: u16) -> Self {
+        State::from(value != 0)
+    }
+}
+
 impl From<State> for bool {
     fn from(value: State) -> Self {
         match value {
@@ -289,45 +466,209 @@ impl From<bool> for State {
 }
 
 /// "Protection status register".
-#[bitfield]
-#[derive(Debug, Clone, Copy)]
-pub struct ProtectionStatus {
+///
+/// Each bit is set while the corresponding protection has been triggered. The upper 5 bits are not used.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProtectionStatus(u16);
+
+impl ProtectionStatus {
+    const OVER_VOLTAGE: u16 = 1 << 0;
+    const OVER_CURRENT: u16 = 1 << 1;
+    const OVER_POWER: u16 = 1 << 2;
+    const UNDER_VOLTAGE_INPUT: u16 = 1 << 3;
+    const OVER_CAPACITY: u16 = 1 << 4;
+    const OVER_TIME: u16 = 1 << 5;
+    const OVER_TEMPERATURE_INTERNAL: u16 = 1 << 6;
+    const OEP: u16 = 1 << 7;
+    const OVER_ENERGY: u16 = 1 << 8;
+    const OVER_CURRENT_INPUT: u16 = 1 << 9;
+    const OVER_TEMPERATURE_EXTERNAL: u16 = 1 << 10;
+    const USED_BITS: u16 = (1 << 11) - 1;
+
+    /// Create from the raw register value. Unused bits are ignored.
+    pub const fn from_raw(raw: u16) -> Self {
+        Self(raw & Self::USED_BITS)
+    }
+
+    /// Create from the raw register value in little endian byte order.
+    pub const fn from_bytes(bytes: [u8; 2]) -> Self {
+        Self::from_raw(u16::from_le_bytes(bytes))
+    }
+
+    /// Return the raw register value.
+    pub const fn raw(&self) -> u16 {
+        self.0
+    }
+
+    /// Return the raw register value in little endian byte order.
+    pub const fn into_bytes(self) -> [u8; 2] {
+        self.0.to_le_bytes()
+    }
+
+    /// Whether any protection has been triggered.
+    pub const fn any(&self) -> bool {
+        self.0 != 0
+    }
+
+    /// Return the protections which are set here, but weren't in `previous`.
+    pub const fn newly_set(&self, previous: ProtectionStatus) -> Self {
+        Self(self.0 & !previous.0)
+    }
+
+    const fn bit(&self, mask: u16) -> bool {
+        self.0 & mask != 0
+    }
+
+    /// Iterate over the short names (as shown on the PSU display, e.g. "OVP") of all triggered protections.
+    pub fn triggered(&self) -> impl Iterator<Item = &'static str> + '_ {
+        const NAMES: [(u16, &str); 11] = [
+            (ProtectionStatus::OVER_VOLTAGE, "OVP"),
+            (ProtectionStatus::OVER_CURRENT, "OCP"),
+            (ProtectionStatus::OVER_POWER, "OPP"),
+            (ProtectionStatus::UNDER_VOLTAGE_INPUT, "LVP"),
+            (ProtectionStatus::OVER_CAPACITY, "OAH"),
+            (ProtectionStatus::OVER_TIME, "OHP"),
+            (ProtectionStatus::OVER_TEMPERATURE_INTERNAL, "OTP"),
+            (ProtectionStatus::OEP, "OEP"),
+            (ProtectionStatus::OVER_ENERGY, "OWH"),
+            (ProtectionStatus::OVER_CURRENT_INPUT, "ICP"),
+            (ProtectionStatus::OVER_TEMPERATURE_EXTERNAL, "ETP"),
+        ];
+        NAMES
+            .into_iter()
+            .filter(|(mask, _)| self.bit(*mask))
+            .map(|(_, name)| name)
+    }
+
     /// OVP overvoltage protection.
-    #[allow(dead_code)]
-    over_voltage: bool,
+    pub const fn over_voltage(&self) -> bool {
+        self.bit(Self::OVER_VOLTAGE)
+    }
+
     /// OCP overcurrent protection.
-    #[allow(dead_code)]
-    over_current: bool,
+    pub const fn over_current(&self) -> bool {
+        self.bit(Self::OVER_CURRENT)
+    }
+
     /// OPP, over-power protection.
-    #[allow(dead_code)]
-    over_power: bool,
+    pub const fn over_power(&self) -> bool {
+        self.bit(Self::OVER_POWER)
+    }
+
     /// LVP input under voltage protection.
-    #[allow(dead_code)]
-    under_voltage_input: bool,
+    pub const fn under_voltage_input(&self) -> bool {
+        self.bit(Self::UNDER_VOLTAGE_INPUT)
+    }
+
     /// OAH maximum output capacity.
-    #[allow(dead_code)]
-    over_capacity: bool,
+    pub const fn over_capacity(&self) -> bool {
+        self.bit(Self::OVER_CAPACITY)
+    }
+
     /// OHP maximum output time.
-    #[allow(dead_code)]
-    over_time: bool,
+    pub const fn over_time(&self) -> bool {
+        self.bit(Self::OVER_TIME)
+    }
+
     /// OTP over-temperature protection.
-    #[allow(dead_code)]
-    over_temperature_internal: bool,
+    pub const fn over_temperature_internal(&self) -> bool {
+        self.bit(Self::OVER_TEMPERATURE_INTERNAL)
+    }
+
     /// OEP, with no output protection. - I don't understand what this means.
-    #[allow(dead_code)]
-    oep: bool,
+    pub const fn oep(&self) -> bool {
+        self.bit(Self::OEP)
+    }
+
     /// OWH maximum energy output.
-    #[allow(dead_code)]
-    over_energy: bool,
+    pub const fn over_energy(&self) -> bool {
+        self.bit(Self::OVER_ENERGY)
+    }
+
     /// ICP maximum input current protection.
-    #[allow(dead_code)]
-    over_current_input: bool,
+    pub const fn over_current_input(&self) -> bool {
+        self.bit(Self::OVER_CURRENT_INPUT)
+    }
+
     /// ETP, external temperature protection.
-    #[allow(dead_code)]
-    over_temperature_external: bool,
-    // Last 5 MSB are not used.
-    #[skip]
-    __: B5,
+    pub const fn over_temperature_external(&self) -> bool {
+        self.bit(Self::OVER_TEMPERATURE_EXTERNAL)
+    }
+}
+
+impl From<u16> for ProtectionStatus {
+    fn from(value: u16) -> Self {
+        Self::from_raw(value)
+    }
+}
+
+impl From<ProtectionStatus> for u16 {
+    fn from(value: ProtectionStatus) -> Self {
+        value.raw()
+    }
+}
+
+/// Formats as the short names of the triggered protections, e.g. `OVP, OTP`, or `none`.
+impl core::fmt::Display for ProtectionStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if !self.any() {
+            return f.write_str("none");
+        }
+        for (i, name) in self.triggered().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formatted as the short names of the triggered protections, e.g. `ProtectionStatus(OVP OCP)`.
+#[cfg(feature = "defmt")]
+impl defmt::Format for ProtectionStatus {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "ProtectionStatus(");
+        for (i, name) in self.triggered().enumerate() {
+            if i > 0 {
+                defmt::write!(f, " ");
+            }
+            defmt::write!(f, "{=str}", name);
+        }
+        defmt::write!(f, ")");
+    }
+}
+
+/// Serialized as the short names of the triggered protections, e.g. `["OVP","OCP"]`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for ProtectionStatus {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.triggered())
+    }
+}
+
+impl core::fmt::Debug for ProtectionStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ProtectionStatus")
+            .field("over_voltage", &self.over_voltage())
+            .field("over_current", &self.over_current())
+            .field("over_power", &self.over_power())
+            .field("under_voltage_input", &self.under_voltage_input())
+            .field("over_capacity", &self.over_capacity())
+            .field("over_time", &self.over_time())
+            .field(
+                "over_temperature_internal",
+                &self.over_temperature_internal(),
+            )
+            .field("oep", &self.oep())
+            .field("over_energy", &self.over_energy())
+            .field("over_current_input", &self.over_current_input())
+            .field(
+                "over_temperature_external",
+                &self.over_temperature_external(),
+            )
+            .finish()
+    }
 }
 
 /// All possible supported brightness levels of the display.
@@ -342,6 +683,12 @@ pub enum BacklightBrightness {
     Level5 = 0x05,
 }
 
+impl From<BacklightBrightness> for u16 {
+    fn from(value: BacklightBrightness) -> Self {
+        value as u16
+    }
+}
+
 impl TryFrom<u16> for BacklightBrightness {
     type Error = ();
 
@@ -360,7 +707,9 @@ impl TryFrom<u16> for BacklightBrightness {
 }
 
 /// Simple type to represent temperature depending on the unit used.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Temperature {
     Fahrenheit(u16),
     Celsius(u16),
@@ -369,7 +718,7 @@ pub enum Temperature {
 impl Temperature {
     /// Create a [`Temperature`] from a temperature value pass in using the units of centi-degree C/F.
     ///
-    /// E.g. 294 => 29.4° but get rounded to 29°
+    /// E.g. 294 => 29.4° but get rounded to 29°. To keep the fractional part, see [`DeciTemperature`].
     pub const fn from_centi(value: u16, unit: TemperatureUnit) -> Self {
         let rounded = Self::div_10_and_round(value);
         Self::new(rounded, unit)
@@ -407,26 +756,115 @@ impl Temperature {
     }
 
     /// Convert fahrenheit to celsius.
+    ///
+    /// Temperatures below 0°C saturate at 0 as they can't be represented.
     const fn f_to_c(temp_f: u16) -> u16 {
-        let multiplied = ((temp_f * 10 - 320) * 5) / 9;
-        Self::div_10_and_round(multiplied)
+        let multiplied = ((temp_f as u32 * 10).saturating_sub(320) * 5) / 9;
+        Self::div_10_and_round_u32(multiplied)
     }
 
     /// Convert celsius to fahrenheit.
     const fn c_to_f(temp_c: u16) -> u16 {
         // We calculate with one fixed centimal place and manually calculate rounding.
-        let multiplied = ((temp_c * 90) / 5) + 320;
-        Self::div_10_and_round(multiplied)
+        let multiplied = ((temp_c as u32 * 90) / 5) + 320;
+        Self::div_10_and_round_u32(multiplied)
     }
 
     const fn div_10_and_round(value: u16) -> u16 {
+        Self::div_10_and_round_u32(value as u32)
+    }
+
+    /// Divide by 10 with rounding, saturating at `u16::MAX`.
+    const fn div_10_and_round_u32(value: u32) -> u16 {
         let centimal = value % 10;
-        if centimal >= 5 {
+        let rounded = if centimal >= 5 {
             (value / 10) + 1
         } else {
             value / 10
+        };
+        if rounded > u16::MAX as u32 {
+            u16::MAX
+        } else {
+            rounded as u16
+        }
+    }
+}
+
+/// Temperature in tenths of a degree, as the PSU measures it, depending on the unit used.
+///
+/// Unlike [`Temperature`], keeps the fractional part, e.g. for logging or temperature compensation of a charge voltage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeciTemperature {
+    Fahrenheit(u16),
+    Celsius(u16),
+}
+
+impl DeciTemperature {
+    /// Create a [`DeciTemperature`] from a value in tenths of a degree C/F, e.g. 294 => 29.4°.
+    pub const fn new(value: u16, unit: TemperatureUnit) -> Self {
+        match unit {
+            TemperatureUnit::Celsius => Self::Celsius(value),
+            TemperatureUnit::Fahrenheit => Self::Fahrenheit(value),
+        }
+    }
+
+    /// Convert this temperature into tenths of a degree celsius.
+    ///
+    /// Temperatures below 0°C saturate at 0 as they can't be represented.
+    pub const fn as_deci_celsius(&self) -> u16 {
+        match *self {
+            Self::Celsius(inner) => inner,
+            Self::Fahrenheit(inner) => {
+                Temperature::div_10_and_round_u32((inner as u32).saturating_sub(320) * 50 / 9)
+            }
+        }
+    }
+
+    /// Convert this temperature into tenths of a degree fahrenheit.
+    pub const fn as_deci_fahrenheit(&self) -> u16 {
+        match *self {
+            Self::Celsius(inner) => {
+                let tenths = Temperature::div_10_and_round_u32(inner as u32 * 18) as u32 + 320;
+                if tenths > u16::MAX as u32 {
+                    u16::MAX
+                } else {
+                    tenths as u16
+                }
+            }
+            Self::Fahrenheit(inner) => inner,
+        }
+    }
+
+    /// Convert this temperature into tenths of a degree of a target temperature unit.
+    pub const fn as_deci_unit(&self, unit: TemperatureUnit) -> u16 {
+        match unit {
+            TemperatureUnit::Celsius => self.as_deci_celsius(),
+            TemperatureUnit::Fahrenheit => self.as_deci_fahrenheit(),
         }
     }
+
+    /// Convert this temperature into thousandths of a degree celsius.
+    pub const fn as_millicelsius(&self) -> u32 {
+        self.as_deci_celsius() as u32 * 100
+    }
+
+    /// Round this temperature to whole degrees, in the same unit.
+    pub const fn rounded(&self) -> Temperature {
+        match *self {
+            Self::Celsius(inner) => Temperature::Celsius(Temperature::div_10_and_round(inner)),
+            Self::Fahrenheit(inner) => {
+                Temperature::Fahrenheit(Temperature::div_10_and_round(inner))
+            }
+        }
+    }
+}
+
+impl From<DeciTemperature> for Temperature {
+    fn from(value: DeciTemperature) -> Self {
+        value.rounded()
+    }
 }
 
 #[cfg(test)]
@@ -445,5 +883,66 @@ mod tests {
         let temp = Temperature::Fahrenheit(70);
         assert_eq!(temp.as_fahrenheit(), 70);
         assert_eq!(temp.as_celsius(), 21);
+
+        // Values which would overflow or underflow must not panic.
+        assert_eq!(Temperature::Fahrenheit(0).as_celsius(), 0);
+        assert_eq!(Temperature::Celsius(u16::MAX).as_fahrenheit(), u16::MAX);
+        assert_eq!(Temperature::Fahrenheit(u16::MAX).as_celsius(), 36391);
+    }
+
+    #[test]
+    fn deci_temperature_conversions() {
+        let temp = DeciTemperature::new(294, TemperatureUnit::Celsius);
+        assert_eq!(temp.as_deci_celsius(), 294);
+        assert_eq!(temp.as_deci_fahrenheit(), 849);
+        assert_eq!(temp.as_millicelsius(), 29_400);
+        assert_eq!(temp.rounded(), Temperature::Celsius(29));
+
+        let temp = DeciTemperature::Fahrenheit(849);
+        assert_eq!(temp.as_deci_celsius(), 294);
+        assert_eq!(Temperature::from(temp), Temperature::Fahrenheit(85));
+
+        assert_eq!(DeciTemperature::Fahrenheit(0).as_deci_celsius(), 0);
+        assert_eq!(
+            DeciTemperature::Celsius(u16::MAX).as_deci_fahrenheit(),
+            u16::MAX
+        );
+    }
+
+    #[test]
+    fn protection_status_bits() {
+        let status = ProtectionStatus::from_bytes([0b0100_0001, 0b0000_0010]);
+        assert!(status.over_voltage());
+        assert!(status.over_temperature_internal());
+        assert!(status.over_current_input());
+        assert!(!status.over_current());
+        assert!(status.any());
+        assert_eq!(status.into_bytes(), [0b0100_0001, 0b0000_0010]);
+        assert!(status.triggered().eq(["OVP", "OTP", "ICP"]));
+
+        // Unused upper bits are ignored.
+        assert!(!ProtectionStatus::from_raw(0xF800).any());
+    }
+
+    #[test]
+    fn display() {
+        use core::fmt::Write;
+
+        let mut text: heapless::String<64> = heapless::String::new();
+        let status = ProtectionStatus::from_bytes([0b0100_0001, 0b0000_0000]);
+        write!(text, "{status}; {}", ProtectionStatus::from_raw(0)).unwrap();
+        assert_eq!(text, "OVP, OTP; none");
+
+        text.clear();
+        write!(
+            text,
+            "{} {} {} {}",
+            ProductModel::XYSK60S,
+            ControlMode::Cc,
+            State::On,
+            BaudRate::_576000
+        )
+        .unwrap();
+        assert_eq!(text, "XY-SK60S CC on 57600 baud");
     }
 }
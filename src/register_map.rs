@@ -0,0 +1,258 @@
+//! Mapping of [`XyRegister`]s to the addresses used by a particular model.
+//!
+//! All known models use the addresses given by [`XyRegister`], but new or clone models may move some registers
+//! around. A [`RegisterMap`] overrides the address of individual registers, and is used by all of the
+//! [`XyPsu`](crate::psu::XyPsu) methods which access a named register.
+//!
+//! With the `model-files` feature, a register map and scaling factors can be loaded from a TOML file at runtime using
+//! [`ModelDescription`]. This allows experimenting with the mapping of a brand-new model without recompiling, before
+//! adding it to the crate.
+
+use crate::register::XyRegister;
+
+/// Maximum number of registers which can be moved by a [`RegisterMap`].
+pub const MAX_REGISTER_OVERRIDES: usize = 16;
+
+/// Overrides of register addresses for a particular model.
+///
+/// Registers without an override use their default address from [`XyRegister`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegisterMap {
+    overrides: heapless::Vec<(XyRegister, u16), MAX_REGISTER_OVERRIDES>,
+    /// Address of the input current limit (ICP) register, on models which have one.
+    input_current_limit: Option<u16>,
+    /// Whether the battery full current ([`XyRegister::BatFul`]) register works on this model.
+    battery_full_current: bool,
+}
+
+impl RegisterMap {
+    /// Create a register map with every register at its default address.
+    pub const fn new() -> Self {
+        Self {
+            overrides: heapless::Vec::new(),
+            input_current_limit: None,
+            battery_full_current: false,
+        }
+    }
+
+    /// Return the address to use for `register`.
+    pub fn address(&self, register: XyRegister) -> u16 {
+        self.overrides
+            .iter()
+            .find(|(r, _)| *r == register)
+            .map(|(_, address)| *address)
+            .unwrap_or(register as u16)
+    }
+
+    /// Move `register` to `address`.
+    ///
+    /// Returns `Err` with the register if more than [`MAX_REGISTER_OVERRIDES`] registers have been moved.
+    pub fn set_address(&mut self, register: XyRegister, address: u16) -> Result<(), XyRegister> {
+        if let Some(entry) = self.overrides.iter_mut().find(|(r, _)| *r == register) {
+            entry.1 = address;
+            return Ok(());
+        }
+        self.overrides
+            .push((register, address))
+            .map_err(|(register, _)| register)
+    }
+
+    /// Move `register` back to its default address.
+    pub fn reset_address(&mut self, register: XyRegister) {
+        self.overrides.retain(|(r, _)| *r != register);
+    }
+
+    /// Return the address of the first of `registers`, if they are at consecutive addresses in the order given, so can
+    /// be accessed with one bulk read or write.
+    pub fn contiguous_start(&self, registers: &[XyRegister]) -> Option<u16> {
+        let start = self.address(*registers.first()?);
+        registers
+            .iter()
+            .zip(start..)
+            .all(|(register, address)| self.address(*register) == address)
+            .then_some(start)
+    }
+
+    /// Iterate over all registers which have been moved, and their new addresses.
+    pub fn overrides(&self) -> impl Iterator<Item = (XyRegister, u16)> + '_ {
+        self.overrides.iter().copied()
+    }
+
+    /// Return the address of the input current limit (ICP) register, if this model has one.
+    pub fn input_current_limit_address(&self) -> Option<u16> {
+        self.input_current_limit
+    }
+
+    /// Set the address of the input current limit (ICP) register, or `None` if this model doesn't have one.
+    ///
+    /// The protection status has an ICP bit, but none of the models we know of document the register used to configure
+    /// the limit, so it isn't mapped by default.
+    pub fn set_input_current_limit_address(&mut self, address: Option<u16>) {
+        self.input_current_limit = address;
+    }
+
+    /// Whether the battery full current ([`XyRegister::BatFul`]) register works on this model.
+    pub fn has_battery_full_current(&self) -> bool {
+        self.battery_full_current
+    }
+
+    /// Set whether the battery full current ([`XyRegister::BatFul`]) register works on this model.
+    ///
+    /// The register is documented for the MPPT models, but writing it had no visible effect on the firmware we tested,
+    /// so it is off by default. Turn it on once it is confirmed to work on a model, e.g. in its model file.
+    pub fn set_battery_full_current(&mut self, supported: bool) {
+        self.battery_full_current = supported;
+    }
+}
+
+#[cfg(all(feature = "model-files", not(feature = "no_std")))]
+pub use model_file::*;
+
+#[cfg(all(feature = "model-files", not(feature = "no_std")))]
+mod model_file {
+    use std::{collections::BTreeMap, io, path::Path, string::String};
+
+    use serde::{Deserialize, Serialize};
+
+    use super::RegisterMap;
+    use crate::{register::XyRegister, scaling::ScalingFactors};
+
+    /// Description of a model's register map and scaling, loaded from a TOML file.
+    ///
+    /// ```toml
+    /// name = "XY-SK120S"
+    /// model_id = 0x4B52
+    ///
+    /// [scaling]
+    /// voltage_divisor = 10
+    /// current_divisor = 1
+    /// power_divisor = 100
+    /// capacity_divisor = 1
+    /// energy_divisor = 10
+    ///
+    /// # Only registers which differ from the defaults need to be listed.
+    /// [registers]
+    /// Model = 0x22
+    /// ```
+    ///
+    /// Models with an input current limit (ICP) register give its address with `input_current_limit_register`. Models
+    /// where the battery full current register works set `battery_full_current = true`.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct ModelDescription {
+        /// Human readable model name.
+        pub name: String,
+        /// Raw value of the "MODEL" register.
+        pub model_id: u16,
+        /// Scaling factors for this model.
+        pub scaling: ScalingFactors,
+        /// Registers which are at a different address to the defaults.
+        #[serde(default)]
+        pub registers: BTreeMap<XyRegister, u16>,
+        /// Address of the input current limit (ICP) register, if this model has one.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub input_current_limit_register: Option<u16>,
+        /// Whether the battery full current register works on this model.
+        #[serde(default, skip_serializing_if = "core::ops::Not::not")]
+        pub battery_full_current: bool,
+    }
+
+    impl ModelDescription {
+        /// Load a model description from a TOML file.
+        pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+            Self::from_toml_str(&std::fs::read_to_string(path)?)
+        }
+
+        /// Parse a model description from a TOML string.
+        pub fn from_toml_str(toml: &str) -> io::Result<Self> {
+            toml::from_str(toml).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        }
+
+        /// Serialise to TOML, e.g. for contributing the model to this crate.
+        pub fn to_toml_string(&self) -> io::Result<String> {
+            toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        }
+
+        /// Create the [`RegisterMap`] described by this file.
+        ///
+        /// Returns `Err` with the first register which didn't fit if too many registers are moved.
+        pub fn register_map(&self) -> Result<RegisterMap, XyRegister> {
+            let mut map = RegisterMap::new();
+            for (register, address) in &self.registers {
+                if *address != *register as u16 {
+                    map.set_address(*register, *address)?;
+                }
+            }
+            map.set_input_current_limit_address(self.input_current_limit_register);
+            map.set_battery_full_current(self.battery_full_current);
+            Ok(map)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_map_overrides() {
+        let mut map = RegisterMap::new();
+        assert_eq!(map.address(XyRegister::Model), 0x16);
+
+        map.set_address(XyRegister::Model, 0x22).unwrap();
+        map.set_address(XyRegister::Model, 0x23).unwrap();
+        assert_eq!(map.address(XyRegister::Model), 0x23);
+        assert_eq!(map.address(XyRegister::VSet), 0x00);
+        assert_eq!(map.overrides().count(), 1);
+
+        map.reset_address(XyRegister::Model);
+        assert_eq!(map.address(XyRegister::Model), 0x16);
+
+        let block = [XyRegister::MpptSw, XyRegister::MpptK, XyRegister::BatFul];
+        assert_eq!(map.contiguous_start(&block), Some(0x1F));
+        map.set_address(XyRegister::BatFul, 0x30).unwrap();
+        assert_eq!(map.contiguous_start(&block), None);
+    }
+
+    #[test]
+    #[cfg(all(feature = "model-files", not(feature = "no_std")))]
+    fn model_description_from_toml() {
+        let description = ModelDescription::from_toml_str(
+            r#"
+            name = "XY-TEST"
+            model_id = 0x1234
+            battery_full_current = true
+            input_current_limit_register = 0x24
+
+            [scaling]
+            voltage_divisor = 10
+            current_divisor = 1
+            power_divisor = 100
+            capacity_divisor = 1
+            energy_divisor = 10
+
+            [registers]
+            Model = 0x22
+            VSet = 0x00
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(description.model_id, 0x1234);
+        assert_eq!(
+            description.scaling,
+            crate::scaling::ScalingFactors::new(10, 1, 100, 1, 10).unwrap()
+        );
+        let map = description.register_map().unwrap();
+        assert_eq!(map.address(XyRegister::Model), 0x22);
+        // Registers at their default address don't use an override.
+        assert_eq!(map.overrides().count(), 1);
+        assert_eq!(map.input_current_limit_address(), Some(0x24));
+        assert!(map.has_battery_full_current());
+
+        let round_trip =
+            ModelDescription::from_toml_str(&description.to_toml_string().unwrap()).unwrap();
+        assert_eq!(round_trip, description);
+
+        assert!(ModelDescription::from_toml_str("name = \"missing fields\"").is_err());
+    }
+}
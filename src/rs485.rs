@@ -0,0 +1,221 @@
+//! Direction control of RS485 transceivers without automatic direction, e.g. a bare MAX485.
+//!
+//! [`Rs485`] wraps an interface and drives the transceiver's joined DE and /RE pins: high while a request is sent, and
+//! low again once it has left the UART, so the PSU's response can be received.
+//!
+//! ```ignore
+//! let interface = Rs485::new(uart, de_pin, delay, BaudRate::_115200);
+//! let mut psu: XyPsu<_> = XyPsu::new(interface, 0x01);
+//! ```
+//!
+//! The UART's [`flush`](embedded_io::Write::flush) must wait until the last byte has been shifted out, not just
+//! queued, or the end of each request is cut off. It can be combined with [`RtuTiming`](crate::timing::RtuTiming) by
+//! wrapping one in the other.
+//!
+//! Only available with the `embedded-hal` feature.
+
+use embedded_hal::{delay::DelayNs, digital::OutputPin};
+
+use crate::register::BaudRate;
+
+/// Return the time to send one character at `bits_per_second`: 11 bits, with start, parity or second stop, and stop.
+pub const fn character_time_ns(bits_per_second: u32) -> u32 {
+    let bits_per_second = if bits_per_second == 0 {
+        1
+    } else {
+        bits_per_second
+    };
+    let ns = 11_000_000_000 / bits_per_second as u64;
+    if ns > u32::MAX as u64 {
+        u32::MAX
+    } else {
+        ns as u32
+    }
+}
+
+/// Error of an interface wrapped by [`Rs485`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Rs485Error<E, P> {
+    /// Error from the wrapped interface.
+    Interface(E),
+    /// Error setting the direction pin.
+    Pin(P),
+}
+
+impl<E: embedded_io::Error, P: core::fmt::Debug> embedded_io::Error for Rs485Error<E, P> {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            Rs485Error::Interface(e) => e.kind(),
+            Rs485Error::Pin(_) => embedded_io::ErrorKind::Other,
+        }
+    }
+}
+
+impl<E: core::fmt::Debug, P: core::fmt::Debug> core::fmt::Display for Rs485Error<E, P> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Rs485Error::Interface(e) => write!(f, "interface error: {e:?}"),
+            Rs485Error::Pin(e) => write!(f, "direction pin error: {e:?}"),
+        }
+    }
+}
+
+impl<E: core::fmt::Debug, P: core::fmt::Debug> core::error::Error for Rs485Error<E, P> {}
+
+/// Interface which switches an RS485 transceiver between sending and receiving, with `P` driving DE and /RE.
+///
+/// Before the first write of a request, the pin is set high, then the turnaround time is waited for the driver to
+/// enable. Before the first read after a request, the interface is flushed and the turnaround time waited again, so
+/// the stop bit of the last byte isn't cut short, then the pin is set low.
+#[derive(Debug)]
+pub struct Rs485<S, P, D> {
+    interface: S,
+    pin: P,
+    delay: D,
+    turnaround_ns: u32,
+    /// Whether the transceiver is set to send.
+    transmitting: bool,
+}
+
+impl<S, P: OutputPin, D: DelayNs> Rs485<S, P, D> {
+    /// Wrap `interface`, with a turnaround time of one character at `baud_rate`. The pin is set low to receive.
+    pub fn new(interface: S, pin: P, delay: D, baud_rate: BaudRate) -> Result<Self, P::Error> {
+        Self::with_turnaround_ns(
+            interface,
+            pin,
+            delay,
+            character_time_ns(baud_rate.bits_per_second()),
+        )
+    }
+
+    /// Wrap `interface`, with a turnaround time of `turnaround_ns`, e.g. longer for a slow opto-isolated transceiver.
+    /// The pin is set low to receive.
+    pub fn with_turnaround_ns(
+        interface: S,
+        mut pin: P,
+        delay: D,
+        turnaround_ns: u32,
+    ) -> Result<Self, P::Error> {
+        pin.set_low()?;
+        Ok(Self {
+            interface,
+            pin,
+            delay,
+            turnaround_ns,
+            transmitting: false,
+        })
+    }
+
+    /// Return the turnaround time, in nanoseconds.
+    pub fn turnaround_ns(&self) -> u32 {
+        self.turnaround_ns
+    }
+
+    /// Unwrap the interface, pin and delay.
+    pub fn into_inner(self) -> (S, P, D) {
+        (self.interface, self.pin, self.delay)
+    }
+}
+
+impl<S: embedded_io::ErrorType, P: OutputPin, D> embedded_io::ErrorType for Rs485<S, P, D> {
+    type Error = Rs485Error<S::Error, P::Error>;
+}
+
+impl<S: embedded_io::Write, P: OutputPin, D: DelayNs> embedded_io::Write for Rs485<S, P, D> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if !self.transmitting {
+            self.pin.set_high().map_err(Rs485Error::Pin)?;
+            self.transmitting = true;
+            self.delay.delay_ns(self.turnaround_ns);
+        }
+        self.interface.write(buf).map_err(Rs485Error::Interface)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.interface.flush().map_err(Rs485Error::Interface)
+    }
+}
+
+impl<S: embedded_io::Read + embedded_io::Write, P: OutputPin, D: DelayNs> embedded_io::Read
+    for Rs485<S, P, D>
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if self.transmitting {
+            self.transmitting = false;
+            let flushed = self.interface.flush().map_err(Rs485Error::Interface);
+            self.delay.delay_ns(self.turnaround_ns);
+            // Stop driving the bus even if the flush failed.
+            self.pin.set_low().map_err(Rs485Error::Pin)?;
+            flushed?;
+        }
+        self.interface.read(buf).map_err(Rs485Error::Interface)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock_serial::MockSerial, psu::XyPsu};
+
+    /// Records the pin level at each delay, and adds up the time waited.
+    #[derive(Debug, Default)]
+    struct Recorder {
+        high: bool,
+        waited_ns: u64,
+        switches: u8,
+    }
+
+    #[derive(Debug)]
+    struct Pin<'a>(&'a core::cell::RefCell<Recorder>);
+
+    impl embedded_hal::digital::ErrorType for Pin<'_> {
+        type Error = core::convert::Infallible;
+    }
+
+    impl OutputPin for Pin<'_> {
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.0.borrow_mut().high = false;
+            self.0.borrow_mut().switches += 1;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.0.borrow_mut().high = true;
+            self.0.borrow_mut().switches += 1;
+            Ok(())
+        }
+    }
+
+    struct Delay<'a>(&'a core::cell::RefCell<Recorder>);
+
+    impl DelayNs for Delay<'_> {
+        fn delay_ns(&mut self, ns: u32) {
+            let mut recorder = self.0.borrow_mut();
+            // Turnarounds are only waited with the transceiver set to send.
+            assert!(recorder.high);
+            recorder.waited_ns += ns as u64;
+        }
+    }
+
+    #[test]
+    fn direction_switched_around_requests() {
+        assert_eq!(character_time_ns(9600), 1_145_833);
+
+        let recorder = core::cell::RefCell::new(Recorder::default());
+        let interface =
+            Rs485::with_turnaround_ns(MockSerial::new(), Pin(&recorder), Delay(&recorder), 100)
+                .unwrap();
+        let mut psu: XyPsu<_, 128> = XyPsu::new(interface, 0x01);
+        psu.interface_mut()
+            .interface
+            .queue_read_response(0x01, &[1, 2]);
+        assert_eq!(psu.read_modbus_bulk(0x00_u16, 2).unwrap(), [1, 2]);
+
+        let recorder = recorder.borrow();
+        // Low on creation, high to send, low to receive.
+        assert_eq!(recorder.switches, 3);
+        assert!(!recorder.high);
+        assert_eq!(recorder.waited_ns, 200);
+    }
+}
@@ -0,0 +1,195 @@
+//! PSU handle with scaling factors known at compile time.
+//!
+//! The scaled methods of [`XyPsu`] (e.g. [`XyPsu::read_output_voltage_mv`]) return
+//! [`Error::ScalingNotAvailable`] at runtime for unknown models. A [`ScaledPsu`] can only be created once scaling
+//! factors are known, either from the product model with [`XyPsu::into_scaled`] or given by the user with
+//! [`XyPsu::with_scaling`], so its scaled methods can't fail for that reason.
+//!
+//! All other [`XyPsu`] methods are available through `Deref`.
+//!
+//! ```ignore
+//! let psu = XyPsu::<_, 128>::new(serial, 0x01);
+//! let mut psu = match psu.into_scaled() {
+//!     Ok(psu) => psu,
+//!     // Unknown model, fall back to our own measurements.
+//!     Err((psu, _)) => psu.with_scaling(ScalingFactors::new(10, 1, 100, 1, 10)?),
+//! };
+//! psu.set_output_voltage_mv(5000)?;
+//! ```
+
+use core::ops::{Deref, DerefMut};
+
+use crate::{
+    error::{Error, Result},
+    limits::setpoint_to_raw,
+    psu::XyPsu,
+    register::XyRegister,
+    scaling::ScalingFactors,
+    transport::Transport,
+    units::{Milliamps, Millivolts, Milliwatts},
+};
+
+/// An [`XyPsu`] with known scaling factors.
+pub struct ScaledPsu<S: Transport, const L: usize = 128> {
+    psu: XyPsu<S, L>,
+    scaling: ScalingFactors,
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Convert into a [`ScaledPsu`] using the given scaling factors.
+    pub fn with_scaling(mut self, scaling: ScalingFactors) -> ScaledPsu<S, L> {
+        self.set_scaling_factors(scaling);
+        ScaledPsu { psu: self, scaling }
+    }
+
+    /// Convert into a [`ScaledPsu`] using the scaling factors of the product model.
+    ///
+    /// On failure, the [`XyPsu`] is returned along with the error. For unknown models, this is
+    /// [`Error::ScalingNotAvailable`] and you can use [`Self::with_scaling`] instead.
+    // The PSU is large, but has to be handed back and can't be boxed without an allocator.
+    #[allow(clippy::result_large_err)]
+    pub fn into_scaled(mut self) -> core::result::Result<ScaledPsu<S, L>, (Self, Error<S::Error>)> {
+        match self.ensure_scaling() {
+            Ok(scaling) => Ok(self.with_scaling(scaling)),
+            Err(e) => Err((self, e)),
+        }
+    }
+}
+
+impl<S: Transport, const L: usize> ScaledPsu<S, L> {
+    /// Return the scaling factors in use.
+    pub fn scaling(&self) -> ScalingFactors {
+        self.scaling
+    }
+
+    /// Change the scaling factors in use.
+    pub fn set_scaling_factors(&mut self, scaling: ScalingFactors) {
+        self.scaling = scaling;
+        self.psu.set_scaling_factors(scaling);
+    }
+
+    /// Convert back into a plain [`XyPsu`]. The scaling factors stay set.
+    pub fn into_inner(self) -> XyPsu<S, L> {
+        self.psu
+    }
+
+    /// Return the measured output voltage in millivolts.
+    pub fn read_output_voltage_mv(&mut self) -> Result<Millivolts, S::Error> {
+        let raw = self.psu.read_register(XyRegister::VOut)?;
+        Ok(Millivolts(self.scaling.raw_to_voltage_mv(raw)))
+    }
+
+    /// Return the measured supply input voltage in millivolts.
+    pub fn read_input_voltage_mv(&mut self) -> Result<Millivolts, S::Error> {
+        let raw = self.psu.read_register(XyRegister::UIn)?;
+        Ok(Millivolts(self.scaling.raw_to_voltage_mv(raw)))
+    }
+
+    /// Return the measured output current in milliamps.
+    pub fn read_current_ma(&mut self) -> Result<Milliamps, S::Error> {
+        let raw = self.psu.read_register(XyRegister::IOut)?;
+        Ok(Milliamps(self.scaling.raw_to_current_ma(raw)))
+    }
+
+    /// Return the measured output power in milliwatts.
+    pub fn read_power_mw(&mut self) -> Result<Milliwatts, S::Error> {
+        let raw = self.psu.read_register(XyRegister::Power)?;
+        Ok(Milliwatts(self.scaling.raw_to_power_mw(raw)))
+    }
+
+    /// Set the output voltage. Value supplied in millivolts.
+    ///
+    /// Returns `InvalidRange` if the voltage is above the model's [`limits`](XyPsu::limits).
+    pub fn set_output_voltage_mv(
+        &mut self,
+        voltage_mv: impl Into<Millivolts>,
+    ) -> Result<(), S::Error> {
+        let voltage_mv = voltage_mv.into().0;
+        let max = self.psu.limits().map(|limits| limits.max_voltage_mv);
+        let raw = setpoint_to_raw(voltage_mv, max, self.scaling.voltage_divisor)
+            .ok_or(Error::InvalidRange)?;
+        self.psu.write_register(XyRegister::VSet, raw)?;
+        Ok(())
+    }
+
+    /// Get the current output target voltage. Value returned in millivolts.
+    pub fn get_output_voltage_mv(&mut self) -> Result<Millivolts, S::Error> {
+        let raw = self.psu.read_register(XyRegister::VSet)?;
+        Ok(Millivolts(self.scaling.raw_to_voltage_mv(raw)))
+    }
+
+    /// Set the output current limit. Value supplied in milliamps.
+    ///
+    /// Returns `InvalidRange` if the current is above the model's [`limits`](XyPsu::limits).
+    pub fn set_current_limit_ma(
+        &mut self,
+        current_ma: impl Into<Milliamps>,
+    ) -> Result<(), S::Error> {
+        let current_ma = current_ma.into().0;
+        let max = self.psu.limits().map(|limits| limits.max_current_ma);
+        let raw = setpoint_to_raw(current_ma, max, self.scaling.current_divisor)
+            .ok_or(Error::InvalidRange)?;
+        self.psu.write_register(XyRegister::ISet, raw)?;
+        Ok(())
+    }
+
+    /// Get the current output current limit value. Value returned in milliamps.
+    pub fn get_current_limit_ma(&mut self) -> Result<Milliamps, S::Error> {
+        let raw = self.psu.read_register(XyRegister::ISet)?;
+        Ok(Milliamps(self.scaling.raw_to_current_ma(raw)))
+    }
+}
+
+impl<S: Transport, const L: usize> Deref for ScaledPsu<S, L> {
+    type Target = XyPsu<S, L>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.psu
+    }
+}
+
+impl<S: Transport, const L: usize> DerefMut for ScaledPsu<S, L> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.psu
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{limits::ModelLimits, mock_serial::MockSerial};
+
+    #[test]
+    fn into_scaled_unknown_model() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.interface_mut().queue_read_response(0x01, &[0xFFFF]);
+
+        let Err((psu, Error::UnknownModel(0xFFFF))) = psu.into_scaled() else {
+            panic!("expected an unknown model");
+        };
+
+        let scaling = ScalingFactors::new(10, 1, 100, 1, 10).unwrap();
+        let mut psu = psu.with_scaling(scaling);
+        psu.interface_mut().queue_read_response(0x01, &[1234]);
+        assert_eq!(psu.read_output_voltage_mv().unwrap().0, 12340);
+        assert_eq!(psu.scaling_factors(), Some(scaling));
+    }
+
+    #[test]
+    fn scaled_setters_check_range() {
+        let scaling = ScalingFactors::new(10, 1, 100, 1, 10).unwrap();
+        let mut psu = XyPsu::<MockSerial, 128>::new(MockSerial::new(), 0x01).with_scaling(scaling);
+        // Too large for the register, rather than wrapping around to a low voltage.
+        assert!(matches!(
+            psu.set_output_voltage_mv(655_360),
+            Err(Error::InvalidRange)
+        ));
+
+        psu.set_limits(Some(ModelLimits::new(60_000, 20_000, None)));
+        assert!(matches!(
+            psu.set_current_limit_ma(20_001),
+            Err(Error::InvalidRange)
+        ));
+        assert!(psu.last_transaction().request.is_empty());
+    }
+}
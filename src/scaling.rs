@@ -3,10 +3,19 @@
 //! Different Sinilink XY-PSU models use different scaling factors for voltage, current,
 //! and power measurements. This module defines the scaling factors for each known model.
 
-use crate::register::ProductModel;
+use thiserror::Error;
+
+use crate::{
+    error::{Error, Result},
+    psu::XyPsu,
+    register::{ProductModel, State, XyRegister},
+    transport::Transport,
+};
 
 /// Scaling factors for converting raw register values to standard units
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ScalingFactors {
     /// Multiplier for voltage values (e.g., 10 means raw value is in centivolts, multiply by 10 to get mV)
     pub voltage_divisor: u32,
@@ -15,10 +24,10 @@ pub struct ScalingFactors {
     /// Multiplier for power values (e.g., 100 means raw value is in deciwatts, multiply by 100 to get mW)
     pub power_divisor: u32,
     /// Multiplier for capacity values (e.g., 10 means raw value is in units of 10mAh, multiply by 10 to get mAh)
-    /// Defaults to current_divisor if not specified
+    /// Defaults to current_divisor if not specified, see [`ScalingFactorsBuilder`]
     pub capacity_divisor: u32,
     /// Multiplier for energy values (e.g., 100 means raw value is in units of 100mWh, multiply by 100 to get mWh)
-    /// Defaults to power_divisor/10 if not specified
+    /// Defaults to power_divisor/10 if not specified, see [`ScalingFactorsBuilder`]
     pub energy_divisor: u32,
 }
 
@@ -45,19 +54,34 @@ impl ScalingFactors {
     /// * `power_divisor` - Multiplier for power values (raw to mW).
     /// * `capacity_divisor` - Multiplier for capacity values (raw to mAh).
     /// * `energy_divisor` - Multiplier for energy values (raw to mWh).
+    ///
+    /// Validated as by [`ScalingFactorsBuilder::build`], so returns `NotPowerOfTen` if a divisor is zero or isn't a
+    /// power of ten.
     pub const fn new(
         voltage_divisor: u32,
         current_divisor: u32,
         power_divisor: u32,
         capacity_divisor: u32,
         energy_divisor: u32,
-    ) -> Self {
-        Self {
-            voltage_divisor,
-            current_divisor,
-            power_divisor,
-            capacity_divisor,
-            energy_divisor,
+    ) -> core::result::Result<Self, ScalingFactorsError> {
+        Self::builder()
+            .voltage(voltage_divisor)
+            .current(current_divisor)
+            .power(power_divisor)
+            .capacity(capacity_divisor)
+            .energy(energy_divisor)
+            .build()
+    }
+
+    /// Start building scaling factors, deriving the ones not given, e.g.
+    /// `ScalingFactors::builder().voltage(10).current(10).build()?`.
+    pub const fn builder() -> ScalingFactorsBuilder {
+        ScalingFactorsBuilder {
+            voltage_divisor: None,
+            current_divisor: None,
+            power_divisor: None,
+            capacity_divisor: None,
+            energy_divisor: None,
         }
     }
 
@@ -66,13 +90,13 @@ impl ScalingFactors {
     /// If divisor is 10, raw is in centivolts (10mV units), so we multiply by 10.
     #[inline]
     pub const fn raw_to_voltage_mv(&self, raw: u16) -> u32 {
-        (raw as u32) * self.voltage_divisor
+        (raw as u32).saturating_mul(self.voltage_divisor)
     }
 
-    /// Convert millivolts to raw voltage register value
+    /// Convert millivolts to raw voltage register value, or `None` if it is too large for the register
     #[inline]
-    pub const fn voltage_mv_to_raw(&self, voltage_mv: u32) -> u16 {
-        (voltage_mv / self.voltage_divisor) as u16
+    pub const fn voltage_mv_to_raw(&self, voltage_mv: u32) -> Option<u16> {
+        Self::div_to_raw(voltage_mv, self.voltage_divisor)
     }
 
     /// Convert raw current register value to milliamps
@@ -80,13 +104,13 @@ impl ScalingFactors {
     /// If divisor is 10, raw is in units of 10mA, so we multiply by 10.
     #[inline]
     pub const fn raw_to_current_ma(&self, raw: u16) -> u32 {
-        (raw as u32) * self.current_divisor
+        (raw as u32).saturating_mul(self.current_divisor)
     }
 
-    /// Convert milliamps to raw current register value
+    /// Convert milliamps to raw current register value, or `None` if it is too large for the register
     #[inline]
-    pub const fn current_ma_to_raw(&self, current_ma: u32) -> u16 {
-        (current_ma / self.current_divisor) as u16
+    pub const fn current_ma_to_raw(&self, current_ma: u32) -> Option<u16> {
+        Self::div_to_raw(current_ma, self.current_divisor)
     }
 
     /// Convert raw power register value to milliwatts
@@ -94,14 +118,151 @@ impl ScalingFactors {
     /// If divisor is 100, raw is in units of 100mW (deciwatts), so we multiply by 100.
     #[inline]
     pub const fn raw_to_power_mw(&self, raw: u16) -> u32 {
-        (raw as u32) * self.power_divisor
+        (raw as u32).saturating_mul(self.power_divisor)
+    }
+
+    /// Convert milliwatts to raw power register value, or `None` if it is too large for the register
+    #[inline]
+    pub const fn power_mw_to_raw(&self, power_mw: u32) -> Option<u16> {
+        Self::div_to_raw(power_mw, self.power_divisor)
+    }
+
+    /// Convert a raw 32-bit capacity counter, low and high registers combined, to milliamp-hours
+    #[inline]
+    pub const fn raw_to_capacity_mah(&self, raw: u32) -> u32 {
+        raw.saturating_mul(self.capacity_divisor)
     }
 
-    /// Convert milliwatts to raw power register value
+    /// Convert a raw 32-bit energy counter, low and high registers combined, to milliwatt-hours
     #[inline]
-    pub const fn power_mw_to_raw(&self, power_mw: u32) -> u16 {
-        (power_mw / self.power_divisor) as u16
+    pub const fn raw_to_energy_mwh(&self, raw: u32) -> u32 {
+        raw.saturating_mul(self.energy_divisor)
+    }
+
+    /// Divide a value down to its raw register value, or `None` if it doesn't fit in the register.
+    ///
+    /// A divisor of zero gives zero rather than panicking.
+    #[inline]
+    const fn div_to_raw(value: u32, divisor: u32) -> Option<u16> {
+        match value.checked_div(divisor) {
+            Some(raw) if raw > u16::MAX as u32 => None,
+            Some(raw) => Some(raw as u16),
+            None => Some(0),
+        }
+    }
+}
+
+/// Reasons [`ScalingFactorsBuilder::build`] can fail.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ScalingFactorsError {
+    #[error("Voltage divisor not set")]
+    MissingVoltage,
+    #[error("Current divisor not set")]
+    MissingCurrent,
+    #[error("Divisors must be a power of ten: 1, 10, 100, ... Zero isn't allowed.")]
+    NotPowerOfTen,
+}
+
+/// Builds [`ScalingFactors`], see [`ScalingFactors::builder`].
+///
+/// The voltage and current divisors are required. The rest follow from them as on every confirmed model:
+/// - power: `voltage * current * 10`, e.g. 100 for hundredths of a volt and milliamps, so deciwatts.
+/// - capacity: the current divisor, as capacity is accumulated current.
+/// - energy: `power / 10`, at least 1.
+///
+/// Every divisor must be a power of ten, as the registers hold decimal fixed-point values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScalingFactorsBuilder {
+    voltage_divisor: Option<u32>,
+    current_divisor: Option<u32>,
+    power_divisor: Option<u32>,
+    capacity_divisor: Option<u32>,
+    energy_divisor: Option<u32>,
+}
+
+impl ScalingFactorsBuilder {
+    /// Set the voltage divisor, e.g. 10 for hundredths of a volt.
+    pub const fn voltage(mut self, divisor: u32) -> Self {
+        self.voltage_divisor = Some(divisor);
+        self
+    }
+
+    /// Set the current divisor, e.g. 10 for hundredths of an amp.
+    pub const fn current(mut self, divisor: u32) -> Self {
+        self.current_divisor = Some(divisor);
+        self
+    }
+
+    /// Set the power divisor, rather than deriving it.
+    pub const fn power(mut self, divisor: u32) -> Self {
+        self.power_divisor = Some(divisor);
+        self
+    }
+
+    /// Set the capacity divisor, rather than deriving it from the current divisor.
+    pub const fn capacity(mut self, divisor: u32) -> Self {
+        self.capacity_divisor = Some(divisor);
+        self
+    }
+
+    /// Set the energy divisor, rather than deriving it from the power divisor.
+    pub const fn energy(mut self, divisor: u32) -> Self {
+        self.energy_divisor = Some(divisor);
+        self
+    }
+
+    /// Return the scaling factors, deriving the divisors which weren't set.
+    ///
+    /// Returns `MissingVoltage` or `MissingCurrent` if either of those divisors wasn't set, and `NotPowerOfTen` if any
+    /// divisor, given or derived, is zero or isn't a power of ten.
+    pub const fn build(self) -> core::result::Result<ScalingFactors, ScalingFactorsError> {
+        let Some(voltage_divisor) = self.voltage_divisor else {
+            return Err(ScalingFactorsError::MissingVoltage);
+        };
+        let Some(current_divisor) = self.current_divisor else {
+            return Err(ScalingFactorsError::MissingCurrent);
+        };
+        let power_divisor = match self.power_divisor {
+            Some(divisor) => divisor,
+            None => voltage_divisor
+                .saturating_mul(current_divisor)
+                .saturating_mul(10),
+        };
+        let capacity_divisor = match self.capacity_divisor {
+            Some(divisor) => divisor,
+            None => current_divisor,
+        };
+        let energy_divisor = match self.energy_divisor {
+            Some(divisor) => divisor,
+            None if power_divisor >= 10 => power_divisor / 10,
+            None => 1,
+        };
+        let scaling = ScalingFactors {
+            voltage_divisor,
+            current_divisor,
+            power_divisor,
+            capacity_divisor,
+            energy_divisor,
+        };
+        if !(is_power_of_ten(voltage_divisor)
+            && is_power_of_ten(current_divisor)
+            && is_power_of_ten(power_divisor)
+            && is_power_of_ten(capacity_divisor)
+            && is_power_of_ten(energy_divisor))
+        {
+            return Err(ScalingFactorsError::NotPowerOfTen);
+        }
+        Ok(scaling)
+    }
+}
+
+/// Whether `value` is 1, 10, 100, ...
+const fn is_power_of_ten(mut value: u32) -> bool {
+    while value >= 10 && value.is_multiple_of(10) {
+        value /= 10;
     }
+    value == 1
 }
 
 impl ProductModel {
@@ -121,6 +282,8 @@ impl ProductModel {
     ///
     /// If this method returns `None` for your model, you can use [`XyPsu::set_scaling_factors`]
     /// to manually specify scaling factors, and then use the normal scaled methods as normal.
+    /// Or add them to the PSU's [`ScalingRegistry`], see
+    /// [`XyPsu::scaling_registry_mut`](crate::psu::XyPsu::scaling_registry_mut).
     pub const fn scaling_factors(&self) -> Option<ScalingFactors> {
         match self {
             // These scaling factors have been checked.
@@ -161,9 +324,168 @@ impl ProductModel {
     }
 }
 
+/// Number of models a [`ScalingRegistry`] can hold.
+pub const SCALING_REGISTRY_CAPACITY: usize = 8;
+
+/// Scaling factors added at runtime, for models which this crate doesn't know the scaling of yet.
+///
+/// Keyed by the raw "MODEL" register value, see
+/// [`XyPsu::get_product_model_raw`](crate::psu::XyPsu::get_product_model_raw). Entries take priority over the built-in [`ProductModel::scaling_factors`], so can also correct them.
+///
+/// If you confirm the scaling factors of a new model, please submit a Github ticket so we can add them!
+#[derive(Debug, Clone, Default)]
+pub struct ScalingRegistry {
+    entries: heapless::LinearMap<u16, ScalingFactors, SCALING_REGISTRY_CAPACITY>,
+}
+
+impl ScalingRegistry {
+    /// Create an empty registry.
+    pub const fn new() -> Self {
+        Self {
+            entries: heapless::LinearMap::new(),
+        }
+    }
+
+    /// Add the scaling factors of a model, returning the ones it replaces, if any.
+    ///
+    /// Returns the scaling factors back as an error if the registry is full.
+    pub fn insert(
+        &mut self,
+        model_id: u16,
+        scaling: ScalingFactors,
+    ) -> core::result::Result<Option<ScalingFactors>, ScalingFactors> {
+        self.entries
+            .insert(model_id, scaling)
+            .map_err(|(_, scaling)| scaling)
+    }
+
+    /// Remove the scaling factors of a model.
+    pub fn remove(&mut self, model_id: u16) -> Option<ScalingFactors> {
+        self.entries.remove(&model_id)
+    }
+
+    /// Return the scaling factors added for a model.
+    pub fn get(&self, model_id: u16) -> Option<ScalingFactors> {
+        self.entries.get(&model_id).copied()
+    }
+
+    /// Return the scaling factors for a model, from this registry, otherwise the built-in ones.
+    pub fn lookup(&self, model_id: u16) -> Option<ScalingFactors> {
+        self.get(model_id)
+            .or_else(|| ProductModel::from_raw(model_id)?.scaling_factors())
+    }
+
+    /// Like [`Self::lookup`], but with the error to report if the scaling factors are unknown.
+    pub(crate) fn resolve<I: embedded_io::Error>(
+        &self,
+        model_id: u16,
+    ) -> Result<ScalingFactors, I> {
+        match self.lookup(model_id) {
+            Some(scaling) => Ok(scaling),
+            None if ProductModel::from_raw(model_id).is_none() => {
+                Err(Error::UnknownModel(model_id))
+            }
+            None => Err(Error::ScalingNotAvailable),
+        }
+    }
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Guess the scaling factors of an unknown model by probing it.
+    ///
+    /// The "MODEL" and "VERSION" registers don't say what precision the display uses, so instead the voltage and
+    /// current settings are set to their maximum raw values, with the output off. The PSU limits them to its rated
+    /// maximums, which are in the thousands when set in hundredths of a volt, or milliamps, and the hundreds or low
+    /// thousands otherwise. Power, capacity and energy scaling follow the current precision, as on the known models.
+    ///
+    /// The output is turned off while probing. Afterwards the previous settings and output state are restored.
+    ///
+    /// The result is a guess, check it against a multimeter before relying on it. Use it with
+    /// [`Self::set_scaling_factors`], or add it to the [`ScalingRegistry`]. Returns `ScalingNotAvailable` if the PSU
+    /// doesn't limit the settings, so nothing can be inferred.
+    ///
+    /// @TODO confirm on more models that settings are limited rather than rejected.
+    pub fn detect_scaling(&mut self) -> Result<ScalingFactors, S::Error> {
+        let output = State::from(self.read_register(XyRegister::OnOff)?);
+        let voltage_raw = self.read_register(XyRegister::VSet)?;
+        let current_raw = self.read_register(XyRegister::ISet)?;
+        if output == State::On {
+            self.write_register(XyRegister::OnOff, State::Off)?;
+        }
+
+        let probe = self.probe_limits();
+
+        // Restore the settings even if probing failed.
+        self.write_register(XyRegister::VSet, voltage_raw)?;
+        self.write_register(XyRegister::ISet, current_raw)?;
+        if output == State::On {
+            self.write_register(XyRegister::OnOff, State::On)?;
+        }
+
+        let (max_voltage_raw, max_current_raw) = probe?;
+        if max_voltage_raw == u16::MAX || max_current_raw == u16::MAX {
+            return Err(Error::ScalingNotAvailable);
+        }
+
+        // E.g. 60V is 6000 in centivolts, but 600 in decivolts.
+        let voltage_divisor = if max_voltage_raw >= 1000 { 10 } else { 100 };
+        // E.g. 7A is 7000 in milliamps, but 20A is 2000 in centiamps.
+        let (current_divisor, power_divisor) = if max_current_raw > 5000 {
+            (1, 100)
+        } else {
+            (10, 1000)
+        };
+        Ok(ScalingFactors::new(
+            voltage_divisor,
+            current_divisor,
+            power_divisor,
+            current_divisor,
+            power_divisor / 10,
+        )?)
+    }
+
+    /// Set the voltage and current settings as high as possible, returning what the PSU limited them to.
+    fn probe_limits(&mut self) -> Result<(u16, u16), S::Error> {
+        self.write_register(XyRegister::VSet, u16::MAX)?;
+        let max_voltage_raw = self.read_register(XyRegister::VSet)?;
+        self.write_register(XyRegister::ISet, u16::MAX)?;
+        let max_current_raw = self.read_register(XyRegister::ISet)?;
+        Ok((max_voltage_raw, max_current_raw))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mock_serial::{MockResponse, MockSerial};
+
+    #[test]
+    fn detect_scaling_from_limits() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        let serial = psu.interface_mut();
+        serial.queue_read_response(0x01, &[1]);
+        serial.queue_read_response(0x01, &[1200]);
+        serial.queue_read_response(0x01, &[500]);
+        serial.queue_response(MockResponse::Echo);
+        // Limited to 60V and 20A.
+        serial.queue_response(MockResponse::Echo);
+        serial.queue_read_response(0x01, &[6000]);
+        serial.queue_response(MockResponse::Echo);
+        serial.queue_read_response(0x01, &[2000]);
+        for _ in 0..3 {
+            serial.queue_response(MockResponse::Echo);
+        }
+
+        assert_eq!(
+            psu.detect_scaling().unwrap(),
+            ScalingFactors::new(10, 10, 1000, 10, 100).unwrap()
+        );
+        // Output turned back on last.
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([0x00, 0x12, 0x00, 0x01].as_slice())
+        );
+    }
 
     #[test]
     fn test_voltage_scaling() {
@@ -178,7 +500,9 @@ mod tests {
         // Raw value 1234 centvolts = 12340 mV
         assert_eq!(scaling.raw_to_voltage_mv(1234), 12340);
         // 12340 mV should convert back to 1234 raw
-        assert_eq!(scaling.voltage_mv_to_raw(12340), 1234);
+        assert_eq!(scaling.voltage_mv_to_raw(12340), Some(1234));
+        // Too large for the register, rather than wrapping around.
+        assert_eq!(scaling.voltage_mv_to_raw(655_360), None);
     }
 
     #[test]
@@ -194,7 +518,7 @@ mod tests {
         // Raw value 500 (units of 10mA) = 5000 mA
         assert_eq!(scaling.raw_to_current_ma(500), 5000);
         // 5000 mA should convert back to 500 raw
-        assert_eq!(scaling.current_ma_to_raw(5000), 500);
+        assert_eq!(scaling.current_ma_to_raw(5000), Some(500));
     }
 
     #[test]
@@ -211,6 +535,54 @@ mod tests {
         assert_eq!(scaling.raw_to_power_mw(123), 12300);
     }
 
+    #[test]
+    fn test_zero_divisor_does_not_panic() {
+        assert!(ScalingFactors::new(0, 0, 0, 0, 0).is_err());
+        // The fields are public, so zero divisors can still be set directly.
+        let scaling = ScalingFactors {
+            voltage_divisor: 0,
+            current_divisor: 0,
+            power_divisor: 0,
+            capacity_divisor: 0,
+            energy_divisor: 0,
+        };
+        assert_eq!(scaling.voltage_mv_to_raw(12340), Some(0));
+        assert_eq!(scaling.current_ma_to_raw(5000), Some(0));
+        assert_eq!(scaling.power_mw_to_raw(12300), Some(0));
+    }
+
+    #[test]
+    fn test_builder_derives_known_models() {
+        assert_eq!(
+            ScalingFactors::builder().voltage(10).current(1).build(),
+            Ok(ProductModel::XY3607F.scaling_factors().unwrap())
+        );
+        assert_eq!(
+            ScalingFactors::builder().voltage(10).current(10).build(),
+            Ok(ProductModel::XY6020L.scaling_factors().unwrap())
+        );
+        assert_eq!(
+            ScalingFactors::builder().current(10).build(),
+            Err(ScalingFactorsError::MissingVoltage)
+        );
+        assert_eq!(
+            ScalingFactors::builder().voltage(10).current(0).build(),
+            Err(ScalingFactorsError::NotPowerOfTen)
+        );
+        assert_eq!(
+            ScalingFactors::builder()
+                .voltage(10)
+                .current(10)
+                .energy(50)
+                .build(),
+            Err(ScalingFactorsError::NotPowerOfTen)
+        );
+        assert_eq!(
+            ScalingFactors::new(10, 0, 100, 1, 10),
+            Err(ScalingFactorsError::NotPowerOfTen)
+        );
+    }
+
     #[test]
     fn test_known_models_have_scaling() {
         assert!(ProductModel::XY3607F.scaling_factors().is_some());
@@ -0,0 +1,110 @@
+//! Find PSUs on an RS-485 bus when their unit IDs are unknown, e.g. on a second-hand board.
+//!
+//! Each unit ID is probed by reading its "MODEL" register. IDs which don't respond are skipped once the interface's
+//! read times out, so configure a short read timeout (e.g. 50ms) before scanning, otherwise a full scan is slow.
+//!
+//! ```ignore
+//! port.set_timeout(Duration::from_millis(50))?;
+//! let found: heapless::Vec<FoundUnit, 8> = scan_bus(&mut port, UNIT_IDS)?;
+//! for unit in &found {
+//!     println!("{}: {:?}", unit.unit_id, unit.model());
+//! }
+//! ```
+
+use crate::{
+    error::{Error, Result, TransactionFailure},
+    psu::XyPsu,
+    register::ProductModel,
+};
+use embedded_io::Error as _;
+
+/// Every unit ID usable by a single Modbus device.
+pub const UNIT_IDS: core::ops::RangeInclusive<u8> = 1..=247;
+
+/// A PSU which responded to [`scan_bus`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoundUnit {
+    /// Modbus unit ID the PSU responded on.
+    pub unit_id: u8,
+    /// Raw value of the "MODEL" register.
+    pub model_raw: u16,
+}
+
+impl FoundUnit {
+    /// Return the interpreted product model, if it is known.
+    pub fn model(&self) -> Option<ProductModel> {
+        ProductModel::from_raw(self.model_raw)
+    }
+}
+
+/// Probe each of `unit_ids` in turn, returning the PSUs which respond.
+///
+/// IDs which time out or respond with garbage, e.g. from a bus collision, are skipped. Other interface errors are
+/// returned. Returns [`Error::BufferError`] if more than `N` PSUs respond.
+pub fn scan_bus<S: embedded_io::Read + embedded_io::Write, const N: usize>(
+    interface: &mut S,
+    unit_ids: impl IntoIterator<Item = u8>,
+) -> Result<heapless::Vec<FoundUnit, N>, S::Error> {
+    let mut found = heapless::Vec::new();
+    for unit_id in unit_ids {
+        let mut psu: XyPsu<&mut S, 32> = XyPsu::new(&mut *interface, unit_id);
+        let model_raw = match psu.get_product_model_raw() {
+            Ok(model_raw) => model_raw,
+            Err(Error::SerialError(e))
+                if matches!(
+                    e.kind(),
+                    embedded_io::ErrorKind::TimedOut | embedded_io::ErrorKind::Other
+                ) =>
+            {
+                continue;
+            }
+            Err(Error::Transaction {
+                failure: TransactionFailure::InvalidResponse,
+                ..
+            }) => continue,
+            Err(e) => return Err(e),
+        };
+        found
+            .push(FoundUnit { unit_id, model_raw })
+            .map_err(|_| Error::BufferError)?;
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_serial::{MockResponse, MockSerial};
+
+    #[test]
+    fn scan_finds_responding_units() {
+        let mut serial = MockSerial::new();
+        serial.queue_response(MockResponse::Data(heapless::Vec::new()));
+        serial.queue_response(MockResponse::Data(crate::mock_serial::read_response(
+            0x02,
+            &[0x6500],
+        )));
+        serial.queue_response(MockResponse::Data(heapless::Vec::new()));
+        serial.queue_response(MockResponse::Data(crate::mock_serial::read_response(
+            0x04,
+            &[0x1234],
+        )));
+
+        let found: heapless::Vec<FoundUnit, 4> = scan_bus(&mut serial, 1..=4).unwrap();
+        assert_eq!(
+            found,
+            [
+                FoundUnit {
+                    unit_id: 0x02,
+                    model_raw: 0x6500
+                },
+                FoundUnit {
+                    unit_id: 0x04,
+                    model_raw: 0x1234
+                },
+            ]
+        );
+        assert!(matches!(found[0].model(), Some(ProductModel::XY7025)));
+        assert!(found[1].model().is_none());
+    }
+}
@@ -0,0 +1,209 @@
+//! Run a list of output settings in order, e.g. for burn-in or margin testing.
+//!
+//! Each step's settings are written and read back to check the PSU accepted them, then held for the step's dwell time.
+//! The PSU's protections are checked every [`PROTECTION_POLL_MS`] while dwelling, and the sequence is aborted with the
+//! output off if any trip.
+//!
+//! ```ignore
+//! let steps = [
+//!     Step::new(11_400, 2_000, 60_000),
+//!     Step::new(12_000, 2_000, 60_000),
+//!     Step::new(12_600, 2_000, 60_000),
+//! ];
+//! match run_sequence(&mut psu, &steps, |ms| {
+//!     sleep(Duration::from_millis(ms.into()));
+//!     true
+//! })? {
+//!     SequenceOutcome::Completed => println!("Passed"),
+//!     SequenceOutcome::Aborted { step, reason } => println!("Step {step} failed: {reason:?}"),
+//! }
+//! ```
+
+use crate::{
+    error::{Error, Result},
+    psu::XyPsu,
+    register::{ProtectionStatus, State, XyRegister},
+    transport::Transport,
+};
+
+/// Longest time between protection checks while dwelling, in milliseconds.
+pub const PROTECTION_POLL_MS: u32 = 500;
+
+/// Output settings held for a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    /// Output voltage setting in millivolts.
+    pub voltage_mv: u32,
+    /// Output current limit in milliamps.
+    pub current_ma: u32,
+    /// Time to hold the settings, in milliseconds.
+    pub dwell_ms: u32,
+}
+
+impl Step {
+    pub fn new(voltage_mv: u32, current_ma: u32, dwell_ms: u32) -> Self {
+        Self {
+            voltage_mv,
+            current_ma,
+            dwell_ms,
+        }
+    }
+}
+
+/// Why a sequence was aborted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbortReason {
+    /// The settings read back didn't match those written, in raw register values.
+    SettingMismatch { voltage_raw: u16, current_raw: u16 },
+    /// A protection tripped.
+    Protection(ProtectionStatus),
+    /// The delay callback returned `false`.
+    Cancelled,
+}
+
+/// Result of [`run_sequence`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceOutcome {
+    /// Every step ran.
+    Completed,
+    /// Stopped during the step at index `step`.
+    Aborted { step: usize, reason: AbortReason },
+}
+
+/// Run `steps` in order, turning the output on for the first and off after the last.
+///
+/// `delay` is called while dwelling with the time to wait in milliseconds, at most [`PROTECTION_POLL_MS`], and returns
+/// `false` to cancel the sequence. The output is turned off when the sequence ends, however it ends, unless
+/// communicating with the PSU fails.
+///
+/// Requires known scaling factors for the PSU model.
+pub fn run_sequence<S: Transport, const L: usize>(
+    psu: &mut XyPsu<S, L>,
+    steps: &[Step],
+    mut delay: impl FnMut(u32) -> bool,
+) -> Result<SequenceOutcome, S::Error> {
+    let mut outcome = SequenceOutcome::Completed;
+    for (i, step) in steps.iter().enumerate() {
+        if let Some(reason) = run_step(psu, step, i == 0, &mut delay)? {
+            outcome = SequenceOutcome::Aborted { step: i, reason };
+            break;
+        }
+    }
+    psu.set_output_state(State::Off)?;
+    Ok(outcome)
+}
+
+/// Apply and hold one step, returning why it failed, if it did.
+fn run_step<S: Transport, const L: usize>(
+    psu: &mut XyPsu<S, L>,
+    step: &Step,
+    first: bool,
+    delay: &mut impl FnMut(u32) -> bool,
+) -> Result<Option<AbortReason>, S::Error> {
+    psu.set_output_voltage_mv(step.voltage_mv)?;
+    psu.set_current_limit_ma(step.current_ma)?;
+    let scaling = psu.scaling_factors().ok_or(Error::ScalingNotAvailable)?;
+    let voltage_raw = psu.read_register(XyRegister::VSet)?;
+    let current_raw = psu.read_register(XyRegister::ISet)?;
+    if Some(voltage_raw) != scaling.voltage_mv_to_raw(step.voltage_mv)
+        || Some(current_raw) != scaling.current_ma_to_raw(step.current_ma)
+    {
+        return Ok(Some(AbortReason::SettingMismatch {
+            voltage_raw,
+            current_raw,
+        }));
+    }
+    if first {
+        psu.set_output_state(State::On)?;
+    }
+
+    let mut remaining_ms = step.dwell_ms;
+    loop {
+        let protection = psu.get_protection_status()?;
+        if protection.any() {
+            return Ok(Some(AbortReason::Protection(protection)));
+        }
+        if remaining_ms == 0 {
+            return Ok(None);
+        }
+        let wait_ms = remaining_ms.min(PROTECTION_POLL_MS);
+        if !delay(wait_ms) {
+            return Ok(Some(AbortReason::Cancelled));
+        }
+        remaining_ms -= wait_ms;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_serial::{MockResponse, MockSerial},
+        scaling::ScalingFactors,
+    };
+
+    fn psu() -> XyPsu<MockSerial, 128> {
+        let mut psu = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        psu
+    }
+
+    #[test]
+    fn sequence_runs_steps() {
+        let mut psu = psu();
+        let serial = psu.interface_mut();
+        for (voltage_raw, current_raw) in [(1200, 1000), (1300, 500)] {
+            serial.queue_response(MockResponse::Echo);
+            serial.queue_response(MockResponse::Echo);
+            serial.queue_read_response(0x01, &[voltage_raw]);
+            serial.queue_read_response(0x01, &[current_raw]);
+            if voltage_raw == 1200 {
+                serial.queue_response(MockResponse::Echo);
+            }
+            // Checked before, midway through, and after the dwell.
+            for _ in 0..3 {
+                serial.queue_read_response(0x01, &[0]);
+            }
+        }
+        serial.queue_response(MockResponse::Echo);
+
+        let steps = [Step::new(12_000, 1_000, 600), Step::new(13_000, 500, 1000)];
+        let mut dwelled_ms = 0;
+        let outcome = run_sequence(&mut psu, &steps, |ms| {
+            dwelled_ms += ms;
+            true
+        })
+        .unwrap();
+        assert_eq!(outcome, SequenceOutcome::Completed);
+        assert_eq!(dwelled_ms, 1600);
+    }
+
+    #[test]
+    fn sequence_aborts_on_protection() {
+        let mut psu = psu();
+        let serial = psu.interface_mut();
+        serial.queue_response(MockResponse::Echo);
+        serial.queue_response(MockResponse::Echo);
+        serial.queue_read_response(0x01, &[1200]);
+        serial.queue_read_response(0x01, &[1000]);
+        serial.queue_response(MockResponse::Echo);
+        serial.queue_read_response(0x01, &[0]);
+        serial.queue_read_response(0x01, &[0b10]);
+        serial.queue_response(MockResponse::Echo);
+
+        let steps = [Step::new(12_000, 1_000, 1000), Step::new(13_000, 500, 1000)];
+        let outcome = run_sequence(&mut psu, &steps, |_| true).unwrap();
+        assert_eq!(
+            outcome,
+            SequenceOutcome::Aborted {
+                step: 0,
+                reason: AbortReason::Protection(ProtectionStatus::from_raw(0b10))
+            }
+        );
+        // Output turned off.
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([0x00, 0x12, 0x00, 0x00].as_slice())
+        );
+    }
+}
@@ -0,0 +1,110 @@
+//! Share one serial port between several [`XyPsu`](crate::psu::XyPsu)s, e.g. PSUs with different unit IDs on one
+//! RS-485 bus, without any of them owning the port.
+//!
+//! ```ignore
+//! let port = RefCell::new(port);
+//! let mut psu_a = XyPsu::<_, 128>::new(RefCellInterface::new(&port), 0x01);
+//! let mut psu_b = XyPsu::<_, 128>::new(RefCellInterface::new(&port), 0x02);
+//! psu_a.set_output_state(true)?;
+//! psu_b.set_output_state(true)?;
+//! ```
+//!
+//! The port is only borrowed for each read or write, so this relies on transactions not interleaving, which holds for
+//! the blocking driver on one thread. To share a port between threads, lock around whole driver calls instead.
+
+use core::cell::RefCell;
+use thiserror::Error;
+
+/// Error from a [`RefCellInterface`].
+#[derive(Error, Debug)]
+pub enum SharedError<E: embedded_io::Error> {
+    #[error("Interface is already borrowed")]
+    Busy,
+    #[error("Interface error")]
+    Interface(E),
+}
+
+impl<E: embedded_io::Error> embedded_io::Error for SharedError<E> {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            // Not `Other` or `TimedOut`, which would end a response early and be retried as a truncated frame.
+            SharedError::Busy => embedded_io::ErrorKind::AddrInUse,
+            SharedError::Interface(e) => e.kind(),
+        }
+    }
+}
+
+/// Interface borrowed from a [`RefCell`] for each read or write.
+pub struct RefCellInterface<'a, S> {
+    interface: &'a RefCell<S>,
+}
+
+impl<'a, S> RefCellInterface<'a, S> {
+    pub fn new(interface: &'a RefCell<S>) -> Self {
+        Self { interface }
+    }
+
+    fn borrow(&self) -> Result<core::cell::RefMut<'a, S>, SharedError<S::Error>>
+    where
+        S: embedded_io::ErrorType,
+    {
+        self.interface
+            .try_borrow_mut()
+            .map_err(|_| SharedError::Busy)
+    }
+}
+
+impl<S: embedded_io::ErrorType> embedded_io::ErrorType for RefCellInterface<'_, S> {
+    type Error = SharedError<S::Error>;
+}
+
+impl<S: embedded_io::Read> embedded_io::Read for RefCellInterface<'_, S> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.borrow()?.read(buf).map_err(SharedError::Interface)
+    }
+}
+
+impl<S: embedded_io::Write> embedded_io::Write for RefCellInterface<'_, S> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.borrow()?.write(buf).map_err(SharedError::Interface)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.borrow()?.flush().map_err(SharedError::Interface)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock_serial::MockSerial, psu::XyPsu};
+
+    #[test]
+    fn drivers_share_interface() {
+        let serial = RefCell::new(MockSerial::new());
+        let mut psu_a: XyPsu<_, 128> = XyPsu::new(RefCellInterface::new(&serial), 0x01);
+        let mut psu_b: XyPsu<_, 128> = XyPsu::new(RefCellInterface::new(&serial), 0x02);
+
+        serial.borrow_mut().queue_read_response(0x01, &[0x6500]);
+        serial.borrow_mut().queue_read_response(0x02, &[0x6509]);
+        assert_eq!(psu_a.get_product_model_raw().unwrap(), 0x6500);
+        assert_eq!(psu_b.get_product_model_raw().unwrap(), 0x6509);
+
+        let _held = serial.borrow_mut();
+        assert!(matches!(
+            psu_a.get_product_model_raw(),
+            Err(crate::error::Error::SerialError(SharedError::Busy))
+        ));
+    }
+
+    #[test]
+    fn busy_does_not_end_response() {
+        use embedded_io::Error as _;
+
+        let busy = SharedError::<crate::mock_serial::MockSerialError>::Busy;
+        assert!(!crate::frame::ends_response(busy.kind(), 3));
+        assert!(!crate::frame::is_transient(
+            &crate::error::Error::SerialError(busy)
+        ));
+    }
+}
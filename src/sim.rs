@@ -0,0 +1,493 @@
+//! A simulated PSU, for testing applications without hardware. Only available with the `test-util` feature.
+//!
+//! [`SimulatedXyPsu`] implements [`embedded_io::Read`] & [`embedded_io::Write`], and answers Modbus RTU frames from a
+//! register file like a real PSU. The output follows the settings into a resistive load, switching between CV and CC,
+//! and the over voltage, current and power protections of the active preset group trip.
+//!
+//! ```ignore
+//! let mut sim = SimulatedXyPsu::for_model(ProductModel::XY6020L, 0x01).unwrap();
+//! sim.set_load_mohm(Some(10_000));
+//! let mut psu: XyPsu<_> = XyPsu::new(&mut sim, 0x01);
+//! psu.set_output_voltage_mv(5000)?;
+//! psu.set_output_state(true)?;
+//! assert_eq!(psu.read_current_ma()?, 500);
+//! ```
+
+use thiserror::Error;
+
+use crate::{
+    codec::crc16,
+    register::{ProductModel, XyRegister},
+    scaling::ScalingFactors,
+};
+
+/// Number of registers simulated, enough for the main registers and all 10 preset groups.
+pub const REGISTER_COUNT: usize = 0x100;
+
+/// Address of the first register of preset group M0.
+const PRESET_BASE: u16 = 0x50;
+/// Registers in each preset group.
+const PRESET_SIZE: u16 = 0x10;
+/// Offsets of preset settings within a group, see `XyPresetOffsets`.
+const PRESET_LVP: u16 = 0x02;
+const PRESET_OVP: u16 = 0x03;
+const PRESET_OCP: u16 = 0x04;
+const PRESET_OPP: u16 = 0x05;
+const PRESET_INI: u16 = 0x0D;
+
+/// Protection status bits, see [`ProtectionStatus`](crate::register::ProtectionStatus).
+const OVP: u16 = 1 << 0;
+const OCP: u16 = 1 << 1;
+const OPP: u16 = 1 << 2;
+const LVP: u16 = 1 << 3;
+
+/// Modbus exception codes.
+const ILLEGAL_FUNCTION: u8 = 0x01;
+const ILLEGAL_DATA_ADDRESS: u8 = 0x02;
+const ILLEGAL_DATA_VALUE: u8 = 0x03;
+
+/// Error from a [`SimulatedXyPsu`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimError {
+    /// There is no response to read, as a real PSU doesn't respond to frames with a bad CRC or another unit ID.
+    #[error("No response")]
+    Timeout,
+}
+
+impl embedded_io::Error for SimError {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        embedded_io::ErrorKind::TimedOut
+    }
+}
+
+/// A PSU simulated in memory.
+pub struct SimulatedXyPsu {
+    unit_id: u8,
+    scaling: ScalingFactors,
+    registers: [u16; REGISTER_COUNT],
+    /// Resistance of the load in milliohms, or `None` for no load.
+    load_mohm: Option<u32>,
+    input_voltage_mv: u32,
+    /// Bytes of the request being received.
+    request: heapless::Vec<u8, 256>,
+    /// Response to the last request, and how much of it has been read.
+    response: heapless::Vec<u8, 256>,
+    response_position: usize,
+}
+
+impl SimulatedXyPsu {
+    /// Simulate a PSU with the raw "MODEL" register value `model_raw` and `scaling`, answering to `unit_id`.
+    pub fn new(unit_id: u8, model_raw: u16, scaling: ScalingFactors) -> Self {
+        let mut sim = Self {
+            unit_id,
+            scaling,
+            registers: [0; REGISTER_COUNT],
+            load_mohm: None,
+            input_voltage_mv: 24_000,
+            request: heapless::Vec::new(),
+            response: heapless::Vec::new(),
+            response_position: 0,
+        };
+        sim.set_register(XyRegister::Model as u16, model_raw);
+        sim.set_register(XyRegister::SlaveAdd as u16, u16::from(unit_id));
+        sim.update();
+        sim
+    }
+
+    /// Simulate a known model. Returns `None` if its scaling factors aren't known.
+    pub fn for_model(model: ProductModel, unit_id: u8) -> Option<Self> {
+        let scaling = model.scaling_factors()?;
+        Some(Self::new(unit_id, model.raw(), scaling))
+    }
+
+    /// Return the value of the register at `address`, or `None` if it isn't simulated.
+    pub fn register(&self, address: u16) -> Option<u16> {
+        self.registers.get(usize::from(address)).copied()
+    }
+
+    /// Set the register at `address` directly, without the side effects of a Modbus write. Returns `false` if it isn't
+    /// simulated.
+    pub fn set_register(&mut self, address: u16, value: u16) -> bool {
+        match self.registers.get_mut(usize::from(address)) {
+            Some(register) => {
+                *register = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Connect a resistive load in milliohms, or `None` to disconnect it.
+    pub fn set_load_mohm(&mut self, load_mohm: Option<u32>) {
+        self.load_mohm = load_mohm;
+        self.update();
+    }
+
+    /// Set the input voltage in millivolts. Defaults to 24 V.
+    pub fn set_input_voltage_mv(&mut self, input_voltage_mv: u32) {
+        self.input_voltage_mv = input_voltage_mv;
+        self.update();
+    }
+
+    fn reg(&self, register: XyRegister) -> u16 {
+        self.register(register as u16).unwrap_or(0)
+    }
+
+    /// Value of a setting in the active preset group.
+    fn preset_setting(&self, offset: u16) -> u16 {
+        let group = self.reg(XyRegister::ExtractM);
+        self.register(preset_address(group, offset)).unwrap_or(0)
+    }
+
+    /// Write a register as the PSU would, including side effects.
+    fn write_register(&mut self, address: u16, value: u16) -> bool {
+        if !self.set_register(address, value) {
+            return false;
+        }
+        if address == XyRegister::OnOff as u16 && value != 0 {
+            self.set_register(XyRegister::Protect as u16, 0);
+        }
+        if address == XyRegister::ExtractM as u16 {
+            // Load the group's settings.
+            for (register, offset) in [
+                (XyRegister::VSet, 0),
+                (XyRegister::ISet, 1),
+                (XyRegister::OnOff, PRESET_INI),
+            ] {
+                let setting = self.register(preset_address(value, offset)).unwrap_or(0);
+                self.set_register(register as u16, setting);
+            }
+        }
+        true
+    }
+
+    /// Recalculate the measurements from the settings and load, and trip protections.
+    fn update(&mut self) {
+        let scaling = self.scaling;
+        let (voltage_mv, current_ma, cc) = self.output();
+        let power_mw = (u64::from(voltage_mv) * u64::from(current_ma) / 1000) as u32;
+
+        let mut tripped = 0;
+        if self.reg(XyRegister::OnOff) != 0 {
+            // A level of 0 is treated as disabled.
+            let trips = [
+                (
+                    scaling.raw_to_voltage_mv(self.preset_setting(PRESET_OVP)),
+                    voltage_mv,
+                    OVP,
+                ),
+                (
+                    scaling.raw_to_current_ma(self.preset_setting(PRESET_OCP)),
+                    current_ma,
+                    OCP,
+                ),
+                (
+                    scaling.raw_to_power_mw(self.preset_setting(PRESET_OPP)),
+                    power_mw,
+                    OPP,
+                ),
+            ];
+            for (level, value, bit) in trips {
+                if level != 0 && value > level {
+                    tripped |= bit;
+                }
+            }
+            let lvp = scaling.raw_to_voltage_mv(self.preset_setting(PRESET_LVP));
+            if self.input_voltage_mv < lvp {
+                tripped |= LVP;
+            }
+        }
+        if tripped != 0 {
+            let protect = self.reg(XyRegister::Protect) | tripped;
+            self.set_register(XyRegister::Protect as u16, protect);
+            self.set_register(XyRegister::OnOff as u16, 0);
+            return self.update();
+        }
+
+        let measurements = [
+            (
+                XyRegister::VOut,
+                to_raw(voltage_mv, scaling.voltage_divisor),
+            ),
+            (
+                XyRegister::IOut,
+                to_raw(current_ma, scaling.current_divisor),
+            ),
+            (XyRegister::Power, to_raw(power_mw, scaling.power_divisor)),
+            (
+                XyRegister::UIn,
+                to_raw(self.input_voltage_mv, scaling.voltage_divisor),
+            ),
+            (XyRegister::CvCc, u16::from(cc)),
+        ];
+        for (register, value) in measurements {
+            self.set_register(register as u16, value);
+        }
+    }
+
+    /// Output voltage and current, and whether in constant current mode.
+    fn output(&self) -> (u32, u32, bool) {
+        if self.reg(XyRegister::OnOff) == 0 {
+            return (0, 0, false);
+        }
+        let voltage_mv = self
+            .scaling
+            .raw_to_voltage_mv(self.reg(XyRegister::VSet))
+            .min(self.input_voltage_mv);
+        let current_limit_ma = self.scaling.raw_to_current_ma(self.reg(XyRegister::ISet));
+        let Some(load_mohm) = self.load_mohm else {
+            return (voltage_mv, 0, false);
+        };
+        let load_mohm = u64::from(load_mohm.max(1));
+        let current_ma = u64::from(voltage_mv) * 1000 / load_mohm;
+        if current_ma <= u64::from(current_limit_ma) {
+            (voltage_mv, current_ma as u32, false)
+        } else {
+            let voltage_mv = u64::from(current_limit_ma) * load_mohm / 1000;
+            (
+                voltage_mv.min(u64::from(u32::MAX)) as u32,
+                current_limit_ma,
+                true,
+            )
+        }
+    }
+
+    /// Handle a complete request frame, queueing the response if there is one.
+    fn handle_request(&mut self, frame: &[u8]) {
+        let Some((body, crc)) = frame.split_last_chunk::<2>() else {
+            return;
+        };
+        if crc16(body) != *crc {
+            return;
+        }
+        let [unit_id, function, data @ ..] = body else {
+            return;
+        };
+        if *unit_id != self.unit_id {
+            return;
+        }
+
+        let mut response: heapless::Vec<u8, 256> = heapless::Vec::new();
+        let result = match (*function, data) {
+            (0x03, [start_hi, start_lo, count_hi, count_lo]) => {
+                let start = u16::from_be_bytes([*start_hi, *start_lo]);
+                let count = u16::from_be_bytes([*count_hi, *count_lo]);
+                self.read_response(start, count, &mut response)
+            }
+            (0x06, [address_hi, address_lo, value_hi, value_lo]) => {
+                let address = u16::from_be_bytes([*address_hi, *address_lo]);
+                let value = u16::from_be_bytes([*value_hi, *value_lo]);
+                match self.write_register(address, value) {
+                    true => response
+                        .extend_from_slice(body)
+                        .map_err(|_| ILLEGAL_DATA_VALUE),
+                    false => Err(ILLEGAL_DATA_ADDRESS),
+                }
+            }
+            (
+                0x10,
+                [
+                    start_hi,
+                    start_lo,
+                    count_hi,
+                    count_lo,
+                    _byte_count,
+                    values @ ..,
+                ],
+            ) => {
+                let start = u16::from_be_bytes([*start_hi, *start_lo]);
+                let count = u16::from_be_bytes([*count_hi, *count_lo]);
+                self.write_bulk(start, count, values, &mut response)
+            }
+            (0x03 | 0x06 | 0x10, _) => Err(ILLEGAL_DATA_VALUE),
+            _ => Err(ILLEGAL_FUNCTION),
+        };
+        if let Err(code) = result {
+            response.clear();
+            _ = response.extend_from_slice(&[*unit_id, function | 0x80, code]);
+        }
+        self.update();
+
+        let crc = crc16(&response);
+        _ = response.extend_from_slice(&crc);
+        self.response = response;
+        self.response_position = 0;
+    }
+
+    fn read_response(
+        &self,
+        start: u16,
+        count: u16,
+        response: &mut heapless::Vec<u8, 256>,
+    ) -> core::result::Result<(), u8> {
+        if count == 0 || count > 125 {
+            return Err(ILLEGAL_DATA_VALUE);
+        }
+        let byte_count = u8::try_from(count * 2).map_err(|_| ILLEGAL_DATA_VALUE)?;
+        response
+            .extend_from_slice(&[self.unit_id, 0x03, byte_count])
+            .map_err(|_| ILLEGAL_DATA_VALUE)?;
+        for address in start..start.saturating_add(count) {
+            let value = self.register(address).ok_or(ILLEGAL_DATA_ADDRESS)?;
+            response
+                .extend_from_slice(&value.to_be_bytes())
+                .map_err(|_| ILLEGAL_DATA_VALUE)?;
+        }
+        Ok(())
+    }
+
+    fn write_bulk(
+        &mut self,
+        start: u16,
+        count: u16,
+        values: &[u8],
+        response: &mut heapless::Vec<u8, 256>,
+    ) -> core::result::Result<(), u8> {
+        if values.len() != usize::from(count) * 2 {
+            return Err(ILLEGAL_DATA_VALUE);
+        }
+        if usize::from(start) + usize::from(count) > REGISTER_COUNT {
+            return Err(ILLEGAL_DATA_ADDRESS);
+        }
+        for (address, value) in (start..).zip(values.chunks_exact(2)) {
+            let value = u16::from_be_bytes([
+                value.first().copied().unwrap_or(0),
+                value.get(1).copied().unwrap_or(0),
+            ]);
+            self.write_register(address, value);
+        }
+        let [start_hi, start_lo] = start.to_be_bytes();
+        let [count_hi, count_lo] = count.to_be_bytes();
+        response
+            .extend_from_slice(&[self.unit_id, 0x10, start_hi, start_lo, count_hi, count_lo])
+            .map_err(|_| ILLEGAL_DATA_VALUE)
+    }
+}
+
+/// Address of a setting in preset `group`.
+fn preset_address(group: u16, offset: u16) -> u16 {
+    PRESET_BASE
+        .saturating_add(group.saturating_mul(PRESET_SIZE))
+        .saturating_add(offset)
+}
+
+/// Length of the request frame starting with `request`, if enough of it has been received to tell.
+fn request_len(request: &[u8]) -> Option<usize> {
+    match request.get(1)? {
+        // Unit ID, function, start, count, byte count, values and CRC.
+        0x10 => Some(9 + usize::from(*request.get(6)?)),
+        // Unit ID, function, two words and CRC.
+        _ => Some(8),
+    }
+}
+
+/// Divide a value down to its raw register value, saturating.
+fn to_raw(value: u32, divisor: u32) -> u16 {
+    u16::try_from(value.checked_div(divisor).unwrap_or(0)).unwrap_or(u16::MAX)
+}
+
+impl embedded_io::ErrorType for SimulatedXyPsu {
+    type Error = SimError;
+}
+
+impl embedded_io::Write for SimulatedXyPsu {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        for &byte in buf {
+            if self.request.push(byte).is_err() {
+                // Not a frame we understand, start again.
+                self.request.clear();
+            }
+            if request_len(&self.request) == Some(self.request.len()) {
+                let request = core::mem::take(&mut self.request);
+                self.handle_request(&request);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl embedded_io::Read for SimulatedXyPsu {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let remaining = self
+            .response
+            .get(self.response_position..)
+            .unwrap_or_default();
+        if remaining.is_empty() {
+            return Err(SimError::Timeout);
+        }
+        let len = remaining.len().min(buf.len());
+        for (dst, src) in buf.iter_mut().zip(remaining) {
+            *dst = *src;
+        }
+        self.response_position += len;
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_io_async::Write for SimulatedXyPsu {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        embedded_io::Write::write(self, buf)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        embedded_io::Write::flush(self)
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_io_async::Read for SimulatedXyPsu {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        embedded_io::Read::read(self, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::Error, psu::XyPsu, register::ControlMode};
+
+    #[test]
+    fn simulated_output_follows_load() {
+        let mut sim = SimulatedXyPsu::for_model(ProductModel::XY6020L, 0x01).unwrap();
+        sim.set_load_mohm(Some(6_000));
+        let mut psu: XyPsu<_, 128> = XyPsu::new(&mut sim, 0x01);
+
+        psu.set_output_voltage_mv(12_000).unwrap();
+        psu.set_current_limit_ma(1_000).unwrap();
+        psu.set_output_state(true).unwrap();
+        assert_eq!(psu.read_current_ma().unwrap().0, 1_000);
+        assert_eq!(psu.read_output_voltage_mv().unwrap().0, 6_000);
+        assert_eq!(psu.get_current_control_mode().unwrap(), ControlMode::Cc);
+
+        psu.set_current_limit_ma(5_000).unwrap();
+        assert_eq!(psu.read_current_ma().unwrap().0, 2_000);
+        assert_eq!(psu.get_current_control_mode().unwrap(), ControlMode::Cv);
+
+        // Other units don't respond.
+        let mut other: XyPsu<_, 128> = XyPsu::new(&mut sim, 0x02);
+        assert!(matches!(
+            other.get_output_state(),
+            Err(Error::SerialError(SimError::Timeout))
+        ));
+    }
+
+    #[test]
+    fn simulated_protection_trips() {
+        let mut sim = SimulatedXyPsu::for_model(ProductModel::XY6020L, 0x01).unwrap();
+        // Over voltage protection of group M0 at 10 V.
+        sim.set_register(PRESET_BASE + PRESET_OVP, 100);
+        let mut psu: XyPsu<_, 128> = XyPsu::new(&mut sim, 0x01);
+
+        psu.set_output_voltage_mv(12_000).unwrap();
+        psu.set_output_state(true).unwrap();
+        assert!(psu.get_protection_status().unwrap().over_voltage());
+        assert_eq!(psu.read_output_voltage_mv().unwrap().0, 0);
+        // Past the last register.
+        assert!(psu.read_modbus_bulk(0xFF, 2).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+//! Specifications of each model, so applications can build generic UIs, e.g. choosing how many decimals to show and
+//! which controls to offer.
+//!
+//! ```ignore
+//! let specs = psu.get_product_model()?.specs();
+//! if let Some(precision) = specs.precision {
+//!     println!("{:.*} V", precision.voltage_decimals as usize, voltage_mv as f32 / 1000.0);
+//! }
+//! if specs.mppt {
+//!     // Show the MPPT settings.
+//! }
+//! ```
+//!
+//! Boards this crate doesn't know yet can be described at runtime with [`XyPsu::register_model`], so they are
+//! supported without waiting for a release:
+//!
+//! ```ignore
+//! psu.register_model(0x6503, ModelInfo {
+//!     scaling: ScalingFactors::new(10, 10, 100, 10, 10)?,
+//!     specs: ProductModel::XY6020L.specs(),
+//!     name: "XY6020L v5.2",
+//! })?;
+//! let info = psu.get_model_info()?;
+//! ```
+
+use crate::{
+    error::{Error, Result},
+    limits::ModelLimits,
+    psu::XyPsu,
+    register::ProductModel,
+    scaling::ScalingFactors,
+    transport::Transport,
+};
+
+/// Number of decimals the front panel shows, in volts, amps and watts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DisplayPrecision {
+    pub voltage_decimals: u8,
+    pub current_decimals: u8,
+    pub power_decimals: u8,
+}
+
+/// Ratings and supported features of a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ModelSpecs {
+    /// Rated voltage, current and power, see [`ProductModel::limits`].
+    pub limits: Option<ModelLimits>,
+    /// Resolution of the voltage, current and power registers. `None` if the scaling factors are unknown.
+    pub precision: Option<DisplayPrecision>,
+    /// Whether the model has MPPT for solar panel inputs.
+    pub mppt: bool,
+    /// Whether the model has an external temperature probe input.
+    pub external_temperature: bool,
+    /// Whether the model has a constant power (CW) mode.
+    pub constant_power: bool,
+}
+
+/// Number of decimals in a value in whole units, whose raw value is in `1 / divisor` of the milli-unit.
+const fn decimals(mut divisor: u32) -> u8 {
+    let mut decimals = 3;
+    while divisor >= 10 && decimals > 0 {
+        divisor /= 10;
+        decimals -= 1;
+    }
+    decimals
+}
+
+impl ProductModel {
+    /// Get the specifications of this product model.
+    ///
+    /// @TODO the features have only been checked on the XY6020L, the others are from the product listings.
+    pub const fn specs(&self) -> ModelSpecs {
+        let precision = match self.scaling_factors() {
+            Some(scaling) => Some(DisplayPrecision {
+                voltage_decimals: decimals(scaling.voltage_divisor),
+                current_decimals: decimals(scaling.current_divisor),
+                power_decimals: decimals(scaling.power_divisor),
+            }),
+            None => None,
+        };
+        let mppt = matches!(
+            self,
+            ProductModel::XY7025 | ProductModel::XY12522 | ProductModel::XY6020L
+        );
+        ModelSpecs {
+            limits: self.limits(),
+            precision,
+            mppt,
+            // Not confirmed on the SK models.
+            external_temperature: !matches!(
+                self,
+                ProductModel::XYSK60S | ProductModel::XYSK120S | ProductModel::XYSK150S
+            ),
+            // Constant power is part of the MPPT settings.
+            constant_power: mppt,
+        }
+    }
+}
+
+/// Everything this crate needs to support a model: its scaling factors, specifications and name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ModelInfo {
+    pub scaling: ScalingFactors,
+    /// Specifications. The limits are enforced on setpoints, as for the built-in models.
+    pub specs: ModelSpecs,
+    /// Name to show, e.g. as printed on the board.
+    pub name: &'static str,
+}
+
+/// Number of models a [`ModelRegistry`] can hold.
+pub const MODEL_REGISTRY_CAPACITY: usize = 8;
+
+/// Models added at runtime, keyed by the raw "MODEL" register value, see [`XyPsu::register_model`].
+///
+/// Entries take priority over the built-in models and the [`ScalingRegistry`](crate::scaling::ScalingRegistry), so
+/// can also correct them.
+#[derive(Debug, Clone, Default)]
+pub struct ModelRegistry {
+    entries: heapless::LinearMap<u16, ModelInfo, MODEL_REGISTRY_CAPACITY>,
+}
+
+impl ModelRegistry {
+    /// Create an empty registry.
+    pub const fn new() -> Self {
+        Self {
+            entries: heapless::LinearMap::new(),
+        }
+    }
+
+    /// Add a model, returning the description it replaces, if any.
+    ///
+    /// Returns the description back as an error if the registry is full.
+    pub fn insert(
+        &mut self,
+        model_id: u16,
+        info: ModelInfo,
+    ) -> core::result::Result<Option<ModelInfo>, ModelInfo> {
+        self.entries
+            .insert(model_id, info)
+            .map_err(|(_, info)| info)
+    }
+
+    /// Remove a model.
+    pub fn remove(&mut self, model_id: u16) -> Option<ModelInfo> {
+        self.entries.remove(&model_id)
+    }
+
+    /// Return the description added for a model.
+    pub fn get(&self, model_id: u16) -> Option<ModelInfo> {
+        self.entries.get(&model_id).copied()
+    }
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Describe a model this crate doesn't know, or correct a known one, keyed by its raw "MODEL" register value.
+    ///
+    /// Takes effect the next time scaling factors are detected, so register models before the first scaled
+    /// measurement. Returns `BufferError` if [`MODEL_REGISTRY_CAPACITY`] models are already registered.
+    ///
+    /// If you confirm the details of a new model, please submit a Github ticket so we can add it!
+    pub fn register_model(&mut self, model_id: u16, info: ModelInfo) -> Result<(), S::Error> {
+        self.model_registry_mut()
+            .insert(model_id, info)
+            .map_err(|_| Error::BufferError)?;
+        Ok(())
+    }
+
+    /// Return the description of this PSU's model: one added with [`Self::register_model`], otherwise the built-in one.
+    /// Returns `None` for models whose scaling factors aren't known.
+    pub fn get_model_info(&mut self) -> Result<Option<ModelInfo>, S::Error> {
+        let raw = self.get_product_model_raw()?;
+        if let Some(info) = self.model_registry().get(raw) {
+            return Ok(Some(info));
+        }
+        let model = ProductModel::from(raw);
+        Ok(model.scaling_factors().map(|scaling| ModelInfo {
+            scaling,
+            specs: model.specs(),
+            name: model.name(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_serial::MockSerial;
+
+    #[test]
+    fn precision_from_scaling() {
+        let specs = ProductModel::XY3607F.specs();
+        assert_eq!(
+            specs.precision,
+            Some(DisplayPrecision {
+                voltage_decimals: 2,
+                current_decimals: 3,
+                power_decimals: 1,
+            })
+        );
+        assert!(!specs.mppt);
+
+        let specs = ProductModel::XY6020L.specs();
+        assert_eq!(specs.precision.map(|p| p.current_decimals), Some(2));
+        assert!(specs.mppt && specs.constant_power);
+        assert_eq!(ProductModel::XYSK60S.specs().precision, None);
+    }
+
+    #[test]
+    fn registered_model_used() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        let info = ModelInfo {
+            scaling: ScalingFactors::new(10, 10, 100, 10, 10).unwrap(),
+            specs: ProductModel::XY6020L.specs(),
+            name: "XY6020L v5.2",
+        };
+        psu.register_model(0x6503, info).unwrap();
+
+        psu.interface_mut().queue_read_response(0x01, &[0x6503]);
+        assert_eq!(psu.get_model_info().unwrap(), Some(info));
+        psu.interface_mut().queue_read_response(0x01, &[0x6503]);
+        psu.interface_mut().queue_read_response(0x01, &[1234]);
+        assert_eq!(psu.read_output_voltage_mv().unwrap().0, 12340);
+        // The XY6020L's limits are enforced.
+        assert!(matches!(
+            psu.set_output_voltage_mv(61_000),
+            Err(Error::InvalidRange)
+        ));
+
+        psu.interface_mut().queue_read_response(0x01, &[0x6502]);
+        assert_eq!(psu.get_model_info().unwrap().unwrap().name, "XY6020L");
+    }
+}
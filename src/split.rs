@@ -0,0 +1,137 @@
+//! Transactions split into sending the request and parsing the response, for firmware which receives bytes by DMA or
+//! UART interrupts rather than blocking reads.
+//!
+//! [`XyPsuSplit`] only encodes requests and checks responses, moving the bytes is left to the caller:
+//!
+//! ```ignore
+//! let mut psu: XyPsuSplit = XyPsuSplit::new(0x01);
+//! uart.start_dma_tx(psu.start_read(XyRegister::VOut)?);
+//! // Later, once the response has arrived, e.g. on an idle line interrupt.
+//! let raw_voltage = psu.finish_read(&rx_bytes)?[0];
+//! ```
+
+use core::convert::Infallible;
+
+use crate::{
+    error::{Error, Operation, Result},
+    frame::Frame,
+};
+
+/// Driver which leaves moving the bytes of each transaction to the caller.
+///
+/// Only one transaction is in progress at a time: starting another abandons the one in progress.
+pub struct XyPsuSplit<const L: usize = 128> {
+    /// Default for PSU is 0x01.
+    unit_id: u8,
+    /// Request awaiting its response.
+    pending: Option<Frame<L>>,
+}
+
+impl<const L: usize> XyPsuSplit<L> {
+    /// Create a new XyPsuSplit for the PSU with the given unit ID.
+    pub fn new(unit_id: u8) -> Self {
+        Self {
+            unit_id,
+            pending: None,
+        }
+    }
+
+    /// Return the Modbus unit ID used to talk to this PSU.
+    pub fn unit_id(&self) -> u8 {
+        self.unit_id
+    }
+
+    /// Start reading a single register, returning the request frame to send.
+    pub fn start_read(&mut self, register: impl Into<u16>) -> Result<&[u8], Infallible> {
+        self.start_read_bulk(register.into(), 1)
+    }
+
+    /// Start reading `count` sequential registers from `start_register`, returning the request frame to send.
+    pub fn start_read_bulk(
+        &mut self,
+        start_register: u16,
+        count: u16,
+    ) -> Result<&[u8], Infallible> {
+        let frame = Frame::read(self.unit_id, start_register, count)?;
+        Ok(&self.pending.insert(frame).bytes)
+    }
+
+    /// Start writing `data` to a single register, returning the request frame to send.
+    pub fn start_write(
+        &mut self,
+        register: impl Into<u16>,
+        data: impl Into<u16>,
+    ) -> Result<&[u8], Infallible> {
+        let frame = Frame::write_single(self.unit_id, register.into(), data.into())?;
+        Ok(&self.pending.insert(frame).bytes)
+    }
+
+    /// Return the length of the response to the transaction in progress, once enough of it has been received to tell,
+    /// e.g. to set up the rest of a DMA transfer.
+    pub fn response_len(&self, received: &[u8]) -> Option<usize> {
+        self.pending.as_ref()?.response_len(received)
+    }
+
+    /// Finish the read in progress with the response `rx_bytes`, returning the register values.
+    ///
+    /// Returns `InvalidResponse` if no read is in progress.
+    pub fn finish_read(&mut self, rx_bytes: &[u8]) -> Result<heapless::Vec<u16, 64>, Infallible> {
+        self.finish(Operation::Read, rx_bytes)
+    }
+
+    /// Finish the write in progress with the response `rx_bytes`.
+    ///
+    /// Returns `InvalidResponse` if no write is in progress.
+    pub fn finish_write(&mut self, rx_bytes: &[u8]) -> Result<(), Infallible> {
+        self.finish(Operation::Write, rx_bytes)?;
+        Ok(())
+    }
+
+    fn finish(
+        &mut self,
+        operation: Operation,
+        rx_bytes: &[u8],
+    ) -> Result<heapless::Vec<u16, 64>, Infallible> {
+        let frame = self
+            .pending
+            .take_if(|frame| frame.context.operation == operation)
+            .ok_or(Error::InvalidResponse)?;
+        frame
+            .parse(rx_bytes)
+            .map_err(|e| e.in_transaction(frame.context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::TransactionFailure, mock_serial::read_response, register::XyRegister};
+
+    #[test]
+    fn read_and_write() {
+        let mut psu: XyPsuSplit = XyPsuSplit::new(0x01);
+        assert_eq!(
+            psu.start_read(0x0000_u16).unwrap(),
+            [0x01, 0x03, 0x00, 0x00, 0x00, 0x01, 0x84, 0x0A]
+        );
+        let response = read_response(0x01, &[1200]);
+        assert_eq!(psu.response_len(&response[..3]), Some(response.len()));
+        assert_eq!(psu.finish_read(&response).unwrap(), [1200]);
+        assert!(matches!(
+            psu.finish_read(&response),
+            Err(Error::InvalidResponse)
+        ));
+
+        let request: heapless::Vec<u8, 16> =
+            heapless::Vec::from_slice(psu.start_write(XyRegister::OnOff, 1_u16).unwrap()).unwrap();
+        assert!(matches!(
+            psu.finish_write(&request[..7]),
+            Err(Error::Transaction {
+                failure: TransactionFailure::InvalidResponse,
+                ..
+            })
+        ));
+        psu.start_write(XyRegister::OnOff, 1_u16).unwrap();
+        psu.finish_write(&request).unwrap();
+    }
+}
@@ -0,0 +1,439 @@
+//! Snapshot of everything needed to show the state of a PSU, read in a single transaction.
+//!
+//! [`XyPsu::read_status`] suits a compact display line, while [`XyPsu::poll`] also returns the counters and both
+//! temperatures, e.g. for a dashboard. [`XyPsu::get_status`] reads just the output, mode, lock and protections, and
+//! [`XyPsu::read_accumulators`] just the counters.
+
+use crate::{
+    error::{Error, Result},
+    psu::XyPsu,
+    register::{ControlMode, ProtectionStatus, State, Temperature, TemperatureUnit, XyRegister},
+    transport::Transport,
+};
+
+/// Number of registers read by [`XyPsu::read_status`], starting at address 0.
+const STATUS_BLOCK_LEN: u16 = XyRegister::FC as u16 + 1;
+
+/// Counter registers read by [`XyPsu::read_accumulators`], in address order.
+const ACCUMULATOR_REGISTERS: [XyRegister; 7] = [
+    XyRegister::AhLow,
+    XyRegister::AhHigh,
+    XyRegister::WhLow,
+    XyRegister::WhHigh,
+    XyRegister::OutH,
+    XyRegister::OutM,
+    XyRegister::OutS,
+];
+
+/// Snapshot of the settings and measurements of a PSU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PsuStatus {
+    /// Output voltage setting in millivolts.
+    pub voltage_set_mv: u32,
+    /// Output current limit in milliamps.
+    pub current_limit_ma: u32,
+    /// Measured output voltage in millivolts.
+    pub voltage_mv: u32,
+    /// Measured output current in milliamps.
+    pub current_ma: u32,
+    /// Measured output power in milliwatts.
+    pub power_mw: u32,
+    /// Measured input voltage in millivolts.
+    pub input_voltage_mv: u32,
+    /// Active control mode.
+    pub control_mode: ControlMode,
+    /// Whether the output is on.
+    pub output: State,
+    /// Whether the keys are locked.
+    pub lock: State,
+    /// Triggered protections.
+    pub protection: ProtectionStatus,
+    /// Measured internal temperature.
+    pub temperature_internal: Temperature,
+}
+
+/// Live measurements, see [`XyPsu::poll`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Measurements {
+    /// Output voltage in millivolts.
+    pub voltage_mv: u32,
+    /// Output current in milliamps.
+    pub current_ma: u32,
+    /// Output power in milliwatts.
+    pub power_mw: u32,
+    /// Input voltage in millivolts.
+    pub input_voltage_mv: u32,
+    /// Output capacity in milliamp-hours.
+    pub capacity_mah: u32,
+    /// Output energy in milliwatt-hours.
+    pub energy_mwh: u32,
+    /// Time the output has been enabled, in seconds.
+    pub output_time_secs: u32,
+    /// Internal temperature.
+    pub temperature_internal: Temperature,
+    /// External temperature sensor.
+    pub temperature_external: Temperature,
+}
+
+/// State of the device, see [`XyPsu::poll`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DeviceStatus {
+    /// Whether the output is on.
+    pub output: State,
+    /// Active control mode.
+    pub control_mode: ControlMode,
+    /// Whether the keys are locked.
+    pub lock: State,
+    /// Triggered protections.
+    pub protection: ProtectionStatus,
+}
+
+/// Registers read by [`XyPsu::get_status`], in address order.
+const DEVICE_STATUS_REGISTERS: [XyRegister; 4] = [
+    XyRegister::Lock,
+    XyRegister::Protect,
+    XyRegister::CvCc,
+    XyRegister::OnOff,
+];
+
+/// Output counters, see [`XyPsu::read_accumulators`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Accumulators {
+    /// Output capacity in milliamp-hours.
+    pub capacity_mah: u32,
+    /// Output energy in milliwatt-hours.
+    pub energy_mwh: u32,
+    /// Time the output has been enabled, in seconds.
+    pub output_time_secs: u32,
+}
+
+impl Accumulators {
+    /// Return the time the output has been enabled.
+    pub fn output_duration(&self) -> core::time::Duration {
+        core::time::Duration::from_secs(self.output_time_secs as u64)
+    }
+}
+
+/// Formats a value in thousandths with 3 decimal places, e.g. millivolts `12001` as `12.001`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Milli(pub u32);
+
+impl core::fmt::Display for Milli {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}.{:03}", self.0 / 1000, self.0 % 1000)
+    }
+}
+
+/// Write a one line summary of `status`, e.g. `12.001V 0.534A 6.41W CV OUT:ON T:41C`.
+///
+/// Any triggered protections are appended, e.g. ` PROT:OVP,OCP`. Intended for serial consoles and RTT logs.
+pub fn format_status(status: &PsuStatus, out: &mut impl core::fmt::Write) -> core::fmt::Result {
+    let mode = match status.control_mode {
+        ControlMode::Cv => "CV",
+        ControlMode::Cc => "CC",
+    };
+    let output = match status.output {
+        State::On => "ON",
+        State::Off => "OFF",
+    };
+    let (temperature, unit) = match status.temperature_internal {
+        Temperature::Celsius(t) => (t, 'C'),
+        Temperature::Fahrenheit(t) => (t, 'F'),
+    };
+    write!(
+        out,
+        "{}V {}A {}.{:02}W {mode} OUT:{output} T:{temperature}{unit}",
+        Milli(status.voltage_mv),
+        Milli(status.current_ma),
+        status.power_mw / 1000,
+        status.power_mw % 1000 / 10,
+    )?;
+
+    for (i, name) in status.protection.triggered().enumerate() {
+        out.write_str(if i == 0 { " PROT:" } else { "," })?;
+        out.write_str(name)?;
+    }
+    Ok(())
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Read a [`PsuStatus`] snapshot.
+    ///
+    /// All values come from one bulk read, so they are consistent with each other. Registers which have been moved
+    /// outside of the block by the register map are read individually.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown.
+    pub fn read_status(&mut self) -> Result<PsuStatus, S::Error> {
+        let scaling = self.ensure_scaling()?;
+        let block = self.read_modbus_bulk(0, STATUS_BLOCK_LEN)?;
+        let mut get = |register| self.status_block_value(&block, register);
+
+        let unit =
+            TemperatureUnit::try_from(get(XyRegister::FC)?).map_err(|_| Error::InvalidResponse)?;
+        Ok(PsuStatus {
+            voltage_set_mv: scaling.raw_to_voltage_mv(get(XyRegister::VSet)?),
+            current_limit_ma: scaling.raw_to_current_ma(get(XyRegister::ISet)?),
+            voltage_mv: scaling.raw_to_voltage_mv(get(XyRegister::VOut)?),
+            current_ma: scaling.raw_to_current_ma(get(XyRegister::IOut)?),
+            power_mw: scaling.raw_to_power_mw(get(XyRegister::Power)?),
+            input_voltage_mv: scaling.raw_to_voltage_mv(get(XyRegister::UIn)?),
+            control_mode: ControlMode::from(get(XyRegister::CvCc)?),
+            output: State::from(get(XyRegister::OnOff)? != 0),
+            lock: State::from(get(XyRegister::Lock)? != 0),
+            protection: ProtectionStatus::from_raw(get(XyRegister::Protect)?),
+            temperature_internal: Temperature::from_centi(get(XyRegister::TIn)?, unit),
+        })
+    }
+
+    /// Read all live measurements and the device status.
+    ///
+    /// Everything comes from one bulk read, instead of a call per value. Registers which have been moved outside of
+    /// the block by the register map are read individually.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown.
+    pub fn poll(&mut self) -> Result<(Measurements, DeviceStatus), S::Error> {
+        let scaling = self.ensure_scaling()?;
+        let block = self.read_modbus_bulk(0, STATUS_BLOCK_LEN)?;
+        let mut get = |register| self.status_block_value(&block, register);
+
+        let unit =
+            TemperatureUnit::try_from(get(XyRegister::FC)?).map_err(|_| Error::InvalidResponse)?;
+        let pair = |low: u16, high: u16| low as u32 + ((high as u32) << 16);
+        let measurements = Measurements {
+            voltage_mv: scaling.raw_to_voltage_mv(get(XyRegister::VOut)?),
+            current_ma: scaling.raw_to_current_ma(get(XyRegister::IOut)?),
+            power_mw: scaling.raw_to_power_mw(get(XyRegister::Power)?),
+            input_voltage_mv: scaling.raw_to_voltage_mv(get(XyRegister::UIn)?),
+            capacity_mah: scaling
+                .raw_to_capacity_mah(pair(get(XyRegister::AhLow)?, get(XyRegister::AhHigh)?)),
+            energy_mwh: scaling
+                .raw_to_energy_mwh(pair(get(XyRegister::WhLow)?, get(XyRegister::WhHigh)?)),
+            output_time_secs: get(XyRegister::OutH)? as u32 * 3600
+                + get(XyRegister::OutM)? as u32 * 60
+                + get(XyRegister::OutS)? as u32,
+            temperature_internal: Temperature::from_centi(get(XyRegister::TIn)?, unit),
+            temperature_external: Temperature::from_centi(get(XyRegister::TEx)?, unit),
+        };
+        let status = DeviceStatus {
+            output: State::from(get(XyRegister::OnOff)? != 0),
+            control_mode: ControlMode::from(get(XyRegister::CvCc)?),
+            lock: State::from(get(XyRegister::Lock)? != 0),
+            protection: ProtectionStatus::from_raw(get(XyRegister::Protect)?),
+        };
+        Ok((measurements, status))
+    }
+
+    /// Read the output state, control mode, key lock and protections in one transaction, e.g. for each refresh of a UI.
+    ///
+    /// Doesn't need the scaling factors, unlike [`Self::read_status`]. If the register map has moved the registers
+    /// apart, they are read individually instead.
+    pub fn get_status(&mut self) -> Result<DeviceStatus, S::Error> {
+        let mut registers = [0; DEVICE_STATUS_REGISTERS.len()];
+        match self
+            .register_map()
+            .contiguous_start(&DEVICE_STATUS_REGISTERS)
+        {
+            Some(start) => {
+                let values = self.read_modbus_bulk(start, DEVICE_STATUS_REGISTERS.len() as u16)?;
+                registers = values
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| Error::InvalidResponse)?;
+            }
+            None => {
+                for (value, register) in registers.iter_mut().zip(DEVICE_STATUS_REGISTERS) {
+                    *value = self.read_register(register)?;
+                }
+            }
+        }
+        let [lock, protect, cv_cc, on_off] = registers;
+        Ok(DeviceStatus {
+            output: State::from(on_off != 0),
+            control_mode: ControlMode::from(cv_cc),
+            lock: State::from(lock != 0),
+            protection: ProtectionStatus::from_raw(protect),
+        })
+    }
+
+    /// Read the capacity, energy and output time counters in one transaction.
+    ///
+    /// Unlike [`Self::read_capacity_mah`], [`Self::read_energy_mwh`] and [`Self::read_output_time_secs`], which take
+    /// seven reads between them, the high and low words of each counter are sampled together, so a counter can't
+    /// appear to jump when its low word wraps between reads. If the register map has moved the counters apart, they
+    /// are read individually instead.
+    ///
+    /// Requires known scaling factors for the PSU model. Returns `ScalingNotAvailable`
+    /// error if the model's scaling factors are unknown.
+    pub fn read_accumulators(&mut self) -> Result<Accumulators, S::Error> {
+        let scaling = self.ensure_scaling()?;
+        let mut registers = [0; ACCUMULATOR_REGISTERS.len()];
+        match self.register_map().contiguous_start(&ACCUMULATOR_REGISTERS) {
+            Some(start) => {
+                let values = self.read_modbus_bulk(start, ACCUMULATOR_REGISTERS.len() as u16)?;
+                registers = values
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| Error::InvalidResponse)?;
+            }
+            None => {
+                for (value, register) in registers.iter_mut().zip(ACCUMULATOR_REGISTERS) {
+                    *value = self.read_register(register)?;
+                }
+            }
+        }
+        let [ah_low, ah_high, wh_low, wh_high, hours, minutes, seconds] = registers.map(u32::from);
+        Ok(Accumulators {
+            capacity_mah: scaling.raw_to_capacity_mah(ah_low + (ah_high << 16)),
+            energy_mwh: scaling.raw_to_energy_mwh(wh_low + (wh_high << 16)),
+            output_time_secs: hours * 3600 + minutes * 60 + seconds,
+        })
+    }
+
+    /// Return a register's value from a block read from address 0, or read it if the register map moved it out.
+    fn status_block_value(&mut self, block: &[u16], register: XyRegister) -> Result<u16, S::Error> {
+        let address = self.register_map().address(register);
+        match block.get(address as usize) {
+            Some(value) if address < STATUS_BLOCK_LEN => Ok(*value),
+            _ => self.read_register(register),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock_serial::MockSerial, scaling::ScalingFactors};
+
+    #[test]
+    fn read_status_from_block() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 10, 1, 10).unwrap());
+
+        let mut block = [0u16; STATUS_BLOCK_LEN as usize];
+        block[XyRegister::VSet as usize] = 1200;
+        block[XyRegister::ISet as usize] = 1000;
+        block[XyRegister::VOut as usize] = 1199;
+        block[XyRegister::IOut as usize] = 534;
+        block[XyRegister::Power as usize] = 641;
+        block[XyRegister::UIn as usize] = 2400;
+        block[XyRegister::TIn as usize] = 412;
+        block[XyRegister::CvCc as usize] = 1;
+        block[XyRegister::OnOff as usize] = 1;
+        psu.interface_mut().queue_read_response(0x01, &block);
+
+        let status = psu.read_status().unwrap();
+        assert_eq!(status.voltage_set_mv, 12000);
+        assert_eq!(status.voltage_mv, 11990);
+        assert_eq!(status.current_ma, 534);
+        assert_eq!(status.power_mw, 6410);
+        assert_eq!(status.control_mode, ControlMode::Cc);
+        assert_eq!(status.output, State::On);
+        assert_eq!(status.lock, State::Off);
+        assert_eq!(status.temperature_internal, Temperature::Celsius(41));
+        assert!(!status.protection.any());
+    }
+
+    #[test]
+    fn poll_from_block() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 10, 1, 10).unwrap());
+
+        let mut block = [0u16; STATUS_BLOCK_LEN as usize];
+        block[XyRegister::VOut as usize] = 1199;
+        block[XyRegister::IOut as usize] = 534;
+        block[XyRegister::AhLow as usize] = 2;
+        block[XyRegister::AhHigh as usize] = 1;
+        block[XyRegister::OutH as usize] = 1;
+        block[XyRegister::OutM as usize] = 2;
+        block[XyRegister::OutS as usize] = 3;
+        block[XyRegister::TEx as usize] = 220;
+        block[XyRegister::Protect as usize] = 0b10;
+        block[XyRegister::OnOff as usize] = 1;
+        psu.interface_mut().queue_read_response(0x01, &block);
+
+        let (measurements, status) = psu.poll().unwrap();
+        assert_eq!(measurements.voltage_mv, 11990);
+        assert_eq!(measurements.current_ma, 534);
+        assert_eq!(measurements.capacity_mah, 0x1_0002);
+        assert_eq!(measurements.output_time_secs, 3723);
+        assert_eq!(measurements.temperature_external, Temperature::Celsius(22));
+        assert_eq!(status.output, State::On);
+        assert_eq!(status.control_mode, ControlMode::Cv);
+        assert!(status.protection.over_current());
+    }
+
+    #[test]
+    fn device_status_in_one_read() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.interface_mut()
+            .queue_read_response(0x01, &[1, 0b10, 1, 1]);
+
+        let status = psu.get_status().unwrap();
+        assert_eq!(status.output, State::On);
+        assert_eq!(status.control_mode, ControlMode::Cc);
+        assert_eq!(status.lock, State::On);
+        assert!(status.protection.over_current());
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([0x00, 0x0F, 0x00, 0x04].as_slice())
+        );
+    }
+
+    #[test]
+    fn accumulators_in_one_read() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 10, 100, 10, 10).unwrap());
+        psu.interface_mut()
+            .queue_read_response(0x01, &[0xFFFF, 1, 500, 0, 2, 30, 15]);
+
+        let accumulators = psu.read_accumulators().unwrap();
+        assert_eq!(
+            accumulators,
+            Accumulators {
+                capacity_mah: 0x1FFFF * 10,
+                energy_mwh: 5000,
+                output_time_secs: 2 * 3600 + 30 * 60 + 15,
+            }
+        );
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([0x00, 0x06, 0x00, 0x07].as_slice())
+        );
+    }
+
+    #[test]
+    fn format_status_line() {
+        let mut status = PsuStatus {
+            voltage_set_mv: 12000,
+            current_limit_ma: 1000,
+            voltage_mv: 12001,
+            current_ma: 534,
+            power_mw: 6410,
+            input_voltage_mv: 24000,
+            control_mode: ControlMode::Cv,
+            output: State::On,
+            lock: State::Off,
+            protection: ProtectionStatus::default(),
+            temperature_internal: Temperature::Celsius(41),
+        };
+        let mut line: heapless::String<64> = heapless::String::new();
+        format_status(&status, &mut line).unwrap();
+        assert_eq!(line, "12.001V 0.534A 6.41W CV OUT:ON T:41C");
+
+        status.protection = ProtectionStatus::from_raw(0b11);
+        status.output = State::Off;
+        line.clear();
+        format_status(&status, &mut line).unwrap();
+        assert_eq!(line, "12.001V 0.534A 6.41W CV OUT:OFF T:41C PROT:OVP,OCP");
+    }
+}
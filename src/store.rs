@@ -0,0 +1,486 @@
+//! File-based storage of per-device calibration. Only available with `std`.
+//!
+//! Scaling factors and temperature sensor corrections which have been determined by hand can be saved to a
+//! [`CalibrationStore`] and re-applied the next time the same device is opened with [`CalibrationStore::open_device`].
+//! A device opened with [`XyPsu::new`] instead gets its calibration with [`CalibrationStore::restore`].
+//!
+//! Devices are identified by a [`DeviceKey`], made up of the raw product model, firmware version and either the
+//! Modbus slave address, a label chosen by the user or the tag register of a [`Fingerprint`].
+//!
+//! The file is plain text with one device per line, e.g:
+//!
+//! ```text
+//! model=25856 firmware=71 address=1 scaling=10,10,1000,10,100 tin_offset=0 tex_offset=0
+//! model=22869 firmware=70 label=bench-left scaling=10,1,100,1,10
+//! model=25858 firmware=71 tag=42 scaling=10,10,1000,10,100
+//! ```
+
+use std::{collections::BTreeMap, fmt::Write as _, io, path::PathBuf, string::String};
+
+use thiserror::Error;
+
+use crate::{
+    error::Result, fingerprint::Fingerprint, psu::XyPsu, scaling::ScalingFactors,
+    transport::Transport,
+};
+
+/// Error from a [`CalibrationStore`] operation which talks to a device.
+#[derive(Error, Debug)]
+pub enum StoreError<I: embedded_io::Error> {
+    #[error(transparent)]
+    Device(#[from] crate::error::Error<I>),
+    #[error("Calibration store error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// How a device is told apart from other devices of the same model and firmware.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DeviceIdentity {
+    /// The Modbus slave address the device is configured with.
+    SlaveAddress(u8),
+    /// A user chosen label. Must not contain whitespace.
+    Label(String),
+    /// The value of the tag register, see [`Fingerprint::tag`].
+    Tag(u16),
+}
+
+/// Key used to look up the calibration of a device.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DeviceKey {
+    /// Raw value of the "MODEL" register.
+    pub model_raw: u16,
+    /// Raw value of the firmware "VERSION" register.
+    pub firmware_version: u16,
+    /// Slave address, user label or tag.
+    pub identity: DeviceIdentity,
+}
+
+impl DeviceKey {
+    /// Create a key from a [`Fingerprint`], identifying the device by its tag.
+    ///
+    /// Returns `None` if the fingerprint was read without a tag register.
+    pub fn from_fingerprint(fingerprint: &Fingerprint) -> Option<Self> {
+        Some(Self {
+            model_raw: fingerprint.model_raw,
+            firmware_version: fingerprint.firmware_version,
+            identity: DeviceIdentity::Tag(fingerprint.tag?),
+        })
+    }
+}
+
+/// Calibration values saved for a device.
+///
+/// Fields left as `None` are not applied when restoring.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Calibration {
+    /// Manually determined scaling factors.
+    pub scaling: Option<ScalingFactors>,
+    /// Raw internal temperature correction value. See [`XyPsu::set_temperature_offset_internal_raw`].
+    pub temperature_offset_internal: Option<u16>,
+    /// Raw external temperature correction value. See [`XyPsu::set_temperature_offset_external_raw`].
+    pub temperature_offset_external: Option<u16>,
+}
+
+/// A collection of [`Calibration`]s backed by a file.
+#[derive(Debug)]
+pub struct CalibrationStore {
+    path: PathBuf,
+    entries: BTreeMap<DeviceKey, Calibration>,
+}
+
+impl CalibrationStore {
+    /// Open the store at `path`, loading any existing entries.
+    ///
+    /// A missing file is treated as an empty store and will be created on [`Self::save`].
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(contents) => parse(&contents)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self { path, entries })
+    }
+
+    /// Write all entries back to the file.
+    pub fn save(&self) -> io::Result<()> {
+        std::fs::write(&self.path, serialise(&self.entries))
+    }
+
+    /// Get the calibration stored for a device.
+    pub fn get(&self, key: &DeviceKey) -> Option<&Calibration> {
+        self.entries.get(key)
+    }
+
+    /// Store the calibration for a device, replacing any previous entry.
+    ///
+    /// Labels containing whitespace are rejected as they can't be stored in the file.
+    pub fn insert(&mut self, key: DeviceKey, calibration: Calibration) -> io::Result<()> {
+        if let DeviceIdentity::Label(label) = &key.identity
+            && (label.is_empty() || label.contains(char::is_whitespace))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "labels must be non-empty and not contain whitespace",
+            ));
+        }
+        self.entries.insert(key, calibration);
+        Ok(())
+    }
+
+    /// Remove the calibration stored for a device.
+    pub fn remove(&mut self, key: &DeviceKey) -> Option<Calibration> {
+        self.entries.remove(key)
+    }
+
+    /// Iterate over all stored devices.
+    pub fn iter(&self) -> impl Iterator<Item = (&DeviceKey, &Calibration)> {
+        self.entries.iter()
+    }
+
+    /// Create a driver for the device at `unit_id`, then [`XyPsu::identify`] it and [`Self::restore`] its calibration.
+    pub fn open_device<S: Transport, const L: usize>(
+        &self,
+        interface: S,
+        unit_id: u8,
+        label: Option<&str>,
+    ) -> Result<XyPsu<S, L>, S::Error> {
+        let mut psu = XyPsu::new(interface, unit_id);
+        psu.identify()?;
+        self.restore(&mut psu, label)?;
+        Ok(psu)
+    }
+
+    /// Look up the connected device and apply its stored calibration, if there is one.
+    ///
+    /// Pass a `label` if the device was stored using [`DeviceIdentity::Label`], otherwise the slave address is used.
+    ///
+    /// Returns the calibration which was applied.
+    pub fn restore<S: Transport, const L: usize>(
+        &self,
+        psu: &mut XyPsu<S, L>,
+        label: Option<&str>,
+    ) -> Result<Option<Calibration>, S::Error> {
+        let key = device_key(psu, label)?;
+        self.apply(psu, &key)
+    }
+
+    /// Look up the connected device by its [`Fingerprint`] tag and apply its stored calibration, if there is one.
+    ///
+    /// See [`XyPsu::fingerprint`] for how `tag_register` is used.
+    pub fn restore_tagged<S: Transport, const L: usize>(
+        &self,
+        psu: &mut XyPsu<S, L>,
+        tag_register: u16,
+    ) -> Result<Option<Calibration>, S::Error> {
+        let fingerprint = psu.fingerprint(Some(tag_register))?;
+        match DeviceKey::from_fingerprint(&fingerprint) {
+            Some(key) => self.apply(psu, &key),
+            None => Ok(None),
+        }
+    }
+
+    fn apply<S: Transport, const L: usize>(
+        &self,
+        psu: &mut XyPsu<S, L>,
+        key: &DeviceKey,
+    ) -> Result<Option<Calibration>, S::Error> {
+        let Some(calibration) = self.entries.get(key).copied() else {
+            return Ok(None);
+        };
+
+        if let Some(scaling) = calibration.scaling {
+            psu.set_scaling_factors(scaling);
+        }
+        if let Some(offset) = calibration.temperature_offset_internal {
+            psu.set_temperature_offset_internal_raw(offset)?;
+        }
+        if let Some(offset) = calibration.temperature_offset_external {
+            psu.set_temperature_offset_external_raw(offset)?;
+        }
+        Ok(Some(calibration))
+    }
+
+    /// Record the scaling factors and temperature corrections currently in use by the device.
+    ///
+    /// `key` is usually created with [`device_key`] or [`DeviceKey::from_fingerprint`].
+    ///
+    /// The store is only updated in memory, call [`Self::save`] to persist it. Returns [`StoreError::Io`] if the key
+    /// is rejected by [`Self::insert`].
+    pub fn capture<S: Transport, const L: usize>(
+        &mut self,
+        psu: &mut XyPsu<S, L>,
+        key: DeviceKey,
+    ) -> core::result::Result<(), StoreError<S::Error>> {
+        let calibration = Calibration {
+            scaling: psu.scaling_factors(),
+            temperature_offset_internal: Some(psu.get_temperature_offset_internal_raw()?),
+            temperature_offset_external: Some(psu.get_temperature_offset_external_raw()?),
+        };
+        self.insert(key, calibration)?;
+        Ok(())
+    }
+}
+
+/// Read the identifying registers of a device and build its [`DeviceKey`].
+pub fn device_key<S: Transport, const L: usize>(
+    psu: &mut XyPsu<S, L>,
+    label: Option<&str>,
+) -> Result<DeviceKey, S::Error> {
+    let model_raw = psu.get_product_model_raw()?;
+    let firmware_version = psu.get_firmware_version()?;
+    let identity = match label {
+        Some(label) => DeviceIdentity::Label(label.into()),
+        None => DeviceIdentity::SlaveAddress(psu.get_slave_address()?),
+    };
+    Ok(DeviceKey {
+        model_raw,
+        firmware_version,
+        identity,
+    })
+}
+
+fn serialise(entries: &BTreeMap<DeviceKey, Calibration>) -> String {
+    let mut out = String::new();
+    for (key, calibration) in entries {
+        // Writing to a String can't fail.
+        let _ = write!(
+            out,
+            "model={} firmware={}",
+            key.model_raw, key.firmware_version
+        );
+        let _ = match &key.identity {
+            DeviceIdentity::SlaveAddress(address) => write!(out, " address={address}"),
+            DeviceIdentity::Label(label) => write!(out, " label={label}"),
+            DeviceIdentity::Tag(tag) => write!(out, " tag={tag}"),
+        };
+        if let Some(s) = calibration.scaling {
+            let _ = write!(
+                out,
+                " scaling={},{},{},{},{}",
+                s.voltage_divisor,
+                s.current_divisor,
+                s.power_divisor,
+                s.capacity_divisor,
+                s.energy_divisor
+            );
+        }
+        if let Some(offset) = calibration.temperature_offset_internal {
+            let _ = write!(out, " tin_offset={offset}");
+        }
+        if let Some(offset) = calibration.temperature_offset_external {
+            let _ = write!(out, " tex_offset={offset}");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn parse(contents: &str) -> io::Result<BTreeMap<DeviceKey, Calibration>> {
+    fn invalid(line: usize, what: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("calibration store line {line}: {what}"),
+        )
+    }
+
+    let mut entries = BTreeMap::new();
+    for (line_no, line) in contents.lines().enumerate() {
+        let line_no = line_no + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut model_raw = None;
+        let mut firmware_version = None;
+        let mut identity = None;
+        let mut calibration = Calibration::default();
+
+        for field in line.split_whitespace() {
+            let (name, value) = field
+                .split_once('=')
+                .ok_or_else(|| invalid(line_no, "expected name=value"))?;
+            let bad_value = || invalid(line_no, name);
+            match name {
+                "model" => model_raw = Some(value.parse().map_err(|_| bad_value())?),
+                "firmware" => firmware_version = Some(value.parse().map_err(|_| bad_value())?),
+                "address" => {
+                    identity = Some(DeviceIdentity::SlaveAddress(
+                        value.parse().map_err(|_| bad_value())?,
+                    ))
+                }
+                "label" => identity = Some(DeviceIdentity::Label(value.into())),
+                "tag" => {
+                    identity = Some(DeviceIdentity::Tag(value.parse().map_err(|_| bad_value())?))
+                }
+                "scaling" => {
+                    let mut divisors = [0u32; 5];
+                    let mut values = value.split(',');
+                    for divisor in divisors.iter_mut() {
+                        *divisor = values
+                            .next()
+                            .and_then(|v| v.parse().ok())
+                            .ok_or_else(bad_value)?;
+                    }
+                    if values.next().is_some() {
+                        return Err(bad_value());
+                    }
+                    let [v, i, p, c, e] = divisors;
+                    calibration.scaling =
+                        Some(ScalingFactors::new(v, i, p, c, e).map_err(|_| bad_value())?);
+                }
+                "tin_offset" => {
+                    calibration.temperature_offset_internal =
+                        Some(value.parse().map_err(|_| bad_value())?)
+                }
+                "tex_offset" => {
+                    calibration.temperature_offset_external =
+                        Some(value.parse().map_err(|_| bad_value())?)
+                }
+                _ => return Err(invalid(line_no, "unknown field")),
+            }
+        }
+
+        let key = DeviceKey {
+            model_raw: model_raw.ok_or_else(|| invalid(line_no, "missing model"))?,
+            firmware_version: firmware_version
+                .ok_or_else(|| invalid(line_no, "missing firmware"))?,
+            identity: identity.ok_or_else(|| invalid(line_no, "missing address, label or tag"))?,
+        };
+        entries.insert(key, calibration);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_round_trip() {
+        let mut entries = BTreeMap::new();
+        entries.insert(
+            DeviceKey {
+                model_raw: 25856,
+                firmware_version: 71,
+                identity: DeviceIdentity::SlaveAddress(1),
+            },
+            Calibration {
+                scaling: Some(ScalingFactors::new(10, 10, 1000, 10, 100).unwrap()),
+                temperature_offset_internal: Some(3),
+                temperature_offset_external: None,
+            },
+        );
+        entries.insert(
+            DeviceKey {
+                model_raw: 22869,
+                firmware_version: 70,
+                identity: DeviceIdentity::Label("bench-left".into()),
+            },
+            Calibration::default(),
+        );
+        entries.insert(
+            DeviceKey::from_fingerprint(&Fingerprint {
+                model_raw: 25858,
+                firmware_version: 71,
+                tag: Some(42),
+            })
+            .unwrap(),
+            Calibration {
+                temperature_offset_external: Some(5),
+                ..Default::default()
+            },
+        );
+
+        let text = serialise(&entries);
+        assert_eq!(parse(&text).unwrap(), entries);
+    }
+
+    #[test]
+    fn store_rejects_bad_lines() {
+        assert!(parse("model=1 firmware=2").is_err());
+        assert!(parse("model=1 firmware=2 address=1 scaling=1,2,3").is_err());
+        assert!(parse("model=1 firmware=2 address=1 scaling=10,0,100,1,10").is_err());
+        assert!(parse("model=1 firmware=2 address=1 colour=red").is_err());
+        assert!(parse("# comment only\n\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn store_save_and_open() {
+        let path = std::env::temp_dir().join(format!(
+            "sinilink-xy-psu-store-test-{}.txt",
+            std::process::id()
+        ));
+        let key = DeviceKey {
+            model_raw: 25858,
+            firmware_version: 71,
+            identity: DeviceIdentity::Label("rack-3".into()),
+        };
+        let calibration = Calibration {
+            scaling: Some(ScalingFactors::new(10, 10, 1000, 10, 100).unwrap()),
+            ..Default::default()
+        };
+
+        let mut store = CalibrationStore::open(&path).unwrap();
+        store.insert(key.clone(), calibration).unwrap();
+        store.save().unwrap();
+
+        let reopened = CalibrationStore::open(&path).unwrap();
+        assert_eq!(reopened.get(&key), Some(&calibration));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(
+            store
+                .insert(
+                    DeviceKey {
+                        identity: DeviceIdentity::Label("has space".into()),
+                        ..key
+                    },
+                    calibration
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn open_device_restores_calibration() {
+        use crate::{mock_serial::MockSerial, register::ProductModel};
+
+        let key = DeviceKey {
+            model_raw: ProductModel::XY6020L.raw(),
+            firmware_version: 71,
+            identity: DeviceIdentity::Label("bench".into()),
+        };
+        let calibration = Calibration {
+            scaling: Some(ScalingFactors::new(100, 100, 100, 100, 100).unwrap()),
+            ..Default::default()
+        };
+        let mut store = CalibrationStore {
+            path: PathBuf::new(),
+            entries: BTreeMap::new(),
+        };
+        store.insert(key.clone(), calibration).unwrap();
+
+        let mut serial = MockSerial::new();
+        serial.queue_read_response(0x01, &[key.model_raw]);
+        serial.queue_read_response(0x01, &[key.firmware_version]);
+        serial.queue_read_response(0x01, &[key.model_raw]);
+        serial.queue_read_response(0x01, &[key.firmware_version]);
+        let mut psu: XyPsu<MockSerial, 128> =
+            store.open_device(serial, 0x01, Some("bench")).unwrap();
+        assert_eq!(psu.scaling_factors(), calibration.scaling);
+
+        // Capturing under a key which can't be stored is an I/O error, not a device one.
+        psu.interface_mut().queue_read_response(0x01, &[0]);
+        psu.interface_mut().queue_read_response(0x01, &[0]);
+        let bad_key = DeviceKey {
+            identity: DeviceIdentity::Label("has space".into()),
+            ..key
+        };
+        assert!(matches!(
+            store.capture(&mut psu, bad_key),
+            Err(StoreError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,100 @@
+//! Compact JSON encoding of [`Measurements`], [`DeviceStatus`] and [`PsuStatus`], for piping to dashboards and web UIs.
+//!
+//! Only available with the `telemetry` feature. Values keep the units of their fields, e.g.:
+//!
+//! ```text
+//! {"voltage_mv":12001,"current_ma":534,"power_mw":6408,...,"temperature_internal":{"Celsius":41}}
+//! {"output":"on","control_mode":"CV","lock":"off","protection":["OVP"]}
+//! ```
+//!
+//! [`to_slice`] encodes into a buffer with serde-json-core, so works without std. With the `telemetry-std` feature,
+//! [`to_string`] and [`to_writer`] encode with serde_json.
+//!
+//! [`Measurements`]: crate::status::Measurements
+//! [`DeviceStatus`]: crate::status::DeviceStatus
+//! [`PsuStatus`]: crate::status::PsuStatus
+
+use serde::Serialize;
+
+pub use serde_json_core::ser::Error;
+
+/// Encode `value` into `buf`, returning the number of bytes written.
+///
+/// Returns [`Error::BufferFull`] if `buf` is too small. 256 bytes fits any of the status structs.
+pub fn to_slice<T: Serialize + ?Sized>(value: &T, buf: &mut [u8]) -> Result<usize, Error> {
+    serde_json_core::to_slice(value, buf)
+}
+
+/// Encode `value` as a string.
+#[cfg(all(feature = "telemetry-std", not(feature = "no_std")))]
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+    serde_json::to_string(value)
+}
+
+/// Encode `value` to `writer`, e.g. a socket or stdout. Add a newline after each value to stream them as JSON lines.
+#[cfg(all(feature = "telemetry-std", not(feature = "no_std")))]
+pub fn to_writer<W: std::io::Write, T: Serialize + ?Sized>(
+    writer: W,
+    value: &T,
+) -> serde_json::Result<()> {
+    serde_json::to_writer(writer, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        register::{ControlMode, ProtectionStatus, State, Temperature},
+        status::{DeviceStatus, Measurements},
+    };
+
+    const MEASUREMENTS: Measurements = Measurements {
+        voltage_mv: 12001,
+        current_ma: 534,
+        power_mw: 6408,
+        input_voltage_mv: 24100,
+        capacity_mah: 12,
+        energy_mwh: 150,
+        output_time_secs: 61,
+        temperature_internal: Temperature::Celsius(41),
+        temperature_external: Temperature::Fahrenheit(70),
+    };
+    const MEASUREMENTS_JSON: &str = concat!(
+        r#"{"voltage_mv":12001,"current_ma":534,"power_mw":6408,"input_voltage_mv":24100,"capacity_mah":12,"#,
+        r#""energy_mwh":150,"output_time_secs":61,"temperature_internal":{"Celsius":41},"#,
+        r#""temperature_external":{"Fahrenheit":70}}"#
+    );
+
+    #[test]
+    fn encode_to_slice() {
+        let mut buf = [0; 256];
+        let len = to_slice(&MEASUREMENTS, &mut buf).unwrap();
+        assert_eq!(buf.get(..len), Some(MEASUREMENTS_JSON.as_bytes()));
+
+        let status = DeviceStatus {
+            output: State::On,
+            control_mode: ControlMode::Cv,
+            lock: State::Off,
+            protection: ProtectionStatus::from_raw(0b11),
+        };
+        let len = to_slice(&status, &mut buf).unwrap();
+        assert_eq!(
+            buf.get(..len),
+            Some(
+                br#"{"output":"on","control_mode":"CV","lock":"off","protection":["OVP","OCP"]}"#
+                    .as_slice()
+            )
+        );
+
+        assert_eq!(
+            to_slice(&MEASUREMENTS, &mut [0; 16]),
+            Err(Error::BufferFull)
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "telemetry-std", not(feature = "no_std")))]
+    fn encode_to_string() {
+        assert_eq!(to_string(&MEASUREMENTS).unwrap(), MEASUREMENTS_JSON);
+    }
+}
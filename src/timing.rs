@@ -0,0 +1,216 @@
+//! Modbus RTU inter-frame timing, for interfaces whose reads don't time out on their own.
+//!
+//! Modbus RTU frames are separated by at least 3.5 characters of silence. [`RtuTiming`] wraps an interface and uses an
+//! [`embedded_hal::delay::DelayNs`] to keep that silence before each request, and to end a response once the line has
+//! been silent that long.
+//!
+//! ```ignore
+//! let interface = RtuTiming::new(uart, delay, BaudRate::_115200);
+//! let mut psu: XyPsu<_> = XyPsu::new(interface, 0x01);
+//! ```
+//!
+//! Only available with the `embedded-hal` feature.
+
+use embedded_hal::delay::DelayNs;
+
+use crate::register::BaudRate;
+
+/// Silence between frames above 19200 baud, which the Modbus RTU spec fixes rather than scaling with the baud rate.
+pub const FIXED_FRAME_SILENCE_NS: u32 = 1_750_000;
+
+/// Return the silence between frames at `bits_per_second`: 3.5 characters of 11 bits.
+pub const fn frame_silence_ns(bits_per_second: u32) -> u32 {
+    if bits_per_second > 19200 {
+        return FIXED_FRAME_SILENCE_NS;
+    }
+    let bits_per_second = if bits_per_second == 0 {
+        1
+    } else {
+        bits_per_second
+    };
+    let ns = 38_500_000_000 / bits_per_second as u64;
+    if ns > u32::MAX as u64 {
+        u32::MAX
+    } else {
+        ns as u32
+    }
+}
+
+/// Error of an interface wrapped by [`RtuTiming`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TimingError<E> {
+    /// Error from the wrapped interface.
+    Interface(E),
+    /// The line was silent for long enough to end the frame being received.
+    Silence,
+}
+
+impl<E: embedded_io::Error> embedded_io::Error for TimingError<E> {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            TimingError::Interface(e) => e.kind(),
+            TimingError::Silence => embedded_io::ErrorKind::TimedOut,
+        }
+    }
+}
+
+impl<E: core::fmt::Debug> core::fmt::Display for TimingError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TimingError::Interface(e) => write!(f, "interface error: {e:?}"),
+            TimingError::Silence => write!(f, "end of frame"),
+        }
+    }
+}
+
+impl<E: core::fmt::Debug> core::error::Error for TimingError<E> {}
+
+/// Interface which keeps Modbus RTU inter-frame silence, using `D` to wait.
+///
+/// Before the first write of a request, waits for the frame silence. While receiving, a read which returns no data
+/// (`Ok(0)`, or an error of kind `Other` or `TimedOut`) is retried after the frame silence; if there is still no data,
+/// the read returns [`TimingError::Silence`], which ends the response. Reads before the first byte of a response are
+/// passed through, so the wrapped interface's own timeout still applies while waiting for the PSU to answer.
+#[derive(Debug)]
+pub struct RtuTiming<S, D> {
+    interface: S,
+    delay: D,
+    silence_ns: u32,
+    /// Whether a request is being written.
+    transmitting: bool,
+    /// Whether any of the response has been received.
+    receiving: bool,
+}
+
+impl<S, D: DelayNs> RtuTiming<S, D> {
+    /// Wrap `interface`, with the frame silence for `baud_rate`.
+    pub fn new(interface: S, delay: D, baud_rate: BaudRate) -> Self {
+        Self::with_silence_ns(
+            interface,
+            delay,
+            frame_silence_ns(baud_rate.bits_per_second()),
+        )
+    }
+
+    /// Wrap `interface`, with a frame silence of `silence_ns`, e.g. longer than the spec for a slow USB adapter.
+    pub fn with_silence_ns(interface: S, delay: D, silence_ns: u32) -> Self {
+        Self {
+            interface,
+            delay,
+            silence_ns,
+            transmitting: false,
+            receiving: false,
+        }
+    }
+
+    /// Return the frame silence, in nanoseconds.
+    pub fn silence_ns(&self) -> u32 {
+        self.silence_ns
+    }
+
+    /// Unwrap the interface and delay.
+    pub fn into_inner(self) -> (S, D) {
+        (self.interface, self.delay)
+    }
+}
+
+impl<S: embedded_io::ErrorType, D> embedded_io::ErrorType for RtuTiming<S, D> {
+    type Error = TimingError<S::Error>;
+}
+
+impl<S: embedded_io::Write, D: DelayNs> embedded_io::Write for RtuTiming<S, D> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if !self.transmitting {
+            self.delay.delay_ns(self.silence_ns);
+            self.transmitting = true;
+            self.receiving = false;
+        }
+        self.interface.write(buf).map_err(TimingError::Interface)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.interface.flush().map_err(TimingError::Interface)
+    }
+}
+
+impl<S: embedded_io::Read, D: DelayNs> embedded_io::Read for RtuTiming<S, D> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.transmitting = false;
+        let mut waited = false;
+        loop {
+            let result = self.interface.read(buf);
+            let silent = match &result {
+                Ok(0) => true,
+                Ok(_) => false,
+                Err(e) => matches!(
+                    embedded_io::Error::kind(e),
+                    embedded_io::ErrorKind::Other | embedded_io::ErrorKind::TimedOut
+                ),
+            };
+            if !silent || !self.receiving {
+                if result.as_ref().is_ok_and(|&read| read > 0) {
+                    self.receiving = true;
+                }
+                return result.map_err(TimingError::Interface);
+            }
+            if waited {
+                return Err(TimingError::Silence);
+            }
+            self.delay.delay_ns(self.silence_ns);
+            waited = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::{Error, TransactionFailure},
+        mock_serial::{MockResponse, MockSerial},
+        psu::XyPsu,
+    };
+
+    /// Adds up the time waited.
+    #[derive(Debug, Default)]
+    struct TotalDelay(u64);
+
+    impl DelayNs for TotalDelay {
+        fn delay_ns(&mut self, ns: u32) {
+            self.0 += ns as u64;
+        }
+    }
+
+    #[test]
+    fn silence_kept_between_frames() {
+        assert_eq!(frame_silence_ns(9600), 4_010_416);
+        assert_eq!(frame_silence_ns(115200), FIXED_FRAME_SILENCE_NS);
+
+        let interface = RtuTiming::with_silence_ns(MockSerial::new(), TotalDelay::default(), 1000);
+        let mut psu: XyPsu<_, 128> = XyPsu::new(interface, 0x01);
+        psu.interface_mut()
+            .interface
+            .queue_read_response(0x01, &[1, 2, 3]);
+        assert_eq!(psu.read_modbus_bulk(0x00_u16, 3).unwrap(), [1, 2, 3]);
+        // Only the silence before the request, the response ends on its length.
+        assert_eq!(psu.interface_mut().delay.0, 1000);
+
+        // A truncated response ends once the line has been silent.
+        let mut truncated: heapless::Vec<u8, 256> = heapless::Vec::new();
+        truncated
+            .extend_from_slice(&[0x01, 0x03, 0x06, 0x00])
+            .unwrap();
+        psu.interface_mut()
+            .interface
+            .queue_response(MockResponse::Data(truncated));
+        assert!(matches!(
+            psu.read_modbus_bulk(0x00_u16, 3),
+            Err(Error::Transaction {
+                failure: TransactionFailure::InvalidResponse,
+                ..
+            })
+        ));
+        assert_eq!(psu.interface_mut().delay.0, 3000);
+    }
+}
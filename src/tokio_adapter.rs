@@ -0,0 +1,112 @@
+//! Serial ports for [`XyPsuAsync`](crate::asynch::XyPsuAsync) in tokio applications on desktops.
+//!
+//! ```ignore
+//! let serial = TokioSerial::open("/dev/ttyUSB0", 115200)?.with_read_timeout(Duration::from_millis(300));
+//! let mut psu: XyPsuAsync<_> = XyPsuAsync::new(serial, 0x01);
+//! let voltage_mv = psu.read_output_voltage_mv().await?;
+//! ```
+//!
+//! Only available with the `tokio` feature.
+
+use core::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_serial::{DataBits, Parity, SerialPortBuilderExt, SerialStream, StopBits};
+
+/// Async interface over a tokio serial port, or any other tokio byte stream.
+///
+/// Reads wait as long as the stream does, unless a timeout is set with [`Self::with_read_timeout`].
+#[derive(Debug)]
+pub struct TokioSerial<T = SerialStream> {
+    stream: T,
+    read_timeout: Option<Duration>,
+}
+
+impl TokioSerial {
+    /// Open the serial port at `path`, configured as the PSUs expect: 8 data bits, no parity and 1 stop bit.
+    pub fn open(path: &str, baud_rate: u32) -> std::io::Result<Self> {
+        let stream = tokio_serial::new(path, baud_rate)
+            .data_bits(DataBits::Eight)
+            .parity(Parity::None)
+            .stop_bits(StopBits::One)
+            .open_native_async()?;
+        Ok(Self::new(stream))
+    }
+}
+
+impl<T> TokioSerial<T> {
+    /// Wrap an already opened `stream`.
+    pub fn new(stream: T) -> Self {
+        Self {
+            stream,
+            read_timeout: None,
+        }
+    }
+
+    /// Return a `TimedOut` error from reads which get no data within `timeout`, which ends a response or fails the
+    /// transaction if nothing was received. The PSU can take a while to respond, 300ms works well.
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Unwrap the stream.
+    pub fn into_inner(self) -> T {
+        self.stream
+    }
+}
+
+impl<T> embedded_io::ErrorType for TokioSerial<T> {
+    type Error = std::io::Error;
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> embedded_io_async::Read for TokioSerial<T> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        match self.read_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.stream.read(buf))
+                .await
+                .map_err(|_| std::io::ErrorKind::TimedOut)?,
+            None => self.stream.read(buf).await,
+        }
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> embedded_io_async::Write for TokioSerial<T> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.stream.write(buf).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.stream.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asynch::XyPsuAsync;
+
+    #[tokio::test]
+    async fn read_over_stream() {
+        let (client, mut server) = tokio::io::duplex(64);
+        let serial = TokioSerial::new(client).with_read_timeout(Duration::from_millis(100));
+        let mut psu: XyPsuAsync<_, 128> = XyPsuAsync::new(serial, 0x01);
+
+        let respond = async {
+            let mut request = [0; 8];
+            server.read_exact(&mut request).await.unwrap();
+            // Read of one register holding 0x1234.
+            server
+                .write_all(&[0x01, 0x03, 0x02, 0x12, 0x34, 0xB5, 0x33])
+                .await
+                .unwrap();
+            request
+        };
+        let (value, request) = tokio::join!(psu.read_modbus_single(0x00_u16), respond);
+        assert_eq!(value.unwrap(), 0x1234);
+        assert_eq!(request, [0x01, 0x03, 0x00, 0x00, 0x00, 0x01, 0x84, 0x0A]);
+
+        // Nothing sent back.
+        assert!(psu.read_modbus_single(0x00_u16).await.is_err());
+    }
+}
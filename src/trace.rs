@@ -0,0 +1,97 @@
+//! Logging of every frame sent to and received from the PSU, to diagnose framing and scaling problems on new models.
+//!
+//! Only available with the `trace-frames` feature. Frames are logged at trace level through `defmt` with the `defmt`
+//! feature, and through [`log`](https://docs.rs/log) otherwise, e.g.:
+//!
+//! ```text
+//! TX read of 1 register at 0x0002 on unit 1: [01, 03, 00, 02, 00, 01, 25, CA]
+//! RX read of 1 register at 0x0002 on unit 1 after 4.2ms: [01, 03, 02, 04, B0, BB, 30]
+//! ```
+//!
+//! How long the PSU took to respond is only measured with std.
+
+use crate::error::TransactionContext;
+
+/// When a request was sent, to time its response.
+pub(crate) struct Sent {
+    #[cfg(not(feature = "no_std"))]
+    at: std::time::Instant,
+}
+
+/// Log the request `bytes` sent for `context`.
+pub(crate) fn sent(context: &TransactionContext, bytes: &[u8]) -> Sent {
+    #[cfg(feature = "defmt")]
+    defmt::trace!("TX {}: {=[u8]:02X}", context, bytes);
+    #[cfg(not(feature = "defmt"))]
+    log::trace!("TX {context}: {bytes:02X?}");
+    Sent {
+        #[cfg(not(feature = "no_std"))]
+        at: std::time::Instant::now(),
+    }
+}
+
+/// Log the response `bytes` received for `context`, which may be incomplete.
+pub(crate) fn received(context: &TransactionContext, bytes: &[u8], sent: Sent) {
+    #[cfg(not(feature = "no_std"))]
+    let elapsed = sent.at.elapsed();
+    #[cfg(feature = "no_std")]
+    let _ = sent;
+
+    #[cfg(all(feature = "defmt", not(feature = "no_std")))]
+    defmt::trace!(
+        "RX {} after {}us: {=[u8]:02X}",
+        context,
+        elapsed.as_micros() as u64,
+        bytes
+    );
+    #[cfg(all(feature = "defmt", feature = "no_std"))]
+    defmt::trace!("RX {}: {=[u8]:02X}", context, bytes);
+    #[cfg(all(not(feature = "defmt"), not(feature = "no_std")))]
+    log::trace!("RX {context} after {elapsed:.1?}: {bytes:02X?}");
+    #[cfg(all(not(feature = "defmt"), feature = "no_std"))]
+    log::trace!("RX {context}: {bytes:02X?}");
+}
+
+#[cfg(all(test, not(feature = "defmt"), not(feature = "no_std")))]
+mod tests {
+    use crate::{mock_serial::MockSerial, psu::XyPsu};
+    use std::sync::Mutex;
+
+    /// Keeps every message logged.
+    struct Capture(Mutex<Vec<String>>);
+
+    impl log::Log for Capture {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            if let Ok(mut messages) = self.0.lock() {
+                messages.push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+    #[test]
+    fn frames_logged() {
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.interface_mut().queue_read_response(0x01, &[0x04B0]);
+        psu.read_modbus_single(0x0002_u16).unwrap();
+
+        let messages = CAPTURE.0.lock().unwrap();
+        assert_eq!(
+            messages.first().map(String::as_str),
+            Some("TX read of 1 register at 0x0002 on unit 1: [01, 03, 00, 02, 00, 01, 25, CA]")
+        );
+        let received = messages.get(1).unwrap();
+        assert!(received.starts_with("RX read of 1 register at 0x0002 on unit 1 after "));
+        assert!(received.ends_with(": [01, 03, 02, 04, B0, BB, 30]"));
+    }
+}
@@ -0,0 +1,107 @@
+//! How Modbus RTU frames get to and from the PSU.
+//!
+//! [`XyPsu`](crate::psu::XyPsu) works over any [`Transport`]. Every interface which implements [`embedded_io::Read`]
+//! and [`embedded_io::Write`] is one, e.g. a UART or serial port. Implement it yourself for anything else which carries
+//! the frames, e.g. a TCP socket to an RS485 gateway, USB CDC, or a test harness.
+//!
+//! ```ignore
+//! impl Transport for Gateway {
+//!     type Error = GatewayError;
+//!
+//!     fn send(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+//!         self.socket.write_all(frame).map_err(GatewayError)
+//!     }
+//!
+//!     fn receive(&mut self, buf: &mut [u8], timeout: Option<Duration>) -> Result<usize, Self::Error> {
+//!         self.socket.set_read_timeout(timeout).map_err(GatewayError)?;
+//!         self.socket.read(buf).map_err(GatewayError)
+//!     }
+//! }
+//! ```
+
+use core::time::Duration;
+
+/// Carries request frames to the PSU and response frames back.
+pub trait Transport {
+    /// Error of the transport. Errors of kind `TimedOut` or `Other` once some of a response has been received mark the
+    /// end of the response.
+    type Error: embedded_io::Error;
+
+    /// Send a whole request frame.
+    fn send(&mut self, frame: &[u8]) -> Result<(), Self::Error>;
+
+    /// Receive the next bytes of a response frame into `buf`, returning how many were received.
+    ///
+    /// Waits at most `timeout` for them, if given, returning an error of kind `TimedOut` if none arrive. A response may
+    /// be received over several calls.
+    fn receive(&mut self, buf: &mut [u8], timeout: Option<Duration>) -> Result<usize, Self::Error>;
+}
+
+/// `embedded-io` has no timeouts, so `timeout` is left to the interface, e.g. the timeout a serial port was opened
+/// with.
+impl<T: embedded_io::Read + embedded_io::Write> Transport for T {
+    type Error = T::Error;
+
+    fn send(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        self.write_all(frame)
+    }
+
+    fn receive(
+        &mut self,
+        buf: &mut [u8],
+        _timeout: Option<Duration>,
+    ) -> Result<usize, Self::Error> {
+        self.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock_serial::MockSerialError, psu::XyPsu};
+
+    /// Replies to every request with a fixed response, a byte at a time.
+    struct Scripted {
+        response: &'static [u8],
+        position: usize,
+        timeout: Option<Duration>,
+    }
+
+    impl Transport for Scripted {
+        type Error = MockSerialError;
+
+        fn send(&mut self, _frame: &[u8]) -> Result<(), Self::Error> {
+            self.position = 0;
+            Ok(())
+        }
+
+        fn receive(
+            &mut self,
+            buf: &mut [u8],
+            timeout: Option<Duration>,
+        ) -> Result<usize, Self::Error> {
+            self.timeout = timeout;
+            let byte = self
+                .response
+                .get(self.position)
+                .ok_or(MockSerialError::Timeout)?;
+            *buf.first_mut().ok_or(MockSerialError::BufferOverflow)? = *byte;
+            self.position += 1;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn custom_transport() {
+        let transport = Scripted {
+            // Read of one register holding 0x1234.
+            response: &[0x01, 0x03, 0x02, 0x12, 0x34, 0xB5, 0x33],
+            position: 0,
+            timeout: None,
+        };
+        let mut psu: XyPsu<_, 128> = XyPsu::new(transport, 0x01);
+        psu.set_response_timeout(Some(Duration::from_millis(50)));
+        assert_eq!(psu.read_modbus_single(0x00_u16).unwrap(), 0x1234);
+        assert_eq!(psu.interface_mut().timeout, Some(Duration::from_millis(50)));
+    }
+}
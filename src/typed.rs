@@ -0,0 +1,286 @@
+//! Registers which carry their value type, access mode and unit, for reading and writing without hand-converting raw
+//! `u16`s.
+//!
+//! Each register is a type implementing [`Register`], and read-write registers also implement [`Writable`]. So
+//! [`XyPsu::get`] and [`XyPsu::set`] check at compile time that the value has the right type, and that the register can
+//! be written at all.
+//!
+//! ```ignore
+//! use sinilink_xy_psu::typed::{OnOff, VOut, VSet};
+//!
+//! psu.set::<VSet>(12_000)?;
+//! psu.set::<OnOff>(State::On)?;
+//! let voltage_mv: u32 = psu.get::<VOut>()?;
+//! // psu.set::<VOut>(5_000)?; // Doesn't compile, VOut is read-only.
+//! ```
+
+#[cfg(feature = "presets")]
+use crate::preset::PresetGroup;
+use crate::{
+    error::{Error, Result},
+    limits::{ModelLimits, setpoint_to_raw},
+    psu::XyPsu,
+    register::{
+        BacklightBrightness, BaudRate, ControlMode, ProtectionStatus, State, TemperatureUnit,
+        XyRegister,
+    },
+    scaling::ScalingFactors,
+    transport::Transport,
+};
+
+/// Whether a register can be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    ReadOnly,
+    ReadWrite,
+}
+
+/// Unit of a register's value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    /// Scaled using the model's voltage scaling factor.
+    Millivolts,
+    /// Scaled using the model's current scaling factor.
+    Milliamps,
+    /// Scaled using the model's power scaling factor.
+    Milliwatts,
+    Watts,
+    Hours,
+    Minutes,
+    Seconds,
+    /// Hundredths of a degree, in the unit set by [`FC`].
+    CentiDegrees,
+    /// No unit, e.g. a state, enum, or raw value.
+    None,
+}
+
+impl Unit {
+    /// Whether values in this unit need the model's scaling factors to convert.
+    pub const fn is_scaled(&self) -> bool {
+        matches!(self, Unit::Millivolts | Unit::Milliamps | Unit::Milliwatts)
+    }
+}
+
+/// A register with a known value type.
+pub trait Register {
+    /// Type of the register's value.
+    type Value;
+    /// The register, at the address given by the PSU's register map.
+    const REGISTER: XyRegister;
+    const ACCESS: Access;
+    const UNIT: Unit;
+
+    /// Convert from the raw register value. `scaling` is given if [`Unit::is_scaled`].
+    ///
+    /// Returns `None` if the raw value isn't valid for the type.
+    fn decode(raw: u16, scaling: Option<&ScalingFactors>) -> Option<Self::Value>;
+
+    /// Convert to the raw register value. `scaling` is given if [`Unit::is_scaled`].
+    ///
+    /// Returns `None` if a scaled value is above `limits`, or too large for the register.
+    fn encode(
+        value: Self::Value,
+        scaling: Option<&ScalingFactors>,
+        limits: Option<&ModelLimits>,
+    ) -> Option<u16>;
+}
+
+/// A register which can be written.
+pub trait Writable: Register {}
+
+macro_rules! decode {
+    (Millivolts, $raw:ident, $scaling:ident) => {
+        $scaling.map(|s| s.raw_to_voltage_mv($raw))
+    };
+    (Milliamps, $raw:ident, $scaling:ident) => {
+        $scaling.map(|s| s.raw_to_current_ma($raw))
+    };
+    (Milliwatts, $raw:ident, $scaling:ident) => {
+        $scaling.map(|s| s.raw_to_power_mw($raw))
+    };
+    ($unit:ident, $raw:ident, $scaling:ident) => {
+        $raw.try_into().ok()
+    };
+}
+
+macro_rules! encode {
+    (Millivolts, $value:ident, $scaling:ident, $limits:ident) => {
+        $scaling.and_then(|s| {
+            setpoint_to_raw($value, $limits.map(|l| l.max_voltage_mv), s.voltage_divisor)
+        })
+    };
+    (Milliamps, $value:ident, $scaling:ident, $limits:ident) => {
+        $scaling.and_then(|s| {
+            setpoint_to_raw($value, $limits.map(|l| l.max_current_ma), s.current_divisor)
+        })
+    };
+    (Milliwatts, $value:ident, $scaling:ident, $limits:ident) => {
+        $scaling.and_then(|s| {
+            setpoint_to_raw(
+                $value,
+                $limits.and_then(|l| l.max_power_mw),
+                s.power_divisor,
+            )
+        })
+    };
+    ($unit:ident, $value:ident, $scaling:ident, $limits:ident) => {
+        Some($value.into())
+    };
+}
+
+macro_rules! writable {
+    (ReadWrite, $name:ident) => {
+        impl Writable for $name {}
+    };
+    (ReadOnly, $name:ident) => {};
+}
+
+/// Define a type for each register.
+macro_rules! registers {
+    ($($(#[doc = $doc:expr])* $(#[cfg($cfg:meta)])? $name:ident: $access:ident, $unit:ident, $value:ty;)*) => {
+        $(
+            #[doc = concat!("See [`XyRegister::", stringify!($name), "`].")]
+            $(#[doc = ""] #[doc = $doc])*
+            $(#[cfg($cfg)])?
+            #[derive(Debug, Clone, Copy)]
+            pub struct $name;
+
+            $(#[cfg($cfg)])?
+            impl Register for $name {
+                type Value = $value;
+                const REGISTER: XyRegister = XyRegister::$name;
+                const ACCESS: Access = Access::$access;
+                const UNIT: Unit = Unit::$unit;
+
+                #[allow(unused_variables)]
+                fn decode(raw: u16, scaling: Option<&ScalingFactors>) -> Option<$value> {
+                    decode!($unit, raw, scaling)
+                }
+
+                #[allow(unused_variables)]
+                fn encode(
+                    value: $value,
+                    scaling: Option<&ScalingFactors>,
+                    limits: Option<&ModelLimits>,
+                ) -> Option<u16> {
+                    encode!($unit, value, scaling, limits)
+                }
+            }
+
+            $(#[cfg($cfg)])?
+            writable!($access, $name);
+        )*
+
+        impl Unit {
+            /// Return the unit of `register`'s value. Registers without a type here, e.g. the halves of the counters,
+            /// are [`Unit::None`].
+            pub const fn of(register: XyRegister) -> Self {
+                match register {
+                    $(
+                        $(#[cfg($cfg)])?
+                        XyRegister::$name => Unit::$unit,
+                    )*
+                    _ => Unit::None,
+                }
+            }
+        }
+    };
+}
+
+registers! {
+    VSet: ReadWrite, Millivolts, u32;
+    ISet: ReadWrite, Milliamps, u32;
+    VOut: ReadOnly, Millivolts, u32;
+    IOut: ReadOnly, Milliamps, u32;
+    Power: ReadOnly, Milliwatts, u32;
+    UIn: ReadOnly, Millivolts, u32;
+    OutH: ReadOnly, Hours, u16;
+    OutM: ReadOnly, Minutes, u16;
+    OutS: ReadOnly, Seconds, u16;
+    TIn: ReadOnly, CentiDegrees, u16;
+    TEx: ReadOnly, CentiDegrees, u16;
+    Lock: ReadWrite, None, State;
+    Protect: ReadWrite, None, ProtectionStatus;
+    CvCc: ReadOnly, None, ControlMode;
+    OnOff: ReadWrite, None, State;
+    FC: ReadWrite, None, TemperatureUnit;
+    BLed: ReadWrite, None, BacklightBrightness;
+    Sleep: ReadWrite, Minutes, u16;
+    Model: ReadOnly, None, u16;
+    Version: ReadOnly, None, u16;
+    SlaveAdd: ReadWrite, None, u8;
+    BaudRateL: ReadWrite, None, BaudRate;
+    TInOffset: ReadWrite, None, u16;
+    TExOffset: ReadWrite, None, u16;
+    Buzzer: ReadWrite, None, State;
+    #[cfg(feature = "presets")]
+    ExtractM: ReadWrite, None, PresetGroup;
+    /// [`State::On`] while awake, [`State::Off`] while asleep.
+    Device: ReadWrite, None, State;
+    MpptSw: ReadWrite, None, State;
+    MpptK: ReadWrite, None, u16;
+    CwSw: ReadWrite, None, State;
+    Cw: ReadWrite, Watts, u16;
+}
+
+impl<S: Transport, const L: usize> XyPsu<S, L> {
+    /// Read a typed register, e.g. `psu.get::<typed::VOut>()`.
+    ///
+    /// Registers in millivolts, milliamps or milliwatts require known scaling factors for the PSU model. Returns
+    /// `InvalidResponse` if the raw value isn't valid for the register's type.
+    pub fn get<R: Register>(&mut self) -> Result<R::Value, S::Error> {
+        let scaling = if R::UNIT.is_scaled() {
+            Some(self.ensure_scaling()?)
+        } else {
+            None
+        };
+        let raw = self.read_register(R::REGISTER)?;
+        R::decode(raw, scaling.as_ref()).ok_or(Error::InvalidResponse)
+    }
+
+    /// Write a typed register, e.g. `psu.set::<typed::VSet>(12_000)`.
+    ///
+    /// Registers in millivolts, milliamps or milliwatts require known scaling factors for the PSU model, and return
+    /// `InvalidRange` for values above the model's [`limits`](Self::limits).
+    pub fn set<R: Writable>(&mut self, value: R::Value) -> Result<(), S::Error> {
+        let scaling = if R::UNIT.is_scaled() {
+            Some(self.ensure_scaling()?)
+        } else {
+            None
+        };
+        let limits = self.limits();
+        let raw = R::encode(value, scaling.as_ref(), limits.as_ref()).ok_or(Error::InvalidRange)?;
+        self.write_register(R::REGISTER, raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_serial::{MockResponse, MockSerial};
+
+    #[test]
+    fn typed_get_and_set() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+
+        psu.interface_mut().queue_read_response(0x01, &[1234]);
+        assert_eq!(psu.get::<VOut>().unwrap(), 12340);
+
+        psu.interface_mut().queue_read_response(0x01, &[1]);
+        assert_eq!(psu.get::<CvCc>().unwrap(), ControlMode::Cc);
+
+        psu.interface_mut().queue_read_response(0x01, &[7]);
+        assert!(matches!(psu.get::<FC>(), Err(Error::InvalidResponse)));
+
+        psu.interface_mut().queue_response(MockResponse::Echo);
+        psu.set::<VSet>(5000).unwrap();
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([0x00, 0x00, 0x01, 0xF4].as_slice())
+        );
+
+        // Too large for the register, rather than wrapping around.
+        assert!(matches!(psu.set::<VSet>(655_360), Err(Error::InvalidRange)));
+    }
+}
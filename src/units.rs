@@ -0,0 +1,69 @@
+//! Newtypes for millivolts, milliamps and milliwatts, so e.g. a current can't be passed where a voltage is expected.
+//!
+//! The setters accept anything which converts into the newtype, so plain `u32` values still work. The getters return
+//! the newtype, with the value in the public field.
+//!
+//! ```ignore
+//! psu.set_output_voltage_mv(Millivolts(12_000))?;
+//! psu.set_current_limit_ma(1500)?;
+//! let Milliamps(current_ma) = psu.read_current_ma()?;
+//! ```
+
+macro_rules! milli_unit {
+    ($name:ident, $symbol:literal, $doc:literal) => {
+        #[doc = $doc]
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        #[cfg_attr(feature = "serde", serde(transparent))]
+        pub struct $name(pub u32);
+
+        impl From<u32> for $name {
+            fn from(value: u32) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for u32 {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl core::fmt::Display for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                write!(f, concat!("{} ", $symbol), self.0)
+            }
+        }
+    };
+}
+
+milli_unit!(Millivolts, "mV", "A voltage in millivolts.");
+milli_unit!(Milliamps, "mA", "A current in milliamps.");
+milli_unit!(Milliwatts, "mW", "A power in milliwatts.");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_serial::{MockResponse, MockSerial},
+        psu::XyPsu,
+        scaling::ScalingFactors,
+    };
+
+    #[test]
+    fn units_in_setters_and_getters() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+
+        psu.interface_mut().queue_response(MockResponse::Echo);
+        psu.set_output_voltage_mv(Millivolts(12_340)).unwrap();
+        psu.interface_mut().queue_response(MockResponse::Echo);
+        psu.set_current_limit_ma(1_500).unwrap();
+
+        psu.interface_mut().queue_read_response(0x01, &[1500]);
+        let current = psu.read_current_ma().unwrap();
+        assert_eq!(current, Milliamps(1_500));
+        assert_eq!(u32::from(current), 1_500);
+    }
+}
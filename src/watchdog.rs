@@ -0,0 +1,125 @@
+//! Turn the output off if the control software stops responding, e.g. it crashed or hung mid-test.
+//!
+//! The application calls [`Watchdog::feed`] while it is healthy, and something independent of it, e.g. another thread
+//! or a timer interrupt, calls [`Watchdog::poll`]. Once a whole period passes without a feed, the next poll turns the
+//! output off. The deadline is timed with a [`Clock`], in whatever unit it counts in.
+//!
+//! ```ignore
+//! let start = Instant::now();
+//! let mut watchdog = Watchdog::new(5_000, move || start.elapsed().as_millis() as u64);
+//! loop {
+//!     run_test_step(&mut psu)?;
+//!     watchdog.feed();
+//!     watchdog.poll(&mut psu)?;
+//! }
+//! ```
+
+use crate::{clock::Clock, error::Result, psu::XyPsu, register::State, transport::Transport};
+
+/// Whether the watchdog has turned the output off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WatchdogState {
+    /// Fed within the period.
+    Armed,
+    /// The period passed without a feed, and the output was turned off.
+    Tripped,
+}
+
+/// Turns the output off if not fed within a period.
+pub struct Watchdog<C: Clock> {
+    clock: C,
+    period: u64,
+    deadline: u64,
+    state: WatchdogState,
+}
+
+impl<C: Clock> Watchdog<C> {
+    /// Trip if not fed for `period`, in the unit of `clock`. Counts as fed on creation.
+    pub fn new(period: u64, mut clock: C) -> Self {
+        let deadline = clock.now().saturating_add(period);
+        Self {
+            clock,
+            period,
+            deadline,
+            state: WatchdogState::Armed,
+        }
+    }
+
+    /// Push the deadline back by a period. Re-arms a tripped watchdog, but doesn't turn the output back on.
+    pub fn feed(&mut self) {
+        self.deadline = self.clock.now().saturating_add(self.period);
+        self.state = WatchdogState::Armed;
+    }
+
+    /// Return the state as of the last poll.
+    pub fn state(&self) -> WatchdogState {
+        self.state
+    }
+
+    /// Whether the deadline has passed, and the watchdog should trip on this poll.
+    fn expired(&mut self) -> bool {
+        self.state == WatchdogState::Armed && self.clock.now() >= self.deadline
+    }
+
+    /// Turn the output off if the deadline has passed.
+    ///
+    /// The output is only turned off once per trip. If that fails, the watchdog stays armed and tries again on the
+    /// next poll.
+    pub fn poll<S: Transport, const L: usize>(
+        &mut self,
+        psu: &mut XyPsu<S, L>,
+    ) -> Result<WatchdogState, S::Error> {
+        if self.expired() {
+            psu.set_output_state(State::Off)?;
+            self.state = WatchdogState::Tripped;
+        }
+        Ok(self.state)
+    }
+
+    /// See [`Self::poll`].
+    #[cfg(feature = "async")]
+    pub async fn poll_async<
+        S: embedded_io_async::Read + embedded_io_async::Write,
+        const L: usize,
+    >(
+        &mut self,
+        psu: &mut crate::asynch::XyPsuAsync<S, L>,
+    ) -> Result<WatchdogState, S::Error> {
+        if self.expired() {
+            psu.set_output_state(State::Off).await?;
+            self.state = WatchdogState::Tripped;
+        }
+        Ok(self.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_serial::{MockResponse, MockSerial};
+    use core::cell::Cell;
+
+    #[test]
+    fn watchdog_trips_once_when_not_fed() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        let now = Cell::new(0);
+        let mut watchdog = Watchdog::new(100, || now.get());
+
+        now.set(90);
+        watchdog.feed();
+        now.set(150);
+        assert_eq!(watchdog.poll(&mut psu).unwrap(), WatchdogState::Armed);
+
+        now.set(190);
+        psu.interface_mut().queue_response(MockResponse::Echo);
+        assert_eq!(watchdog.poll(&mut psu).unwrap(), WatchdogState::Tripped);
+        assert_eq!(
+            psu.last_transaction().request.get(2..6),
+            Some([0x00, 0x12, 0x00, 0x00].as_slice())
+        );
+        // Nothing more sent.
+        now.set(300);
+        assert_eq!(watchdog.poll(&mut psu).unwrap(), WatchdogState::Tripped);
+    }
+}
@@ -0,0 +1,182 @@
+//! Play back a voltage profile on the output, e.g. a battery discharge curve or a brown-out for testing a load.
+//!
+//! The profile is a list of points, and the voltage setting follows straight lines between them. It is updated at most
+//! every [`WaveformConfig::min_interval_ms`], so this suits slow profiles over seconds to hours rather than fast
+//! waveforms: each update takes a couple of Modbus transactions.
+//!
+//! ```ignore
+//! // Sag from 12V to 9V over 2s, hold for 1s, then recover.
+//! let points = [
+//!     Point::new(0, 12_000),
+//!     Point::new(2_000, 9_000),
+//!     Point::new(3_000, 9_000),
+//!     Point::new(3_100, 12_000),
+//! ];
+//! let outcome = play_waveform(&mut psu, &points, WaveformConfig::default(), |ms| {
+//!     sleep(Duration::from_millis(ms.into()));
+//!     true
+//! })?;
+//! ```
+
+use crate::{error::Result, psu::XyPsu, register::ProtectionStatus, transport::Transport};
+
+/// Output voltage at a time in the profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Point {
+    /// Time from the start of the profile, in milliseconds.
+    pub time_ms: u32,
+    /// Output voltage setting in millivolts.
+    pub voltage_mv: u32,
+}
+
+impl Point {
+    pub fn new(time_ms: u32, voltage_mv: u32) -> Self {
+        Self {
+            time_ms,
+            voltage_mv,
+        }
+    }
+}
+
+/// How a profile is played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaveformConfig {
+    /// Shortest time between updates of the voltage setting, in milliseconds, which caps the update rate. Default
+    /// 100ms, a value of 0 is treated as 1ms.
+    pub min_interval_ms: u32,
+    /// Check the protections before each update, and stop if any tripped. Default on.
+    pub abort_on_protection: bool,
+}
+
+impl Default for WaveformConfig {
+    fn default() -> Self {
+        Self {
+            min_interval_ms: 100,
+            abort_on_protection: true,
+        }
+    }
+}
+
+/// Result of [`play_waveform`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaveformOutcome {
+    /// The whole profile was played, leaving the last point's voltage set.
+    Completed,
+    /// A protection tripped at `at_ms` into the profile.
+    Protection {
+        at_ms: u32,
+        protection: ProtectionStatus,
+    },
+    /// The delay callback returned `false` at `at_ms` into the profile.
+    Cancelled { at_ms: u32 },
+}
+
+/// Play `points`, which must be in time order, by updating the output voltage setting.
+///
+/// `delay` is called between updates with the time to wait in milliseconds, and returns `false` to stop. Time in the
+/// profile is counted from these delays, so the time taken to talk to the PSU adds to each interval. The setting is only
+/// written when it changes. The output state is left as it is, so turn it on first.
+///
+/// Requires known scaling factors for the PSU model.
+pub fn play_waveform<S: Transport, const L: usize>(
+    psu: &mut XyPsu<S, L>,
+    points: &[Point],
+    config: WaveformConfig,
+    mut delay: impl FnMut(u32) -> bool,
+) -> Result<WaveformOutcome, S::Error> {
+    let Some(end_ms) = points.last().map(|p| p.time_ms) else {
+        return Ok(WaveformOutcome::Completed);
+    };
+    let interval_ms = config.min_interval_ms.max(1);
+    let mut at_ms = 0;
+    let mut applied_mv = None;
+    loop {
+        if config.abort_on_protection {
+            let protection = psu.get_protection_status()?;
+            if protection.any() {
+                return Ok(WaveformOutcome::Protection { at_ms, protection });
+            }
+        }
+        let voltage_mv = voltage_at(points, at_ms);
+        if applied_mv != Some(voltage_mv) {
+            psu.set_output_voltage_mv(voltage_mv)?;
+            applied_mv = Some(voltage_mv);
+        }
+        if at_ms >= end_ms {
+            return Ok(WaveformOutcome::Completed);
+        }
+
+        let wait_ms = interval_ms.min(end_ms - at_ms);
+        if !delay(wait_ms) {
+            return Ok(WaveformOutcome::Cancelled { at_ms });
+        }
+        at_ms += wait_ms;
+    }
+}
+
+/// Voltage on the line between the points either side of `at_ms`. Before the first point, the first point's voltage.
+fn voltage_at(points: &[Point], at_ms: u32) -> u32 {
+    let next = points.iter().position(|p| p.time_ms > at_ms);
+    let (from, to) = match next {
+        Some(i) => (i.checked_sub(1).and_then(|i| points.get(i)), points.get(i)),
+        None => (points.last(), None),
+    };
+    match (from, to) {
+        (Some(from), Some(to)) => {
+            let span = u64::from(to.time_ms - from.time_ms);
+            let elapsed = u64::from(at_ms - from.time_ms);
+            let from_mv = i64::from(from.voltage_mv);
+            let change = (i64::from(to.voltage_mv) - from_mv) * elapsed as i64 / span as i64;
+            (from_mv + change) as u32
+        }
+        (Some(point), None) | (None, Some(point)) => point.voltage_mv,
+        (None, None) => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_serial::{MockResponse, MockSerial},
+        scaling::ScalingFactors,
+    };
+
+    #[test]
+    fn waveform_follows_points() {
+        let mut psu: XyPsu<MockSerial, 128> = XyPsu::new(MockSerial::new(), 0x01);
+        psu.set_scaling_factors(ScalingFactors::new(10, 1, 100, 1, 10).unwrap());
+        // Updated at 0, 500 and 1000ms.
+        for _ in 0..3 {
+            psu.interface_mut().queue_read_response(0x01, &[0]);
+            psu.interface_mut().queue_response(MockResponse::Echo);
+        }
+        // Tripped at 1500ms.
+        psu.interface_mut().queue_read_response(0x01, &[0b1]);
+
+        let points = [
+            Point::new(0, 5000),
+            Point::new(1000, 6000),
+            Point::new(1200, 6000),
+            Point::new(2000, 8000),
+        ];
+        let config = WaveformConfig {
+            min_interval_ms: 500,
+            ..Default::default()
+        };
+        let mut waited = heapless::Vec::<u32, 4>::new();
+        let outcome =
+            play_waveform(&mut psu, &points, config, |ms| waited.push(ms).is_ok()).unwrap();
+        assert_eq!(
+            outcome,
+            WaveformOutcome::Protection {
+                at_ms: 1500,
+                protection: ProtectionStatus::from_raw(0b1)
+            }
+        );
+        assert_eq!(waited, [500, 500, 500]);
+        // Held between the points at 6V, then rising.
+        assert_eq!(voltage_at(&points, 1100), 6000);
+        assert_eq!(voltage_at(&points, 1600), 7000);
+    }
+}
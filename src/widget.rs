@@ -0,0 +1,176 @@
+//! [`embedded_graphics`] widget which draws a [`PsuStatus`], for small front panel or remote displays.
+//!
+//! Only available with the `embedded-graphics` feature.
+//!
+//! ```ignore
+//! loop {
+//!     let status = psu.read_status()?;
+//!     display.clear(BinaryColor::Off)?;
+//!     StatusWidget::new(&status, Point::zero(), BinaryColor::On, BinaryColor::Off).draw(&mut display)?;
+//! }
+//! ```
+
+use core::fmt::Write as _;
+
+use embedded_graphics::{
+    mono_font::{
+        MonoTextStyle,
+        ascii::{FONT_6X10, FONT_10X20},
+    },
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
+};
+
+use crate::{
+    register::{ControlMode, State},
+    status::{Milli, PsuStatus},
+};
+
+/// Width of the widget in pixels.
+pub const WIDGET_WIDTH: u32 = 120;
+/// Height of the widget in pixels.
+pub const WIDGET_HEIGHT: u32 = 56;
+
+/// Size of the CV/CC and output badges.
+const BADGE_SIZE: Size = Size::new(30, 12);
+
+/// Draws a [`PsuStatus`]: output voltage and current in large digits, CV/CC and output badges, and a banner listing any
+/// triggered protections.
+///
+/// Occupies [`WIDGET_WIDTH`] by [`WIDGET_HEIGHT`] pixels from `position`.
+pub struct StatusWidget<'a, C> {
+    status: &'a PsuStatus,
+    position: Point,
+    foreground: C,
+    background: C,
+}
+
+impl<'a, C: PixelColor> StatusWidget<'a, C> {
+    /// Create a widget drawing `status`, with its top left corner at `position`.
+    pub fn new(status: &'a PsuStatus, position: Point, foreground: C, background: C) -> Self {
+        Self {
+            status,
+            position,
+            foreground,
+            background,
+        }
+    }
+
+    /// Draw `text` inside a filled badge at `offset`.
+    fn draw_badge<D: DrawTarget<Color = C>>(
+        &self,
+        target: &mut D,
+        offset: Point,
+        text: &str,
+    ) -> Result<(), D::Error> {
+        let top_left = self.position + offset;
+        Rectangle::new(top_left, BADGE_SIZE)
+            .into_styled(PrimitiveStyle::with_fill(self.foreground))
+            .draw(target)?;
+        Text::with_baseline(
+            text,
+            top_left + Point::new(3, 1),
+            MonoTextStyle::new(&FONT_6X10, self.background),
+            Baseline::Top,
+        )
+        .draw(target)?;
+        Ok(())
+    }
+}
+
+impl<C: PixelColor> Drawable for StatusWidget<'_, C> {
+    type Color = C;
+    type Output = ();
+
+    fn draw<D: DrawTarget<Color = C>>(&self, target: &mut D) -> Result<(), D::Error> {
+        let large = MonoTextStyle::new(&FONT_10X20, self.foreground);
+
+        // Text which doesn't fit is truncated, which is fine for a display.
+        let mut line: heapless::String<16> = heapless::String::new();
+        let _ = write!(line, "{:>6}V", Milli(self.status.voltage_mv));
+        Text::with_baseline(&line, self.position, large, Baseline::Top).draw(target)?;
+
+        line.clear();
+        let _ = write!(line, "{:>6}A", Milli(self.status.current_ma));
+        Text::with_baseline(
+            &line,
+            self.position + Point::new(0, 22),
+            large,
+            Baseline::Top,
+        )
+        .draw(target)?;
+
+        let mode = match self.status.control_mode {
+            ControlMode::Cv => "CV",
+            ControlMode::Cc => "CC",
+        };
+        self.draw_badge(target, Point::new(88, 4), mode)?;
+        if self.status.output == State::On {
+            self.draw_badge(target, Point::new(88, 26), "ON")?;
+        }
+
+        if self.status.protection.any() {
+            let mut banner: heapless::String<48> = heapless::String::new();
+            for name in self.status.protection.triggered() {
+                let _ = write!(banner, "{name} ");
+            }
+            let top_left = self.position + Point::new(0, 44);
+            Rectangle::new(top_left, Size::new(WIDGET_WIDTH, 12))
+                .into_styled(PrimitiveStyle::with_fill(self.foreground))
+                .draw(target)?;
+            Text::with_baseline(
+                banner.trim_end(),
+                top_left + Point::new(2, 1),
+                MonoTextStyle::new(&FONT_6X10, self.background),
+                Baseline::Top,
+            )
+            .draw(target)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::register::{ProtectionStatus, Temperature};
+    use embedded_graphics::{mock_display::MockDisplay, pixelcolor::BinaryColor};
+
+    fn status(protection: u16) -> PsuStatus {
+        PsuStatus {
+            voltage_set_mv: 12000,
+            current_limit_ma: 1000,
+            voltage_mv: 12001,
+            current_ma: 534,
+            power_mw: 6410,
+            input_voltage_mv: 24000,
+            control_mode: ControlMode::Cv,
+            output: State::On,
+            lock: State::Off,
+            protection: ProtectionStatus::from_raw(protection),
+            temperature_internal: Temperature::Celsius(41),
+        }
+    }
+
+    #[test]
+    fn draws_within_bounds() {
+        for protection in [0, 0b11] {
+            let mut display: MockDisplay<BinaryColor> = MockDisplay::new();
+            display.set_allow_out_of_bounds_drawing(true);
+            display.set_allow_overdraw(true);
+            StatusWidget::new(
+                &status(protection),
+                Point::zero(),
+                BinaryColor::On,
+                BinaryColor::Off,
+            )
+            .draw(&mut display)
+            .unwrap();
+
+            let bounds = display.affected_area();
+            assert!(bounds.size.width <= WIDGET_WIDTH);
+            assert!(bounds.size.height <= WIDGET_HEIGHT);
+        }
+    }
+}